@@ -1,19 +1,22 @@
 #![no_std]
 #![no_main]
 
-use embedded_hal::digital::OutputPin;
+use embedded_hal::{delay::DelayNs, digital::OutputPin};
 use panic_halt as _;
-use sophgo_rom_rt::{entry, Peripherals};
+use sophgo_rom_rt::prelude::*;
 
 #[entry]
 fn main(p: Peripherals) -> ! {
     let pad_led = p.pwr_pads.gpio2.into_function(&p.pinmux);
     let mut led = p.pwr_gpio.a2.into_pull_up_output(pad_led);
+    let clocks = p.clk_gen.clock_gen().freeze();
+    let mut timer = p.timer.timer(&clocks);
+    let mut delay = timer.split().timer0;
 
     loop {
         led.set_high().unwrap();
-        riscv::asm::delay(10_000_000);
+        delay.delay_ms(500);
         led.set_low().unwrap();
-        riscv::asm::delay(10_000_000);
+        delay.delay_ms(500);
     }
 }
@@ -1,6 +1,7 @@
 #![no_std]
 #![no_main]
 
+use embedded_hal::delay::DelayNs;
 use embedded_io::Write;
 use panic_halt as _;
 use sophgo_rom_rt::prelude::*;
@@ -10,10 +11,15 @@ fn main(p: Peripherals) -> ! {
     let uart0_tx = p.pads.uart0_tx.into_function(&p.pinmux);
     let uart0_rx = p.pads.uart0_rx.into_function(&p.pinmux);
 
-    let mut serial = p.uart0.serial(Default::default(), (uart0_tx, uart0_rx));
+    let clocks = p.clk_gen.clock_gen().freeze();
+    let mut serial = p
+        .uart0
+        .serial(Default::default(), (uart0_tx, uart0_rx), &clocks);
+    let mut timer = p.timer.timer(&clocks);
+    let mut delay = timer.split().timer0;
 
     loop {
         writeln!(serial, "Hello World from Rust!").ok();
-        riscv::asm::delay(10_000_000);
+        delay.delay_ms(500);
     }
 }
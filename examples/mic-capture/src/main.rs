@@ -0,0 +1,58 @@
+#![no_std]
+#![no_main]
+
+use core::ptr::addr_of_mut;
+use embedded_io::Write;
+use panic_halt as _;
+use sophgo_hal::audio_codec::{Config, SampleRate};
+use sophgo_hal::dma::Descriptor;
+use sophgo_hal::i2s::Direction;
+use sophgo_rom_rt::prelude::*;
+
+/// Samples in 100 ms of 16 kHz stereo audio.
+const BLOCK_SAMPLES: usize = 16_000 / 10 * 2;
+
+/// Capture buffer, filled by DMA one block at a time while the other block is inspected.
+static mut SAMPLES: [u32; 2 * BLOCK_SAMPLES] = [0; 2 * BLOCK_SAMPLES];
+/// Descriptor ring of the capture stream.
+static mut DESCRIPTORS: [Descriptor; 2] = [Descriptor::new(); 2];
+
+#[entry]
+fn main(p: Peripherals) -> ! {
+    let uart0_tx = p.pads.uart0_tx.into_function(&p.pinmux);
+    let uart0_rx = p.pads.uart0_rx.into_function(&p.pinmux);
+
+    let clocks = p.clk_gen.clock_gen().freeze();
+    let mut serial = p
+        .uart0
+        .serial(Default::default(), (uart0_tx, uart0_rx), &clocks);
+
+    let config = Config {
+        sample_rate: SampleRate::Hz16000,
+        ..Default::default()
+    };
+    let mut codec = p.audio_codec.audio_codec(config);
+    let mut i2s = p
+        .i2s0
+        .i2s(codec.link_config(Direction::Receive), (), &clocks);
+    let dma = p.dma.dma(p.dma_remap);
+    let channel = dma.channel().unwrap();
+    let samples = unsafe { &mut *addr_of_mut!(SAMPLES) };
+    let descriptors = unsafe { &mut *addr_of_mut!(DESCRIPTORS) };
+    let mut stream = i2s.stream(channel, samples, descriptors);
+    codec.power_up_adc();
+
+    loop {
+        let peak = stream.next_half(|block| {
+            block
+                .iter()
+                .map(|&sample| (sample as u16 as i16).unsigned_abs())
+                .max()
+                .unwrap_or(0)
+        });
+        match peak {
+            Ok(peak) => writeln!(serial, "Peak level: {:5} / 32768", peak).ok(),
+            Err(_) => writeln!(serial, "Capture overrun").ok(),
+        };
+    }
+}
@@ -0,0 +1,404 @@
+//! Successive approximation analog-to-digital converter.
+
+use crate::pad::{AdcFunc, Pad};
+use crate::plic::Interrupt;
+use crate::reset::{self, Controlled};
+use core::convert::Infallible;
+use volatile_register::{RO, RW, WO};
+
+/// Analog-to-digital converter registers.
+#[repr(C)]
+pub struct RegisterBlock {
+    _reserved0: [u8; 0x4],
+    /// Control register.
+    pub control: RW<Control>,
+    /// Status register.
+    pub status: RO<u32>,
+    /// Sample and conversion cycle setting register.
+    pub cycle_setting: RW<u32>,
+    _reserved1: [u8; 0x4],
+    /// Conversion results of channel 1 to 3.
+    pub results: [RO<ConversionResult>; 3],
+    /// Interrupt enable register.
+    pub interrupt_enable: RW<u32>,
+    /// Writing 1 clears conversion done interrupt.
+    pub interrupt_clear: WO<u32>,
+    /// Interrupt status register, after masking.
+    pub interrupt_status: RO<u32>,
+    /// Interrupt status register, before masking.
+    pub raw_interrupt_status: RO<u32>,
+}
+
+/// Analog-to-digital converter control register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct Control(u32);
+
+impl Control {
+    const START: u32 = 1 << 0;
+    const CONTINUOUS: u32 = 1 << 1;
+    const CHANNELS: u32 = 0xE << 4;
+
+    /// Start conversion of selected channels.
+    #[inline]
+    pub const fn start(self) -> Self {
+        Self(self.0 | Self::START)
+    }
+    /// Stop conversion.
+    #[inline]
+    pub const fn stop(self) -> Self {
+        Self(self.0 & !Self::START)
+    }
+    /// Check if conversion is started.
+    #[inline]
+    pub const fn is_started(self) -> bool {
+        self.0 & Self::START != 0
+    }
+    /// Restart conversion of selected channels after each round.
+    #[inline]
+    pub const fn enable_continuous(self) -> Self {
+        Self(self.0 | Self::CONTINUOUS)
+    }
+    /// Convert selected channels once per start.
+    #[inline]
+    pub const fn disable_continuous(self) -> Self {
+        Self(self.0 & !Self::CONTINUOUS)
+    }
+    /// Check if continuous conversion is enabled.
+    #[inline]
+    pub const fn is_continuous_enabled(self) -> bool {
+        self.0 & Self::CONTINUOUS != 0
+    }
+    /// Select channels to convert, bit `n` for channel `n` from 1 to 3.
+    #[inline]
+    pub const fn set_channels(self, channels: u8) -> Self {
+        Self((self.0 & !Self::CHANNELS) | (((channels as u32) << 4) & Self::CHANNELS))
+    }
+    /// Get selected channels.
+    #[inline]
+    pub const fn channels(self) -> u8 {
+        ((self.0 & Self::CHANNELS) >> 4) as u8
+    }
+}
+
+/// Conversion result register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct ConversionResult(u32);
+
+impl ConversionResult {
+    const VALUE: u32 = 0xFFF;
+    const VALID: u32 = 1 << 15;
+
+    /// Raw 12-bit conversion value.
+    #[inline]
+    pub const fn value(self) -> u16 {
+        (self.0 & Self::VALUE) as u16
+    }
+    /// Check if conversion value is valid.
+    #[inline]
+    pub const fn is_valid(self) -> bool {
+        self.0 & Self::VALID != 0
+    }
+}
+
+/// Maximum raw conversion value.
+pub const MAX_VALUE: u16 = 0xFFF;
+
+/// Full scale input voltage in millivolts.
+pub const REFERENCE_MILLIVOLTS: u16 = 1800;
+
+/// Converts a raw conversion value into millivolts on the pad.
+#[inline]
+pub const fn to_millivolts(value: u16) -> u16 {
+    (value as u32 * REFERENCE_MILLIVOLTS as u32 / MAX_VALUE as u32) as u16
+}
+
+/// Managed analog-to-digital converter peripheral.
+pub struct Adc<T, const I: usize> {
+    adc: T,
+    pending: Option<u8>,
+}
+
+/// Extension trait to create analog-to-digital converter drivers from peripherals.
+pub trait AdcExt<const I: usize>: AsRef<RegisterBlock> + Controlled + Sized {
+    /// Creates an analog-to-digital converter driver, conversion stopped.
+    #[inline]
+    fn adc(self) -> Adc<Self, I> {
+        reset::enable(&self);
+        let adc = self.as_ref();
+        unsafe {
+            adc.control.write(Control::default());
+            adc.interrupt_enable.write(0);
+            adc.interrupt_clear.write(1);
+        }
+        Adc {
+            adc: self,
+            pending: None,
+        }
+    }
+}
+
+impl<T: AsRef<RegisterBlock>, const I: usize> Adc<T, I> {
+    /// Converts the channel of `pin` once, returns raw value when done.
+    ///
+    /// The first call starts the conversion, later calls poll it.
+    #[inline]
+    pub fn read<PIN: AdcPad<I>>(&mut self, _pin: &mut PIN) -> nb::Result<u16, Infallible> {
+        let adc = self.adc.as_ref();
+        let n = PIN::CHANNEL;
+        match self.pending {
+            Some(pending) if pending == n => {
+                // result register keeps its valid flag from previous conversions,
+                // so completion is taken from the conversion done flag only
+                if !self.is_done() {
+                    return Err(nb::Error::WouldBlock);
+                }
+                let result = adc.results[n as usize - 1].read();
+                if !result.is_valid() {
+                    return Err(nb::Error::WouldBlock);
+                }
+                self.pending = None;
+                unsafe { adc.control.write(Control::default()) };
+                self.clear_interrupt();
+                Ok(result.value())
+            }
+            _ => {
+                unsafe {
+                    adc.control.write(Control::default());
+                    adc.interrupt_clear.write(1);
+                    adc.control
+                        .write(Control::default().set_channels(1 << n).start());
+                }
+                self.pending = Some(n);
+                Err(nb::Error::WouldBlock)
+            }
+        }
+    }
+    /// Converts the channel of `pin` once, blocking until done, returns raw value.
+    #[inline]
+    pub fn read_blocking<PIN: AdcPad<I>>(&mut self, pin: &mut PIN) -> u16 {
+        nb::block!(self.read(pin)).unwrap_or_else(|e| match e {})
+    }
+    /// Converts the channel of `pin` once, blocking until done, returns millivolts.
+    #[inline]
+    pub fn read_millivolts<PIN: AdcPad<I>>(&mut self, pin: &mut PIN) -> u16 {
+        to_millivolts(self.read_blocking(pin))
+    }
+    /// Starts converting `channels` continuously.
+    #[inline]
+    pub fn start_continuous(&mut self, channels: Channels<I>) {
+        self.pending = None;
+        unsafe {
+            self.adc.as_ref().control.write(
+                Control::default()
+                    .set_channels(channels.0)
+                    .enable_continuous()
+                    .start(),
+            )
+        };
+    }
+    /// Latest valid raw value of the channel of `pin` in continuous conversion.
+    #[inline]
+    pub fn latest<PIN: AdcPad<I>>(&self, _pin: &PIN) -> Option<u16> {
+        let result = self.adc.as_ref().results[PIN::CHANNEL as usize - 1].read();
+        result.is_valid().then_some(result.value())
+    }
+    /// Stops any ongoing conversion.
+    #[inline]
+    pub fn stop(&mut self) {
+        self.pending = None;
+        unsafe { self.adc.as_ref().control.write(Control::default()) };
+    }
+    /// Interrupt source of this converter on the interrupt controller.
+    #[inline]
+    pub const fn interrupt(&self) -> Interrupt {
+        Interrupt::adc(I)
+    }
+    /// Checks if the converter is busy.
+    #[inline]
+    pub fn is_busy(&self) -> bool {
+        self.adc.as_ref().status.read() & 1 != 0
+    }
+    /// Enable conversion done interrupt.
+    #[inline]
+    pub fn listen(&mut self) {
+        unsafe { self.adc.as_ref().interrupt_enable.write(1) };
+    }
+    /// Disable conversion done interrupt.
+    #[inline]
+    pub fn unlisten(&mut self) {
+        unsafe { self.adc.as_ref().interrupt_enable.write(0) };
+    }
+    /// Clear conversion done interrupt.
+    #[inline]
+    pub fn clear_interrupt(&mut self) {
+        unsafe { self.adc.as_ref().interrupt_clear.write(1) };
+    }
+    /// Checks if conversion done is flagged, whether or not its interrupt is enabled.
+    #[inline]
+    fn is_done(&self) -> bool {
+        self.adc.as_ref().raw_interrupt_status.read() & 1 != 0
+    }
+    /// Release analog-to-digital converter and return its peripheral, turning its clocks off.
+    #[inline]
+    pub fn free(mut self) -> T
+    where
+        T: Controlled,
+    {
+        self.stop();
+        self.unlisten();
+        reset::disable(&self.adc);
+        self.adc
+    }
+}
+
+/// Set of channels of analog-to-digital converter `I` for continuous conversion.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct Channels<const I: usize>(u8);
+
+impl<const I: usize> Channels<I> {
+    /// Empty set of channels.
+    #[inline]
+    pub const fn new() -> Self {
+        Self(0)
+    }
+    /// Adds the channel of `pin` to this set.
+    #[inline]
+    pub fn with<PIN: AdcPad<I>>(self, _pin: &PIN) -> Self {
+        Self(self.0 | (1 << PIN::CHANNEL))
+    }
+}
+
+#[cfg(feature = "embedded-hal-02")]
+impl<T: AsRef<RegisterBlock>, const I: usize, PIN> embedded_hal_02::adc::OneShot<Self, u16, PIN>
+    for Adc<T, I>
+where
+    PIN: AdcPad<I> + embedded_hal_02::adc::Channel<Self, ID = u8>,
+{
+    type Error = Infallible;
+
+    #[inline]
+    fn read(&mut self, pin: &mut PIN) -> nb::Result<u16, Self::Error> {
+        Adc::read(self, pin)
+    }
+}
+
+/// Pads valid as input of analog-to-digital converter `I`.
+pub trait AdcPad<const I: usize> {
+    /// Converter channel of this pad, from 1 to 3.
+    const CHANNEL: u8;
+}
+
+macro_rules! adc_channel {
+    ($($i: expr, $n: expr => $c: expr;)+) => {
+        $(
+impl<T> AdcPad<$i> for Pad<T, $n, AdcFunc<$c>> {
+    const CHANNEL: u8 = $c;
+}
+#[cfg(feature = "embedded-hal-02")]
+impl<T, A> embedded_hal_02::adc::Channel<Adc<A, $i>> for Pad<T, $n, AdcFunc<$c>> {
+    type ID = u8;
+    #[inline]
+    fn channel() -> u8 {
+        $c
+    }
+}
+        )+
+    };
+}
+
+adc_channel! {
+    0, 45 => 1;
+    0, 44 => 2;
+    0, 43 => 3;
+    1, 48 => 1;
+    1, 49 => 2;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        to_millivolts, Adc, AdcPad, Channels, Control, ConversionResult, RegisterBlock, MAX_VALUE,
+    };
+    use core::cell::UnsafeCell;
+    use memoffset::offset_of;
+
+    struct Pin;
+
+    struct Sim(RegisterBlock);
+
+    impl AsRef<RegisterBlock> for Sim {
+        fn as_ref(&self) -> &RegisterBlock {
+            &self.0
+        }
+    }
+
+    impl AdcPad<0> for Pin {
+        const CHANNEL: u8 = 2;
+    }
+
+    #[test]
+    fn struct_register_block_offset() {
+        assert_eq!(offset_of!(RegisterBlock, control), 0x04);
+        assert_eq!(offset_of!(RegisterBlock, status), 0x08);
+        assert_eq!(offset_of!(RegisterBlock, cycle_setting), 0x0C);
+        assert_eq!(offset_of!(RegisterBlock, results), 0x14);
+        assert_eq!(offset_of!(RegisterBlock, interrupt_enable), 0x20);
+        assert_eq!(offset_of!(RegisterBlock, interrupt_clear), 0x24);
+        assert_eq!(offset_of!(RegisterBlock, interrupt_status), 0x28);
+        assert_eq!(offset_of!(RegisterBlock, raw_interrupt_status), 0x2C);
+    }
+
+    #[test]
+    fn struct_control_functions() {
+        let val = Control::default()
+            .set_channels(1 << 2)
+            .enable_continuous()
+            .start();
+        assert_eq!(val.0, 0x43);
+        assert_eq!(val.channels(), 1 << 2);
+        assert!(val.is_continuous_enabled());
+        let val = val.stop().disable_continuous().set_channels(0);
+        assert_eq!(val.0, 0);
+        let result = ConversionResult(0x8ABC);
+        assert!(result.is_valid());
+        assert_eq!(result.value(), 0xABC);
+    }
+
+    #[test]
+    fn millivolts_conversion() {
+        assert_eq!(to_millivolts(0), 0);
+        assert_eq!(to_millivolts(MAX_VALUE), 1800);
+        assert_eq!(to_millivolts(2048), 900);
+    }
+
+    #[test]
+    fn one_shot_read_selects_channel() {
+        let sim = Sim(unsafe { core::mem::zeroed() });
+        let regs = &sim.0;
+        let mut adc = Adc::<_, 0> {
+            adc: &sim,
+            pending: None,
+        };
+        assert_eq!(adc.read(&mut Pin), Err(nb::Error::WouldBlock));
+        let control = regs.control.read();
+        assert!(control.is_started());
+        assert_eq!(control.channels(), 1 << 2);
+        assert_eq!(adc.read(&mut Pin), Err(nb::Error::WouldBlock));
+        // registers are backed by `UnsafeCell`, thus writable behind shared references
+        let result = unsafe { &*(&regs.results[1] as *const _ as *const UnsafeCell<u32>) };
+        unsafe { result.get().write_volatile(0x8800) };
+        // valid result left from a previous conversion is not taken before done flag
+        assert_eq!(adc.read(&mut Pin), Err(nb::Error::WouldBlock));
+        let done = unsafe { &*(&regs.raw_interrupt_status as *const _ as *const UnsafeCell<u32>) };
+        unsafe { done.get().write_volatile(1) };
+        assert_eq!(adc.read(&mut Pin), Ok(0x800));
+        assert!(!regs.control.read().is_started());
+    }
+
+    #[test]
+    fn continuous_channels_from_pins() {
+        assert_eq!(Channels::<0>::new().with(&Pin).0, 1 << 2);
+    }
+}
@@ -0,0 +1,523 @@
+//! Internal audio codec.
+//!
+//! Microphone ADC sends samples to I2S-0, and line-out DAC receives samples from I2S-3.
+//! The codec is clocked by the I2S master clock, so link I2S controllers are configured
+//! with [`AudioCodec::link_config`] as bus masters.
+
+use crate::i2s::{self, Direction, Format, Role, SampleWidth};
+use crate::reset::{self, Controlled};
+use volatile_register::{RO, RW};
+
+/// Internal audio codec registers.
+#[repr(C)]
+pub struct RegisterBlock {
+    /// Line-out digital-to-analog converter registers.
+    pub dac: DacRegisters,
+    _reserved0: [u8; 0xDC],
+    /// Microphone analog-to-digital converter registers.
+    pub adc: AdcRegisters,
+}
+
+/// Line-out digital-to-analog converter registers.
+#[repr(C)]
+pub struct DacRegisters {
+    /// Control register.
+    pub control: RW<Control>,
+    /// Sample rate register.
+    pub sample_rate: RW<u32>,
+    /// Digital volume register.
+    pub volume: RW<Volume>,
+    /// Mute register; bit 0 mutes left channel, bit 1 mutes right channel.
+    pub mute: RW<u32>,
+    /// Analog power register.
+    pub analog: RW<Analog>,
+    _reserved0: [u8; 0xC],
+    /// Status register.
+    pub status: RO<Status>,
+}
+
+/// Microphone analog-to-digital converter registers.
+#[repr(C)]
+pub struct AdcRegisters {
+    /// Control register.
+    pub control: RW<Control>,
+    /// Sample rate register.
+    pub sample_rate: RW<u32>,
+    /// Programmable gain amplifier register.
+    pub gain: RW<Gain>,
+    /// Mute register; bit 0 mutes left channel, bit 1 mutes right channel.
+    pub mute: RW<u32>,
+    /// Analog power register.
+    pub analog: RW<Analog>,
+    _reserved0: [u8; 0xC],
+    /// Status register.
+    pub status: RO<Status>,
+}
+
+/// Converter control register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct Control(u32);
+
+impl Control {
+    const ENABLE: u32 = 1 << 0;
+    const CLOCK: u32 = 1 << 1;
+
+    /// Enable digital filter path.
+    #[inline]
+    pub const fn enable(self) -> Self {
+        Self(self.0 | Self::ENABLE)
+    }
+    /// Disable digital filter path.
+    #[inline]
+    pub const fn disable(self) -> Self {
+        Self(self.0 & !Self::ENABLE)
+    }
+    /// Check if digital filter path is enabled.
+    #[inline]
+    pub const fn is_enabled(self) -> bool {
+        self.0 & Self::ENABLE != 0
+    }
+    /// Gate on converter clock from I2S master clock.
+    #[inline]
+    pub const fn enable_clock(self) -> Self {
+        Self(self.0 | Self::CLOCK)
+    }
+    /// Gate off converter clock.
+    #[inline]
+    pub const fn disable_clock(self) -> Self {
+        Self(self.0 & !Self::CLOCK)
+    }
+    /// Check if converter clock is enabled.
+    #[inline]
+    pub const fn is_clock_enabled(self) -> bool {
+        self.0 & Self::CLOCK != 0
+    }
+}
+
+/// Analog power register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct Analog(u32);
+
+impl Analog {
+    const REFERENCE: u32 = 1 << 0;
+    const LEFT: u32 = 1 << 1;
+    const RIGHT: u32 = 1 << 2;
+    const MIC_BIAS: u32 = 1 << 3;
+
+    /// Power up reference voltage and both channels.
+    #[inline]
+    pub const fn power_up(self) -> Self {
+        Self(self.0 | Self::REFERENCE | Self::LEFT | Self::RIGHT)
+    }
+    /// Power down reference voltage and both channels.
+    #[inline]
+    pub const fn power_down(self) -> Self {
+        Self(self.0 & !(Self::REFERENCE | Self::LEFT | Self::RIGHT))
+    }
+    /// Check if reference voltage and both channels are powered up.
+    #[inline]
+    pub const fn is_powered_up(self) -> bool {
+        self.0 & (Self::REFERENCE | Self::LEFT | Self::RIGHT)
+            == Self::REFERENCE | Self::LEFT | Self::RIGHT
+    }
+    /// Supply bias voltage to electret microphones; only effective on ADC.
+    #[inline]
+    pub const fn enable_mic_bias(self) -> Self {
+        Self(self.0 | Self::MIC_BIAS)
+    }
+    /// Turn off microphone bias voltage.
+    #[inline]
+    pub const fn disable_mic_bias(self) -> Self {
+        Self(self.0 & !Self::MIC_BIAS)
+    }
+    /// Check if microphone bias voltage is enabled.
+    #[inline]
+    pub const fn is_mic_bias_enabled(self) -> bool {
+        self.0 & Self::MIC_BIAS != 0
+    }
+}
+
+/// Status register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct Status(u32);
+
+impl Status {
+    const READY: u32 = 1 << 0;
+
+    /// Check if analog circuits settled after power up.
+    #[inline]
+    pub const fn is_ready(self) -> bool {
+        self.0 & Self::READY != 0
+    }
+}
+
+/// DAC digital volume register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct Volume(u32);
+
+impl Volume {
+    const LEFT: u32 = 0xFF;
+    const RIGHT: u32 = 0xFF << 16;
+
+    /// Set attenuation code of both channels, in 0.5 dB steps from 0 dB.
+    #[inline]
+    pub const fn set_attenuation(self, code: u8) -> Self {
+        Self((self.0 & !(Self::LEFT | Self::RIGHT)) | code as u32 | ((code as u32) << 16))
+    }
+    /// Get attenuation code of left channel.
+    #[inline]
+    pub const fn left(self) -> u8 {
+        (self.0 & Self::LEFT) as u8
+    }
+    /// Get attenuation code of right channel.
+    #[inline]
+    pub const fn right(self) -> u8 {
+        ((self.0 & Self::RIGHT) >> 16) as u8
+    }
+}
+
+/// ADC programmable gain amplifier register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct Gain(u32);
+
+impl Gain {
+    const LEFT: u32 = 0x1F;
+    const RIGHT: u32 = 0x1F << 16;
+
+    /// Set gain code of both channels, in 2 dB steps from 0 dB.
+    #[inline]
+    pub const fn set_gain(self, code: u8) -> Self {
+        let code = (code as u32) & Self::LEFT;
+        Self((self.0 & !(Self::LEFT | Self::RIGHT)) | code | (code << 16))
+    }
+    /// Get gain code of left channel.
+    #[inline]
+    pub const fn left(self) -> u8 {
+        (self.0 & Self::LEFT) as u8
+    }
+    /// Get gain code of right channel.
+    #[inline]
+    pub const fn right(self) -> u8 {
+        ((self.0 & Self::RIGHT) >> 16) as u8
+    }
+}
+
+/// Sample rates supported by the codec.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SampleRate {
+    /// 8 kHz.
+    Hz8000 = 0,
+    /// 11.025 kHz.
+    Hz11025 = 1,
+    /// 16 kHz.
+    Hz16000 = 2,
+    /// 22.05 kHz.
+    Hz22050 = 3,
+    /// 32 kHz.
+    Hz32000 = 4,
+    /// 44.1 kHz.
+    Hz44100 = 5,
+    /// 48 kHz.
+    Hz48000 = 6,
+}
+
+impl SampleRate {
+    /// Frames per second.
+    #[inline]
+    pub const fn to_hz(self) -> u32 {
+        match self {
+            SampleRate::Hz8000 => 8_000,
+            SampleRate::Hz11025 => 11_025,
+            SampleRate::Hz16000 => 16_000,
+            SampleRate::Hz22050 => 22_050,
+            SampleRate::Hz32000 => 32_000,
+            SampleRate::Hz44100 => 44_100,
+            SampleRate::Hz48000 => 48_000,
+        }
+    }
+}
+
+/// Maximum microphone gain in decibels.
+pub const MAX_MIC_GAIN_DB: u8 = 48;
+
+/// Maximum line-out attenuation in decibels.
+pub const MAX_ATTENUATION_DB: u8 = 127;
+
+/// Gain amplifier code of `db` decibels, rounded down to a 2 dB step.
+#[inline]
+const fn mic_gain_code(db: u8) -> u8 {
+    let db = if db > MAX_MIC_GAIN_DB {
+        MAX_MIC_GAIN_DB
+    } else {
+        db
+    };
+    db / 2
+}
+
+/// Volume attenuation code of `db` decibels below full scale, in 0.5 dB steps.
+#[inline]
+const fn attenuation_code(db: u8) -> u8 {
+    let db = if db > MAX_ATTENUATION_DB {
+        MAX_ATTENUATION_DB
+    } else {
+        db
+    };
+    db * 2
+}
+
+/// Audio codec configuration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Config {
+    /// Sample rate of both converters.
+    pub sample_rate: SampleRate,
+    /// Microphone gain in decibels, from 0 to 48 in 2 dB steps.
+    pub mic_gain_db: u8,
+    /// Line-out attenuation in decibels below full scale.
+    pub attenuation_db: u8,
+}
+
+impl Default for Config {
+    /// Audio codec configuration defaults to 48 kHz, 24 dB microphone gain and full scale line-out.
+    #[inline]
+    fn default() -> Self {
+        Config {
+            sample_rate: SampleRate::Hz48000,
+            mic_gain_db: 24,
+            attenuation_db: 0,
+        }
+    }
+}
+
+/// Managed internal audio codec peripheral.
+pub struct AudioCodec<T> {
+    codec: T,
+    sample_rate: SampleRate,
+}
+
+/// Extension trait to create audio codec drivers from peripherals.
+pub trait AudioCodecExt: AsRef<RegisterBlock> + Controlled + Sized {
+    /// Creates an audio codec driver with both converters powered down and muted.
+    #[inline]
+    fn audio_codec(self, config: Config) -> AudioCodec<Self> {
+        reset::enable(&self);
+        let codec = self.as_ref();
+        unsafe {
+            codec.dac.control.write(Control::default());
+            codec.dac.analog.write(Analog::default());
+            codec.dac.mute.write(0b11);
+            codec.adc.control.write(Control::default());
+            codec.adc.analog.write(Analog::default());
+            codec.adc.mute.write(0b11);
+        }
+        let mut codec = AudioCodec {
+            codec: self,
+            sample_rate: config.sample_rate,
+        };
+        codec.set_sample_rate(config.sample_rate);
+        codec.set_mic_gain(config.mic_gain_db);
+        codec.set_attenuation(config.attenuation_db);
+        codec
+    }
+}
+
+impl<T: AsRef<RegisterBlock>> AudioCodec<T> {
+    /// Powers up microphone ADC with microphone bias and unmutes it.
+    ///
+    /// The I2S-0 controller must be running as in [`AudioCodec::link_config`].
+    #[inline]
+    pub fn power_up_adc(&mut self) {
+        let adc = &self.codec.as_ref().adc;
+        unsafe {
+            adc.control.write(Control::default().enable_clock());
+            adc.analog
+                .write(Analog::default().power_up().enable_mic_bias());
+            while !adc.status.read().is_ready() {
+                core::hint::spin_loop();
+            }
+            adc.control.modify(|w| w.enable());
+            adc.mute.write(0);
+        }
+    }
+    /// Mutes and powers down microphone ADC.
+    #[inline]
+    pub fn power_down_adc(&mut self) {
+        let adc = &self.codec.as_ref().adc;
+        unsafe {
+            adc.mute.write(0b11);
+            adc.control.write(Control::default());
+            adc.analog.write(Analog::default());
+        }
+    }
+    /// Powers up line-out DAC and unmutes it.
+    ///
+    /// The I2S-3 controller must be running as in [`AudioCodec::link_config`].
+    #[inline]
+    pub fn power_up_dac(&mut self) {
+        let dac = &self.codec.as_ref().dac;
+        unsafe {
+            dac.control.write(Control::default().enable_clock());
+            dac.analog.write(Analog::default().power_up());
+            while !dac.status.read().is_ready() {
+                core::hint::spin_loop();
+            }
+            dac.control.modify(|w| w.enable());
+            dac.mute.write(0);
+        }
+    }
+    /// Mutes and powers down line-out DAC.
+    #[inline]
+    pub fn power_down_dac(&mut self) {
+        let dac = &self.codec.as_ref().dac;
+        unsafe {
+            dac.mute.write(0b11);
+            dac.control.write(Control::default());
+            dac.analog.write(Analog::default());
+        }
+    }
+    /// Mutes or unmutes microphone ADC.
+    #[inline]
+    pub fn set_adc_mute(&mut self, mute: bool) {
+        unsafe {
+            self.codec
+                .as_ref()
+                .adc
+                .mute
+                .write(if mute { 0b11 } else { 0 })
+        };
+    }
+    /// Mutes or unmutes line-out DAC.
+    #[inline]
+    pub fn set_dac_mute(&mut self, mute: bool) {
+        unsafe {
+            self.codec
+                .as_ref()
+                .dac
+                .mute
+                .write(if mute { 0b11 } else { 0 })
+        };
+    }
+    /// Sets microphone gain in decibels, rounded down to 2 dB steps and limited to 48 dB.
+    #[inline]
+    pub fn set_mic_gain(&mut self, db: u8) {
+        let code = mic_gain_code(db);
+        unsafe { self.codec.as_ref().adc.gain.modify(|w| w.set_gain(code)) };
+    }
+    /// Microphone gain in decibels.
+    #[inline]
+    pub fn mic_gain(&self) -> u8 {
+        self.codec.as_ref().adc.gain.read().left() * 2
+    }
+    /// Sets line-out attenuation in decibels below full scale.
+    #[inline]
+    pub fn set_attenuation(&mut self, db: u8) {
+        let code = attenuation_code(db);
+        unsafe {
+            self.codec
+                .as_ref()
+                .dac
+                .volume
+                .modify(|w| w.set_attenuation(code))
+        };
+    }
+    /// Sets sample rate of both converters.
+    ///
+    /// Link I2S controllers must be reconfigured to the same rate.
+    #[inline]
+    pub fn set_sample_rate(&mut self, sample_rate: SampleRate) {
+        let codec = self.codec.as_ref();
+        unsafe {
+            codec.dac.sample_rate.write(sample_rate as u32);
+            codec.adc.sample_rate.write(sample_rate as u32);
+        }
+        self.sample_rate = sample_rate;
+    }
+    /// Sample rate of both converters.
+    #[inline]
+    pub fn sample_rate(&self) -> SampleRate {
+        self.sample_rate
+    }
+    /// I2S configuration of the controller linked to this codec in `direction`.
+    ///
+    /// Receive on I2S-0 to capture from microphone ADC, transmit on I2S-3 to play on line-out DAC.
+    #[inline]
+    pub fn link_config(&self, direction: Direction) -> i2s::Config {
+        link_config(direction, self.sample_rate)
+    }
+    /// Release codec instance and return its peripheral, powering both converters down.
+    #[inline]
+    pub fn free(mut self) -> T
+    where
+        T: Controlled,
+    {
+        self.power_down_adc();
+        self.power_down_dac();
+        reset::disable(&self.codec);
+        self.codec
+    }
+}
+
+/// I2S link format of the codec: 16-bit stereo in standard format with I2S as master.
+#[inline]
+const fn link_config(direction: Direction, sample_rate: SampleRate) -> i2s::Config {
+    i2s::Config {
+        direction,
+        role: Role::Master,
+        format: Format::Standard,
+        sample_width: SampleWidth::Bits16,
+        sample_rate: sample_rate.to_hz(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        attenuation_code, link_config, mic_gain_code, AdcRegisters, Analog, Gain, RegisterBlock,
+        SampleRate, Volume,
+    };
+    use crate::i2s::{Direction, Format, Role, SampleWidth};
+    use memoffset::offset_of;
+
+    #[test]
+    fn struct_register_block_offset() {
+        assert_eq!(offset_of!(RegisterBlock, dac), 0x000);
+        assert_eq!(offset_of!(RegisterBlock, adc), 0x100);
+        assert_eq!(offset_of!(AdcRegisters, gain), 0x08);
+        assert_eq!(offset_of!(AdcRegisters, analog), 0x10);
+        assert_eq!(offset_of!(AdcRegisters, status), 0x20);
+    }
+
+    #[test]
+    fn struct_gain_volume_functions() {
+        let val = Gain::default().set_gain(mic_gain_code(24));
+        assert_eq!(val.0, 0x000C_000C);
+        assert_eq!(val.left(), 12);
+        assert_eq!(val.right(), 12);
+        assert_eq!(mic_gain_code(25), 12);
+        assert_eq!(mic_gain_code(60), 24);
+
+        let val = Volume::default().set_attenuation(attenuation_code(6));
+        assert_eq!(val.0, 0x000C_000C);
+        assert_eq!(attenuation_code(200), 254);
+
+        let val = Analog::default().power_up().enable_mic_bias();
+        assert_eq!(val.0, 0xF);
+        assert!(val.is_powered_up());
+        assert!(!val.power_down().is_powered_up());
+        assert!(val.power_down().is_mic_bias_enabled());
+    }
+
+    #[test]
+    fn codec_link_config() {
+        let config = link_config(Direction::Receive, SampleRate::Hz16000);
+        assert_eq!(config.direction, Direction::Receive);
+        assert_eq!(config.role, Role::Master);
+        assert_eq!(config.format, Format::Standard);
+        assert_eq!(config.sample_width, SampleWidth::Bits16);
+        assert_eq!(config.sample_rate, 16_000);
+    }
+}
@@ -0,0 +1,229 @@
+//! Core Local Interruptor and machine timer.
+
+use core::{
+    ops::{Add, AddAssign, Sub},
+    time::Duration,
+};
+use embedded_hal::delay::DelayNs;
+use volatile_register::RW;
+
+/// Core Local Interruptor registers of the C906 core.
+#[repr(C)]
+pub struct RegisterBlock {
+    /// Machine software interrupt pending register.
+    pub msip: RW<u32>,
+    _reserved0: [u8; 0x3FFC],
+    /// Machine timer compare register, lower 32 bits.
+    pub mtimecmp_low: RW<u32>,
+    /// Machine timer compare register, higher 32 bits.
+    pub mtimecmp_high: RW<u32>,
+    _reserved1: [u8; 0x8FF8],
+    /// Supervisor timer compare register, lower 32 bits.
+    pub stimecmp_low: RW<u32>,
+    /// Supervisor timer compare register, higher 32 bits.
+    pub stimecmp_high: RW<u32>,
+}
+
+/// Frequency of the system counter behind the `time` CSR, in Hz.
+pub const FREQUENCY: u32 = 25_000_000;
+
+/// Reads the system counter from `time` CSR.
+#[inline]
+pub fn read_time() -> u64 {
+    #[cfg(target_arch = "riscv64")]
+    {
+        let time: u64;
+        unsafe { core::arch::asm!("csrr {}, time", out(reg) time) };
+        time
+    }
+    #[cfg(not(target_arch = "riscv64"))]
+    unimplemented!("`time` CSR is only available on RISC-V")
+}
+
+/// Converts system counter ticks into a duration.
+#[inline]
+const fn ticks_to_duration(ticks: u64) -> Duration {
+    let secs = ticks / FREQUENCY as u64;
+    let nanos = (ticks % FREQUENCY as u64) * 1_000_000_000 / FREQUENCY as u64;
+    Duration::new(secs, nanos as u32)
+}
+
+/// Converts a duration into system counter ticks, rounding up.
+#[inline]
+const fn duration_to_ticks(duration: Duration) -> u64 {
+    let nanos = duration.subsec_nanos() as u64 * FREQUENCY as u64;
+    duration.as_secs() * FREQUENCY as u64 + nanos.div_ceil(1_000_000_000)
+}
+
+/// A measurement of the monotonic system counter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Instant(u64);
+
+impl Instant {
+    /// Current value of the system counter.
+    #[inline]
+    pub fn now() -> Self {
+        Self(read_time())
+    }
+    /// Creates an instant from system counter ticks.
+    #[inline]
+    pub const fn from_ticks(ticks: u64) -> Self {
+        Self(ticks)
+    }
+    /// System counter ticks of this instant.
+    #[inline]
+    pub const fn ticks(self) -> u64 {
+        self.0
+    }
+    /// Duration elapsed since `earlier`, or zero if `earlier` is later than this instant.
+    #[inline]
+    pub const fn duration_since(self, earlier: Instant) -> Duration {
+        ticks_to_duration(self.0.saturating_sub(earlier.0))
+    }
+    /// Duration elapsed since this instant.
+    #[inline]
+    pub fn elapsed(self) -> Duration {
+        Self::now().duration_since(self)
+    }
+    /// Instant after `duration`, or `None` if it overflows.
+    #[inline]
+    pub const fn checked_add(self, duration: Duration) -> Option<Instant> {
+        match self.0.checked_add(duration_to_ticks(duration)) {
+            Some(ticks) => Some(Self(ticks)),
+            None => None,
+        }
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Instant;
+
+    #[inline]
+    fn add(self, rhs: Duration) -> Instant {
+        self.checked_add(rhs)
+            .expect("overflow when adding duration to instant")
+    }
+}
+
+impl AddAssign<Duration> for Instant {
+    #[inline]
+    fn add_assign(&mut self, rhs: Duration) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub<Instant> for Instant {
+    type Output = Duration;
+
+    #[inline]
+    fn sub(self, rhs: Instant) -> Duration {
+        self.duration_since(rhs)
+    }
+}
+
+/// Busy-wait delay on the system counter.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Delay;
+
+impl DelayNs for Delay {
+    #[inline]
+    fn delay_ns(&mut self, ns: u32) {
+        let deadline = Instant::now() + Duration::from_nanos(ns as u64);
+        while Instant::now() < deadline {
+            core::hint::spin_loop();
+        }
+    }
+}
+
+/// Managed Core Local Interruptor peripheral.
+pub struct Clint<T> {
+    clint: T,
+}
+
+/// Extension trait to create Core Local Interruptor drivers from peripherals.
+pub trait ClintExt: AsRef<RegisterBlock> + Sized {
+    /// Creates a Core Local Interruptor driver, with no timer alarm pending.
+    #[inline]
+    fn clint(self) -> Clint<Self> {
+        let mut clint = Clint { clint: self };
+        clint.clear_alarm();
+        clint
+    }
+}
+
+impl<T: AsRef<RegisterBlock>> Clint<T> {
+    /// Raises machine timer interrupt once the system counter reaches `at`.
+    #[inline]
+    pub fn set_alarm(&mut self, at: Instant) {
+        let clint = self.clint.as_ref();
+        // prevent a spurious match while only one half is written
+        unsafe {
+            clint.mtimecmp_high.write(u32::MAX);
+            clint.mtimecmp_low.write(at.0 as u32);
+            clint.mtimecmp_high.write((at.0 >> 32) as u32);
+        }
+    }
+    /// Current machine timer alarm.
+    #[inline]
+    pub fn alarm(&self) -> Instant {
+        let clint = self.clint.as_ref();
+        let high = clint.mtimecmp_high.read() as u64;
+        let low = clint.mtimecmp_low.read() as u64;
+        Instant((high << 32) | low)
+    }
+    /// Disarms machine timer alarm, clearing its interrupt.
+    #[inline]
+    pub fn clear_alarm(&mut self) {
+        let clint = self.clint.as_ref();
+        unsafe {
+            clint.mtimecmp_high.write(u32::MAX);
+            clint.mtimecmp_low.write(u32::MAX);
+        }
+    }
+    /// Raises machine software interrupt.
+    #[inline]
+    pub fn set_software_interrupt(&mut self) {
+        unsafe { self.clint.as_ref().msip.write(1) };
+    }
+    /// Clears machine software interrupt.
+    #[inline]
+    pub fn clear_software_interrupt(&mut self) {
+        unsafe { self.clint.as_ref().msip.write(0) };
+    }
+    /// Release Core Local Interruptor driver and return its peripheral.
+    #[inline]
+    pub fn free(mut self) -> T {
+        self.clear_alarm();
+        self.clint
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{duration_to_ticks, ticks_to_duration, Instant, RegisterBlock};
+    use core::time::Duration;
+    use memoffset::offset_of;
+
+    #[test]
+    fn struct_register_block_offset() {
+        assert_eq!(offset_of!(RegisterBlock, msip), 0x0000);
+        assert_eq!(offset_of!(RegisterBlock, mtimecmp_low), 0x4000);
+        assert_eq!(offset_of!(RegisterBlock, mtimecmp_high), 0x4004);
+        assert_eq!(offset_of!(RegisterBlock, stimecmp_low), 0xD000);
+        assert_eq!(offset_of!(RegisterBlock, stimecmp_high), 0xD004);
+    }
+
+    #[test]
+    fn ticks_duration_conversion() {
+        assert_eq!(ticks_to_duration(25_000_000), Duration::from_secs(1));
+        assert_eq!(ticks_to_duration(25), Duration::from_micros(1));
+        assert_eq!(ticks_to_duration(1), Duration::from_nanos(40));
+        assert_eq!(duration_to_ticks(Duration::from_millis(1500)), 37_500_000);
+        assert_eq!(duration_to_ticks(Duration::from_nanos(1)), 1);
+        let start = Instant::from_ticks(100);
+        let end = start + Duration::from_micros(2);
+        assert_eq!(end.ticks(), 150);
+        assert_eq!(end - start, Duration::from_micros(2));
+        assert_eq!(start - end, Duration::ZERO);
+    }
+}
@@ -0,0 +1,686 @@
+//! Clock generator, phase-locked loops and peripheral clocks.
+
+use volatile_register::{RO, RW};
+
+/// Frequency in Hertz.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Hertz(pub u32);
+
+impl Hertz {
+    /// Frequency from Hertz.
+    #[inline]
+    pub const fn from_hz(hz: u32) -> Self {
+        Self(hz)
+    }
+    /// Frequency from kilohertz.
+    #[inline]
+    pub const fn from_khz(khz: u32) -> Self {
+        Self(khz * 1_000)
+    }
+    /// Frequency from megahertz.
+    #[inline]
+    pub const fn from_mhz(mhz: u32) -> Self {
+        Self(mhz * 1_000_000)
+    }
+    /// Frequency in Hertz.
+    #[inline]
+    pub const fn to_hz(self) -> u32 {
+        self.0
+    }
+}
+
+/// Frequency of the external crystal oscillator.
+pub const XTAL: Hertz = Hertz::from_mhz(25);
+
+/// Hardware default divider from `Fpll` to the PWM source clock `clk_pwm_src`.
+const PWM_SOURCE_DIVIDER: u32 = 10;
+
+/// Clock generator registers.
+#[repr(C)]
+pub struct RegisterBlock {
+    /// Clock gate enable registers.
+    pub clock_enable: [RW<u32>; 5],
+    _reserved0: [u8; 0x1C],
+    /// Clock bypass registers; a set bit feeds a clock from crystal oscillator instead of PLL.
+    pub clock_bypass: [RW<u32>; 2],
+    _reserved1: [u8; 0x38],
+    /// SDIO-0 card clock divider.
+    pub div_sd0: RW<Divider>,
+    _reserved2: [u8; 0x8],
+    /// SDIO-1 card clock divider.
+    pub div_sd1: RW<Divider>,
+    _reserved3: [u8; 0x80],
+    /// SPI controller clock divider.
+    pub div_spi: RW<Divider>,
+    /// I2C controller clock divider.
+    pub div_i2c: RW<Divider>,
+    _reserved4: [u8; 0x6F8],
+    /// PLL group 2 power control register.
+    pub pll_g2_control: RW<u32>,
+    /// PLL group 2 status register.
+    pub pll_g2_status: RO<u32>,
+    /// MIPI PLL control and status register.
+    pub mipimpll: RW<PllCsr>,
+    /// Audio PLL control and status register.
+    pub apll: RW<PllCsr>,
+    /// Display PLL control and status register.
+    pub disppll: RW<PllCsr>,
+    /// Camera 0 PLL control and status register.
+    pub cam0pll: RW<PllCsr>,
+    /// Camera 1 PLL control and status register.
+    pub cam1pll: RW<PllCsr>,
+    _reserved5: [u8; 0xE4],
+    /// PLL group 6 power control register.
+    pub pll_g6_control: RW<u32>,
+    /// PLL group 6 status register.
+    pub pll_g6_status: RO<u32>,
+    /// Main PLL control and status register.
+    pub mpll: RW<PllCsr>,
+    /// TPU PLL control and status register.
+    pub tpll: RW<PllCsr>,
+    /// Fixed PLL control and status register.
+    pub fpll: RW<PllCsr>,
+}
+
+/// PLL control and status register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct PllCsr(u32);
+
+impl PllCsr {
+    const PRE_DIV: u32 = 0x7F;
+    const POST_DIV: u32 = 0x7F << 8;
+    const MODE: u32 = 0x3 << 15;
+    const DIV: u32 = 0x7F << 17;
+    const ICTRL: u32 = 0x7 << 24;
+
+    /// Set reference clock pre-divider.
+    #[inline]
+    pub const fn set_pre_divider(self, val: u8) -> Self {
+        Self((self.0 & !Self::PRE_DIV) | (val as u32 & Self::PRE_DIV))
+    }
+    /// Get reference clock pre-divider.
+    #[inline]
+    pub const fn pre_divider(self) -> u8 {
+        (self.0 & Self::PRE_DIV) as u8
+    }
+    /// Set output post-divider.
+    #[inline]
+    pub const fn set_post_divider(self, val: u8) -> Self {
+        Self((self.0 & !Self::POST_DIV) | (((val as u32) << 8) & Self::POST_DIV))
+    }
+    /// Get output post-divider.
+    #[inline]
+    pub const fn post_divider(self) -> u8 {
+        ((self.0 & Self::POST_DIV) >> 8) as u8
+    }
+    /// Set loop mode.
+    #[inline]
+    pub const fn set_mode(self, val: u8) -> Self {
+        Self((self.0 & !Self::MODE) | (((val as u32) << 15) & Self::MODE))
+    }
+    /// Get loop mode.
+    #[inline]
+    pub const fn mode(self) -> u8 {
+        ((self.0 & Self::MODE) >> 15) as u8
+    }
+    /// Set feedback multiplier.
+    #[inline]
+    pub const fn set_multiplier(self, val: u8) -> Self {
+        Self((self.0 & !Self::DIV) | (((val as u32) << 17) & Self::DIV))
+    }
+    /// Get feedback multiplier.
+    #[inline]
+    pub const fn multiplier(self) -> u8 {
+        ((self.0 & Self::DIV) >> 17) as u8
+    }
+    /// Set charge pump current control.
+    #[inline]
+    pub const fn set_current_control(self, val: u8) -> Self {
+        Self((self.0 & !Self::ICTRL) | (((val as u32) << 24) & Self::ICTRL))
+    }
+    /// Get charge pump current control.
+    #[inline]
+    pub const fn current_control(self) -> u8 {
+        ((self.0 & Self::ICTRL) >> 24) as u8
+    }
+    /// Output frequency with given reference clock; zero dividers count as one.
+    #[inline]
+    pub const fn frequency(self, reference: Hertz) -> Hertz {
+        let pre = if self.pre_divider() == 0 {
+            1
+        } else {
+            self.pre_divider() as u64
+        };
+        let post = if self.post_divider() == 0 {
+            1
+        } else {
+            self.post_divider() as u64
+        };
+        Hertz((reference.0 as u64 * self.multiplier() as u64 / pre / post) as u32)
+    }
+}
+
+/// Peripheral clock divider register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct Divider(u32);
+
+impl Divider {
+    const RESET_DEASSERT: u32 = 1 << 0;
+    const CUSTOM_DIVIDER: u32 = 1 << 3;
+    const DIVIDER: u32 = 0x1F << 16;
+
+    /// Release divider from reset.
+    #[inline]
+    pub const fn enable(self) -> Self {
+        Self(self.0 | Self::RESET_DEASSERT)
+    }
+    /// Hold divider in reset.
+    #[inline]
+    pub const fn disable(self) -> Self {
+        Self(self.0 & !Self::RESET_DEASSERT)
+    }
+    /// Check if divider is released from reset.
+    #[inline]
+    pub const fn is_enabled(self) -> bool {
+        self.0 & Self::RESET_DEASSERT != 0
+    }
+    /// Set divider value and use it instead of the hardware default.
+    #[inline]
+    pub const fn set_divider(self, val: u8) -> Self {
+        Self(
+            (self.0 & !Self::DIVIDER)
+                | (((val as u32) << 16) & Self::DIVIDER)
+                | Self::CUSTOM_DIVIDER,
+        )
+    }
+    /// Get divider value.
+    #[inline]
+    pub const fn divider(self) -> u8 {
+        ((self.0 & Self::DIVIDER) >> 16) as u8
+    }
+    /// Check if divider value is set by software.
+    #[inline]
+    pub const fn is_custom_divider(self) -> bool {
+        self.0 & Self::CUSTOM_DIVIDER != 0
+    }
+}
+
+/// Phase-locked loops.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pll {
+    /// Main PLL.
+    Mpll,
+    /// TPU PLL.
+    Tpll,
+    /// Fixed PLL, source of most peripheral clocks.
+    Fpll,
+    /// MIPI PLL.
+    MipiMpll,
+    /// Audio PLL.
+    Apll,
+    /// Display PLL.
+    DispPll,
+    /// Camera 0 PLL.
+    Cam0Pll,
+    /// Camera 1 PLL.
+    Cam1Pll,
+}
+
+/// Peripheral clocks derived from `Fpll` by a divider.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Divided {
+    /// I2C controller clock.
+    I2c,
+    /// SPI controller clock.
+    Spi,
+    /// SDIO-0 card clock.
+    Sd0,
+    /// SDIO-1 card clock.
+    Sd1,
+}
+
+impl Divided {
+    /// Bit in `clock_bypass[0]` selecting crystal oscillator for this clock.
+    #[inline]
+    const fn bypass_bit(self) -> u32 {
+        match self {
+            Divided::Sd0 => 1 << 19,
+            Divided::Sd1 => 1 << 22,
+            Divided::Spi => 1 << 30,
+            Divided::I2c => 1 << 31,
+        }
+    }
+}
+
+/// Peripheral clock gates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Gate {
+    /// SAR ADC clock `clk_saradc`.
+    Saradc,
+    /// SDIO-0 AXI bus clock `clk_axi4_sd0`.
+    AxiSd0,
+    /// SDIO-0 card clock `clk_sd0`.
+    Sd0,
+    /// SDIO-0 100 kHz card detection clock `clk_100k_sd0`.
+    Sd0Slow,
+    /// SDIO-1 AXI bus clock `clk_axi4_sd1`.
+    AxiSd1,
+    /// SDIO-1 card clock `clk_sd1`.
+    Sd1,
+    /// SDIO-1 100 kHz card detection clock `clk_100k_sd1`.
+    Sd1Slow,
+    /// SPI NAND controller clock `clk_spi_nand`.
+    SpiNand,
+    /// SPI NOR flash controller AHB clock `clk_ahb_sf`.
+    AhbSpiNor,
+    /// DMA controller AXI bus clock `clk_sdma_axi`.
+    AxiDma,
+    /// I2S-0 master clock `clk_sdma_aud0`.
+    I2s0,
+    /// I2S-1 master clock `clk_sdma_aud1`.
+    I2s1,
+    /// I2S-2 master clock `clk_sdma_aud2`.
+    I2s2,
+    /// I2S-3 master clock `clk_sdma_aud3`.
+    I2s3,
+    /// I2C APB bus clock `clk_apb_i2c`, shared by all I2C controllers.
+    ApbI2c,
+    /// Watchdog APB bus clock `clk_apb_wdt`.
+    ApbWatchdog,
+    /// PWM clock `clk_pwm`, shared by all PWM controllers.
+    Pwm,
+    /// SPI-0 APB bus clock `clk_apb_spi0`.
+    ApbSpi0,
+    /// SPI-1 APB bus clock `clk_apb_spi1`.
+    ApbSpi1,
+    /// SPI-2 APB bus clock `clk_apb_spi2`.
+    ApbSpi2,
+    /// SPI-3 APB bus clock `clk_apb_spi3`.
+    ApbSpi3,
+    /// UART-0 function clock `clk_uart0`.
+    Uart0,
+    /// UART-0 APB bus clock `clk_apb_uart0`.
+    ApbUart0,
+    /// UART-1 function clock `clk_uart1`.
+    Uart1,
+    /// UART-1 APB bus clock `clk_apb_uart1`.
+    ApbUart1,
+    /// UART-2 function clock `clk_uart2`.
+    Uart2,
+    /// UART-2 APB bus clock `clk_apb_uart2`.
+    ApbUart2,
+    /// UART-3 function clock `clk_uart3`.
+    Uart3,
+    /// UART-3 APB bus clock `clk_apb_uart3`.
+    ApbUart3,
+    /// UART-4 function clock `clk_uart4`.
+    Uart4,
+    /// UART-4 APB bus clock `clk_apb_uart4`.
+    ApbUart4,
+    /// I2S-0 APB bus clock `clk_apb_i2s0`.
+    ApbI2s0,
+    /// I2S-1 APB bus clock `clk_apb_i2s1`.
+    ApbI2s1,
+    /// I2S-2 APB bus clock `clk_apb_i2s2`.
+    ApbI2s2,
+    /// I2S-3 APB bus clock `clk_apb_i2s3`.
+    ApbI2s3,
+    /// USB AXI bus clock `clk_axi4_usb`.
+    AxiUsb,
+    /// USB APB bus clock `clk_apb_usb`.
+    ApbUsb,
+    /// USB 125 MHz controller clock `clk_usb_125m`.
+    Usb125m,
+    /// USB 33 kHz suspend clock `clk_usb_33k`.
+    Usb33k,
+    /// USB 12 MHz PHY reference clock `clk_usb_12m`.
+    Usb12m,
+    /// SPI function clock `clk_spi`, shared by all SPI controllers.
+    Spi,
+    /// I2C function clock `clk_i2c`, shared by all I2C controllers.
+    I2c,
+}
+
+impl Gate {
+    /// Index of `clock_enable` register and bit of this gate.
+    #[inline]
+    pub const fn position(self) -> (usize, u32) {
+        match self {
+            Gate::Saradc => (0, 10),
+            Gate::AxiSd0 => (0, 18),
+            Gate::Sd0 => (0, 19),
+            Gate::Sd0Slow => (0, 20),
+            Gate::AxiSd1 => (0, 21),
+            Gate::Sd1 => (0, 22),
+            Gate::Sd1Slow => (0, 23),
+            Gate::SpiNand => (0, 24),
+            Gate::AhbSpiNor => (1, 0),
+            Gate::AxiDma => (1, 1),
+            Gate::I2s0 => (1, 2),
+            Gate::I2s1 => (1, 3),
+            Gate::I2s2 => (1, 4),
+            Gate::I2s3 => (1, 5),
+            Gate::ApbI2c => (1, 6),
+            Gate::ApbWatchdog => (1, 7),
+            Gate::Pwm => (1, 8),
+            Gate::ApbSpi0 => (1, 9),
+            Gate::ApbSpi1 => (1, 10),
+            Gate::ApbSpi2 => (1, 11),
+            Gate::ApbSpi3 => (1, 12),
+            Gate::Uart0 => (1, 14),
+            Gate::ApbUart0 => (1, 15),
+            Gate::Uart1 => (1, 16),
+            Gate::ApbUart1 => (1, 17),
+            Gate::Uart2 => (1, 18),
+            Gate::ApbUart2 => (1, 19),
+            Gate::Uart3 => (1, 20),
+            Gate::ApbUart3 => (1, 21),
+            Gate::Uart4 => (1, 22),
+            Gate::ApbUart4 => (1, 23),
+            Gate::ApbI2s0 => (1, 24),
+            Gate::ApbI2s1 => (1, 25),
+            Gate::ApbI2s2 => (1, 26),
+            Gate::ApbI2s3 => (1, 27),
+            Gate::AxiUsb => (1, 28),
+            Gate::ApbUsb => (1, 29),
+            Gate::Usb125m => (1, 30),
+            Gate::Usb33k => (1, 31),
+            Gate::Usb12m => (2, 0),
+            Gate::Spi => (3, 6),
+            Gate::I2c => (3, 7),
+        }
+    }
+    /// Checks if this gate feeds more than one peripheral instance.
+    ///
+    /// Shared gates are turned on with any of their peripherals and left on when one is released.
+    #[inline]
+    pub const fn is_shared(self) -> bool {
+        matches!(self, Gate::ApbI2c | Gate::I2c | Gate::Spi | Gate::Pwm)
+    }
+}
+
+/// Enables or disables a peripheral clock gate.
+///
+/// Gate registers are shared by all peripheral drivers, so the update runs in a critical section.
+#[inline]
+pub(crate) fn set_gate(clock_gen: &RegisterBlock, gate: Gate, enable: bool) {
+    let (n, bit) = gate.position();
+    critical_section::with(|_| unsafe {
+        clock_gen.clock_enable[n].modify(|w| {
+            if enable {
+                w | (1 << bit)
+            } else {
+                w & !(1 << bit)
+            }
+        })
+    });
+}
+
+/// Frozen clock frequencies.
+///
+/// Obtained from `ClockGen::freeze`, after which clock settings no longer change.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Clocks {
+    mpll: Hertz,
+    tpll: Hertz,
+    fpll: Hertz,
+    apll: Hertz,
+    i2c: Hertz,
+    spi: Hertz,
+    pwm: Hertz,
+    sd0: Hertz,
+    sd1: Hertz,
+}
+
+impl Clocks {
+    /// Crystal oscillator frequency.
+    #[inline]
+    pub const fn xtal(&self) -> Hertz {
+        XTAL
+    }
+    /// Main PLL frequency.
+    #[inline]
+    pub const fn mpll(&self) -> Hertz {
+        self.mpll
+    }
+    /// TPU PLL frequency.
+    #[inline]
+    pub const fn tpll(&self) -> Hertz {
+        self.tpll
+    }
+    /// Fixed PLL frequency.
+    #[inline]
+    pub const fn fpll(&self) -> Hertz {
+        self.fpll
+    }
+    /// Audio PLL frequency, source of I2S master clocks.
+    #[inline]
+    pub const fn apll(&self) -> Hertz {
+        self.apll
+    }
+    /// I2C controller clock frequency.
+    #[inline]
+    pub const fn i2c(&self) -> Hertz {
+        self.i2c
+    }
+    /// SPI controller clock frequency.
+    #[inline]
+    pub const fn spi(&self) -> Hertz {
+        self.spi
+    }
+    /// PWM controller clock frequency.
+    #[inline]
+    pub const fn pwm(&self) -> Hertz {
+        self.pwm
+    }
+    /// UART function clock frequency; `clk_uart*` run from crystal oscillator.
+    #[inline]
+    pub const fn uart(&self) -> Hertz {
+        XTAL
+    }
+    /// SDIO-0 controller clock frequency.
+    #[inline]
+    pub const fn sd0(&self) -> Hertz {
+        self.sd0
+    }
+    /// SDIO-1 controller clock frequency.
+    #[inline]
+    pub const fn sd1(&self) -> Hertz {
+        self.sd1
+    }
+    /// Hardware timer counter clock frequency.
+    #[inline]
+    pub const fn timer(&self) -> Hertz {
+        XTAL
+    }
+    /// Watchdog counter clock frequency.
+    #[inline]
+    pub const fn watchdog(&self) -> Hertz {
+        XTAL
+    }
+}
+
+/// Managed clock generator peripheral.
+pub struct ClockGen<T> {
+    clock_gen: T,
+}
+
+/// Extension trait to create clock generator drivers from peripherals.
+pub trait ClockGenExt: AsRef<RegisterBlock> + Sized {
+    /// Creates a clock generator driver, keeping current settings.
+    #[inline]
+    fn clock_gen(self) -> ClockGen<Self> {
+        ClockGen { clock_gen: self }
+    }
+}
+
+impl<T: AsRef<RegisterBlock>> ClockGen<T> {
+    #[inline]
+    fn pll_csr(&self, pll: Pll) -> &RW<PllCsr> {
+        let regs = self.clock_gen.as_ref();
+        match pll {
+            Pll::Mpll => &regs.mpll,
+            Pll::Tpll => &regs.tpll,
+            Pll::Fpll => &regs.fpll,
+            Pll::MipiMpll => &regs.mipimpll,
+            Pll::Apll => &regs.apll,
+            Pll::DispPll => &regs.disppll,
+            Pll::Cam0Pll => &regs.cam0pll,
+            Pll::Cam1Pll => &regs.cam1pll,
+        }
+    }
+    /// Status register and update-in-progress bit of a PLL.
+    #[inline]
+    fn pll_status(&self, pll: Pll) -> (&RO<u32>, u32) {
+        let regs = self.clock_gen.as_ref();
+        match pll {
+            Pll::Mpll => (&regs.pll_g6_status, 1 << 0),
+            Pll::Tpll => (&regs.pll_g6_status, 1 << 1),
+            Pll::Fpll => (&regs.pll_g6_status, 1 << 2),
+            Pll::MipiMpll => (&regs.pll_g2_status, 1 << 0),
+            Pll::Apll => (&regs.pll_g2_status, 1 << 1),
+            Pll::DispPll => (&regs.pll_g2_status, 1 << 2),
+            Pll::Cam0Pll => (&regs.pll_g2_status, 1 << 3),
+            Pll::Cam1Pll => (&regs.pll_g2_status, 1 << 4),
+        }
+    }
+    /// Output frequency of a PLL.
+    #[inline]
+    pub fn pll_frequency(&self, pll: Pll) -> Hertz {
+        self.pll_csr(pll).read().frequency(XTAL)
+    }
+    /// Reprograms a PLL and waits until the new setting is applied.
+    ///
+    /// Clocks derived from this PLL change accordingly; reprogram before `freeze`.
+    #[inline]
+    pub fn set_pll(&mut self, pll: Pll, csr: PllCsr) {
+        unsafe { self.pll_csr(pll).write(csr) };
+        let (status, busy) = self.pll_status(pll);
+        while status.read() & busy != 0 {
+            core::hint::spin_loop();
+        }
+    }
+    #[inline]
+    fn divider(&self, clock: Divided) -> &RW<Divider> {
+        let regs = self.clock_gen.as_ref();
+        match clock {
+            Divided::I2c => &regs.div_i2c,
+            Divided::Spi => &regs.div_spi,
+            Divided::Sd0 => &regs.div_sd0,
+            Divided::Sd1 => &regs.div_sd1,
+        }
+    }
+    /// Feeds a peripheral clock from `Fpll` divided by `divider`.
+    #[inline]
+    pub fn set_divider(&mut self, clock: Divided, divider: u8) {
+        let regs = self.clock_gen.as_ref();
+        unsafe {
+            self.divider(clock)
+                .modify(|w| w.set_divider(divider).enable());
+            regs.clock_bypass[0].modify(|w| w & !clock.bypass_bit());
+        }
+    }
+    /// Feeds a peripheral clock directly from crystal oscillator.
+    #[inline]
+    pub fn set_bypass(&mut self, clock: Divided) {
+        let regs = self.clock_gen.as_ref();
+        unsafe { regs.clock_bypass[0].modify(|w| w | clock.bypass_bit()) };
+    }
+    /// Frequency of a peripheral clock.
+    #[inline]
+    pub fn frequency(&self, clock: Divided) -> Hertz {
+        let regs = self.clock_gen.as_ref();
+        if regs.clock_bypass[0].read() & clock.bypass_bit() != 0 {
+            return XTAL;
+        }
+        let divider = self.divider(clock).read().divider().max(1);
+        Hertz(self.pll_frequency(Pll::Fpll).0 / divider as u32)
+    }
+    /// Enables a peripheral clock gate.
+    #[inline]
+    pub fn enable_gate(&mut self, gate: Gate) {
+        set_gate(self.clock_gen.as_ref(), gate, true);
+    }
+    /// Disables a peripheral clock gate.
+    #[inline]
+    pub fn disable_gate(&mut self, gate: Gate) {
+        set_gate(self.clock_gen.as_ref(), gate, false);
+    }
+    /// Checks if a peripheral clock gate is enabled.
+    #[inline]
+    pub fn is_gate_enabled(&self, gate: Gate) -> bool {
+        let (n, bit) = gate.position();
+        self.clock_gen.as_ref().clock_enable[n].read() & (1 << bit) != 0
+    }
+    /// Freezes clock settings and returns resulting frequencies.
+    #[inline]
+    pub fn freeze(self) -> Clocks {
+        Clocks {
+            mpll: self.pll_frequency(Pll::Mpll),
+            tpll: self.pll_frequency(Pll::Tpll),
+            fpll: self.pll_frequency(Pll::Fpll),
+            apll: self.pll_frequency(Pll::Apll),
+            i2c: self.frequency(Divided::I2c),
+            spi: self.frequency(Divided::Spi),
+            pwm: Hertz(self.pll_frequency(Pll::Fpll).0 / PWM_SOURCE_DIVIDER),
+            sd0: self.frequency(Divided::Sd0),
+            sd1: self.frequency(Divided::Sd1),
+        }
+    }
+    /// Release clock generator driver and return its peripheral.
+    #[inline]
+    pub fn free(self) -> T {
+        self.clock_gen
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Divider, Hertz, PllCsr, RegisterBlock, XTAL};
+    use memoffset::offset_of;
+
+    #[test]
+    fn struct_register_block_offset() {
+        assert_eq!(offset_of!(RegisterBlock, clock_enable), 0x000);
+        assert_eq!(offset_of!(RegisterBlock, clock_bypass), 0x030);
+        assert_eq!(offset_of!(RegisterBlock, div_sd0), 0x070);
+        assert_eq!(offset_of!(RegisterBlock, div_sd1), 0x07C);
+        assert_eq!(offset_of!(RegisterBlock, div_spi), 0x100);
+        assert_eq!(offset_of!(RegisterBlock, div_i2c), 0x104);
+        assert_eq!(offset_of!(RegisterBlock, pll_g2_control), 0x800);
+        assert_eq!(offset_of!(RegisterBlock, pll_g2_status), 0x804);
+        assert_eq!(offset_of!(RegisterBlock, mipimpll), 0x808);
+        assert_eq!(offset_of!(RegisterBlock, cam1pll), 0x818);
+        assert_eq!(offset_of!(RegisterBlock, pll_g6_control), 0x900);
+        assert_eq!(offset_of!(RegisterBlock, pll_g6_status), 0x904);
+        assert_eq!(offset_of!(RegisterBlock, mpll), 0x908);
+        assert_eq!(offset_of!(RegisterBlock, tpll), 0x90C);
+        assert_eq!(offset_of!(RegisterBlock, fpll), 0x910);
+    }
+
+    #[test]
+    fn struct_pll_csr_functions() {
+        let val = PllCsr::default()
+            .set_pre_divider(1)
+            .set_post_divider(1)
+            .set_mode(1)
+            .set_multiplier(60)
+            .set_current_control(3);
+        assert_eq!(val.0, 0x0378_8101);
+        assert_eq!(val.multiplier(), 60);
+        assert_eq!(val.frequency(XTAL), Hertz::from_mhz(1500));
+        let val = val.set_pre_divider(0).set_post_divider(2);
+        assert_eq!(val.frequency(XTAL), Hertz::from_mhz(750));
+    }
+
+    #[test]
+    fn struct_divider_functions() {
+        let val = Divider::default().set_divider(15).enable();
+        assert_eq!(val.0, 0x000F_0009);
+        assert_eq!(val.divider(), 15);
+        assert!(val.is_custom_divider());
+        assert!(!val.disable().is_enabled());
+    }
+}
@@ -0,0 +1,1583 @@
+//! System direct memory access controller.
+//!
+//! Channels are allocated from a [`Dma`] driver and consumed by transfers, which take
+//! ownership of their buffers until completion. Peripheral handshakes are routed to
+//! channels through the channel remap registers, so any channel can serve any request.
+
+use crate::reset::{self, Controlled};
+use atomic_waker::AtomicWaker;
+use core::{
+    cell::Cell,
+    future::poll_fn,
+    marker::PhantomData,
+    mem::size_of,
+    ptr::null_mut,
+    sync::atomic::{compiler_fence, AtomicPtr, Ordering},
+    task::Poll,
+};
+use embedded_dma::{ReadBuffer, WriteBuffer};
+use volatile_register::{RO, RW, WO};
+
+/// Number of channels of the system DMA controller.
+pub const CHANNELS: usize = 8;
+
+/// Maximum number of words in one block.
+pub const MAX_BLOCK_SIZE: usize = 65536;
+
+/// System DMA controller registers.
+#[repr(C)]
+pub struct RegisterBlock {
+    /// Identification register.
+    pub id: RO<u64>,
+    /// Component version register.
+    pub version: RO<u64>,
+    /// Global configuration register.
+    pub config: RW<GlobalConfig>,
+    /// Channel enable register.
+    pub channel_enable: RW<ChannelEnable>,
+    _reserved0: [u8; 0x10],
+    /// Combined interrupt status register; bit `n` for channel `n`, bit 16 for common interrupts.
+    pub interrupt_status: RO<u64>,
+    /// Common interrupt clear register.
+    pub common_interrupt_clear: WO<u64>,
+    /// Common interrupt status enable register.
+    pub common_interrupt_status_enable: RW<u64>,
+    /// Common interrupt signal enable register.
+    pub common_interrupt_signal_enable: RW<u64>,
+    /// Common interrupt status register.
+    pub common_interrupt_status: RO<u64>,
+    /// Software reset register; bit 0 resets the controller and clears when done.
+    pub reset: RW<u64>,
+    _reserved1: [u8; 0xA0],
+    /// Channel registers.
+    pub channels: [ChannelRegisters; CHANNELS],
+}
+
+/// Registers of one DMA channel.
+#[repr(C)]
+pub struct ChannelRegisters {
+    /// Source address register.
+    pub source: RW<u64>,
+    /// Destination address register.
+    pub destination: RW<u64>,
+    /// Block transfer size register, number of source words minus one.
+    pub block_size: RW<u64>,
+    /// Control register.
+    pub control: RW<Control>,
+    /// Configuration register.
+    pub config: RW<ChannelConfig>,
+    /// Linked list pointer register, address of next descriptor.
+    pub linked_list: RW<u64>,
+    /// Status register; bits 21:0 hold completed block size.
+    pub status: RO<u64>,
+    /// Software handshake source register.
+    pub software_handshake_source: RW<u64>,
+    /// Software handshake destination register.
+    pub software_handshake_destination: RW<u64>,
+    /// Block transfer resume request register.
+    pub block_resume: WO<u64>,
+    /// AXI identifier register.
+    pub axi_id: RW<u64>,
+    /// AXI quality of service register.
+    pub axi_qos: RW<u64>,
+    _reserved0: [u8; 0x20],
+    /// Interrupt status enable register.
+    pub interrupt_status_enable: RW<Interrupts>,
+    /// Interrupt status register.
+    pub interrupt_status: RO<Interrupts>,
+    /// Interrupt signal enable register.
+    pub interrupt_signal_enable: RW<Interrupts>,
+    /// Interrupt clear register; write 1 to clear.
+    pub interrupt_clear: WO<Interrupts>,
+    _reserved1: [u8; 0x60],
+}
+
+/// Channel request remap registers, at offset 0x154 of the top misc block.
+#[repr(C)]
+pub struct ChannelRemap {
+    /// Request remap registers; byte `n % 4` of register `n / 4` selects request of channel `n`.
+    pub remap: [RW<u32>; 2],
+}
+
+impl ChannelRemap {
+    const REQUEST: u32 = 0x3F;
+    const UPDATE: u32 = 1 << 31;
+
+    /// Routes handshake `request` to hardware handshake interface of `channel`.
+    #[inline]
+    pub fn set_request(&self, channel: usize, request: Request) {
+        let shift = (channel % 4) * 8;
+        unsafe {
+            self.remap[channel / 4].modify(|w| {
+                (w & !(Self::REQUEST << shift)) | ((request as u32) << shift) | Self::UPDATE
+            })
+        };
+    }
+}
+
+/// Global configuration register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct GlobalConfig(u64);
+
+impl GlobalConfig {
+    const ENABLE: u64 = 1 << 0;
+    const INTERRUPT: u64 = 1 << 1;
+
+    /// Enable DMA controller.
+    #[inline]
+    pub const fn enable(self) -> Self {
+        Self(self.0 | Self::ENABLE)
+    }
+    /// Disable DMA controller.
+    #[inline]
+    pub const fn disable(self) -> Self {
+        Self(self.0 & !Self::ENABLE)
+    }
+    /// Check if DMA controller is enabled.
+    #[inline]
+    pub const fn is_enabled(self) -> bool {
+        self.0 & Self::ENABLE != 0
+    }
+    /// Enable interrupt output.
+    #[inline]
+    pub const fn enable_interrupt(self) -> Self {
+        Self(self.0 | Self::INTERRUPT)
+    }
+    /// Disable interrupt output.
+    #[inline]
+    pub const fn disable_interrupt(self) -> Self {
+        Self(self.0 & !Self::INTERRUPT)
+    }
+    /// Check if interrupt output is enabled.
+    #[inline]
+    pub const fn is_interrupt_enabled(self) -> bool {
+        self.0 & Self::INTERRUPT != 0
+    }
+}
+
+/// Channel enable register.
+///
+/// Enable, suspend and abort bits only change where their write enable bit is set,
+/// so channels are controlled by writing a fresh value instead of modifying.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct ChannelEnable(u64);
+
+impl ChannelEnable {
+    const ENABLE: u32 = 0;
+    const ENABLE_WRITE: u32 = 8;
+    const ABORT: u32 = 32;
+    const ABORT_WRITE: u32 = 40;
+
+    /// Enable `channel`.
+    #[inline]
+    pub const fn enable_channel(self, channel: usize) -> Self {
+        Self(
+            self.0
+                | (1 << (Self::ENABLE as usize + channel))
+                | (1 << (Self::ENABLE_WRITE as usize + channel)),
+        )
+    }
+    /// Disable `channel`.
+    #[inline]
+    pub const fn disable_channel(self, channel: usize) -> Self {
+        Self(
+            (self.0 & !(1 << (Self::ENABLE as usize + channel)))
+                | (1 << (Self::ENABLE_WRITE as usize + channel)),
+        )
+    }
+    /// Abort transfer on `channel`.
+    #[inline]
+    pub const fn abort_channel(self, channel: usize) -> Self {
+        Self(
+            self.0
+                | (1 << (Self::ABORT as usize + channel))
+                | (1 << (Self::ABORT_WRITE as usize + channel)),
+        )
+    }
+    /// Check if `channel` is enabled.
+    #[inline]
+    pub const fn is_channel_enabled(self, channel: usize) -> bool {
+        self.0 & (1 << (Self::ENABLE as usize + channel)) != 0
+    }
+}
+
+/// Channel control register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct Control(u64);
+
+impl Control {
+    const SOURCE_FIXED: u64 = 1 << 4;
+    const DESTINATION_FIXED: u64 = 1 << 6;
+    const SOURCE_WIDTH: u64 = 0x7 << 8;
+    const DESTINATION_WIDTH: u64 = 0x7 << 11;
+    const SOURCE_BURST: u64 = 0xF << 14;
+    const DESTINATION_BURST: u64 = 0xF << 18;
+    const BLOCK_INTERRUPT: u64 = 1 << 58;
+    const LAST: u64 = 1 << 62;
+    const VALID: u64 = 1 << 63;
+
+    /// Increment source address after each word.
+    #[inline]
+    pub const fn set_source_increment(self) -> Self {
+        Self(self.0 & !Self::SOURCE_FIXED)
+    }
+    /// Keep source address fixed, as for a peripheral FIFO.
+    #[inline]
+    pub const fn set_source_fixed(self) -> Self {
+        Self(self.0 | Self::SOURCE_FIXED)
+    }
+    /// Check if source address is fixed.
+    #[inline]
+    pub const fn is_source_fixed(self) -> bool {
+        self.0 & Self::SOURCE_FIXED != 0
+    }
+    /// Increment destination address after each word.
+    #[inline]
+    pub const fn set_destination_increment(self) -> Self {
+        Self(self.0 & !Self::DESTINATION_FIXED)
+    }
+    /// Keep destination address fixed, as for a peripheral FIFO.
+    #[inline]
+    pub const fn set_destination_fixed(self) -> Self {
+        Self(self.0 | Self::DESTINATION_FIXED)
+    }
+    /// Check if destination address is fixed.
+    #[inline]
+    pub const fn is_destination_fixed(self) -> bool {
+        self.0 & Self::DESTINATION_FIXED != 0
+    }
+    /// Set source word width as log2 of bytes.
+    #[inline]
+    pub const fn set_source_width(self, width: u8) -> Self {
+        Self((self.0 & !Self::SOURCE_WIDTH) | (((width as u64) << 8) & Self::SOURCE_WIDTH))
+    }
+    /// Get source word width as log2 of bytes.
+    #[inline]
+    pub const fn source_width(self) -> u8 {
+        ((self.0 & Self::SOURCE_WIDTH) >> 8) as u8
+    }
+    /// Set destination word width as log2 of bytes.
+    #[inline]
+    pub const fn set_destination_width(self, width: u8) -> Self {
+        Self(
+            (self.0 & !Self::DESTINATION_WIDTH)
+                | (((width as u64) << 11) & Self::DESTINATION_WIDTH),
+        )
+    }
+    /// Get destination word width as log2 of bytes.
+    #[inline]
+    pub const fn destination_width(self) -> u8 {
+        ((self.0 & Self::DESTINATION_WIDTH) >> 11) as u8
+    }
+    /// Set source burst length.
+    #[inline]
+    pub const fn set_source_burst(self, burst: Burst) -> Self {
+        Self((self.0 & !Self::SOURCE_BURST) | ((burst as u64) << 14))
+    }
+    /// Set destination burst length.
+    #[inline]
+    pub const fn set_destination_burst(self, burst: Burst) -> Self {
+        Self((self.0 & !Self::DESTINATION_BURST) | ((burst as u64) << 18))
+    }
+    /// Raise block done interrupt when this block completes.
+    #[inline]
+    pub const fn enable_block_interrupt(self) -> Self {
+        Self(self.0 | Self::BLOCK_INTERRUPT)
+    }
+    /// Check if block done interrupt is raised on completion.
+    #[inline]
+    pub const fn is_block_interrupt_enabled(self) -> bool {
+        self.0 & Self::BLOCK_INTERRUPT != 0
+    }
+    /// Mark descriptor as last of a linked list.
+    #[inline]
+    pub const fn set_last(self) -> Self {
+        Self(self.0 | Self::LAST)
+    }
+    /// Check if descriptor is last of a linked list.
+    #[inline]
+    pub const fn is_last(self) -> bool {
+        self.0 & Self::LAST != 0
+    }
+    /// Mark descriptor as valid for the controller to fetch.
+    #[inline]
+    pub const fn set_valid(self) -> Self {
+        Self(self.0 | Self::VALID)
+    }
+    /// Check if descriptor is valid.
+    #[inline]
+    pub const fn is_valid(self) -> bool {
+        self.0 & Self::VALID != 0
+    }
+}
+
+/// Burst length in words.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Burst {
+    /// One word per request.
+    Single = 0,
+    /// 4 words per request.
+    Words4 = 1,
+    /// 8 words per request.
+    Words8 = 2,
+    /// 16 words per request.
+    Words16 = 3,
+}
+
+/// Channel configuration register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct ChannelConfig(u64);
+
+impl ChannelConfig {
+    const MULTI_BLOCK: u64 = 0xF;
+    const FLOW: u64 = 0x7 << 32;
+    const SOURCE_SOFTWARE: u64 = 1 << 35;
+    const DESTINATION_SOFTWARE: u64 = 1 << 36;
+    const SOURCE_HANDSHAKE: u64 = 0xF << 39;
+    const DESTINATION_HANDSHAKE: u64 = 0xF << 44;
+    const PRIORITY: u64 = 0x7 << 49;
+
+    /// Fetch source and destination of each block from linked list descriptors.
+    #[inline]
+    pub const fn enable_linked_list(self) -> Self {
+        Self(self.0 | Self::MULTI_BLOCK)
+    }
+    /// Transfer a single contiguous block.
+    #[inline]
+    pub const fn disable_linked_list(self) -> Self {
+        Self(self.0 & !Self::MULTI_BLOCK)
+    }
+    /// Check if linked list descriptors are used.
+    #[inline]
+    pub const fn is_linked_list_enabled(self) -> bool {
+        self.0 & Self::MULTI_BLOCK == Self::MULTI_BLOCK
+    }
+    /// Set transfer type with controller as flow controller.
+    #[inline]
+    pub const fn set_transfer_type(self, transfer_type: TransferType) -> Self {
+        Self((self.0 & !Self::FLOW) | ((transfer_type as u64) << 32))
+    }
+    /// Get transfer type.
+    #[inline]
+    pub const fn transfer_type(self) -> TransferType {
+        match (self.0 & Self::FLOW) >> 32 {
+            1 => TransferType::MemoryToPeripheral,
+            2 => TransferType::PeripheralToMemory,
+            _ => TransferType::MemoryToMemory,
+        }
+    }
+    /// Use hardware handshake interface `interface` for source.
+    #[inline]
+    pub const fn set_source_handshake(self, interface: u8) -> Self {
+        Self(
+            (self.0 & !(Self::SOURCE_HANDSHAKE | Self::SOURCE_SOFTWARE))
+                | (((interface as u64) << 39) & Self::SOURCE_HANDSHAKE),
+        )
+    }
+    /// Get hardware handshake interface of source.
+    #[inline]
+    pub const fn source_handshake(self) -> u8 {
+        ((self.0 & Self::SOURCE_HANDSHAKE) >> 39) as u8
+    }
+    /// Use hardware handshake interface `interface` for destination.
+    #[inline]
+    pub const fn set_destination_handshake(self, interface: u8) -> Self {
+        Self(
+            (self.0 & !(Self::DESTINATION_HANDSHAKE | Self::DESTINATION_SOFTWARE))
+                | (((interface as u64) << 44) & Self::DESTINATION_HANDSHAKE),
+        )
+    }
+    /// Get hardware handshake interface of destination.
+    #[inline]
+    pub const fn destination_handshake(self) -> u8 {
+        ((self.0 & Self::DESTINATION_HANDSHAKE) >> 44) as u8
+    }
+    /// Set channel priority, 7 is highest.
+    #[inline]
+    pub const fn set_priority(self, priority: u8) -> Self {
+        Self((self.0 & !Self::PRIORITY) | (((priority as u64) << 49) & Self::PRIORITY))
+    }
+    /// Get channel priority.
+    #[inline]
+    pub const fn priority(self) -> u8 {
+        ((self.0 & Self::PRIORITY) >> 49) as u8
+    }
+}
+
+/// Transfer type with DMA controller as flow controller.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransferType {
+    /// Memory to memory.
+    MemoryToMemory = 0,
+    /// Memory to peripheral.
+    MemoryToPeripheral = 1,
+    /// Peripheral to memory.
+    PeripheralToMemory = 2,
+}
+
+/// Channel interrupt enable, status and clear register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct Interrupts(u64);
+
+impl Interrupts {
+    const ERRORS: u64 = 0x0001_1FE0;
+
+    /// Enable, set or clear an interrupt, depending on register.
+    #[inline]
+    pub const fn set_interrupt(self, interrupt: Interrupt) -> Self {
+        Self(self.0 | (1 << interrupt as u64))
+    }
+    /// Remove an interrupt from this value.
+    #[inline]
+    pub const fn clear_interrupt(self, interrupt: Interrupt) -> Self {
+        Self(self.0 & !(1 << interrupt as u64))
+    }
+    /// Check if an interrupt is enabled or pending.
+    #[inline]
+    pub const fn has_interrupt(self, interrupt: Interrupt) -> bool {
+        self.0 & (1 << interrupt as u64) != 0
+    }
+    /// Enable, set or clear all error interrupts.
+    #[inline]
+    pub const fn set_errors(self) -> Self {
+        Self(self.0 | Self::ERRORS)
+    }
+    /// Check if any error interrupt is pending.
+    #[inline]
+    pub const fn has_error(self) -> bool {
+        self.0 & Self::ERRORS != 0
+    }
+}
+
+/// DMA channel interrupt sources.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interrupt {
+    /// A block completed.
+    BlockDone = 0,
+    /// Whole transfer completed.
+    TransferDone = 1,
+    /// Source address decode error.
+    SourceDecodeError = 5,
+    /// Destination address decode error.
+    DestinationDecodeError = 6,
+    /// Source slave error.
+    SourceSlaveError = 7,
+    /// Destination slave error.
+    DestinationSlaveError = 8,
+    /// Descriptor read decode error.
+    DescriptorReadDecodeError = 9,
+    /// Descriptor write decode error.
+    DescriptorWriteDecodeError = 10,
+    /// Descriptor read slave error.
+    DescriptorReadSlaveError = 11,
+    /// Descriptor write slave error.
+    DescriptorWriteSlaveError = 12,
+    /// Fetched descriptor was not marked valid.
+    InvalidDescriptor = 16,
+    /// Channel was aborted.
+    Aborted = 31,
+}
+
+/// Hardware handshake requests of peripherals.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Request {
+    /// I2S-0 receive.
+    I2s0Rx = 0,
+    /// I2S-0 transmit.
+    I2s0Tx = 1,
+    /// I2S-1 receive.
+    I2s1Rx = 2,
+    /// I2S-1 transmit.
+    I2s1Tx = 3,
+    /// I2S-2 receive.
+    I2s2Rx = 4,
+    /// I2S-2 transmit.
+    I2s2Tx = 5,
+    /// I2S-3 receive.
+    I2s3Rx = 6,
+    /// I2S-3 transmit.
+    I2s3Tx = 7,
+    /// UART-0 receive.
+    Uart0Rx = 8,
+    /// UART-0 transmit.
+    Uart0Tx = 9,
+    /// UART-1 receive.
+    Uart1Rx = 10,
+    /// UART-1 transmit.
+    Uart1Tx = 11,
+    /// UART-2 receive.
+    Uart2Rx = 12,
+    /// UART-2 transmit.
+    Uart2Tx = 13,
+    /// UART-3 receive.
+    Uart3Rx = 14,
+    /// UART-3 transmit.
+    Uart3Tx = 15,
+    /// SPI-0 receive.
+    Spi0Rx = 16,
+    /// SPI-0 transmit.
+    Spi0Tx = 17,
+    /// SPI-1 receive.
+    Spi1Rx = 18,
+    /// SPI-1 transmit.
+    Spi1Tx = 19,
+    /// SPI-2 receive.
+    Spi2Rx = 20,
+    /// SPI-2 transmit.
+    Spi2Tx = 21,
+    /// SPI-3 receive.
+    Spi3Rx = 22,
+    /// SPI-3 transmit.
+    Spi3Tx = 23,
+}
+
+impl Request {
+    /// Receive request of I2S peripheral `i`.
+    #[inline]
+    pub const fn i2s_rx(i: usize) -> Request {
+        [
+            Request::I2s0Rx,
+            Request::I2s1Rx,
+            Request::I2s2Rx,
+            Request::I2s3Rx,
+        ][i]
+    }
+    /// Transmit request of I2S peripheral `i`.
+    #[inline]
+    pub const fn i2s_tx(i: usize) -> Request {
+        [
+            Request::I2s0Tx,
+            Request::I2s1Tx,
+            Request::I2s2Tx,
+            Request::I2s3Tx,
+        ][i]
+    }
+    /// Receive request of UART peripheral `i`.
+    #[inline]
+    pub const fn uart_rx(i: usize) -> Request {
+        [
+            Request::Uart0Rx,
+            Request::Uart1Rx,
+            Request::Uart2Rx,
+            Request::Uart3Rx,
+        ][i]
+    }
+    /// Transmit request of UART peripheral `i`.
+    #[inline]
+    pub const fn uart_tx(i: usize) -> Request {
+        [
+            Request::Uart0Tx,
+            Request::Uart1Tx,
+            Request::Uart2Tx,
+            Request::Uart3Tx,
+        ][i]
+    }
+    /// Receive request of SPI peripheral `i`.
+    #[inline]
+    pub const fn spi_rx(i: usize) -> Request {
+        [
+            Request::Spi0Rx,
+            Request::Spi1Rx,
+            Request::Spi2Rx,
+            Request::Spi3Rx,
+        ][i]
+    }
+    /// Transmit request of SPI peripheral `i`.
+    #[inline]
+    pub const fn spi_tx(i: usize) -> Request {
+        [
+            Request::Spi0Tx,
+            Request::Spi1Tx,
+            Request::Spi2Tx,
+            Request::Spi3Tx,
+        ][i]
+    }
+}
+
+/// Linked list descriptor of one block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(C, align(64))]
+pub struct Descriptor {
+    source: u64,
+    destination: u64,
+    block_size: u32,
+    _reserved0: u32,
+    next: u64,
+    control: Control,
+    source_status: u32,
+    destination_status: u32,
+    status: u64,
+    _reserved1: u64,
+}
+
+// plain integer fields, valid for any bit pattern; lets `&'static mut [Descriptor; N]` serve as a list
+unsafe impl embedded_dma::Word for Descriptor {}
+
+impl Descriptor {
+    /// Creates an empty descriptor.
+    #[inline]
+    pub const fn new() -> Self {
+        Descriptor {
+            source: 0,
+            destination: 0,
+            block_size: 0,
+            _reserved0: 0,
+            next: 0,
+            control: Control(0),
+            source_status: 0,
+            destination_status: 0,
+            status: 0,
+            _reserved1: 0,
+        }
+    }
+    /// Describes a block moving `len` words of type `W` from `source` to `destination`.
+    ///
+    /// # Safety
+    ///
+    /// Both addresses must stay valid for `len` words, or one word for a fixed peripheral
+    /// port, until the transfer using this descriptor completes.
+    #[inline]
+    pub unsafe fn set_block<W: Word>(
+        &mut self,
+        source: Address<W>,
+        destination: Address<W>,
+        len: usize,
+    ) {
+        assert!(len > 0 && len <= MAX_BLOCK_SIZE);
+        self.source = source.address as u64;
+        self.destination = destination.address as u64;
+        self.block_size = (len - 1) as u32;
+        self.control = block_control(W::WIDTH, source.fixed, destination.fixed);
+    }
+    /// Number of words in this block.
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.block_size as usize + 1
+    }
+    /// Check if this descriptor is empty.
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.control.0 == 0
+    }
+}
+
+/// Source or destination of a descriptor block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Address<W> {
+    address: usize,
+    fixed: bool,
+    _word: PhantomData<W>,
+}
+
+impl<W: Word> Address<W> {
+    /// Memory buffer starting at `pointer`, incremented after each word.
+    #[inline]
+    pub fn memory(pointer: *const W) -> Self {
+        Address {
+            address: pointer as usize,
+            fixed: false,
+            _word: PhantomData,
+        }
+    }
+    /// Fixed peripheral data port.
+    #[inline]
+    pub const fn port(port: &Port<W>) -> Self {
+        Address {
+            address: port.address,
+            fixed: true,
+            _word: PhantomData,
+        }
+    }
+}
+
+/// Word sizes a DMA channel can move.
+pub trait Word: embedded_dma::Word + Copy + 'static {
+    /// Word width as log2 of bytes.
+    const WIDTH: u8;
+}
+
+impl Word for u8 {
+    const WIDTH: u8 = 0;
+}
+
+impl Word for u16 {
+    const WIDTH: u8 = 1;
+}
+
+impl Word for u32 {
+    const WIDTH: u8 = 2;
+}
+
+impl Word for u64 {
+    const WIDTH: u8 = 3;
+}
+
+/// Data register of a peripheral served by DMA handshakes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Port<W> {
+    address: usize,
+    _word: PhantomData<W>,
+}
+
+impl<W: Word> Port<W> {
+    /// Creates a port for data register at `address` of `W` sized accesses.
+    ///
+    /// # Safety
+    ///
+    /// `address` must be a peripheral FIFO register accepting DMA accesses of type `W`.
+    #[inline]
+    pub const unsafe fn new(address: usize) -> Self {
+        Port {
+            address,
+            _word: PhantomData,
+        }
+    }
+    /// Bus address of this port.
+    #[inline]
+    pub const fn address(&self) -> usize {
+        self.address
+    }
+}
+
+/// Direction and handshake of a transfer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Flow {
+    /// Memory to memory, without handshake.
+    MemoryToMemory,
+    /// Memory to peripheral paced by `Request`.
+    MemoryToPeripheral(Request),
+    /// Peripheral to memory paced by `Request`.
+    PeripheralToMemory(Request),
+}
+
+/// DMA transfer error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// Bus error on source, destination or descriptor access.
+    Bus,
+    /// Fetched descriptor was not valid.
+    InvalidDescriptor,
+    /// A circular buffer half was overwritten before it was handed out.
+    Overrun,
+}
+
+/// Control value of a single block of `width` words.
+#[inline]
+const fn block_control(width: u8, source_fixed: bool, destination_fixed: bool) -> Control {
+    let control = Control(0)
+        .set_source_width(width)
+        .set_destination_width(width)
+        .set_source_burst(Burst::Single)
+        .set_destination_burst(Burst::Single);
+    let control = if source_fixed {
+        control.set_source_fixed()
+    } else {
+        control.set_source_increment()
+    };
+    if destination_fixed {
+        control.set_destination_fixed()
+    } else {
+        control.set_destination_increment()
+    }
+}
+
+/// Channel configuration of `flow` on `channel`.
+#[inline]
+const fn channel_config(flow: Flow, channel: usize, linked_list: bool) -> ChannelConfig {
+    let config = ChannelConfig(0);
+    let config = if linked_list {
+        config.enable_linked_list()
+    } else {
+        config.disable_linked_list()
+    };
+    match flow {
+        Flow::MemoryToMemory => config.set_transfer_type(TransferType::MemoryToMemory),
+        Flow::MemoryToPeripheral(_) => config
+            .set_transfer_type(TransferType::MemoryToPeripheral)
+            .set_destination_handshake(channel as u8),
+        Flow::PeripheralToMemory(_) => config
+            .set_transfer_type(TransferType::PeripheralToMemory)
+            .set_source_handshake(channel as u8),
+    }
+}
+
+/// Links `descriptors` in order; the last one points back to the first if `circular`.
+#[inline]
+fn link_descriptors(descriptors: &mut [Descriptor], base: usize, circular: bool) {
+    let len = descriptors.len();
+    for (i, descriptor) in descriptors.iter_mut().enumerate() {
+        let last = i + 1 == len;
+        let control = descriptor.control.set_valid().enable_block_interrupt();
+        descriptor.control = if last && !circular {
+            control.set_last()
+        } else {
+            control
+        };
+        descriptor.next = if last {
+            if circular {
+                base as u64
+            } else {
+                0
+            }
+        } else {
+            (base + (i + 1) * size_of::<Descriptor>()) as u64
+        };
+    }
+}
+
+/// Cache line size of the C906 data cache.
+const CACHE_LINE: usize = 64;
+
+/// Writes back data cache lines covering `len` bytes at `address` so the DMA controller reads them.
+#[inline]
+fn clean_dcache(address: usize, len: usize) {
+    #[cfg(target_arch = "riscv64")]
+    {
+        let mut line = address & !(CACHE_LINE - 1);
+        while line < address + len {
+            // th.dcache.cva
+            unsafe { core::arch::asm!(".insn i 0x0B, 0, x0, {0}, 0x025", in(reg) line) };
+            line += CACHE_LINE;
+        }
+        // th.sync.s
+        unsafe { core::arch::asm!(".insn i 0x0B, 0, x0, x0, 0x019") };
+    }
+    #[cfg(not(target_arch = "riscv64"))]
+    let _ = (address, len, CACHE_LINE);
+}
+
+/// Writes back and discards data cache lines covering `len` bytes at `address`
+/// so the CPU reads data written by the DMA controller.
+#[inline]
+fn flush_dcache(address: usize, len: usize) {
+    #[cfg(target_arch = "riscv64")]
+    {
+        let mut line = address & !(CACHE_LINE - 1);
+        while line < address + len {
+            // th.dcache.civa
+            unsafe { core::arch::asm!(".insn i 0x0B, 0, x0, {0}, 0x027", in(reg) line) };
+            line += CACHE_LINE;
+        }
+        // th.sync.s
+        unsafe { core::arch::asm!(".insn i 0x0B, 0, x0, x0, 0x019") };
+    }
+    #[cfg(not(target_arch = "riscv64"))]
+    let _ = (address, len);
+}
+
+/// Managed system DMA controller.
+pub struct Dma<T, R> {
+    dma: T,
+    remap: R,
+    allocated: Cell<u8>,
+}
+
+/// Extension trait to create DMA controller drivers from peripherals.
+pub trait DmaExt: AsRef<RegisterBlock> + Controlled + Sized {
+    /// Resets and enables the DMA controller; `remap` routes peripheral requests to channels.
+    #[inline]
+    fn dma<R: AsRef<ChannelRemap>>(self, remap: R) -> Dma<Self, R> {
+        reset::enable(&self);
+        let dma = self.as_ref();
+        unsafe {
+            dma.reset.write(1);
+            while dma.reset.read() & 1 != 0 {
+                core::hint::spin_loop();
+            }
+            dma.config
+                .write(GlobalConfig::default().enable().enable_interrupt());
+        }
+        Dma {
+            dma: self,
+            remap,
+            allocated: Cell::new(0),
+        }
+    }
+}
+
+impl<T: AsRef<RegisterBlock>, R: AsRef<ChannelRemap>> Dma<T, R> {
+    /// Allocates a free channel, or returns `None` if all channels are in use.
+    ///
+    /// The channel returns to the pool when dropped.
+    #[inline]
+    pub fn channel(&self) -> Option<Channel<'_, T, R>> {
+        let allocated = self.allocated.get();
+        let index = (0..CHANNELS).find(|i| allocated & (1 << i) == 0)?;
+        self.allocated.set(allocated | (1 << index));
+        Some(Channel { dma: self, index })
+    }
+    /// Interrupt source of this controller on the interrupt controller.
+    #[inline]
+    pub const fn interrupt(&self) -> crate::plic::Interrupt {
+        crate::plic::Interrupt::Dma
+    }
+    /// Release DMA controller and return its peripheral and remap registers, turning its clocks off.
+    #[inline]
+    pub fn free(self) -> (T, R)
+    where
+        T: Controlled,
+    {
+        unsafe { self.dma.as_ref().config.write(GlobalConfig::default()) };
+        reset::disable(&self.dma);
+        (self.dma, self.remap)
+    }
+}
+
+/// Allocated DMA channel.
+pub struct Channel<'a, T: AsRef<RegisterBlock>, R: AsRef<ChannelRemap>> {
+    dma: &'a Dma<T, R>,
+    index: usize,
+}
+
+impl<'a, T: AsRef<RegisterBlock>, R: AsRef<ChannelRemap>> Channel<'a, T, R> {
+    /// Index of this channel.
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.index
+    }
+    /// Copies `source` into `destination`, as many words as the shorter buffer holds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the copy is empty or longer than [`MAX_BLOCK_SIZE`] words.
+    #[inline]
+    pub fn memory_to_memory<S, D, W>(
+        self,
+        source: S,
+        mut destination: D,
+    ) -> Transfer<'a, T, R, (S, D)>
+    where
+        S: ReadBuffer<Word = W>,
+        D: WriteBuffer<Word = W>,
+        W: Word,
+    {
+        let (from, from_len) = unsafe { source.read_buffer() };
+        let (to, to_len) = unsafe { destination.write_buffer() };
+        let len = from_len.min(to_len);
+        self.start_block(
+            Flow::MemoryToMemory,
+            Address::memory(from),
+            Address::memory(to),
+            len,
+        );
+        Transfer::new(
+            self,
+            (source, destination),
+            to as usize,
+            len * size_of::<W>(),
+        )
+    }
+    /// Sends all of `buffer` to peripheral `port` paced by `request`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buffer` is empty or longer than [`MAX_BLOCK_SIZE`] words.
+    #[inline]
+    pub fn memory_to_peripheral<B, W>(
+        self,
+        buffer: B,
+        request: Request,
+        port: Port<W>,
+    ) -> Transfer<'a, T, R, B>
+    where
+        B: ReadBuffer<Word = W>,
+        W: Word,
+    {
+        let (from, len) = unsafe { buffer.read_buffer() };
+        self.start_block(
+            Flow::MemoryToPeripheral(request),
+            Address::memory(from),
+            Address::port(&port),
+            len,
+        );
+        Transfer::new(self, buffer, 0, 0)
+    }
+    /// Fills all of `buffer` from peripheral `port` paced by `request`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buffer` is empty or longer than [`MAX_BLOCK_SIZE`] words.
+    #[inline]
+    pub fn peripheral_to_memory<B, W>(
+        self,
+        request: Request,
+        port: Port<W>,
+        mut buffer: B,
+    ) -> Transfer<'a, T, R, B>
+    where
+        B: WriteBuffer<Word = W>,
+        W: Word,
+    {
+        let (to, len) = unsafe { buffer.write_buffer() };
+        self.start_block(
+            Flow::PeripheralToMemory(request),
+            Address::port(&port),
+            Address::memory(to),
+            len,
+        );
+        Transfer::new(self, buffer, to as usize, len * size_of::<W>())
+    }
+    /// Runs blocks described by `descriptors` in order as one scatter-gather transfer.
+    ///
+    /// Descriptors are linked by this function; blocks must be set with [`Descriptor::set_block`].
+    /// Memory written by the blocks should be flushed from the data cache by the caller
+    /// before reading, as this transfer does not know the block addresses.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `descriptors` is empty or any descriptor has no block set.
+    #[inline]
+    pub fn linked_list<L>(self, flow: Flow, mut descriptors: L) -> Transfer<'a, T, R, L>
+    where
+        L: WriteBuffer<Word = Descriptor>,
+    {
+        let (list, len) = unsafe { descriptors.write_buffer() };
+        let list = unsafe { core::slice::from_raw_parts_mut(list, len) };
+        assert!(!list.is_empty() && list.iter().all(|d| !d.is_empty()));
+        for descriptor in list.iter() {
+            let bytes = descriptor.len() << descriptor.control.source_width();
+            if !descriptor.control.is_source_fixed() {
+                clean_dcache(descriptor.source as usize, bytes);
+            }
+            if !descriptor.control.is_destination_fixed() {
+                flush_dcache(descriptor.destination as usize, bytes);
+            }
+        }
+        self.start_list(flow, list, false);
+        Transfer::new(self, descriptors, 0, 0)
+    }
+    /// Streams `buffer` to or from a peripheral endlessly as two alternating halves.
+    ///
+    /// The first two `descriptors` are linked in a ring; each half raises a block done event
+    /// when the controller moves on to the other half, which [`Circular`] hands out.
+    ///
+    /// # Panics
+    ///
+    /// Panics on a memory to memory `flow`, if `buffer` has an odd or zero length,
+    /// a half longer than [`MAX_BLOCK_SIZE`] words, or `descriptors` holds less than two.
+    #[inline]
+    pub fn circular<B, L, W>(
+        self,
+        flow: Flow,
+        port: Port<W>,
+        mut buffer: B,
+        mut descriptors: L,
+    ) -> Circular<'a, T, R, B, L, W>
+    where
+        B: WriteBuffer<Word = W>,
+        L: WriteBuffer<Word = Descriptor>,
+        W: Word,
+    {
+        let (pointer, len) = unsafe { buffer.write_buffer() };
+        assert!(len > 0 && len.is_multiple_of(2));
+        let half = len / 2;
+        let (list, count) = unsafe { descriptors.write_buffer() };
+        assert!(count >= 2);
+        let list = unsafe { core::slice::from_raw_parts_mut(list, 2) };
+        for (i, descriptor) in list.iter_mut().enumerate() {
+            let memory = Address::memory(unsafe { pointer.add(i * half) });
+            let (source, destination) = match flow {
+                Flow::MemoryToPeripheral(_) => (memory, Address::port(&port)),
+                Flow::PeripheralToMemory(_) => (Address::port(&port), memory),
+                Flow::MemoryToMemory => panic!("circular transfers need a peripheral"),
+            };
+            unsafe { descriptor.set_block(source, destination, half) };
+        }
+        let bytes = len * size_of::<W>();
+        match flow {
+            Flow::MemoryToPeripheral(_) => clean_dcache(pointer as usize, bytes),
+            _ => flush_dcache(pointer as usize, bytes),
+        }
+        self.start_list(flow, list, true);
+        Circular {
+            channel: Some(self),
+            buffer: Some((buffer, descriptors)),
+            pointer,
+            ring: list.as_ptr() as usize,
+            half,
+            receive: matches!(flow, Flow::PeripheralToMemory(_)),
+            next: 0,
+        }
+    }
+    #[inline]
+    fn registers(&self) -> &ChannelRegisters {
+        &self.dma.dma.as_ref().channels[self.index]
+    }
+    #[inline]
+    fn prepare(&self, flow: Flow) {
+        match flow {
+            Flow::MemoryToPeripheral(request) | Flow::PeripheralToMemory(request) => {
+                self.dma.remap.as_ref().set_request(self.index, request)
+            }
+            Flow::MemoryToMemory => {}
+        }
+        let channel = self.registers();
+        unsafe {
+            channel.interrupt_clear.write(Interrupts(u64::MAX));
+            channel.interrupt_status_enable.write(
+                Interrupts::default()
+                    .set_interrupt(Interrupt::BlockDone)
+                    .set_interrupt(Interrupt::TransferDone)
+                    .set_interrupt(Interrupt::Aborted)
+                    .set_errors(),
+            );
+            channel.interrupt_signal_enable.write(Interrupts::default());
+        }
+    }
+    #[inline]
+    fn start_block<W: Word>(
+        &self,
+        flow: Flow,
+        source: Address<W>,
+        destination: Address<W>,
+        len: usize,
+    ) {
+        assert!(len > 0 && len <= MAX_BLOCK_SIZE);
+        let bytes = len * size_of::<W>();
+        if !source.fixed {
+            clean_dcache(source.address, bytes);
+        }
+        if !destination.fixed {
+            flush_dcache(destination.address, bytes);
+        }
+        self.prepare(flow);
+        let channel = self.registers();
+        unsafe {
+            channel.source.write(source.address as u64);
+            channel.destination.write(destination.address as u64);
+            channel.block_size.write((len - 1) as u64);
+            channel.control.write(
+                block_control(W::WIDTH, source.fixed, destination.fixed)
+                    .set_valid()
+                    .set_last(),
+            );
+            channel
+                .config
+                .write(channel_config(flow, self.index, false));
+        }
+        self.enable();
+    }
+    #[inline]
+    fn start_list(&self, flow: Flow, list: &mut [Descriptor], circular: bool) {
+        let base = list.as_ptr() as usize;
+        link_descriptors(list, base, circular);
+        clean_dcache(base, core::mem::size_of_val(list));
+        self.prepare(flow);
+        let channel = self.registers();
+        unsafe {
+            channel.linked_list.write(base as u64);
+            channel.config.write(channel_config(flow, self.index, true));
+        }
+        self.enable();
+    }
+    #[inline]
+    fn enable(&self) {
+        compiler_fence(Ordering::SeqCst);
+        let dma = self.dma.dma.as_ref();
+        unsafe {
+            dma.channel_enable
+                .write(ChannelEnable::default().enable_channel(self.index))
+        };
+    }
+    #[inline]
+    fn is_enabled(&self) -> bool {
+        let dma = self.dma.dma.as_ref();
+        dma.channel_enable.read().is_channel_enabled(self.index)
+    }
+    /// Aborts any running transfer and waits for the channel to stop.
+    #[inline]
+    fn stop(&self) {
+        if self.is_enabled() {
+            let dma = self.dma.dma.as_ref();
+            unsafe {
+                dma.channel_enable
+                    .write(ChannelEnable::default().abort_channel(self.index))
+            };
+            while self.is_enabled() {
+                core::hint::spin_loop();
+            }
+        }
+        compiler_fence(Ordering::SeqCst);
+    }
+    #[inline]
+    fn check_errors(&self) -> Result<(), Error> {
+        let status = self.registers().interrupt_status.read();
+        if status.has_interrupt(Interrupt::InvalidDescriptor) {
+            Err(Error::InvalidDescriptor)
+        } else if status.has_error() {
+            Err(Error::Bus)
+        } else {
+            Ok(())
+        }
+    }
+    /// Waits until `ready` holds, sleeping on channel interrupts meanwhile.
+    async fn wait(&self, state: &State, ready: impl Fn(&Self) -> bool) {
+        let registers = self.dma.dma.as_ref();
+        state
+            .registers
+            .store(registers as *const _ as *mut _, Ordering::Release);
+        poll_fn(|cx| {
+            if ready(self) {
+                return Poll::Ready(());
+            }
+            state.wakers[self.index].register(cx.waker());
+            unsafe {
+                self.registers().interrupt_signal_enable.write(
+                    Interrupts::default()
+                        .set_interrupt(Interrupt::BlockDone)
+                        .set_interrupt(Interrupt::TransferDone)
+                        .set_interrupt(Interrupt::Aborted)
+                        .set_errors(),
+                )
+            };
+            // condition may have changed before interrupt was enabled
+            if ready(self) {
+                return Poll::Ready(());
+            }
+            Poll::Pending
+        })
+        .await;
+        unsafe {
+            self.registers()
+                .interrupt_signal_enable
+                .write(Interrupts::default())
+        };
+    }
+}
+
+impl<'a, T: AsRef<RegisterBlock>, R: AsRef<ChannelRemap>> Drop for Channel<'a, T, R> {
+    #[inline]
+    fn drop(&mut self) {
+        self.stop();
+        let allocated = self.dma.allocated.get();
+        self.dma.allocated.set(allocated & !(1 << self.index));
+    }
+}
+
+/// Running DMA transfer owning its channel and buffers.
+///
+/// Dropping a transfer aborts it; buffers are dropped after the channel stops.
+pub struct Transfer<'a, T: AsRef<RegisterBlock>, R: AsRef<ChannelRemap>, B> {
+    channel: Option<Channel<'a, T, R>>,
+    buffer: Option<B>,
+    written: (usize, usize),
+}
+
+impl<'a, T: AsRef<RegisterBlock>, R: AsRef<ChannelRemap>, B> Transfer<'a, T, R, B> {
+    #[inline]
+    fn new(channel: Channel<'a, T, R>, buffer: B, address: usize, len: usize) -> Self {
+        Transfer {
+            channel: Some(channel),
+            buffer: Some(buffer),
+            written: (address, len),
+        }
+    }
+    /// Check if the transfer stopped, either completed or failed.
+    #[inline]
+    pub fn is_done(&self) -> bool {
+        let channel = self.channel.as_ref().unwrap();
+        !channel.is_enabled() || channel.check_errors().is_err()
+    }
+    /// Waits for the transfer to stop, returns its buffers and channel and the transfer result.
+    #[inline]
+    pub fn wait(mut self) -> (Result<(), Error>, B, Channel<'a, T, R>) {
+        while !self.is_done() {
+            core::hint::spin_loop();
+        }
+        self.finish()
+    }
+    /// Waits for the transfer to stop on completion interrupts.
+    ///
+    /// The interrupt handler of the DMA controller must call `state.on_interrupt()`.
+    #[inline]
+    pub async fn wait_async(mut self, state: &State) -> (Result<(), Error>, B, Channel<'a, T, R>) {
+        let channel = self.channel.as_ref().unwrap();
+        channel
+            .wait(state, |channel| {
+                !channel.is_enabled() || channel.check_errors().is_err()
+            })
+            .await;
+        self.finish()
+    }
+    /// Aborts the transfer, returns its buffers and channel.
+    #[inline]
+    pub fn abort(mut self) -> (B, Channel<'a, T, R>) {
+        let (_, buffer, channel) = self.finish();
+        (buffer, channel)
+    }
+    #[inline]
+    fn finish(&mut self) -> (Result<(), Error>, B, Channel<'a, T, R>) {
+        let channel = self.channel.take().unwrap();
+        let result = channel.check_errors();
+        channel.stop();
+        let (address, len) = self.written;
+        flush_dcache(address, len);
+        (result, self.buffer.take().unwrap(), channel)
+    }
+}
+
+impl<'a, T: AsRef<RegisterBlock>, R: AsRef<ChannelRemap>, B> Drop for Transfer<'a, T, R, B> {
+    #[inline]
+    fn drop(&mut self) {
+        if let Some(channel) = &self.channel {
+            channel.stop();
+        }
+    }
+}
+
+/// Endless double-buffered peripheral stream.
+///
+/// While the controller moves one half of the buffer, the other half is handed
+/// out to be refilled for transmit or consumed for receive.
+pub struct Circular<'a, T: AsRef<RegisterBlock>, R: AsRef<ChannelRemap>, B, L, W> {
+    channel: Option<Channel<'a, T, R>>,
+    buffer: Option<(B, L)>,
+    pointer: *mut W,
+    ring: usize,
+    half: usize,
+    receive: bool,
+    next: usize,
+}
+
+impl<'a, T: AsRef<RegisterBlock>, R: AsRef<ChannelRemap>, B, L, W: Word>
+    Circular<'a, T, R, B, L, W>
+{
+    /// Check if a buffer half is ready to be handed out.
+    #[inline]
+    pub fn is_half_ready(&self) -> bool {
+        let channel = self.channel.as_ref().unwrap();
+        let status = channel.registers().interrupt_status.read();
+        status.has_interrupt(Interrupt::BlockDone) || status.has_error()
+    }
+    /// Waits until the controller finished a half, then calls `f` with it.
+    ///
+    /// Returns [`Error::Overrun`] without calling `f` if the controller completed
+    /// both halves since last call; the stream keeps running.
+    #[inline]
+    pub fn next_half<U>(&mut self, f: impl FnOnce(&mut [W]) -> U) -> Result<U, Error> {
+        while !self.is_half_ready() {
+            core::hint::spin_loop();
+        }
+        self.take_half(f)
+    }
+    /// Waits for a finished half on completion interrupts, then calls `f` with it.
+    ///
+    /// The interrupt handler of the DMA controller must call `state.on_interrupt()`.
+    #[inline]
+    pub async fn next_half_async<U>(
+        &mut self,
+        state: &State,
+        f: impl FnOnce(&mut [W]) -> U,
+    ) -> Result<U, Error> {
+        let channel = self.channel.as_ref().unwrap();
+        channel
+            .wait(state, |channel| {
+                let status = channel.registers().interrupt_status.read();
+                status.has_interrupt(Interrupt::BlockDone) || status.has_error()
+            })
+            .await;
+        self.take_half(f)
+    }
+    /// Stops streaming, returns buffer, descriptors and channel.
+    #[inline]
+    pub fn stop(mut self) -> (B, L, Channel<'a, T, R>) {
+        let channel = self.channel.take().unwrap();
+        channel.stop();
+        if self.receive {
+            flush_dcache(self.pointer as usize, self.half * 2 * size_of::<W>());
+        }
+        let (buffer, descriptors) = self.buffer.take().unwrap();
+        (buffer, descriptors, channel)
+    }
+    #[inline]
+    fn take_half<U>(&mut self, f: impl FnOnce(&mut [W]) -> U) -> Result<U, Error> {
+        let channel = self.channel.as_ref().unwrap();
+        channel.check_errors()?;
+        unsafe {
+            channel
+                .registers()
+                .interrupt_clear
+                .write(Interrupts::default().set_interrupt(Interrupt::BlockDone))
+        };
+        // next descriptor to fetch tells which half is in flight
+        let fetching = channel.registers().linked_list.read();
+        let in_flight = if fetching == self.ring as u64 { 1 } else { 0 };
+        let done = 1 - in_flight;
+        if done != self.next {
+            self.next = in_flight;
+            return Err(Error::Overrun);
+        }
+        self.next = in_flight;
+        let pointer = unsafe { self.pointer.add(done * self.half) };
+        let bytes = self.half * size_of::<W>();
+        if self.receive {
+            flush_dcache(pointer as usize, bytes);
+        }
+        let half = unsafe { core::slice::from_raw_parts_mut(pointer, self.half) };
+        let output = f(half);
+        if !self.receive {
+            clean_dcache(pointer as usize, bytes);
+        }
+        Ok(output)
+    }
+}
+
+impl<'a, T: AsRef<RegisterBlock>, R: AsRef<ChannelRemap>, B, L, W> Drop
+    for Circular<'a, T, R, B, L, W>
+{
+    #[inline]
+    fn drop(&mut self) {
+        if let Some(channel) = &self.channel {
+            channel.stop();
+        }
+    }
+}
+
+/// Shared state between DMA transfers awaited asynchronously and the DMA interrupt handler.
+pub struct State {
+    wakers: [AtomicWaker; CHANNELS],
+    registers: AtomicPtr<RegisterBlock>,
+}
+
+impl State {
+    /// Creates an empty state, usually placed in a `static`.
+    #[inline]
+    pub const fn new() -> Self {
+        State {
+            wakers: [const { AtomicWaker::new() }; CHANNELS],
+            registers: AtomicPtr::new(null_mut()),
+        }
+    }
+    /// Interrupt handler hook for the DMA controller.
+    ///
+    /// Disables interrupts of channels with pending events and wakes tasks waiting on them;
+    /// does nothing if no transfer was awaited on this state yet.
+    #[inline]
+    pub fn on_interrupt(&self) {
+        let registers = self.registers.load(Ordering::Acquire);
+        if registers.is_null() {
+            return;
+        }
+        let dma = unsafe { &*registers };
+        let pending = dma.interrupt_status.read();
+        for (index, waker) in self.wakers.iter().enumerate() {
+            if pending & (1 << index) != 0 {
+                unsafe {
+                    dma.channels[index]
+                        .interrupt_signal_enable
+                        .write(Interrupts::default())
+                };
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl Default for State {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        block_control, channel_config, link_descriptors, ChannelConfig, ChannelEnable,
+        ChannelRegisters, ChannelRemap, Descriptor, Flow, Interrupt, Interrupts, RegisterBlock,
+        Request, TransferType,
+    };
+    use core::mem::size_of;
+    use memoffset::offset_of;
+
+    #[test]
+    fn struct_register_block_offset() {
+        assert_eq!(offset_of!(RegisterBlock, config), 0x10);
+        assert_eq!(offset_of!(RegisterBlock, channel_enable), 0x18);
+        assert_eq!(offset_of!(RegisterBlock, interrupt_status), 0x30);
+        assert_eq!(offset_of!(RegisterBlock, reset), 0x58);
+        assert_eq!(offset_of!(RegisterBlock, channels), 0x100);
+        assert_eq!(size_of::<ChannelRegisters>(), 0x100);
+        assert_eq!(offset_of!(ChannelRegisters, control), 0x18);
+        assert_eq!(offset_of!(ChannelRegisters, config), 0x20);
+        assert_eq!(offset_of!(ChannelRegisters, linked_list), 0x28);
+        assert_eq!(offset_of!(ChannelRegisters, interrupt_status_enable), 0x80);
+        assert_eq!(offset_of!(ChannelRegisters, interrupt_clear), 0x98);
+        assert_eq!(offset_of!(ChannelRemap, remap), 0x0);
+        assert_eq!(offset_of!(Descriptor, next), 0x18);
+        assert_eq!(offset_of!(Descriptor, control), 0x20);
+        assert_eq!(size_of::<Descriptor>(), 64);
+    }
+
+    #[test]
+    fn struct_channel_functions() {
+        let val = ChannelEnable::default().enable_channel(3);
+        assert_eq!(val.0, 0x0808);
+        assert!(val.is_channel_enabled(3));
+        assert_eq!(ChannelEnable::default().disable_channel(2).0, 0x0400);
+        assert_eq!(
+            ChannelEnable::default().abort_channel(1).0,
+            0x0000_0202_0000_0000
+        );
+
+        let val = block_control(2, true, false);
+        assert_eq!(val.0, 0x0000_0000_0000_1210);
+        assert!(val.is_source_fixed() && !val.is_destination_fixed());
+        assert_eq!(val.source_width(), 2);
+        assert_eq!(val.destination_width(), 2);
+
+        let val = channel_config(Flow::MemoryToPeripheral(Request::I2s3Tx), 5, false);
+        assert_eq!(val.transfer_type(), TransferType::MemoryToPeripheral);
+        assert_eq!(val.destination_handshake(), 5);
+        assert!(!val.is_linked_list_enabled());
+        let val = channel_config(Flow::PeripheralToMemory(Request::Uart0Rx), 2, true);
+        assert_eq!(val.0, 0x0000_0102_0000_000F);
+        assert_eq!(ChannelConfig::default().set_priority(7).priority(), 7);
+
+        let val = Interrupts::default()
+            .set_interrupt(Interrupt::TransferDone)
+            .set_errors();
+        assert!(val.has_error());
+        assert!(!val
+            .clear_interrupt(Interrupt::TransferDone)
+            .has_interrupt(Interrupt::TransferDone));
+        assert!(!Interrupts::default()
+            .set_interrupt(Interrupt::BlockDone)
+            .has_error());
+    }
+
+    #[test]
+    fn descriptor_linking() {
+        let mut list = [Descriptor::new(); 3];
+        for descriptor in &mut list {
+            descriptor.control = block_control(0, false, false);
+        }
+        link_descriptors(&mut list, 0x8000_0000, false);
+        assert_eq!(list[0].next, 0x8000_0040);
+        assert_eq!(list[1].next, 0x8000_0080);
+        assert_eq!(list[2].next, 0);
+        assert!(list.iter().all(|d| d.control.is_valid()));
+        assert!(list[2].control.is_last() && !list[1].control.is_last());
+
+        let mut ring = [Descriptor::new(); 2];
+        link_descriptors(&mut ring, 0x8000_1000, true);
+        assert_eq!(ring[0].next, 0x8000_1040);
+        assert_eq!(ring[1].next, 0x8000_1000);
+        assert!(!ring[1].control.is_last());
+        assert!(ring[1].control.is_block_interrupt_enabled());
+    }
+
+    #[test]
+    fn request_numbers() {
+        assert_eq!(Request::i2s_rx(0), Request::I2s0Rx);
+        assert_eq!(Request::i2s_tx(3) as u8, 7);
+        assert_eq!(Request::uart_tx(2) as u8, 13);
+        assert_eq!(Request::spi_rx(1) as u8, 18);
+    }
+}
@@ -112,7 +112,7 @@ pub struct GpioPad<T, U> {
     pad: U,
 }
 
-impl<T: AsRef<RegisterBlock>, U: AsRef<pad::PadConfigs>, const I: u8, const N: usize, M>
+impl<T: AsRef<RegisterBlock>, U: pad::PadDomain, const I: u8, const N: usize, M>
     GpioPad<Gpio<T, I, M>, Pad<U, N, GpioFunc<T>>>
 {
     /// Reconfigures the `GpioPad` to operate as a pull up output.
@@ -0,0 +1,1440 @@
+//! Inter-Integrated Circuit bus.
+
+use crate::clock::Clocks;
+use crate::pad::{I2cFunc, Pad};
+use crate::reset::{self, Controlled};
+use atomic_waker::AtomicWaker;
+use core::{
+    future::poll_fn,
+    mem::ManuallyDrop,
+    ptr::null_mut,
+    sync::atomic::{AtomicPtr, Ordering},
+    task::Poll,
+};
+use embedded_hal::i2c::{
+    ErrorKind, NoAcknowledgeSource, Operation, SevenBitAddress, TenBitAddress,
+};
+use volatile_register::{RO, RW};
+
+/// Inter-Integrated Circuit registers.
+#[repr(C)]
+pub struct RegisterBlock {
+    /// Control register.
+    pub control: RW<Control>,
+    /// Target address register.
+    pub target_address: RW<TargetAddress>,
+    /// Slave address register.
+    pub slave_address: RW<u32>,
+    /// High speed master mode code address register.
+    pub hs_master_code: RW<u32>,
+    /// Rx/Tx data buffer and command register.
+    pub data_command: RW<DataCommand>,
+    /// Standard speed SCL high count register.
+    pub ss_scl_high_count: RW<u32>,
+    /// Standard speed SCL low count register.
+    pub ss_scl_low_count: RW<u32>,
+    /// Fast mode or fast mode plus SCL high count register.
+    pub fs_scl_high_count: RW<u32>,
+    /// Fast mode or fast mode plus SCL low count register.
+    pub fs_scl_low_count: RW<u32>,
+    /// High speed SCL high count register.
+    pub hs_scl_high_count: RW<u32>,
+    /// High speed SCL low count register.
+    pub hs_scl_low_count: RW<u32>,
+    /// Interrupt status register.
+    pub interrupt_status: RO<Interrupts>,
+    /// Interrupt mask register.
+    pub interrupt_mask: RW<Interrupts>,
+    /// Raw interrupt status register.
+    pub raw_interrupt_status: RO<Interrupts>,
+    /// Receive FIFO threshold register.
+    pub rx_threshold: RW<u32>,
+    /// Transmit FIFO threshold register.
+    pub tx_threshold: RW<u32>,
+    /// Clear combined and individual interrupt register, cleared on read.
+    pub clear_interrupt: RO<u32>,
+    /// Clear RX_UNDER interrupt register, cleared on read.
+    pub clear_rx_under: RO<u32>,
+    /// Clear RX_OVER interrupt register, cleared on read.
+    pub clear_rx_over: RO<u32>,
+    /// Clear TX_OVER interrupt register, cleared on read.
+    pub clear_tx_over: RO<u32>,
+    /// Clear RD_REQ interrupt register, cleared on read.
+    pub clear_read_request: RO<u32>,
+    /// Clear TX_ABRT interrupt register, cleared on read.
+    pub clear_tx_abort: RO<u32>,
+    /// Clear RX_DONE interrupt register, cleared on read.
+    pub clear_rx_done: RO<u32>,
+    /// Clear ACTIVITY interrupt register, cleared on read.
+    pub clear_activity: RO<u32>,
+    /// Clear STOP_DET interrupt register, cleared on read.
+    pub clear_stop_detect: RO<u32>,
+    /// Clear START_DET interrupt register, cleared on read.
+    pub clear_start_detect: RO<u32>,
+    /// Clear GEN_CALL interrupt register, cleared on read.
+    pub clear_general_call: RO<u32>,
+    /// Enable register.
+    pub enable: RW<Enable>,
+    /// Status register.
+    pub status: RO<Status>,
+    /// Transmit FIFO level register.
+    pub tx_fifo_level: RO<u32>,
+    /// Receive FIFO level register.
+    pub rx_fifo_level: RO<u32>,
+    /// SDA hold time length register.
+    pub sda_hold: RW<u32>,
+    /// Transmit abort source register.
+    pub tx_abort_source: RO<TxAbortSource>,
+    /// Generate slave data NACK register.
+    pub slave_data_nack_only: RW<u32>,
+    /// DMA control register.
+    pub dma_control: RW<u32>,
+    /// DMA transmit data level register.
+    pub dma_tx_data_level: RW<u32>,
+    /// DMA receive data level register.
+    pub dma_rx_data_level: RW<u32>,
+    /// SDA setup register.
+    pub sda_setup: RW<u32>,
+    /// ACK general call register.
+    pub ack_general_call: RW<u32>,
+    /// Enable status register.
+    pub enable_status: RO<u32>,
+    /// Standard speed, fast mode or fast mode plus spike suppression limit register.
+    pub fs_spike_length: RW<u32>,
+    /// High speed spike suppression limit register.
+    pub hs_spike_length: RW<u32>,
+}
+
+/// Control register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct Control(u32);
+
+impl Control {
+    const MASTER_MODE: u32 = 1 << 0;
+    const SPEED: u32 = 0b11 << 1;
+    const SLAVE_10BIT_ADDRESS: u32 = 1 << 3;
+    const MASTER_10BIT_ADDRESS: u32 = 1 << 4;
+    const RESTART_ENABLE: u32 = 1 << 5;
+    const SLAVE_DISABLE: u32 = 1 << 6;
+    const STOP_DETECT_IF_ADDRESSED: u32 = 1 << 7;
+    const RX_FIFO_FULL_HOLD: u32 = 1 << 9;
+
+    /// Enable master mode.
+    #[inline]
+    pub fn enable_master_mode(self) -> Self {
+        Self(self.0 | Self::MASTER_MODE)
+    }
+    /// Disable master mode.
+    #[inline]
+    pub fn disable_master_mode(self) -> Self {
+        Self(self.0 & !Self::MASTER_MODE)
+    }
+    /// Check if master mode is enabled.
+    #[inline]
+    pub fn is_master_mode_enabled(self) -> bool {
+        self.0 & Self::MASTER_MODE != 0
+    }
+    /// Set bus speed mode.
+    #[inline]
+    pub fn set_speed(self, speed: SpeedMode) -> Self {
+        Self((self.0 & !Self::SPEED) | ((speed as u32) << 1))
+    }
+    /// Get bus speed mode, or `None` if the field holds the reserved value 0.
+    #[inline]
+    pub fn speed(self) -> Option<SpeedMode> {
+        match (self.0 & Self::SPEED) >> 1 {
+            1 => Some(SpeedMode::Standard),
+            2 => Some(SpeedMode::Fast),
+            3 => Some(SpeedMode::High),
+            _ => None,
+        }
+    }
+    /// Enable 10-bit addressing when acting as a slave.
+    #[inline]
+    pub fn enable_slave_10bit_address(self) -> Self {
+        Self(self.0 | Self::SLAVE_10BIT_ADDRESS)
+    }
+    /// Disable 10-bit addressing when acting as a slave.
+    #[inline]
+    pub fn disable_slave_10bit_address(self) -> Self {
+        Self(self.0 & !Self::SLAVE_10BIT_ADDRESS)
+    }
+    /// Enable 10-bit addressing when acting as a master.
+    #[inline]
+    pub fn enable_master_10bit_address(self) -> Self {
+        Self(self.0 | Self::MASTER_10BIT_ADDRESS)
+    }
+    /// Disable 10-bit addressing when acting as a master.
+    #[inline]
+    pub fn disable_master_10bit_address(self) -> Self {
+        Self(self.0 & !Self::MASTER_10BIT_ADDRESS)
+    }
+    /// Check if 10-bit addressing is enabled when acting as a master.
+    #[inline]
+    pub fn is_master_10bit_address_enabled(self) -> bool {
+        self.0 & Self::MASTER_10BIT_ADDRESS != 0
+    }
+    /// Enable sending RESTART conditions.
+    #[inline]
+    pub fn enable_restart(self) -> Self {
+        Self(self.0 | Self::RESTART_ENABLE)
+    }
+    /// Disable sending RESTART conditions.
+    #[inline]
+    pub fn disable_restart(self) -> Self {
+        Self(self.0 & !Self::RESTART_ENABLE)
+    }
+    /// Enable slave mode.
+    #[inline]
+    pub fn enable_slave(self) -> Self {
+        Self(self.0 & !Self::SLAVE_DISABLE)
+    }
+    /// Disable slave mode.
+    #[inline]
+    pub fn disable_slave(self) -> Self {
+        Self(self.0 | Self::SLAVE_DISABLE)
+    }
+    /// Check if slave mode is enabled.
+    #[inline]
+    pub fn is_slave_enabled(self) -> bool {
+        self.0 & Self::SLAVE_DISABLE == 0
+    }
+    /// Only raise STOP_DET in slave mode when this slave is addressed.
+    #[inline]
+    pub fn enable_stop_detect_if_addressed(self) -> Self {
+        Self(self.0 | Self::STOP_DETECT_IF_ADDRESSED)
+    }
+    /// Raise STOP_DET in slave mode on every STOP condition on the bus.
+    #[inline]
+    pub fn disable_stop_detect_if_addressed(self) -> Self {
+        Self(self.0 & !Self::STOP_DETECT_IF_ADDRESSED)
+    }
+    /// Hold the bus by stretching SCL when receive FIFO is full.
+    #[inline]
+    pub fn enable_rx_fifo_full_hold(self) -> Self {
+        Self(self.0 | Self::RX_FIFO_FULL_HOLD)
+    }
+    /// Overflow receive FIFO instead of holding the bus when it is full.
+    #[inline]
+    pub fn disable_rx_fifo_full_hold(self) -> Self {
+        Self(self.0 & !Self::RX_FIFO_FULL_HOLD)
+    }
+    /// Check if the bus is held when receive FIFO is full.
+    #[inline]
+    pub fn is_rx_fifo_full_hold_enabled(self) -> bool {
+        self.0 & Self::RX_FIFO_FULL_HOLD != 0
+    }
+}
+
+/// Bus speed mode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpeedMode {
+    /// Standard mode, up to 100 kbit/s.
+    Standard = 1,
+    /// Fast mode or fast mode plus, up to 400 kbit/s or 1000 kbit/s.
+    Fast = 2,
+    /// High speed mode, up to 3.4 Mbit/s.
+    High = 3,
+}
+
+/// Target address register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct TargetAddress(u32);
+
+impl TargetAddress {
+    const ADDRESS: u32 = 0x3FF;
+
+    /// Set target address.
+    #[inline]
+    pub fn set_address(self, address: u16) -> Self {
+        Self((self.0 & !Self::ADDRESS) | (address as u32 & Self::ADDRESS))
+    }
+    /// Get target address.
+    #[inline]
+    pub fn address(self) -> u16 {
+        (self.0 & Self::ADDRESS) as u16
+    }
+}
+
+/// Rx/Tx data buffer and command register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct DataCommand(u32);
+
+impl DataCommand {
+    const DATA: u32 = 0xFF;
+    const READ: u32 = 1 << 8;
+    const STOP: u32 = 1 << 9;
+    const RESTART: u32 = 1 << 10;
+
+    /// Command to write a byte to the bus.
+    #[inline]
+    pub const fn write(data: u8) -> Self {
+        Self(data as u32)
+    }
+    /// Command to read a byte from the bus.
+    #[inline]
+    pub const fn read() -> Self {
+        Self(Self::READ)
+    }
+    /// Issue a STOP condition after this byte.
+    #[inline]
+    pub const fn stop(self) -> Self {
+        Self(self.0 | Self::STOP)
+    }
+    /// Issue a RESTART condition before this byte.
+    #[inline]
+    pub const fn restart(self) -> Self {
+        Self(self.0 | Self::RESTART)
+    }
+    /// Get received data byte.
+    #[inline]
+    pub const fn data(self) -> u8 {
+        (self.0 & Self::DATA) as u8
+    }
+}
+
+/// Interrupt status, mask or raw status register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct Interrupts(u32);
+
+impl Interrupts {
+    /// Check if the given interrupt is set.
+    #[inline]
+    pub const fn has_interrupt(self, val: Interrupt) -> bool {
+        self.0 & (val as u32) != 0
+    }
+    /// Set the given interrupt bit.
+    #[inline]
+    pub const fn set_interrupt(self, val: Interrupt) -> Self {
+        Self(self.0 | (val as u32))
+    }
+    /// Clear the given interrupt bit.
+    #[inline]
+    pub const fn clear_interrupt(self, val: Interrupt) -> Self {
+        Self(self.0 & !(val as u32))
+    }
+    /// Clear all interrupt bits.
+    #[inline]
+    pub const fn clear_all(self) -> Self {
+        Self(0)
+    }
+}
+
+/// I2C interrupt sources.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum Interrupt {
+    /// Receive buffer read while empty.
+    RxUnder = 1 << 0,
+    /// Receive buffer overflowed.
+    RxOver = 1 << 1,
+    /// Receive buffer reached or exceeded threshold.
+    RxFull = 1 << 2,
+    /// Transmit buffer overflowed.
+    TxOver = 1 << 3,
+    /// Transmit buffer reached or went below threshold.
+    TxEmpty = 1 << 4,
+    /// Remote master is attempting to read data from this slave.
+    ReadRequest = 1 << 5,
+    /// Transmit aborted.
+    TxAbort = 1 << 6,
+    /// Remote master did not acknowledge a transmitted byte at end of slave transmission.
+    RxDone = 1 << 7,
+    /// Controller activity.
+    Activity = 1 << 8,
+    /// STOP condition occurred.
+    StopDetect = 1 << 9,
+    /// START or RESTART condition occurred.
+    StartDetect = 1 << 10,
+    /// General call address received.
+    GeneralCall = 1 << 11,
+}
+
+/// Enable register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct Enable(u32);
+
+impl Enable {
+    const ENABLE: u32 = 1 << 0;
+    const ABORT: u32 = 1 << 1;
+
+    /// Enable the controller.
+    #[inline]
+    pub fn enable(self) -> Self {
+        Self(self.0 | Self::ENABLE)
+    }
+    /// Disable the controller.
+    #[inline]
+    pub fn disable(self) -> Self {
+        Self(self.0 & !Self::ENABLE)
+    }
+    /// Check if the controller is enabled.
+    #[inline]
+    pub fn is_enabled(self) -> bool {
+        self.0 & Self::ENABLE != 0
+    }
+    /// Abort current master transfer.
+    #[inline]
+    pub fn abort(self) -> Self {
+        Self(self.0 | Self::ABORT)
+    }
+}
+
+/// Status register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct Status(u32);
+
+impl Status {
+    const ACTIVITY: u32 = 1 << 0;
+    const TX_FIFO_NOT_FULL: u32 = 1 << 1;
+    const TX_FIFO_EMPTY: u32 = 1 << 2;
+    const RX_FIFO_NOT_EMPTY: u32 = 1 << 3;
+    const RX_FIFO_FULL: u32 = 1 << 4;
+    const MASTER_ACTIVITY: u32 = 1 << 5;
+    const SLAVE_ACTIVITY: u32 = 1 << 6;
+
+    /// Check if the controller is active.
+    #[inline]
+    pub fn is_active(self) -> bool {
+        self.0 & Self::ACTIVITY != 0
+    }
+    /// Check if transmit FIFO is not full.
+    #[inline]
+    pub fn is_tx_fifo_not_full(self) -> bool {
+        self.0 & Self::TX_FIFO_NOT_FULL != 0
+    }
+    /// Check if transmit FIFO is empty.
+    #[inline]
+    pub fn is_tx_fifo_empty(self) -> bool {
+        self.0 & Self::TX_FIFO_EMPTY != 0
+    }
+    /// Check if receive FIFO is not empty.
+    #[inline]
+    pub fn is_rx_fifo_not_empty(self) -> bool {
+        self.0 & Self::RX_FIFO_NOT_EMPTY != 0
+    }
+    /// Check if receive FIFO is full.
+    #[inline]
+    pub fn is_rx_fifo_full(self) -> bool {
+        self.0 & Self::RX_FIFO_FULL != 0
+    }
+    /// Check if master state machine is active.
+    #[inline]
+    pub fn is_master_active(self) -> bool {
+        self.0 & Self::MASTER_ACTIVITY != 0
+    }
+    /// Check if slave state machine is active.
+    #[inline]
+    pub fn is_slave_active(self) -> bool {
+        self.0 & Self::SLAVE_ACTIVITY != 0
+    }
+}
+
+/// Transmit abort source register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct TxAbortSource(u32);
+
+impl TxAbortSource {
+    const ADDRESS_NO_ACK: u32 = 0b111;
+    const DATA_NO_ACK: u32 = 1 << 3;
+    const ARBITRATION_LOST: u32 = 1 << 12;
+
+    /// Check if the address byte was not acknowledged.
+    #[inline]
+    pub fn is_address_no_ack(self) -> bool {
+        self.0 & Self::ADDRESS_NO_ACK != 0
+    }
+    /// Check if a data byte was not acknowledged.
+    #[inline]
+    pub fn is_data_no_ack(self) -> bool {
+        self.0 & Self::DATA_NO_ACK != 0
+    }
+    /// Check if the master has lost arbitration.
+    #[inline]
+    pub fn is_arbitration_lost(self) -> bool {
+        self.0 & Self::ARBITRATION_LOST != 0
+    }
+    /// Convert abort source into an I2C error.
+    #[inline]
+    pub fn error(self) -> Error {
+        if self.is_arbitration_lost() {
+            Error::ArbitrationLoss
+        } else if self.is_address_no_ack() {
+            Error::NoAcknowledge(NoAcknowledgeSource::Address)
+        } else if self.is_data_no_ack() {
+            Error::NoAcknowledge(NoAcknowledgeSource::Data)
+        } else {
+            Error::Abort(self.0)
+        }
+    }
+}
+
+/// Maximum number of read commands in flight, bounded by receive FIFO depth.
+const MAX_PENDING_READS: usize = 8;
+
+/// Managed I2C master peripheral.
+pub struct I2c<T, PADS> {
+    i2c: T,
+    pads: PADS,
+}
+
+impl<T: AsRef<RegisterBlock> + Controlled, PADS> I2c<T, PADS> {
+    /// Release I2C instance and return its peripheral and pads, turning its clocks off.
+    #[inline]
+    pub fn free(self) -> (T, PADS) {
+        let i2c = self.i2c.as_ref();
+        unsafe { i2c.enable.modify(|w| w.disable()) };
+        reset::disable(&self.i2c);
+        (self.i2c, self.pads)
+    }
+}
+
+/// Extension trait to create I2C masters from peripherals.
+pub trait I2cExt<const I: usize>: AsRef<RegisterBlock> + Controlled + Sized {
+    /// Configures the peripheral as an I2C master running on `pads`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `config.frequency` is zero.
+    #[inline]
+    fn i2c<PADS>(self, config: Config, pads: PADS, clocks: &Clocks) -> I2c<Self, PADS>
+    where
+        PADS: Pads<I>,
+    {
+        assert!(config.frequency != 0, "I2C bus frequency must be non-zero");
+        reset::enable(&self);
+        let i2c = self.as_ref();
+        let spike_length = 1;
+        let (high, low) = scl_counts(clocks.i2c().to_hz(), config.frequency, spike_length);
+        let speed = if config.frequency > 100_000 {
+            SpeedMode::Fast
+        } else {
+            SpeedMode::Standard
+        };
+        unsafe {
+            i2c.enable.modify(|w| w.disable());
+            i2c.control.write(
+                Control::default()
+                    .enable_master_mode()
+                    .set_speed(speed)
+                    .enable_restart()
+                    .disable_slave(),
+            );
+            i2c.fs_spike_length.write(spike_length);
+            match speed {
+                SpeedMode::Standard => {
+                    i2c.ss_scl_high_count.write(high);
+                    i2c.ss_scl_low_count.write(low);
+                }
+                _ => {
+                    i2c.fs_scl_high_count.write(high);
+                    i2c.fs_scl_low_count.write(low);
+                }
+            }
+            i2c.interrupt_mask.write(Interrupts::default());
+            i2c.rx_threshold.write(0);
+            i2c.tx_threshold.write(0);
+        }
+        I2c { i2c: self, pads }
+    }
+    /// Configures the peripheral as an I2C target (slave) running on `pads`.
+    ///
+    /// The target answers to `config.address`; SCL is stretched while it
+    /// waits for response data or while its receive FIFO is full.
+    #[inline]
+    fn i2c_target<PADS>(self, config: TargetConfig, pads: PADS) -> I2cTarget<Self, PADS>
+    where
+        PADS: Pads<I>,
+    {
+        reset::enable(&self);
+        let mut target = I2cTarget { i2c: self, pads };
+        let i2c = target.i2c.as_ref();
+        unsafe {
+            i2c.enable.modify(|w| w.disable());
+            i2c.control.write(
+                Control::default()
+                    .disable_master_mode()
+                    .set_speed(SpeedMode::Fast)
+                    .enable_restart()
+                    .enable_slave()
+                    .enable_stop_detect_if_addressed()
+                    .enable_rx_fifo_full_hold(),
+            );
+            i2c.interrupt_mask.write(Interrupts::default());
+            i2c.rx_threshold.write(0);
+            i2c.tx_threshold.write(0);
+        }
+        target.set_address(config.address);
+        target
+    }
+}
+
+/// Computes SCL high and low count register values.
+///
+/// The controller stretches the programmed high count by `spike_length + 7`
+/// cycles and the low count by one cycle; these are compensated here, while
+/// minimum high and low periods from the I2C specification are respected.
+fn scl_counts(clock: u32, frequency: u32, spike_length: u32) -> (u32, u32) {
+    // minimum (high, low) periods in nanoseconds
+    let (min_high, min_low) = match frequency {
+        0..=100_000 => (4000, 4700),
+        100_001..=400_000 => (600, 1300),
+        _ => (260, 500),
+    };
+    let ns_to_cycles = |ns: u64| ((clock as u64 * ns).div_ceil(1_000_000_000)) as u32;
+    let period = clock.div_ceil(frequency);
+    let high = ns_to_cycles(min_high);
+    let low = ns_to_cycles(min_low).max(period.saturating_sub(high));
+    let high_count = high.saturating_sub(spike_length + 7).max(spike_length + 5);
+    let low_count = (low - 1).max(spike_length + 7);
+    (high_count, low_count)
+}
+
+impl<T: AsRef<RegisterBlock>, PADS> I2c<T, PADS> {
+    #[inline]
+    fn set_target(&mut self, address: u16, ten_bit: bool) {
+        let i2c = self.i2c.as_ref();
+        unsafe {
+            i2c.enable.modify(|w| w.disable());
+            i2c.control.modify(|w| {
+                if ten_bit {
+                    w.enable_master_10bit_address()
+                } else {
+                    w.disable_master_10bit_address()
+                }
+            });
+            i2c.target_address.modify(|w| w.set_address(address));
+            i2c.enable.modify(|w| w.enable());
+        }
+    }
+    #[inline]
+    fn check_abort(&self) -> Result<(), Error> {
+        let i2c = self.i2c.as_ref();
+        if i2c
+            .raw_interrupt_status
+            .read()
+            .has_interrupt(Interrupt::TxAbort)
+        {
+            let source = i2c.tx_abort_source.read();
+            let _ = i2c.clear_tx_abort.read();
+            return Err(source.error());
+        }
+        Ok(())
+    }
+    fn transaction_inner(
+        &mut self,
+        address: u16,
+        ten_bit: bool,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Error> {
+        self.set_target(address, ten_bit);
+        let i2c = self.i2c.as_ref();
+        let _ = i2c.clear_interrupt.read();
+        let Some(last) = last_operation(operations) else {
+            return Err(Error::AddressOnly);
+        };
+        let mut previous_read = None;
+        let mut result = Ok(());
+        'ops: for (idx, op) in operations.iter_mut().enumerate().take(last + 1) {
+            let is_read = matches!(op, Operation::Read(_));
+            let len = operation_len(op);
+            if len == 0 {
+                continue;
+            }
+            let restart = previous_read.is_some_and(|prev| prev != is_read);
+            previous_read = Some(is_read);
+            let mut received = 0;
+            for n in 0..len {
+                let command = command(op, n, n == 0 && restart, idx == last && n == len - 1);
+                while !i2c.status.read().is_tx_fifo_not_full()
+                    || (is_read && n - received >= MAX_PENDING_READS)
+                {
+                    if let Err(e) = self.check_abort() {
+                        result = Err(e);
+                        break 'ops;
+                    }
+                    if let Operation::Read(buf) = op {
+                        while received < n && i2c.status.read().is_rx_fifo_not_empty() {
+                            buf[received] = i2c.data_command.read().data();
+                            received += 1;
+                        }
+                    }
+                    core::hint::spin_loop();
+                }
+                unsafe { i2c.data_command.write(command) };
+            }
+            if let Operation::Read(buf) = op {
+                while received < len {
+                    if let Err(e) = self.check_abort() {
+                        result = Err(e);
+                        break 'ops;
+                    }
+                    if i2c.status.read().is_rx_fifo_not_empty() {
+                        buf[received] = i2c.data_command.read().data();
+                        received += 1;
+                    }
+                }
+            }
+        }
+        loop {
+            let raw = i2c.raw_interrupt_status.read();
+            if raw.has_interrupt(Interrupt::StopDetect) {
+                let _ = i2c.clear_stop_detect.read();
+                break;
+            }
+            if result.is_ok() {
+                result = self.check_abort();
+            }
+            // a master that lost arbitration leaves the bus without issuing stop
+            if result == Err(Error::ArbitrationLoss) {
+                break;
+            }
+            core::hint::spin_loop();
+        }
+        if result.is_ok() {
+            result = self.check_abort();
+        }
+        result
+    }
+}
+
+/// Gets index of the last non-empty operation in a transaction.
+#[inline]
+fn last_operation(operations: &[Operation<'_>]) -> Option<usize> {
+    operations.iter().rposition(|op| operation_len(op) != 0)
+}
+
+#[inline]
+fn operation_len(op: &Operation<'_>) -> usize {
+    match op {
+        Operation::Read(buf) => buf.len(),
+        Operation::Write(buf) => buf.len(),
+    }
+}
+
+/// Builds the command for `n`-th byte of an operation.
+#[inline]
+fn command(op: &Operation<'_>, n: usize, restart: bool, stop: bool) -> DataCommand {
+    let mut command = match op {
+        Operation::Read(_) => DataCommand::read(),
+        Operation::Write(buf) => DataCommand::write(buf[n]),
+    };
+    if restart {
+        command = command.restart();
+    }
+    if stop {
+        command = command.stop();
+    }
+    command
+}
+
+impl embedded_hal::i2c::Error for Error {
+    #[inline]
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Error::NoAcknowledge(source) => ErrorKind::NoAcknowledge(*source),
+            Error::ArbitrationLoss => ErrorKind::ArbitrationLoss,
+            Error::Overrun => ErrorKind::Overrun,
+            Error::Abort(_) | Error::AddressOnly => ErrorKind::Other,
+        }
+    }
+}
+
+impl<T: AsRef<RegisterBlock>, PADS> embedded_hal::i2c::ErrorType for I2c<T, PADS> {
+    type Error = Error;
+}
+
+impl<T: AsRef<RegisterBlock>, PADS> embedded_hal::i2c::I2c<SevenBitAddress> for I2c<T, PADS> {
+    #[inline]
+    fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.transaction_inner(address as u16, false, operations)
+    }
+}
+
+impl<T: AsRef<RegisterBlock>, PADS> embedded_hal::i2c::I2c<TenBitAddress> for I2c<T, PADS> {
+    #[inline]
+    fn transaction(
+        &mut self,
+        address: TenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.transaction_inner(address, true, operations)
+    }
+}
+
+impl<T: AsRef<RegisterBlock>, PADS> I2c<T, PADS> {
+    /// Converts into an interrupt driven asynchronous I2C master.
+    ///
+    /// The interrupt handler of this I2C peripheral must call `state.on_interrupt()`.
+    #[inline]
+    pub fn into_async(self, state: &State) -> AsyncI2c<'_, T, PADS> {
+        let i2c = self.i2c.as_ref();
+        unsafe { i2c.interrupt_mask.write(Interrupts::default()) };
+        state
+            .registers
+            .store(i2c as *const _ as *mut _, Ordering::Release);
+        AsyncI2c { inner: self, state }
+    }
+}
+
+/// Shared state between an asynchronous I2C master and its interrupt handler.
+pub struct State {
+    waker: AtomicWaker,
+    registers: AtomicPtr<RegisterBlock>,
+}
+
+impl State {
+    /// Creates an empty state, usually placed in a `static`.
+    #[inline]
+    pub const fn new() -> Self {
+        State {
+            waker: AtomicWaker::new(),
+            registers: AtomicPtr::new(null_mut()),
+        }
+    }
+    /// Interrupt handler hook for the I2C peripheral.
+    ///
+    /// Masks pending interrupts and wakes the task waiting on them;
+    /// does nothing if no asynchronous I2C master is attached to this state.
+    #[inline]
+    pub fn on_interrupt(&self) {
+        let registers = self.registers.load(Ordering::Acquire);
+        if registers.is_null() {
+            return;
+        }
+        let i2c = unsafe { &*registers };
+        let pending = i2c.interrupt_status.read();
+        unsafe { i2c.interrupt_mask.modify(|w| Interrupts(w.0 & !pending.0)) };
+        self.waker.wake();
+    }
+}
+
+impl Default for State {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Interrupt driven asynchronous I2C master.
+pub struct AsyncI2c<'a, T: AsRef<RegisterBlock>, PADS> {
+    inner: I2c<T, PADS>,
+    state: &'a State,
+}
+
+impl<'a, T: AsRef<RegisterBlock>, PADS> AsyncI2c<'a, T, PADS> {
+    /// Converts back into a blocking I2C master.
+    #[inline]
+    pub fn into_blocking(self) -> I2c<T, PADS> {
+        let this = ManuallyDrop::new(self);
+        this.detach();
+        unsafe { core::ptr::read(&this.inner) }
+    }
+    /// Release I2C instance and return its peripheral and pads, turning its clocks off.
+    #[inline]
+    pub fn free(self) -> (T, PADS)
+    where
+        T: Controlled,
+    {
+        self.into_blocking().free()
+    }
+    #[inline]
+    fn detach(&self) {
+        let i2c = self.inner.i2c.as_ref();
+        unsafe { i2c.interrupt_mask.write(Interrupts::default()) };
+        self.state.registers.store(null_mut(), Ordering::Release);
+    }
+    /// Waits until `ready` holds, sleeping on `interrupt` meanwhile.
+    async fn wait(
+        &self,
+        interrupt: Interrupt,
+        ready: impl Fn(&RegisterBlock) -> bool,
+    ) -> Result<(), Error> {
+        let i2c = self.inner.i2c.as_ref();
+        poll_fn(|cx| {
+            self.inner.check_abort()?;
+            if ready(i2c) {
+                return Poll::Ready(Ok(()));
+            }
+            self.state.waker.register(cx.waker());
+            unsafe {
+                i2c.interrupt_mask
+                    .modify(|w| w.set_interrupt(interrupt).set_interrupt(Interrupt::TxAbort))
+            };
+            // condition may have changed before interrupt was unmasked
+            self.inner.check_abort()?;
+            if ready(i2c) {
+                return Poll::Ready(Ok(()));
+            }
+            Poll::Pending
+        })
+        .await
+    }
+    async fn transaction_inner(
+        &mut self,
+        address: u16,
+        ten_bit: bool,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Error> {
+        self.inner.set_target(address, ten_bit);
+        let result = self.transfer(operations).await;
+        let i2c = self.inner.i2c.as_ref();
+        unsafe { i2c.interrupt_mask.write(Interrupts::default()) };
+        result
+    }
+    async fn transfer(&mut self, operations: &mut [Operation<'_>]) -> Result<(), Error> {
+        let i2c = self.inner.i2c.as_ref();
+        let _ = i2c.clear_interrupt.read();
+        let Some(last) = last_operation(operations) else {
+            return Err(Error::AddressOnly);
+        };
+        let mut previous_read = None;
+        for (idx, op) in operations.iter_mut().enumerate().take(last + 1) {
+            let is_read = matches!(op, Operation::Read(_));
+            let len = operation_len(op);
+            if len == 0 {
+                continue;
+            }
+            let restart = previous_read.is_some_and(|prev| prev != is_read);
+            previous_read = Some(is_read);
+            let mut received = 0;
+            for n in 0..len {
+                let command = command(op, n, n == 0 && restart, idx == last && n == len - 1);
+                if let Operation::Read(buf) = op {
+                    if n - received >= MAX_PENDING_READS {
+                        received += self.receive(&mut buf[received..n]).await?;
+                    }
+                }
+                self.wait(Interrupt::TxEmpty, |i2c| {
+                    i2c.status.read().is_tx_fifo_not_full()
+                })
+                .await?;
+                unsafe { i2c.data_command.write(command) };
+            }
+            if let Operation::Read(buf) = op {
+                while received < len {
+                    received += self.receive(&mut buf[received..]).await?;
+                }
+            }
+        }
+        self.wait(Interrupt::StopDetect, |i2c| {
+            i2c.raw_interrupt_status
+                .read()
+                .has_interrupt(Interrupt::StopDetect)
+        })
+        .await?;
+        let _ = i2c.clear_stop_detect.read();
+        self.inner.check_abort()
+    }
+    /// Receives at least one byte into `buf`, returning number of bytes received.
+    async fn receive(&self, buf: &mut [u8]) -> Result<usize, Error> {
+        let i2c = self.inner.i2c.as_ref();
+        let threshold = buf.len().min(MAX_PENDING_READS) - 1;
+        unsafe { i2c.rx_threshold.write(threshold as u32) };
+        self.wait(Interrupt::RxFull, |i2c| {
+            i2c.status.read().is_rx_fifo_not_empty()
+        })
+        .await?;
+        let mut received = 0;
+        while received < buf.len() && i2c.status.read().is_rx_fifo_not_empty() {
+            buf[received] = i2c.data_command.read().data();
+            received += 1;
+        }
+        Ok(received)
+    }
+}
+
+impl<'a, T: AsRef<RegisterBlock>, PADS> Drop for AsyncI2c<'a, T, PADS> {
+    #[inline]
+    fn drop(&mut self) {
+        self.detach();
+    }
+}
+
+impl<'a, T: AsRef<RegisterBlock>, PADS> embedded_hal::i2c::ErrorType for AsyncI2c<'a, T, PADS> {
+    type Error = Error;
+}
+
+impl<'a, T: AsRef<RegisterBlock>, PADS> embedded_hal_async::i2c::I2c<SevenBitAddress>
+    for AsyncI2c<'a, T, PADS>
+{
+    #[inline]
+    async fn transaction(
+        &mut self,
+        address: SevenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.transaction_inner(address as u16, false, operations)
+            .await
+    }
+}
+
+impl<'a, T: AsRef<RegisterBlock>, PADS> embedded_hal_async::i2c::I2c<TenBitAddress>
+    for AsyncI2c<'a, T, PADS>
+{
+    #[inline]
+    async fn transaction(
+        &mut self,
+        address: TenBitAddress,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.transaction_inner(address, true, operations).await
+    }
+}
+
+/// Managed I2C target (slave) peripheral.
+pub struct I2cTarget<T, PADS> {
+    i2c: T,
+    pads: PADS,
+}
+
+impl<T: AsRef<RegisterBlock>, PADS> I2cTarget<T, PADS> {
+    /// Changes own address of this target.
+    #[inline]
+    pub fn set_address(&mut self, address: Address) {
+        let i2c = self.i2c.as_ref();
+        unsafe {
+            i2c.enable.modify(|w| w.disable());
+            match address {
+                Address::SevenBit(address) => {
+                    i2c.control.modify(|w| w.disable_slave_10bit_address());
+                    i2c.slave_address.write(address as u32);
+                }
+                Address::TenBit(address) => {
+                    i2c.control.modify(|w| w.enable_slave_10bit_address());
+                    i2c.slave_address.write(address as u32 & 0x3FF);
+                }
+            }
+            i2c.enable.modify(|w| w.enable());
+        }
+    }
+    /// Checks for a pending bus event without blocking.
+    ///
+    /// Received data is reported before a read request, so that a
+    /// write-then-read transaction is observed in bus order.
+    #[inline]
+    pub fn poll_event(&mut self) -> Option<TargetEvent> {
+        let i2c = self.i2c.as_ref();
+        let raw = i2c.raw_interrupt_status.read();
+        if raw.has_interrupt(Interrupt::TxAbort) {
+            // transmit FIFO flushed after master ended a read early
+            let _ = i2c.clear_tx_abort.read();
+        }
+        if i2c.status.read().is_rx_fifo_not_empty() {
+            Some(TargetEvent::Write)
+        } else if raw.has_interrupt(Interrupt::ReadRequest) {
+            let _ = i2c.clear_read_request.read();
+            Some(TargetEvent::Read)
+        } else if raw.has_interrupt(Interrupt::GeneralCall) {
+            let _ = i2c.clear_general_call.read();
+            Some(TargetEvent::GeneralCall)
+        } else if raw.has_interrupt(Interrupt::StopDetect) {
+            let _ = i2c.clear_stop_detect.read();
+            Some(TargetEvent::Stop)
+        } else {
+            None
+        }
+    }
+    /// Blocks until the next bus event.
+    #[inline]
+    pub fn wait_event(&mut self) -> TargetEvent {
+        loop {
+            if let Some(event) = self.poll_event() {
+                return event;
+            }
+            core::hint::spin_loop();
+        }
+    }
+    /// Reads data written by the master into `buf`, returning number of bytes read.
+    ///
+    /// Only bytes already in the receive FIFO are read; this function does not block.
+    #[inline]
+    pub fn read(&mut self, buf: &mut [u8]) -> usize {
+        let i2c = self.i2c.as_ref();
+        let mut len = 0;
+        while len < buf.len() && i2c.status.read().is_rx_fifo_not_empty() {
+            buf[len] = i2c.data_command.read().data();
+            len += 1;
+        }
+        len
+    }
+    /// Queues response data for a read request, returning number of bytes queued.
+    ///
+    /// SCL is stretched until the first byte is queued. Bytes not fetched by the
+    /// master before it ends the transfer are flushed by the controller.
+    #[inline]
+    pub fn respond(&mut self, data: &[u8]) -> usize {
+        let i2c = self.i2c.as_ref();
+        let mut len = 0;
+        while len < data.len() && i2c.status.read().is_tx_fifo_not_full() {
+            unsafe { i2c.data_command.write(DataCommand::write(data[len])) };
+            len += 1;
+        }
+        len
+    }
+    /// Enables interrupts of the given target events.
+    #[inline]
+    pub fn listen(&mut self, event: TargetEvent) {
+        let i2c = self.i2c.as_ref();
+        unsafe {
+            i2c.interrupt_mask
+                .modify(|w| w.set_interrupt(event.interrupt()))
+        };
+    }
+    /// Disables interrupts of the given target events.
+    #[inline]
+    pub fn unlisten(&mut self, event: TargetEvent) {
+        let i2c = self.i2c.as_ref();
+        unsafe {
+            i2c.interrupt_mask
+                .modify(|w| w.clear_interrupt(event.interrupt()))
+        };
+    }
+    /// Release I2C target instance and return its peripheral and pads, turning its clocks off.
+    #[inline]
+    pub fn free(self) -> (T, PADS)
+    where
+        T: Controlled,
+    {
+        let i2c = self.i2c.as_ref();
+        unsafe {
+            i2c.enable.modify(|w| w.disable());
+            i2c.interrupt_mask.write(Interrupts::default());
+        }
+        reset::disable(&self.i2c);
+        (self.i2c, self.pads)
+    }
+}
+
+/// I2C target (slave) configuration.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TargetConfig {
+    /// Own address of this target.
+    pub address: Address,
+}
+
+/// I2C target own address.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Address {
+    /// 7-bit address.
+    SevenBit(SevenBitAddress),
+    /// 10-bit address.
+    TenBit(TenBitAddress),
+}
+
+/// I2C target bus events.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TargetEvent {
+    /// Master has written data to this target; fetch it with `read`.
+    Write,
+    /// Master requests data from this target; answer it with `respond`.
+    Read,
+    /// General call address received.
+    GeneralCall,
+    /// Transaction addressed to this target has ended with a STOP condition.
+    Stop,
+}
+
+impl TargetEvent {
+    #[inline]
+    const fn interrupt(self) -> Interrupt {
+        match self {
+            TargetEvent::Write => Interrupt::RxFull,
+            TargetEvent::Read => Interrupt::ReadRequest,
+            TargetEvent::GeneralCall => Interrupt::GeneralCall,
+            TargetEvent::Stop => Interrupt::StopDetect,
+        }
+    }
+}
+
+/// I2C configuration.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Config {
+    /// SCL clock frequency in Hz.
+    ///
+    /// Frequencies up to 100 kHz use standard mode, up to 400 kHz fast mode,
+    /// and above that fast mode plus up to 1 MHz.
+    pub frequency: u32,
+}
+
+impl Default for Config {
+    /// I2C configuration defaults to standard mode at 100 kHz.
+    #[inline]
+    fn default() -> Self {
+        Config { frequency: 100_000 }
+    }
+}
+
+/// I2C error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// Address or data byte not acknowledged.
+    NoAcknowledge(NoAcknowledgeSource),
+    /// Arbitration lost to another master.
+    ArbitrationLoss,
+    /// Receive buffer overrun.
+    Overrun,
+    /// Transfer aborted for another reason, carrying raw abort source.
+    Abort(u32),
+    /// Transaction without data bytes; the controller cannot issue address-only transfers.
+    AddressOnly,
+}
+
+/// Valid I2C pads.
+pub trait Pads<const I: usize> {}
+
+impl<T1, T2> Pads<0> for (Pad<T1, 28, I2cFunc<0>>, Pad<T2, 29, I2cFunc<0>>) {}
+
+impl<T1, T2> Pads<1> for (Pad<T1, 7, I2cFunc<1>>, Pad<T2, 6, I2cFunc<1>>) {}
+
+impl<T1, T2> Pads<1> for (Pad<T1, 11, I2cFunc<1>>, Pad<T2, 10, I2cFunc<1>>) {}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        scl_counts, Address, DataCommand, Error, I2c, I2cTarget, Interrupt, Interrupts,
+        RegisterBlock, State, TargetEvent,
+    };
+    use core::{
+        cell::UnsafeCell,
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+    use embedded_hal::i2c::{NoAcknowledgeSource, Operation};
+    use embedded_hal_async::i2c::I2c as _;
+    use memoffset::offset_of;
+
+    #[test]
+    fn struct_register_block_offset() {
+        assert_eq!(offset_of!(RegisterBlock, control), 0x00);
+        assert_eq!(offset_of!(RegisterBlock, target_address), 0x04);
+        assert_eq!(offset_of!(RegisterBlock, slave_address), 0x08);
+        assert_eq!(offset_of!(RegisterBlock, data_command), 0x10);
+        assert_eq!(offset_of!(RegisterBlock, ss_scl_high_count), 0x14);
+        assert_eq!(offset_of!(RegisterBlock, fs_scl_low_count), 0x20);
+        assert_eq!(offset_of!(RegisterBlock, interrupt_status), 0x2C);
+        assert_eq!(offset_of!(RegisterBlock, interrupt_mask), 0x30);
+        assert_eq!(offset_of!(RegisterBlock, raw_interrupt_status), 0x34);
+        assert_eq!(offset_of!(RegisterBlock, rx_threshold), 0x38);
+        assert_eq!(offset_of!(RegisterBlock, tx_threshold), 0x3C);
+        assert_eq!(offset_of!(RegisterBlock, clear_interrupt), 0x40);
+        assert_eq!(offset_of!(RegisterBlock, clear_tx_abort), 0x54);
+        assert_eq!(offset_of!(RegisterBlock, clear_stop_detect), 0x60);
+        assert_eq!(offset_of!(RegisterBlock, clear_general_call), 0x68);
+        assert_eq!(offset_of!(RegisterBlock, enable), 0x6C);
+        assert_eq!(offset_of!(RegisterBlock, status), 0x70);
+        assert_eq!(offset_of!(RegisterBlock, tx_fifo_level), 0x74);
+        assert_eq!(offset_of!(RegisterBlock, rx_fifo_level), 0x78);
+        assert_eq!(offset_of!(RegisterBlock, sda_hold), 0x7C);
+        assert_eq!(offset_of!(RegisterBlock, tx_abort_source), 0x80);
+        assert_eq!(offset_of!(RegisterBlock, dma_control), 0x88);
+        assert_eq!(offset_of!(RegisterBlock, sda_setup), 0x94);
+        assert_eq!(offset_of!(RegisterBlock, enable_status), 0x9C);
+        assert_eq!(offset_of!(RegisterBlock, fs_spike_length), 0xA0);
+        assert_eq!(offset_of!(RegisterBlock, hs_spike_length), 0xA4);
+    }
+
+    #[test]
+    fn scl_counts_respect_bus_timing() {
+        // 100 MHz controller clock, standard mode
+        let (high, low) = scl_counts(100_000_000, 100_000, 1);
+        assert!(high + 1 + 7 >= 400);
+        assert!(low + 1 >= 470);
+        assert!(high + 1 + 7 + low + 1 >= 1000);
+        // fast mode plus
+        let (high, low) = scl_counts(100_000_000, 1_000_000, 1);
+        assert!(high + 1 + 7 >= 26);
+        assert!(low + 1 >= 50);
+        assert_eq!(high + 1 + 7 + low + 1, 100);
+    }
+
+    /// Simulated I2C register block living in host memory.
+    struct Sim(RegisterBlock);
+
+    impl AsRef<RegisterBlock> for Sim {
+        fn as_ref(&self) -> &RegisterBlock {
+            &self.0
+        }
+    }
+
+    impl Sim {
+        fn new() -> Self {
+            Sim(unsafe { core::mem::zeroed() })
+        }
+        /// Forces a register value as if written by hardware.
+        fn set<R>(register: &R, value: u32) {
+            // registers are backed by `UnsafeCell`, thus writable behind shared references
+            let cell = unsafe { &*(register as *const R).cast::<UnsafeCell<u32>>() };
+            unsafe { cell.get().write_volatile(value) }
+        }
+        fn raise(&self, interrupt: Interrupt) {
+            let raw = self.0.raw_interrupt_status.read().set_interrupt(interrupt);
+            Self::set(&self.0.raw_interrupt_status, raw.0);
+            let masked = Interrupts(raw.0 & self.0.interrupt_mask.read().0);
+            Self::set(&self.0.interrupt_status, masked.0);
+        }
+    }
+
+    const TX_FIFO_NOT_FULL: u32 = 1 << 1;
+    const RX_FIFO_NOT_EMPTY: u32 = 1 << 3;
+
+    fn poll<F: Future>(f: core::pin::Pin<&mut F>) -> Poll<F::Output> {
+        f.poll(&mut Context::from_waker(Waker::noop()))
+    }
+
+    #[test]
+    fn empty_transaction_is_rejected() {
+        let sim = Sim::new();
+        let mut i2c = I2c {
+            i2c: &sim,
+            pads: (),
+        };
+        let result = i2c.transaction_inner(0x50, false, &mut [Operation::Write(&[])]);
+        assert_eq!(result, Err(Error::AddressOnly));
+        assert_eq!(sim.0.data_command.read().0, 0);
+    }
+
+    #[test]
+    fn arbitration_loss_does_not_wait_for_stop() {
+        let sim = Sim::new();
+        Sim::set(&sim.0.status, TX_FIFO_NOT_FULL);
+        Sim::set(&sim.0.tx_abort_source, 1 << 12);
+        sim.raise(Interrupt::TxAbort);
+        let mut i2c = I2c {
+            i2c: &sim,
+            pads: (),
+        };
+        let result = i2c.transaction_inner(0x50, false, &mut [Operation::Write(&[0xA5])]);
+        assert_eq!(result, Err(Error::ArbitrationLoss));
+    }
+
+    #[test]
+    fn async_write_completes_on_stop_detect() {
+        let sim = Sim::new();
+        Sim::set(&sim.0.status, TX_FIFO_NOT_FULL);
+        let state = State::new();
+        let mut i2c = I2c {
+            i2c: &sim,
+            pads: (),
+        }
+        .into_async(&state);
+        let mut fut = pin!(i2c.write(0x50u8, &[0xA5]));
+        assert!(poll(fut.as_mut()).is_pending());
+        assert_eq!(sim.0.data_command.read(), DataCommand::write(0xA5).stop());
+        let mask = sim.0.interrupt_mask.read();
+        assert!(mask.has_interrupt(Interrupt::StopDetect));
+        assert!(mask.has_interrupt(Interrupt::TxAbort));
+        assert_eq!(sim.0.target_address.read().address(), 0x50);
+
+        sim.raise(Interrupt::StopDetect);
+        state.on_interrupt();
+        assert!(!sim
+            .0
+            .interrupt_mask
+            .read()
+            .has_interrupt(Interrupt::StopDetect));
+        assert_eq!(poll(fut.as_mut()), Poll::Ready(Ok(())));
+    }
+
+    #[test]
+    fn async_address_nack_maps_to_error() {
+        let sim = Sim::new();
+        Sim::set(&sim.0.status, TX_FIFO_NOT_FULL);
+        let state = State::new();
+        let mut i2c = I2c {
+            i2c: &sim,
+            pads: (),
+        }
+        .into_async(&state);
+        let mut fut = pin!(i2c.write(0x50u8, &[0x00, 0x01]));
+        assert!(poll(fut.as_mut()).is_pending());
+
+        Sim::set(&sim.0.tx_abort_source, 1 << 0);
+        sim.raise(Interrupt::TxAbort);
+        state.on_interrupt();
+        assert_eq!(
+            poll(fut.as_mut()),
+            Poll::Ready(Err(Error::NoAcknowledge(NoAcknowledgeSource::Address)))
+        );
+    }
+
+    #[test]
+    fn async_read_uses_rx_threshold() {
+        let sim = Sim::new();
+        Sim::set(&sim.0.status, TX_FIFO_NOT_FULL);
+        let state = State::new();
+        let mut i2c = I2c {
+            i2c: &sim,
+            pads: (),
+        }
+        .into_async(&state);
+        let mut buf = [0u8; 3];
+        {
+            let mut fut = pin!(i2c.read(0x50u8, &mut buf));
+            assert!(poll(fut.as_mut()).is_pending());
+            assert_eq!(sim.0.data_command.read(), DataCommand::read().stop());
+            assert_eq!(sim.0.rx_threshold.read(), 2);
+            assert!(sim.0.interrupt_mask.read().has_interrupt(Interrupt::RxFull));
+
+            Sim::set(&sim.0.status, TX_FIFO_NOT_FULL | RX_FIFO_NOT_EMPTY);
+            Sim::set(&sim.0.data_command, 0x5A);
+            sim.raise(Interrupt::RxFull);
+            state.on_interrupt();
+            assert!(poll(fut.as_mut()).is_pending());
+            assert!(sim
+                .0
+                .interrupt_mask
+                .read()
+                .has_interrupt(Interrupt::StopDetect));
+
+            sim.raise(Interrupt::StopDetect);
+            state.on_interrupt();
+            assert_eq!(poll(fut.as_mut()), Poll::Ready(Ok(())));
+        }
+        assert_eq!(buf, [0x5A; 3]);
+    }
+
+    #[test]
+    fn target_reports_write_before_read_request() {
+        let sim = Sim::new();
+        Sim::set(&sim.0.status, TX_FIFO_NOT_FULL | RX_FIFO_NOT_EMPTY);
+        Sim::set(&sim.0.data_command, 0x12);
+        let mut target = I2cTarget {
+            i2c: &sim,
+            pads: (),
+        };
+        target.set_address(Address::TenBit(0x2A5));
+        assert!(sim.0.control.read().is_slave_enabled());
+        assert_eq!(sim.0.slave_address.read(), 0x2A5);
+
+        sim.raise(Interrupt::ReadRequest);
+        assert_eq!(target.poll_event(), Some(TargetEvent::Write));
+        let mut buf = [0u8; 1];
+        assert_eq!(target.read(&mut buf), 1);
+        assert_eq!(buf, [0x12]);
+
+        Sim::set(&sim.0.status, TX_FIFO_NOT_FULL);
+        assert_eq!(target.poll_event(), Some(TargetEvent::Read));
+        assert_eq!(target.respond(&[0x34]), 1);
+        assert_eq!(sim.0.data_command.read(), DataCommand::write(0x34));
+    }
+
+    #[test]
+    fn on_interrupt_without_driver_does_nothing() {
+        let state = State::new();
+        state.on_interrupt();
+    }
+}
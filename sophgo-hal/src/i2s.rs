@@ -0,0 +1,1052 @@
+//! Inter-IC Sound bus.
+
+use crate::clock::Clocks;
+use crate::dma;
+use crate::pad::{I2sFunc, Pad};
+use crate::reset::{self, Controlled};
+use atomic_waker::AtomicWaker;
+use core::{
+    future::poll_fn,
+    mem::ManuallyDrop,
+    ptr::null_mut,
+    sync::atomic::{AtomicPtr, Ordering},
+    task::Poll,
+};
+use embedded_dma::WriteBuffer;
+use volatile_register::{RO, RW, WO};
+
+/// Inter-IC Sound controller registers.
+#[repr(C)]
+pub struct RegisterBlock {
+    /// Block mode register.
+    pub block_mode: RW<BlockMode>,
+    /// Frame sync setting register.
+    pub frame: RW<FrameSetting>,
+    /// Slot setting register.
+    pub slot: RW<SlotSetting>,
+    /// Slot enable register, one bit per slot.
+    pub slot_enable: RW<u32>,
+    /// Data format register.
+    pub data_format: RW<u32>,
+    /// Block configuration register.
+    pub block_config: RW<u32>,
+    /// Enable register.
+    pub enable: RW<u32>,
+    /// Reset register; bit 0 resets receiver, bit 1 resets transmitter.
+    pub reset: RW<u32>,
+    /// Interrupt enable register.
+    pub interrupt_enable: RW<Interrupts>,
+    /// Interrupt status register; write 1 to clear.
+    pub interrupt_status: RW<Interrupts>,
+    /// FIFO threshold register; receive threshold in bits 4:0, transmit threshold in bits 20:16.
+    pub fifo_threshold: RW<u32>,
+    /// Frame clock master register.
+    pub lrck_master: RW<u32>,
+    /// FIFO reset register; bit 0 flushes receive FIFO, bit 16 flushes transmit FIFO.
+    pub fifo_reset: RW<u32>,
+    _reserved0: [u8; 0xC],
+    /// Receive FIFO status register.
+    pub rx_status: RO<FifoStatus>,
+    _reserved1: [u8; 0x4],
+    /// Transmit FIFO status register.
+    pub tx_status: RO<FifoStatus>,
+    _reserved2: [u8; 0x14],
+    /// Clock control register 0.
+    pub clock_control0: RW<ClockControl0>,
+    /// Clock control register 1.
+    pub clock_control1: RW<ClockControl1>,
+    _reserved3: [u8; 0x18],
+    /// Receive FIFO data port.
+    pub rx_data: RO<u32>,
+    _reserved4: [u8; 0x3C],
+    /// Transmit FIFO data port.
+    pub tx_data: WO<u32>,
+}
+
+/// Block mode register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct BlockMode(u32);
+
+impl BlockMode {
+    const TRANSMIT: u32 = 1 << 0;
+    const MASTER: u32 = 1 << 1;
+    const DMA: u32 = 1 << 4;
+
+    /// Set data direction.
+    #[inline]
+    pub const fn set_direction(self, direction: Direction) -> Self {
+        match direction {
+            Direction::Transmit => Self(self.0 | Self::TRANSMIT),
+            Direction::Receive => Self(self.0 & !Self::TRANSMIT),
+        }
+    }
+    /// Get data direction.
+    #[inline]
+    pub const fn direction(self) -> Direction {
+        if self.0 & Self::TRANSMIT != 0 {
+            Direction::Transmit
+        } else {
+            Direction::Receive
+        }
+    }
+    /// Set bus role.
+    #[inline]
+    pub const fn set_role(self, role: Role) -> Self {
+        match role {
+            Role::Master => Self(self.0 | Self::MASTER),
+            Role::Slave => Self(self.0 & !Self::MASTER),
+        }
+    }
+    /// Get bus role.
+    #[inline]
+    pub const fn role(self) -> Role {
+        if self.0 & Self::MASTER != 0 {
+            Role::Master
+        } else {
+            Role::Slave
+        }
+    }
+    /// Enable DMA handshake on FIFO thresholds.
+    #[inline]
+    pub const fn enable_dma(self) -> Self {
+        Self(self.0 | Self::DMA)
+    }
+    /// Disable DMA handshake.
+    #[inline]
+    pub const fn disable_dma(self) -> Self {
+        Self(self.0 & !Self::DMA)
+    }
+    /// Check if DMA handshake is enabled.
+    #[inline]
+    pub const fn is_dma_enabled(self) -> bool {
+        self.0 & Self::DMA != 0
+    }
+}
+
+/// Frame sync setting register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct FrameSetting(u32);
+
+impl FrameSetting {
+    const FRAME_LENGTH: u32 = 0x1FF;
+    const ACTIVE_LENGTH: u32 = 0xFF << 16;
+    const ACTIVE_HIGH: u32 = 1 << 24;
+    const EARLY: u32 = 1 << 25;
+
+    /// Set number of bit clocks per frame, from 1 to 512.
+    #[inline]
+    pub const fn set_frame_length(self, bits: u16) -> Self {
+        Self((self.0 & !Self::FRAME_LENGTH) | ((bits as u32 - 1) & Self::FRAME_LENGTH))
+    }
+    /// Get number of bit clocks per frame.
+    #[inline]
+    pub const fn frame_length(self) -> u16 {
+        (self.0 & Self::FRAME_LENGTH) as u16 + 1
+    }
+    /// Set number of bit clocks frame sync stays active, from 1 to 256.
+    #[inline]
+    pub const fn set_active_length(self, bits: u16) -> Self {
+        Self((self.0 & !Self::ACTIVE_LENGTH) | (((bits as u32 - 1) & 0xFF) << 16))
+    }
+    /// Get number of bit clocks frame sync stays active.
+    #[inline]
+    pub const fn active_length(self) -> u16 {
+        ((self.0 & Self::ACTIVE_LENGTH) >> 16) as u16 + 1
+    }
+    /// Frame sync is high while active.
+    #[inline]
+    pub const fn set_active_high(self) -> Self {
+        Self(self.0 | Self::ACTIVE_HIGH)
+    }
+    /// Frame sync is low while active.
+    #[inline]
+    pub const fn set_active_low(self) -> Self {
+        Self(self.0 & !Self::ACTIVE_HIGH)
+    }
+    /// Check if frame sync is high while active.
+    #[inline]
+    pub const fn is_active_high(self) -> bool {
+        self.0 & Self::ACTIVE_HIGH != 0
+    }
+    /// Frame sync becomes active one bit clock before first data bit.
+    #[inline]
+    pub const fn enable_early(self) -> Self {
+        Self(self.0 | Self::EARLY)
+    }
+    /// Frame sync becomes active with first data bit.
+    #[inline]
+    pub const fn disable_early(self) -> Self {
+        Self(self.0 & !Self::EARLY)
+    }
+    /// Check if frame sync becomes active one bit clock early.
+    #[inline]
+    pub const fn is_early(self) -> bool {
+        self.0 & Self::EARLY != 0
+    }
+}
+
+/// Slot setting register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct SlotSetting(u32);
+
+impl SlotSetting {
+    const SLOT_COUNT: u32 = 0xF;
+    const SLOT_SIZE: u32 = 0x3F << 8;
+    const DATA_SIZE: u32 = 0x1F << 16;
+
+    /// Set number of slots per frame, from 1 to 16.
+    #[inline]
+    pub const fn set_slot_count(self, slots: u8) -> Self {
+        Self((self.0 & !Self::SLOT_COUNT) | ((slots as u32 - 1) & Self::SLOT_COUNT))
+    }
+    /// Get number of slots per frame.
+    #[inline]
+    pub const fn slot_count(self) -> u8 {
+        (self.0 & Self::SLOT_COUNT) as u8 + 1
+    }
+    /// Set number of bit clocks per slot, from 1 to 64.
+    #[inline]
+    pub const fn set_slot_size(self, bits: u8) -> Self {
+        Self((self.0 & !Self::SLOT_SIZE) | (((bits as u32 - 1) & 0x3F) << 8))
+    }
+    /// Get number of bit clocks per slot.
+    #[inline]
+    pub const fn slot_size(self) -> u8 {
+        ((self.0 & Self::SLOT_SIZE) >> 8) as u8 + 1
+    }
+    /// Set number of valid data bits per slot, from 1 to 32.
+    #[inline]
+    pub const fn set_data_size(self, bits: u8) -> Self {
+        Self((self.0 & !Self::DATA_SIZE) | (((bits as u32 - 1) & 0x1F) << 16))
+    }
+    /// Get number of valid data bits per slot.
+    #[inline]
+    pub const fn data_size(self) -> u8 {
+        ((self.0 & Self::DATA_SIZE) >> 16) as u8 + 1
+    }
+}
+
+/// Interrupt enable and status register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct Interrupts(u32);
+
+impl Interrupts {
+    /// Enable or set an interrupt.
+    #[inline]
+    pub const fn set_interrupt(self, interrupt: Interrupt) -> Self {
+        Self(self.0 | (1 << interrupt as u32))
+    }
+    /// Disable or clear an interrupt.
+    #[inline]
+    pub const fn clear_interrupt(self, interrupt: Interrupt) -> Self {
+        Self(self.0 & !(1 << interrupt as u32))
+    }
+    /// Check if an interrupt is enabled or pending.
+    #[inline]
+    pub const fn has_interrupt(self, interrupt: Interrupt) -> bool {
+        self.0 & (1 << interrupt as u32) != 0
+    }
+}
+
+/// I2S interrupt sources.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interrupt {
+    /// Receive FIFO level reached threshold.
+    RxThreshold = 0,
+    /// Receive FIFO overflowed; samples were dropped.
+    RxOverflow = 1,
+    /// Receive FIFO was read while empty.
+    RxUnderflow = 2,
+    /// Transmit FIFO level fell to threshold.
+    TxThreshold = 4,
+    /// Transmit FIFO was written while full.
+    TxOverflow = 5,
+    /// Transmit FIFO ran empty while a sample was due.
+    TxUnderflow = 6,
+}
+
+/// FIFO status register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct FifoStatus(u32);
+
+impl FifoStatus {
+    const LEVEL: u32 = 0x7F;
+
+    /// Number of samples in FIFO.
+    #[inline]
+    pub const fn level(self) -> usize {
+        (self.0 & Self::LEVEL) as usize
+    }
+}
+
+/// Clock control register 0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct ClockControl0(u32);
+
+impl ClockControl0 {
+    const BCLK_OUTPUT: u32 = 1 << 6;
+    const MCLK_OUTPUT: u32 = 1 << 7;
+    const AUDIO_CLOCK: u32 = 1 << 8;
+
+    /// Drive bit clock and frame sync as bus master.
+    #[inline]
+    pub const fn enable_bclk_output(self) -> Self {
+        Self(self.0 | Self::BCLK_OUTPUT)
+    }
+    /// Check if bit clock output is enabled.
+    #[inline]
+    pub const fn is_bclk_output_enabled(self) -> bool {
+        self.0 & Self::BCLK_OUTPUT != 0
+    }
+    /// Drive master clock output.
+    #[inline]
+    pub const fn enable_mclk_output(self) -> Self {
+        Self(self.0 | Self::MCLK_OUTPUT)
+    }
+    /// Check if master clock output is enabled.
+    #[inline]
+    pub const fn is_mclk_output_enabled(self) -> bool {
+        self.0 & Self::MCLK_OUTPUT != 0
+    }
+    /// Enable audio clock from audio PLL.
+    #[inline]
+    pub const fn enable_audio_clock(self) -> Self {
+        Self(self.0 | Self::AUDIO_CLOCK)
+    }
+    /// Check if audio clock is enabled.
+    #[inline]
+    pub const fn is_audio_clock_enabled(self) -> bool {
+        self.0 & Self::AUDIO_CLOCK != 0
+    }
+}
+
+/// Clock control register 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct ClockControl1(u32);
+
+impl ClockControl1 {
+    const BCLK_DIVIDER: u32 = 0xFFFF;
+    const MCLK_DIVIDER: u32 = 0xFFFF << 16;
+
+    /// Set bit clock divider of master clock.
+    #[inline]
+    pub const fn set_bclk_divider(self, divider: u16) -> Self {
+        Self((self.0 & !Self::BCLK_DIVIDER) | divider as u32)
+    }
+    /// Get bit clock divider.
+    #[inline]
+    pub const fn bclk_divider(self) -> u16 {
+        (self.0 & Self::BCLK_DIVIDER) as u16
+    }
+    /// Set master clock divider of audio PLL.
+    #[inline]
+    pub const fn set_mclk_divider(self, divider: u16) -> Self {
+        Self((self.0 & !Self::MCLK_DIVIDER) | ((divider as u32) << 16))
+    }
+    /// Get master clock divider.
+    #[inline]
+    pub const fn mclk_divider(self) -> u16 {
+        ((self.0 & Self::MCLK_DIVIDER) >> 16) as u16
+    }
+}
+
+/// Data direction of an I2S controller.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// Controller sends samples.
+    Transmit,
+    /// Controller receives samples.
+    Receive,
+}
+
+/// Bus role of an I2S controller.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    /// Controller drives bit clock and frame sync.
+    Master,
+    /// Bit clock and frame sync come from another device.
+    Slave,
+}
+
+/// Frame format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Philips I2S, two channels, left channel while frame sync is low, data one bit clock late.
+    Standard,
+    /// Two channels, left channel while frame sync is high, data aligned to frame sync.
+    LeftJustified,
+    /// Time division multiplexed `slots` channels, one bit clock frame sync pulse before first slot.
+    Tdm {
+        /// Number of channels per frame, from 1 to 16.
+        slots: u8,
+    },
+}
+
+/// Sample width.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SampleWidth {
+    /// 16-bit samples in 16-bit slots.
+    Bits16,
+    /// 24-bit samples in 32-bit slots.
+    Bits24,
+    /// 32-bit samples in 32-bit slots.
+    Bits32,
+}
+
+impl SampleWidth {
+    /// Number of valid data bits.
+    #[inline]
+    pub const fn data_bits(self) -> u8 {
+        match self {
+            SampleWidth::Bits16 => 16,
+            SampleWidth::Bits24 => 24,
+            SampleWidth::Bits32 => 32,
+        }
+    }
+    /// Number of bit clocks per slot.
+    #[inline]
+    pub const fn slot_bits(self) -> u8 {
+        match self {
+            SampleWidth::Bits16 => 16,
+            SampleWidth::Bits24 | SampleWidth::Bits32 => 32,
+        }
+    }
+}
+
+/// I2S configuration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Config {
+    /// Data direction.
+    pub direction: Direction,
+    /// Bus role.
+    pub role: Role,
+    /// Frame format.
+    pub format: Format,
+    /// Sample width.
+    pub sample_width: SampleWidth,
+    /// Frames per second in master role.
+    pub sample_rate: u32,
+}
+
+impl Default for Config {
+    /// I2S configuration defaults to transmitting master, Philips format, 16-bit samples at 48 kHz.
+    #[inline]
+    fn default() -> Self {
+        Config {
+            direction: Direction::Transmit,
+            role: Role::Master,
+            format: Format::Standard,
+            sample_width: SampleWidth::Bits16,
+            sample_rate: 48_000,
+        }
+    }
+}
+
+/// I2S error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// Receive FIFO overflowed; samples were dropped.
+    Overrun,
+    /// Transmit FIFO ran empty; silence was sent.
+    Underrun,
+}
+
+/// Depth of transmit and receive FIFOs in samples.
+pub const FIFO_DEPTH: usize = 32;
+
+/// Maximum number of slots in a TDM frame.
+pub const MAX_SLOTS: u8 = 16;
+
+/// Frame sync and slot settings with slot enable mask of a frame format.
+#[inline]
+const fn frame_settings(format: Format, width: SampleWidth) -> (FrameSetting, SlotSetting, u32) {
+    let slot_bits = width.slot_bits();
+    let slots = match format {
+        Format::Standard | Format::LeftJustified => 2,
+        Format::Tdm { slots } => slots,
+    };
+    let frame = FrameSetting(0).set_frame_length(slot_bits as u16 * slots as u16);
+    let frame = match format {
+        Format::Standard => frame
+            .set_active_length(slot_bits as u16)
+            .set_active_low()
+            .enable_early(),
+        Format::LeftJustified => frame
+            .set_active_length(slot_bits as u16)
+            .set_active_high()
+            .disable_early(),
+        Format::Tdm { .. } => frame.set_active_length(1).set_active_high().enable_early(),
+    };
+    let slot = SlotSetting(0)
+        .set_slot_count(slots)
+        .set_slot_size(slot_bits)
+        .set_data_size(width.data_bits());
+    (frame, slot, (1 << slots) - 1)
+}
+
+/// Master clock and bit clock dividers for `sample_rate` from audio PLL `source`.
+///
+/// Master clock runs at 256 times sample rate if frame length divides it, or at bit clock otherwise.
+#[inline]
+const fn clock_dividers(source: u32, sample_rate: u32, frame_bits: u32) -> (u16, u16) {
+    let ratio = if 256 % frame_bits == 0 {
+        256
+    } else {
+        frame_bits
+    };
+    let mclk = sample_rate as u64 * ratio as u64;
+    let divider = (source as u64 + mclk / 2) / mclk;
+    let divider = if divider == 0 {
+        1
+    } else if divider > 0xFFFF {
+        0xFFFF
+    } else {
+        divider
+    };
+    (divider as u16, (ratio / frame_bits) as u16)
+}
+
+/// Managed I2S peripheral.
+pub struct I2s<T, const I: usize, PADS> {
+    i2s: T,
+    pads: PADS,
+    direction: Direction,
+    sample_rate: u32,
+}
+
+/// Extension trait to create I2S controllers from peripherals.
+pub trait I2sExt<const I: usize>: AsRef<RegisterBlock> + Controlled + Sized {
+    /// Configures the peripheral as an I2S controller running on `pads`, stopped.
+    ///
+    /// In master role, master and bit clocks are divided from the audio PLL.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a TDM format has less than 1 or more than 16 slots,
+    /// or if sample rate of a master is zero.
+    #[inline]
+    fn i2s<PADS>(self, config: Config, pads: PADS, clocks: &Clocks) -> I2s<Self, I, PADS>
+    where
+        PADS: Pads<I>,
+    {
+        if let Format::Tdm { slots } = config.format {
+            assert!((1..=MAX_SLOTS).contains(&slots));
+        }
+        if config.role == Role::Master {
+            assert!(config.sample_rate != 0, "I2S sample rate must be non-zero");
+        }
+        reset::enable(&self);
+        let i2s = self.as_ref();
+        let (frame, slot, slot_enable) = frame_settings(config.format, config.sample_width);
+        let frame_bits = frame.frame_length() as u32;
+        let mut sample_rate = config.sample_rate;
+        unsafe {
+            i2s.enable.write(0);
+            i2s.block_mode.write(
+                BlockMode::default()
+                    .set_direction(config.direction)
+                    .set_role(config.role),
+            );
+            i2s.frame.write(frame);
+            i2s.slot.write(slot);
+            i2s.slot_enable.write(slot_enable);
+            i2s.fifo_threshold
+                .write(((FIFO_DEPTH as u32 / 2) << 16) | (FIFO_DEPTH as u32 / 2));
+            i2s.interrupt_enable.write(Interrupts::default());
+            i2s.interrupt_status.write(Interrupts(u32::MAX));
+            if config.role == Role::Master {
+                let apll = clocks.apll().to_hz();
+                let (mclk, bclk) = clock_dividers(apll, config.sample_rate, frame_bits);
+                i2s.clock_control1.write(
+                    ClockControl1::default()
+                        .set_mclk_divider(mclk)
+                        .set_bclk_divider(bclk),
+                );
+                i2s.clock_control0.write(
+                    ClockControl0::default()
+                        .enable_audio_clock()
+                        .enable_mclk_output()
+                        .enable_bclk_output(),
+                );
+                i2s.lrck_master.write(1);
+                sample_rate = apll / mclk as u32 / (frame_bits * bclk as u32);
+            } else {
+                i2s.clock_control0
+                    .write(ClockControl0::default().enable_audio_clock());
+                i2s.lrck_master.write(0);
+            }
+            i2s.fifo_reset.write(0x1_0001);
+            i2s.fifo_reset.write(0);
+        }
+        I2s {
+            i2s: self,
+            pads,
+            direction: config.direction,
+            sample_rate,
+        }
+    }
+}
+
+impl<T: AsRef<RegisterBlock>, const I: usize, PADS> I2s<T, I, PADS> {
+    /// Starts shifting samples.
+    ///
+    /// Transmitters should have samples queued to avoid an underrun.
+    #[inline]
+    pub fn start(&mut self) {
+        unsafe { self.i2s.as_ref().enable.write(1) };
+    }
+    /// Stops shifting samples and flushes FIFOs.
+    #[inline]
+    pub fn stop(&mut self) {
+        let i2s = self.i2s.as_ref();
+        unsafe {
+            i2s.enable.write(0);
+            i2s.fifo_reset.write(0x1_0001);
+            i2s.fifo_reset.write(0);
+        }
+    }
+    /// Frames per second; actual rate after divider rounding in master role.
+    #[inline]
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+    /// Data direction of this controller.
+    #[inline]
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+    /// Interrupt source of this controller on the interrupt controller.
+    #[inline]
+    pub const fn interrupt(&self) -> crate::plic::Interrupt {
+        crate::plic::Interrupt::i2s(I)
+    }
+    /// Address of the FIFO data port in current direction, for DMA transfers.
+    #[inline]
+    pub fn data_port_address(&self) -> usize {
+        let i2s = self.i2s.as_ref();
+        match self.direction {
+            Direction::Transmit => &i2s.tx_data as *const _ as usize,
+            Direction::Receive => &i2s.rx_data as *const _ as usize,
+        }
+    }
+    /// Lets the DMA controller move samples on FIFO threshold requests.
+    #[inline]
+    pub fn enable_dma(&mut self) {
+        unsafe { self.i2s.as_ref().block_mode.modify(|w| w.enable_dma()) };
+    }
+    /// Stops DMA requests.
+    #[inline]
+    pub fn disable_dma(&mut self) {
+        unsafe { self.i2s.as_ref().block_mode.modify(|w| w.disable_dma()) };
+    }
+    /// Enables DMA handshake and returns data port of current direction.
+    ///
+    /// Stream samples with a circular DMA transfer on this port and [`I2s::dma_request`].
+    #[inline]
+    pub fn dma_port(&mut self) -> dma::Port<u32> {
+        self.enable_dma();
+        unsafe { dma::Port::new(self.data_port_address()) }
+    }
+    /// DMA handshake request of current direction.
+    #[inline]
+    pub const fn dma_request(&self) -> dma::Request {
+        match self.direction {
+            Direction::Transmit => dma::Request::i2s_tx(I),
+            Direction::Receive => dma::Request::i2s_rx(I),
+        }
+    }
+    /// Starts shifting samples, streamed through `buffer` by a circular DMA transfer.
+    ///
+    /// Refill or consume the halves of `buffer` with [`dma::Circular::next_half`] or
+    /// [`dma::Circular::next_half_async`]; transmit buffers should hold the first samples.
+    /// Stop the returned stream before calling [`I2s::stop`].
+    #[inline]
+    pub fn stream<'a, D, R, B, L>(
+        &mut self,
+        channel: dma::Channel<'a, D, R>,
+        buffer: B,
+        descriptors: L,
+    ) -> dma::Circular<'a, D, R, B, L, u32>
+    where
+        D: AsRef<dma::RegisterBlock>,
+        R: AsRef<dma::ChannelRemap>,
+        B: WriteBuffer<Word = u32>,
+        L: WriteBuffer<Word = dma::Descriptor>,
+    {
+        let flow = match self.direction {
+            Direction::Transmit => dma::Flow::MemoryToPeripheral(self.dma_request()),
+            Direction::Receive => dma::Flow::PeripheralToMemory(self.dma_request()),
+        };
+        let port = self.dma_port();
+        let stream = channel.circular(flow, port, buffer, descriptors);
+        self.start();
+        stream
+    }
+    /// Queues samples into transmit FIFO without blocking, returns number of samples queued.
+    ///
+    /// Samples are right aligned, one slot per word, in slot order.
+    #[inline]
+    pub fn write(&mut self, samples: &[u32]) -> usize {
+        let i2s = self.i2s.as_ref();
+        let free = FIFO_DEPTH - i2s.tx_status.read().level().min(FIFO_DEPTH);
+        let len = free.min(samples.len());
+        for sample in &samples[..len] {
+            unsafe { i2s.tx_data.write(*sample) };
+        }
+        len
+    }
+    /// Takes samples from receive FIFO without blocking, returns number of samples taken.
+    #[inline]
+    pub fn read(&mut self, samples: &mut [u32]) -> usize {
+        let i2s = self.i2s.as_ref();
+        let len = i2s.rx_status.read().level().min(samples.len());
+        for sample in &mut samples[..len] {
+            *sample = i2s.rx_data.read();
+        }
+        len
+    }
+    /// Checks and clears FIFO overflow and underflow of current direction.
+    #[inline]
+    pub fn check_errors(&mut self) -> Result<(), Error> {
+        let i2s = self.i2s.as_ref();
+        let status = i2s.interrupt_status.read();
+        let (interrupt, error) = match self.direction {
+            Direction::Transmit => (Interrupt::TxUnderflow, Error::Underrun),
+            Direction::Receive => (Interrupt::RxOverflow, Error::Overrun),
+        };
+        if status.has_interrupt(interrupt) {
+            unsafe {
+                i2s.interrupt_status
+                    .write(Interrupts::default().set_interrupt(interrupt))
+            };
+            return Err(error);
+        }
+        Ok(())
+    }
+    /// Converts into an interrupt driven asynchronous I2S controller.
+    ///
+    /// The interrupt handler of this I2S peripheral must call `state.on_interrupt()`.
+    #[inline]
+    pub fn into_async(self, state: &State) -> AsyncI2s<'_, T, I, PADS> {
+        let i2s = self.i2s.as_ref();
+        unsafe {
+            i2s.interrupt_enable.write(Interrupts::default());
+            i2s.interrupt_status.write(Interrupts(u32::MAX));
+        }
+        state
+            .registers
+            .store(i2s as *const _ as *mut _, Ordering::Release);
+        AsyncI2s { inner: self, state }
+    }
+    /// Release I2S instance and return its peripheral and pads, turning its clocks off.
+    #[inline]
+    pub fn free(self) -> (T, PADS)
+    where
+        T: Controlled,
+    {
+        let i2s = self.i2s.as_ref();
+        unsafe {
+            i2s.enable.write(0);
+            i2s.clock_control0.write(ClockControl0::default());
+        }
+        reset::disable(&self.i2s);
+        (self.i2s, self.pads)
+    }
+}
+
+/// Shared state between an asynchronous I2S controller and its interrupt handler.
+pub struct State {
+    waker: AtomicWaker,
+    registers: AtomicPtr<RegisterBlock>,
+}
+
+impl State {
+    /// Creates an empty state, usually placed in a `static`.
+    #[inline]
+    pub const fn new() -> Self {
+        State {
+            waker: AtomicWaker::new(),
+            registers: AtomicPtr::new(null_mut()),
+        }
+    }
+    /// Interrupt handler hook for the I2S peripheral.
+    ///
+    /// Disables pending interrupts and wakes the task waiting on them;
+    /// does nothing if no asynchronous I2S controller is attached to this state.
+    #[inline]
+    pub fn on_interrupt(&self) {
+        let registers = self.registers.load(Ordering::Acquire);
+        if registers.is_null() {
+            return;
+        }
+        let i2s = unsafe { &*registers };
+        let pending = i2s.interrupt_status.read();
+        unsafe {
+            i2s.interrupt_enable
+                .modify(|w| Interrupts(w.0 & !pending.0))
+        };
+        self.waker.wake();
+    }
+}
+
+impl Default for State {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Interrupt driven asynchronous I2S controller.
+///
+/// Samples are moved while FIFO threshold interrupts arrive, so a second buffer
+/// can be refilled meanwhile, for example by joining `write` of one buffer with
+/// the code producing the next one.
+pub struct AsyncI2s<'a, T: AsRef<RegisterBlock>, const I: usize, PADS> {
+    inner: I2s<T, I, PADS>,
+    state: &'a State,
+}
+
+impl<'a, T: AsRef<RegisterBlock>, const I: usize, PADS> AsyncI2s<'a, T, I, PADS> {
+    /// Converts back into a blocking I2S controller.
+    #[inline]
+    pub fn into_blocking(self) -> I2s<T, I, PADS> {
+        let this = ManuallyDrop::new(self);
+        this.detach();
+        unsafe { core::ptr::read(&this.inner) }
+    }
+    /// Starts shifting samples.
+    #[inline]
+    pub fn start(&mut self) {
+        self.inner.start();
+    }
+    /// Stops shifting samples and flushes FIFOs.
+    #[inline]
+    pub fn stop(&mut self) {
+        self.inner.stop();
+    }
+    /// Queues all `samples` into transmit FIFO, waiting for room as FIFO drains.
+    ///
+    /// Returns once the last sample is queued, leaving up to a FIFO of samples to play.
+    pub async fn write(&mut self, samples: &[u32]) -> Result<(), Error> {
+        let mut sent = 0;
+        while sent < samples.len() {
+            sent += self.inner.write(&samples[sent..]);
+            if sent < samples.len() {
+                self.wait(Interrupt::TxThreshold, |i2s| {
+                    i2s.tx_status.read().level() <= FIFO_DEPTH / 2
+                })
+                .await?;
+            }
+        }
+        self.inner.check_errors()
+    }
+    /// Fills all of `samples` from receive FIFO, waiting for samples to arrive.
+    pub async fn read(&mut self, samples: &mut [u32]) -> Result<(), Error> {
+        let mut received = 0;
+        while received < samples.len() {
+            received += self.inner.read(&mut samples[received..]);
+            if received < samples.len() {
+                self.wait(Interrupt::RxThreshold, |i2s| {
+                    i2s.rx_status.read().level() >= FIFO_DEPTH / 2
+                })
+                .await?;
+            }
+        }
+        self.inner.check_errors()
+    }
+    /// Release I2S instance and return its peripheral and pads, turning its clocks off.
+    #[inline]
+    pub fn free(self) -> (T, PADS)
+    where
+        T: Controlled,
+    {
+        self.into_blocking().free()
+    }
+    #[inline]
+    fn detach(&self) {
+        let i2s = self.inner.i2s.as_ref();
+        unsafe { i2s.interrupt_enable.write(Interrupts::default()) };
+        self.state.registers.store(null_mut(), Ordering::Release);
+    }
+    /// Waits until `ready` holds, sleeping on `interrupt` meanwhile.
+    async fn wait(
+        &mut self,
+        interrupt: Interrupt,
+        ready: impl Fn(&RegisterBlock) -> bool,
+    ) -> Result<(), Error> {
+        let result = poll_fn(|cx| {
+            self.inner.check_errors()?;
+            let i2s = self.inner.i2s.as_ref();
+            if ready(i2s) {
+                return Poll::Ready(Ok(()));
+            }
+            self.state.waker.register(cx.waker());
+            unsafe {
+                i2s.interrupt_status
+                    .write(Interrupts::default().set_interrupt(interrupt));
+                i2s.interrupt_enable.modify(|w| w.set_interrupt(interrupt));
+            }
+            // condition may have changed before interrupt was enabled
+            if ready(i2s) {
+                return Poll::Ready(Ok(()));
+            }
+            Poll::Pending
+        })
+        .await;
+        let i2s = self.inner.i2s.as_ref();
+        unsafe { i2s.interrupt_enable.write(Interrupts::default()) };
+        result
+    }
+}
+
+impl<'a, T: AsRef<RegisterBlock>, const I: usize, PADS> Drop for AsyncI2s<'a, T, I, PADS> {
+    #[inline]
+    fn drop(&mut self) {
+        self.detach();
+    }
+}
+
+/// Valid I2S pads.
+pub trait Pads<const I: usize> {}
+
+/// I2S-0 receives from the internal audio codec ADC without external pads.
+impl Pads<0> for () {}
+
+/// I2S-3 transmits to the internal audio codec DAC without external pads.
+impl Pads<3> for () {}
+
+macro_rules! external_pads {
+    ($($i: expr),+) => {
+        $(
+/// Bit clock, frame sync and data pads of an external bus.
+impl<T1, T2, T3, const BCLK: usize, const LRCK: usize, const DATA: usize> Pads<$i>
+    for (
+        Pad<T1, BCLK, I2sFunc<$i>>,
+        Pad<T2, LRCK, I2sFunc<$i>>,
+        Pad<T3, DATA, I2sFunc<$i>>,
+    )
+{
+}
+
+/// Bit clock, frame sync and data pads of an external bus, with master clock output pad.
+impl<T1, T2, T3, T4, const BCLK: usize, const LRCK: usize, const DATA: usize, const MCLK: usize>
+    Pads<$i>
+    for (
+        Pad<T1, BCLK, I2sFunc<$i>>,
+        Pad<T2, LRCK, I2sFunc<$i>>,
+        Pad<T3, DATA, I2sFunc<$i>>,
+        Pad<T4, MCLK, I2sFunc<$i>>,
+    )
+{
+}
+        )+
+    };
+}
+
+external_pads!(1, 2);
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        clock_dividers, frame_settings, BlockMode, ClockControl0, ClockControl1, Direction, Format,
+        FrameSetting, Interrupt, Interrupts, RegisterBlock, Role, SampleWidth, SlotSetting,
+    };
+    use memoffset::offset_of;
+
+    #[test]
+    fn struct_register_block_offset() {
+        assert_eq!(offset_of!(RegisterBlock, block_mode), 0x00);
+        assert_eq!(offset_of!(RegisterBlock, frame), 0x04);
+        assert_eq!(offset_of!(RegisterBlock, slot), 0x08);
+        assert_eq!(offset_of!(RegisterBlock, slot_enable), 0x0C);
+        assert_eq!(offset_of!(RegisterBlock, enable), 0x18);
+        assert_eq!(offset_of!(RegisterBlock, reset), 0x1C);
+        assert_eq!(offset_of!(RegisterBlock, interrupt_enable), 0x20);
+        assert_eq!(offset_of!(RegisterBlock, interrupt_status), 0x24);
+        assert_eq!(offset_of!(RegisterBlock, fifo_threshold), 0x28);
+        assert_eq!(offset_of!(RegisterBlock, fifo_reset), 0x30);
+        assert_eq!(offset_of!(RegisterBlock, rx_status), 0x40);
+        assert_eq!(offset_of!(RegisterBlock, tx_status), 0x48);
+        assert_eq!(offset_of!(RegisterBlock, clock_control0), 0x60);
+        assert_eq!(offset_of!(RegisterBlock, clock_control1), 0x64);
+        assert_eq!(offset_of!(RegisterBlock, rx_data), 0x80);
+        assert_eq!(offset_of!(RegisterBlock, tx_data), 0xC0);
+    }
+
+    #[test]
+    fn struct_block_mode_functions() {
+        let val = BlockMode::default()
+            .set_direction(Direction::Transmit)
+            .set_role(Role::Master)
+            .enable_dma();
+        assert_eq!(val.0, 0x13);
+        assert_eq!(val.direction(), Direction::Transmit);
+        assert_eq!(val.role(), Role::Master);
+        assert!(val.is_dma_enabled());
+        let val = val.set_role(Role::Slave).set_direction(Direction::Receive);
+        assert_eq!(val.0, 0x10);
+        assert!(!val.disable_dma().is_dma_enabled());
+
+        let val = Interrupts::default()
+            .set_interrupt(Interrupt::TxThreshold)
+            .set_interrupt(Interrupt::RxOverflow);
+        assert_eq!(val.0, 0x12);
+        assert!(!val
+            .clear_interrupt(Interrupt::TxThreshold)
+            .has_interrupt(Interrupt::TxThreshold));
+
+        let val = ClockControl0::default()
+            .enable_audio_clock()
+            .enable_bclk_output()
+            .enable_mclk_output();
+        assert_eq!(val.0, 0x1C0);
+        let val = ClockControl1::default()
+            .set_mclk_divider(33)
+            .set_bclk_divider(4);
+        assert_eq!(val.0, 0x0021_0004);
+        assert_eq!(val.mclk_divider(), 33);
+        assert_eq!(val.bclk_divider(), 4);
+    }
+
+    #[test]
+    fn frame_formats() {
+        let (frame, slot, enable) = frame_settings(Format::Standard, SampleWidth::Bits16);
+        assert_eq!(frame.0, 0x020F_001F);
+        assert_eq!(slot.0, 0x000F_0F01);
+        assert_eq!(enable, 0b11);
+
+        let (frame, slot, _) = frame_settings(Format::LeftJustified, SampleWidth::Bits24);
+        assert_eq!(frame, FrameSetting(0x011F_003F));
+        assert_eq!(frame.frame_length(), 64);
+        assert_eq!(frame.active_length(), 32);
+        assert!(frame.is_active_high() && !frame.is_early());
+        assert_eq!(slot.slot_size(), 32);
+        assert_eq!(slot.data_size(), 24);
+
+        let (frame, slot, enable) = frame_settings(Format::Tdm { slots: 8 }, SampleWidth::Bits32);
+        assert_eq!(frame.frame_length(), 256);
+        assert_eq!(frame.active_length(), 1);
+        assert_eq!(slot, SlotSetting(0x001F_1F07));
+        assert_eq!(slot.slot_count(), 8);
+        assert_eq!(enable, 0xFF);
+    }
+
+    #[test]
+    fn audio_clock_dividers() {
+        // 48 kHz family: 12.288 MHz master clock from 393.216 MHz
+        assert_eq!(clock_dividers(393_216_000, 48_000, 32), (32, 8));
+        assert_eq!(clock_dividers(393_216_000, 16_000, 64), (96, 4));
+        // 16 slots of 32 bits exceed 256 bit clocks per frame
+        assert_eq!(clock_dividers(393_216_000, 48_000, 512), (16, 1));
+        // 44.1 kHz from same PLL rounds to nearest divider
+        assert_eq!(clock_dividers(393_216_000, 44_100, 64), (35, 4));
+    }
+}
@@ -1,9 +1,44 @@
 #![no_std]
 
+pub mod adc;
+pub mod audio_codec;
+pub mod clint;
+pub mod clock;
+pub mod dma;
 pub mod gpio;
+pub mod i2c;
+pub mod i2s;
 pub mod pad;
+pub mod plic;
+pub mod pwm;
+pub mod reset;
+pub mod rtc;
+pub mod sdhci;
+pub mod spi;
+pub mod spi_nand;
+pub mod spi_nor;
+pub mod timer;
 pub mod uart;
+pub mod usb;
+pub mod watchdog;
 
 pub mod prelude {
+    pub use crate::adc::AdcExt as __sophgo_hal__adc__AdcExt;
+    pub use crate::audio_codec::AudioCodecExt as __sophgo_hal__audio_codec__AudioCodecExt;
+    pub use crate::clint::ClintExt as __sophgo_hal__clint__ClintExt;
+    pub use crate::clock::ClockGenExt as __sophgo_hal__clock__ClockGenExt;
+    pub use crate::dma::DmaExt as __sophgo_hal__dma__DmaExt;
+    pub use crate::i2c::I2cExt as __sophgo_hal__i2c__I2cExt;
+    pub use crate::i2s::I2sExt as __sophgo_hal__i2s__I2sExt;
+    pub use crate::plic::PlicExt as __sophgo_hal__plic__PlicExt;
+    pub use crate::pwm::PwmExt as __sophgo_hal__pwm__PwmExt;
+    pub use crate::rtc::RtcExt as __sophgo_hal__rtc__RtcExt;
+    pub use crate::sdhci::SdhciExt as __sophgo_hal__sdhci__SdhciExt;
+    pub use crate::spi::SpiExt as __sophgo_hal__spi__SpiExt;
+    pub use crate::spi_nand::SpiNandExt as __sophgo_hal__spi_nand__SpiNandExt;
+    pub use crate::spi_nor::SpiNorExt as __sophgo_hal__spi_nor__SpiNorExt;
+    pub use crate::timer::TimerExt as __sophgo_hal__timer__TimerExt;
     pub use crate::uart::UartExt as __sophgo_hal__uart__UartExt;
+    pub use crate::usb::UsbExt as __sophgo_hal__usb__UsbExt;
+    pub use crate::watchdog::WatchdogExt as __sophgo_hal__watchdog__WatchdogExt;
 }
@@ -23,21 +23,64 @@ impl AsRef<FMux> for PinMux {
 /// Pad function multiplexer registers for all the pads.
 #[repr(C)]
 pub struct FMux {
-    _reserved0: [u8; 0x40],
+    _reserved0: [u8; 0x1C],
+    /// SDIO-0 clock pad function.
+    pub sd0_clk: RW<u32>,
+    /// SDIO-0 command pad function.
+    pub sd0_cmd: RW<u32>,
+    /// SDIO-0 data 0 pad function.
+    pub sd0_d0: RW<u32>,
+    /// SDIO-0 data 1 pad function.
+    pub sd0_d1: RW<u32>,
+    /// SDIO-0 data 2 pad function.
+    pub sd0_d2: RW<u32>,
+    /// SDIO-0 data 3 pad function.
+    pub sd0_d3: RW<u32>,
+    _reserved1: [u8; 0xC],
     /// UART-0 TX pad function.
     pub uart0_tx: RW<u32>,
     /// UART-0 RX pad function.
     pub uart0_rx: RW<u32>,
-    _reserved1: [u8; 0x28],
+    /// SPI-NOR hold pad function.
+    pub spinor_hold_x: RW<u32>,
+    /// SPI-NOR clock pad function.
+    pub spinor_sck: RW<u32>,
+    /// SPI-NOR MOSI pad function.
+    pub spinor_mosi: RW<u32>,
+    /// SPI-NOR write protect pad function.
+    pub spinor_wp_x: RW<u32>,
+    /// SPI-NOR MISO pad function.
+    pub spinor_miso: RW<u32>,
+    /// SPI-NOR chip select pad function.
+    pub spinor_cs_x: RW<u32>,
+    _reserved2: [u8; 0x4],
+    /// CPU JTAG Test Mode Select (TMS) pad function.
+    pub jtag_cpu_tms: RW<u32>,
+    /// CPU JTAG Test Clock (TCK) pad function.
+    pub jtag_cpu_tck: RW<u32>,
+    _reserved3: [u8; 0x4],
     /// I2C-0 Serial Clock (SCL) pad function.
     pub i2c0_scl: RW<u32>,
     /// I2C-0 Serial Data (SDA) pad function.
     pub i2c0_sda: RW<u32>,
-    _reserved2: [u8; 0x34],
+    /// Auxiliary clock output 0 pad function.
+    pub aux0: RW<u32>,
+    _reserved4: [u8; 0x28],
+    /// Power (RTC) domain GPIO-0 pad function.
+    pub pwr_gpio0: RW<u32>,
+    /// Power (RTC) domain GPIO-1 pad function.
+    pub pwr_gpio1: RW<u32>,
     /// Power (RTC) domain GPIO-2 pad function.
     pub pwr_gpio2: RW<u32>,
+    _reserved5: [u8; 0x40],
+    /// Analog input 3 pad function.
+    pub adc3: RW<u32>,
+    /// Analog input 2 pad function.
+    pub adc2: RW<u32>,
+    /// Analog input 1 pad function.
+    pub adc1: RW<u32>,
     // TODO other fields and padding
-    _reserved3: [u8; 0x1750],
+    _reserved6: [u8; 0x1704],
 }
 
 impl FMux {
@@ -45,10 +88,30 @@ impl FMux {
     #[inline]
     pub fn fmux<const N: usize>(&self) -> &RW<u32> {
         match N {
+            6 => &self.sd0_clk,
+            7 => &self.sd0_cmd,
+            8 => &self.sd0_d0,
+            10 => &self.sd0_d1,
+            11 => &self.sd0_d2,
+            12 => &self.sd0_d3,
             18 => &self.uart0_tx,
             19 => &self.uart0_rx,
+            20 => &self.spinor_hold_x,
+            21 => &self.spinor_sck,
+            22 => &self.spinor_mosi,
+            23 => &self.spinor_wp_x,
+            24 => &self.spinor_miso,
+            25 => &self.spinor_cs_x,
+            26 => &self.jtag_cpu_tms,
+            27 => &self.jtag_cpu_tck,
             28 => &self.i2c0_scl,
             29 => &self.i2c0_sda,
+            30 => &self.aux0,
+            43 => &self.adc3,
+            44 => &self.adc2,
+            45 => &self.adc1,
+            47 => &self.pwr_gpio0,
+            48 => &self.pwr_gpio1,
             49 => &self.pwr_gpio2,
             _ => todo!(),
         }
@@ -58,31 +121,92 @@ impl FMux {
 /// Non-RTC domain pad configurations.
 #[repr(C)]
 pub struct PadConfigs {
-    _reserved0: [u8; 0x10C],
+    _reserved0: [u8; 0xF8],
+    /// Non-RTC domain analog input 3 pad configurations.
+    pub adc3: RW<PadConfig>,
+    /// Non-RTC domain analog input 2 pad configurations.
+    pub adc2: RW<PadConfig>,
+    /// Non-RTC domain analog input 1 pad configurations.
+    pub adc1: RW<PadConfig>,
+    _reserved1: [u8; 0x8],
     /// Non-RTC domain UART-0 TX pad configurations.
     pub uart0_tx: RW<PadConfig>,
     /// Non-RTC domain UART-0 RX pad configurations.
     pub uart0_rx: RW<PadConfig>,
-    _reserved1: [u8; 0x28],
+    /// Non-RTC domain SPI-NOR hold pad configurations.
+    pub spinor_hold_x: RW<PadConfig>,
+    /// Non-RTC domain SPI-NOR clock pad configurations.
+    pub spinor_sck: RW<PadConfig>,
+    /// Non-RTC domain SPI-NOR MOSI pad configurations.
+    pub spinor_mosi: RW<PadConfig>,
+    /// Non-RTC domain SPI-NOR write protect pad configurations.
+    pub spinor_wp_x: RW<PadConfig>,
+    /// Non-RTC domain SPI-NOR MISO pad configurations.
+    pub spinor_miso: RW<PadConfig>,
+    /// Non-RTC domain SPI-NOR chip select pad configurations.
+    pub spinor_cs_x: RW<PadConfig>,
+    _reserved2: [u8; 0x4],
+    /// Non-RTC domain CPU JTAG TMS pad configurations.
+    pub jtag_cpu_tms: RW<PadConfig>,
+    /// Non-RTC domain CPU JTAG TCK pad configurations.
+    pub jtag_cpu_tck: RW<PadConfig>,
+    _reserved3: [u8; 0x4],
     /// Non-RTC domain i2c-0 SCL pad configurations.
     pub i2c0_scl: RW<PadConfig>,
     /// Non-RTC domain i2c-0 SDA pad configurations.
     pub i2c0_sda: RW<PadConfig>,
+    /// Non-RTC domain auxiliary clock output 0 pad configurations.
+    pub aux0: RW<PadConfig>,
+    _reserved4: [u8; 0xB8],
+    /// Non-RTC domain SDIO-0 clock pad configurations.
+    pub sd0_clk: RW<PadConfig>,
+    /// Non-RTC domain SDIO-0 command pad configurations.
+    pub sd0_cmd: RW<PadConfig>,
+    /// Non-RTC domain SDIO-0 data 0 pad configurations.
+    pub sd0_d0: RW<PadConfig>,
+    /// Non-RTC domain SDIO-0 data 1 pad configurations.
+    pub sd0_d1: RW<PadConfig>,
+    /// Non-RTC domain SDIO-0 data 2 pad configurations.
+    pub sd0_d2: RW<PadConfig>,
+    /// Non-RTC domain SDIO-0 data 3 pad configurations.
+    pub sd0_d3: RW<PadConfig>,
 }
 
-impl PadConfigs {
+impl PadConfigBlock for PadConfigs {
+    const PADS: &'static [usize] = &[
+        6, 7, 8, 10, 11, 12, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 43, 44, 45,
+    ];
+
     /// Gets the pad configuration register for the given pad number `N`.
     ///
     /// `N` must be number of a pad in the non-RTC domain.
     #[inline]
-    const fn pad_config<const N: usize>(&self) -> &RW<PadConfig> {
+    fn pad_config<const N: usize>(&self) -> &RW<PadConfig> {
         match N {
+            6 => &self.sd0_clk,
+            7 => &self.sd0_cmd,
+            8 => &self.sd0_d0,
+            10 => &self.sd0_d1,
+            11 => &self.sd0_d2,
+            12 => &self.sd0_d3,
             18 => &self.uart0_tx,
             19 => &self.uart0_rx,
+            20 => &self.spinor_hold_x,
+            21 => &self.spinor_sck,
+            22 => &self.spinor_mosi,
+            23 => &self.spinor_wp_x,
+            24 => &self.spinor_miso,
+            25 => &self.spinor_cs_x,
+            26 => &self.jtag_cpu_tms,
+            27 => &self.jtag_cpu_tck,
             28 => &self.i2c0_scl,
             29 => &self.i2c0_sda,
-            // if not a non-RTC pad, return unimplemented!()
-            _ => todo!(),
+            30 => &self.aux0,
+            43 => &self.adc3,
+            44 => &self.adc2,
+            45 => &self.adc1,
+            // rejected at compile time by `Pad`
+            _ => unreachable!(),
         }
     }
 }
@@ -90,25 +214,140 @@ impl PadConfigs {
 /// Power (RTC) domain pad configurations.
 #[repr(C)]
 pub struct PwrPadConfigs {
-    _reserved0: [u8; 0x34],
+    _reserved0: [u8; 0x2C],
+    /// Power (RTC) domain GPIO-0 pad configuration.
+    pub pwr_gpio0: RW<PadConfig>,
+    /// Power (RTC) domain GPIO-1 pad configuration.
+    pub pwr_gpio1: RW<PadConfig>,
     /// Power (RTC) domain GPIO-2 pad configuration.
     pub pwr_gpio2: RW<PadConfig>,
 }
 
-impl PwrPadConfigs {
+impl PadConfigBlock for PwrPadConfigs {
+    const PADS: &'static [usize] = &[47, 48, 49];
+
     /// Gets the pad configuration register for the given pad number `N`.
     ///
     /// `N` must be number of a pad in the power (RTC) domain.
     #[inline]
-    const fn pad_config<const N: usize>(&self) -> &RW<PadConfig> {
+    fn pad_config<const N: usize>(&self) -> &RW<PadConfig> {
         match N {
+            47 => &self.pwr_gpio0,
+            48 => &self.pwr_gpio1,
             49 => &self.pwr_gpio2,
-            // if not a power pad, return unimplemented!()
-            _ => todo!(),
+            // rejected at compile time by `Pad`
+            _ => unreachable!(),
         }
     }
 }
 
+/// Pad configuration registers of one power domain.
+pub trait PadConfigBlock {
+    /// Numbers of pads configured through this register block.
+    const PADS: &'static [usize];
+    /// Gets the pad configuration register for the given pad number `N`.
+    fn pad_config<const N: usize>(&self) -> &RW<PadConfig>;
+}
+
+/// Owner of the pad configuration registers of one power domain.
+///
+/// A pad is configured through the register block of the domain that owns it,
+/// thus power (RTC) domain pads can only be reached through `PwrPadConfigs`
+/// and non-RTC domain pads only through `PadConfigs`. Configuring a pad through
+/// the other domain fails to compile.
+pub trait PadDomain {
+    /// Pad configuration register block of this domain.
+    type Configs: PadConfigBlock;
+    /// Gets the pad configuration register block of this domain.
+    fn pad_configs(&self) -> &Self::Configs;
+}
+
+/// IO group power switch registers.
+///
+/// SDIO-0 is the only IO group with an internal power switch selecting between
+/// 3.3 V and 1.8 V, as SD cards start at 3.3 V and move to 1.8 V for UHS-I modes.
+/// Every other IO group, SDIO-1 included, runs at the voltage of its board supply
+/// and has no switch register. SD hosts on SDIO-0 pads switch it through
+/// `Sdhci::switch_io_voltage`.
+#[repr(C)]
+pub struct IoPower {
+    _reserved0: [u8; 0x1F4],
+    /// SDIO-0 IO group power switch.
+    pub sd0_pwrsw: RW<PowerSwitch>,
+}
+
+/// IO group power switch register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct PowerSwitch(u32);
+
+impl PowerSwitch {
+    const ENABLE: u32 = 1 << 0;
+    const VOLTAGE_SELECT: u32 = 1 << 1;
+    const DISCHARGE: u32 = 1 << 2;
+    const AUTO: u32 = 1 << 3;
+
+    /// Enable power switch.
+    #[inline]
+    pub const fn enable(self) -> Self {
+        Self(self.0 | Self::ENABLE)
+    }
+    /// Disable power switch.
+    #[inline]
+    pub const fn disable(self) -> Self {
+        Self(self.0 & !Self::ENABLE)
+    }
+    /// Check if power switch is enabled.
+    #[inline]
+    pub const fn is_enabled(self) -> bool {
+        self.0 & Self::ENABLE != 0
+    }
+    /// Set IO voltage of this IO group.
+    #[inline]
+    pub const fn set_voltage(self, val: IoVoltage) -> Self {
+        Self((self.0 & !Self::VOLTAGE_SELECT) | ((val as u32) << 1))
+    }
+    /// Get IO voltage of this IO group.
+    #[inline]
+    pub const fn voltage(self) -> IoVoltage {
+        match (self.0 & Self::VOLTAGE_SELECT) >> 1 {
+            0 => IoVoltage::V3_3,
+            1 => IoVoltage::V1_8,
+            _ => unreachable!(),
+        }
+    }
+    /// Enable output discharge when power switch is off.
+    #[inline]
+    pub const fn enable_discharge(self) -> Self {
+        Self(self.0 | Self::DISCHARGE)
+    }
+    /// Disable output discharge when power switch is off.
+    #[inline]
+    pub const fn disable_discharge(self) -> Self {
+        Self(self.0 & !Self::DISCHARGE)
+    }
+    /// Enable automatic power switch sequence.
+    #[inline]
+    pub const fn enable_auto(self) -> Self {
+        Self(self.0 | Self::AUTO)
+    }
+    /// Disable automatic power switch sequence.
+    #[inline]
+    pub const fn disable_auto(self) -> Self {
+        Self(self.0 & !Self::AUTO)
+    }
+}
+
+/// IO group voltage values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum IoVoltage {
+    /// 3.3 V IO voltage.
+    V3_3 = 0,
+    /// 1.8 V IO voltage.
+    V1_8 = 1,
+}
+
 /// Pad configuration register for all the pads.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 #[repr(transparent)]
@@ -168,7 +407,7 @@ pub struct Pad<T, const N: usize, F> {
     _function: PhantomData<F>,
 }
 
-impl<T: AsRef<PadConfigs>, const N: usize, F> Pad<T, N, F> {
+impl<T: PadDomain, const N: usize, F> Pad<T, N, F> {
     /// Converts the function of this pad.
     #[inline]
     pub fn into_function<F2: Function>(self, fmux: impl AsRef<FMux>) -> Pad<T, N, F2> {
@@ -181,19 +420,17 @@ impl<T: AsRef<PadConfigs>, const N: usize, F> Pad<T, N, F> {
     }
     #[inline]
     fn pad_config(&self) -> &RW<PadConfig> {
-        match N {
-            // TODO in range of power pads ...
-            49 => unsafe { &*(self.inner.as_ref() as *const _ as *const PwrPadConfigs) }
-                .pad_config::<N>(),
-            // TODO in range of conventional pads ...
-            18..=19 | 28..=29 => self.inner.as_ref().pad_config::<N>(),
-            // .. => { ... }
-            _ => todo!(),
-        }
+        const {
+            assert!(
+                contains(T::Configs::PADS, N),
+                "pad is not in this power domain"
+            )
+        };
+        self.inner.pad_configs().pad_config::<N>()
     }
 }
 
-impl<T: AsRef<PadConfigs>, const N: usize, F> Pad<T, N, GpioFunc<F>> {
+impl<T: PadDomain, const N: usize, F> Pad<T, N, GpioFunc<F>> {
     #[inline]
     pub(crate) fn into_gpio_pull_up(self) -> Pad<T, N, GpioFunc<PullUp>> {
         unsafe { self.pad_config().modify(|w| w.set_pull(Pull::Up)) };
@@ -204,6 +441,18 @@ impl<T: AsRef<PadConfigs>, const N: usize, F> Pad<T, N, GpioFunc<F>> {
     }
 }
 
+/// Checks if `pads` includes pad number `n`.
+const fn contains(pads: &[usize], n: usize) -> bool {
+    let mut i = 0;
+    while i < pads.len() {
+        if pads[i] == n {
+            return true;
+        }
+        i += 1;
+    }
+    false
+}
+
 /// GPIO function with a pull mode (type state).
 pub struct GpioFunc<T> {
     _pull: PhantomData<T>,
@@ -221,6 +470,37 @@ pub struct Floating;
 /// UART function (type state).
 pub struct UartFunc<const I: usize>;
 
+/// I2C function (type state).
+pub struct I2cFunc<const I: usize>;
+
+/// SPI function (type state).
+pub struct SpiFunc<const I: usize>;
+
+/// PWM function (type state).
+///
+/// `I` is the global PWM channel number; channel `I` belongs to PWM peripheral `I / 4`.
+pub struct PwmFunc<const I: usize>;
+
+/// SDIO function (type state).
+pub struct SdFunc<const I: usize>;
+
+/// SPI-NOR flash controller function (type state).
+pub struct SpiNorFunc;
+
+/// SPI-NAND flash controller function (type state).
+pub struct SpiNandFunc;
+
+/// I2S function (type state).
+pub struct I2sFunc<const I: usize>;
+
+/// Analog input function (type state).
+///
+/// `C` is the channel number of the analog-to-digital converter owning the pad.
+pub struct AdcFunc<const C: usize>;
+
+/// CPU JTAG debug function (type state).
+pub struct JtagFunc;
+
 /// Trait for all valid pad functions.
 pub trait Function {
     /// Pull direction associated with this pad function.
@@ -276,6 +556,7 @@ const fn uart_fmux<const N: usize, const I: usize>() -> u32 {
             _ => unimplemented!(),
         },
         1 => match N {
+            10..=11 | 18..=19 => 4,
             28..=29 => 1,
             _ => unimplemented!(),
         },
@@ -283,10 +564,153 @@ const fn uart_fmux<const N: usize, const I: usize>() -> u32 {
             28..=29 => 2,
             _ => unimplemented!(),
         },
+        3 => match N {
+            8 | 12 => 4,
+            _ => unimplemented!(),
+        },
+        _ => unimplemented!(),
+    }
+}
+
+impl<const I: usize> Function for I2cFunc<I> {
+    const PULL: Pull = Pull::Up;
+    #[inline]
+    fn fmux<const N: usize>() -> u32 {
+        i2c_fmux::<N, I>()
+    }
+}
+
+const fn i2c_fmux<const N: usize, const I: usize>() -> u32 {
+    match I {
+        0 => match N {
+            28..=29 => 0,
+            _ => unimplemented!(),
+        },
+        1 => match N {
+            6..=7 | 10..=11 => 1,
+            _ => unimplemented!(),
+        },
+        _ => unimplemented!(),
+    }
+}
+
+impl<const I: usize> Function for SpiFunc<I> {
+    const PULL: Pull = Pull::None;
+    #[inline]
+    fn fmux<const N: usize>() -> u32 {
+        spi_fmux::<N, I>()
+    }
+}
+
+const fn spi_fmux<const N: usize, const I: usize>() -> u32 {
+    match I {
+        0 => match N {
+            6..=8 | 12 => 2,
+            _ => unimplemented!(),
+        },
+        _ => unimplemented!(),
+    }
+}
+
+impl<const I: usize> Function for PwmFunc<I> {
+    const PULL: Pull = Pull::None;
+    #[inline]
+    fn fmux<const N: usize>() -> u32 {
+        pwm_fmux::<N, I>()
+    }
+}
+
+const fn pwm_fmux<const N: usize, const I: usize>() -> u32 {
+    match (N, I) {
+        (18, 4) | (19, 5) | (26, 7) | (27, 6) => 2,
+        (47, 8) | (48, 9) | (49, 10) => 4,
+        (6, 15) | (7, 14) | (8, 13) | (10, 12) | (11, 11) | (12, 10) => 5,
+        _ => unimplemented!(),
+    }
+}
+
+impl<const I: usize> Function for SdFunc<I> {
+    const PULL: Pull = Pull::Up;
+    #[inline]
+    fn fmux<const N: usize>() -> u32 {
+        sd_fmux::<N, I>()
+    }
+}
+
+const fn sd_fmux<const N: usize, const I: usize>() -> u32 {
+    match I {
+        0 => match N {
+            6..=8 | 10..=12 => 0,
+            _ => unimplemented!(),
+        },
+        _ => unimplemented!(),
+    }
+}
+
+impl Function for SpiNorFunc {
+    const PULL: Pull = Pull::Up;
+    #[inline]
+    fn fmux<const N: usize>() -> u32 {
+        match N {
+            20..=25 => 1,
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl Function for SpiNandFunc {
+    const PULL: Pull = Pull::Up;
+    #[inline]
+    fn fmux<const N: usize>() -> u32 {
+        match N {
+            20..=25 => 2,
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl<const I: usize> Function for I2sFunc<I> {
+    const PULL: Pull = Pull::None;
+    #[inline]
+    fn fmux<const N: usize>() -> u32 {
+        i2s_fmux::<N, I>()
+    }
+}
+
+const fn i2s_fmux<const N: usize, const I: usize>() -> u32 {
+    match I {
+        // I2S-1 master clock output on AUX0.
+        1 => match N {
+            30 => 4,
+            _ => unimplemented!(),
+        },
         _ => unimplemented!(),
     }
 }
 
+impl<const C: usize> Function for AdcFunc<C> {
+    const PULL: Pull = Pull::None;
+    #[inline]
+    fn fmux<const N: usize>() -> u32 {
+        match (N, C) {
+            (45, 1) | (44, 2) | (43, 3) => 0,
+            (48, 1) | (49, 2) => 7,
+            _ => unimplemented!(),
+        }
+    }
+}
+
+impl Function for JtagFunc {
+    const PULL: Pull = Pull::Up;
+    #[inline]
+    fn fmux<const N: usize>() -> u32 {
+        match N {
+            26..=27 => 0,
+            _ => unimplemented!(),
+        }
+    }
+}
+
 /// Pad internal pull direction values.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(u8)]
@@ -301,7 +725,7 @@ pub enum Pull {
 
 #[cfg(test)]
 mod tests {
-    use super::{FMux, PadConfigs, PinMux};
+    use super::{FMux, IoPower, PadConfigs, PinMux, PwrPadConfigs};
     use memoffset::offset_of;
 
     #[test]
@@ -312,18 +736,60 @@ mod tests {
 
     #[test]
     fn struct_fmux_offset() {
+        assert_eq!(offset_of!(FMux, sd0_clk), 0x1C);
+        assert_eq!(offset_of!(FMux, sd0_cmd), 0x20);
+        assert_eq!(offset_of!(FMux, sd0_d0), 0x24);
+        assert_eq!(offset_of!(FMux, sd0_d1), 0x28);
+        assert_eq!(offset_of!(FMux, sd0_d2), 0x2C);
+        assert_eq!(offset_of!(FMux, sd0_d3), 0x30);
         assert_eq!(offset_of!(FMux, uart0_tx), 0x40);
         assert_eq!(offset_of!(FMux, uart0_rx), 0x44);
+        assert_eq!(offset_of!(FMux, spinor_hold_x), 0x48);
+        assert_eq!(offset_of!(FMux, spinor_cs_x), 0x5C);
+        assert_eq!(offset_of!(FMux, jtag_cpu_tms), 0x64);
+        assert_eq!(offset_of!(FMux, jtag_cpu_tck), 0x68);
         assert_eq!(offset_of!(FMux, i2c0_scl), 0x70);
         assert_eq!(offset_of!(FMux, i2c0_sda), 0x74);
+        assert_eq!(offset_of!(FMux, aux0), 0x78);
+        assert_eq!(offset_of!(FMux, pwr_gpio0), 0xA4);
+        assert_eq!(offset_of!(FMux, pwr_gpio1), 0xA8);
         assert_eq!(offset_of!(FMux, pwr_gpio2), 0xAC);
+        assert_eq!(offset_of!(FMux, adc3), 0xF0);
+        assert_eq!(offset_of!(FMux, adc2), 0xF4);
+        assert_eq!(offset_of!(FMux, adc1), 0xF8);
     }
 
     #[test]
     fn struct_pad_configs_offset() {
+        assert_eq!(offset_of!(PadConfigs, adc3), 0x18F8 - 0x1800);
+        assert_eq!(offset_of!(PadConfigs, adc2), 0x18FC - 0x1800);
+        assert_eq!(offset_of!(PadConfigs, adc1), 0x1900 - 0x1800);
         assert_eq!(offset_of!(PadConfigs, uart0_tx), 0x190C - 0x1800);
         assert_eq!(offset_of!(PadConfigs, uart0_rx), 0x1910 - 0x1800);
+        assert_eq!(offset_of!(PadConfigs, spinor_hold_x), 0x1914 - 0x1800);
+        assert_eq!(offset_of!(PadConfigs, spinor_cs_x), 0x1928 - 0x1800);
+        assert_eq!(offset_of!(PadConfigs, jtag_cpu_tms), 0x1930 - 0x1800);
+        assert_eq!(offset_of!(PadConfigs, jtag_cpu_tck), 0x1934 - 0x1800);
         assert_eq!(offset_of!(PadConfigs, i2c0_scl), 0x193C - 0x1800);
         assert_eq!(offset_of!(PadConfigs, i2c0_sda), 0x1940 - 0x1800);
+        assert_eq!(offset_of!(PadConfigs, aux0), 0x1944 - 0x1800);
+        assert_eq!(offset_of!(PadConfigs, sd0_clk), 0x1A00 - 0x1800);
+        assert_eq!(offset_of!(PadConfigs, sd0_cmd), 0x1A04 - 0x1800);
+        assert_eq!(offset_of!(PadConfigs, sd0_d0), 0x1A08 - 0x1800);
+        assert_eq!(offset_of!(PadConfigs, sd0_d1), 0x1A0C - 0x1800);
+        assert_eq!(offset_of!(PadConfigs, sd0_d2), 0x1A10 - 0x1800);
+        assert_eq!(offset_of!(PadConfigs, sd0_d3), 0x1A14 - 0x1800);
+    }
+
+    #[test]
+    fn struct_pwr_pad_configs_offset() {
+        assert_eq!(offset_of!(PwrPadConfigs, pwr_gpio0), 0x2C);
+        assert_eq!(offset_of!(PwrPadConfigs, pwr_gpio1), 0x30);
+        assert_eq!(offset_of!(PwrPadConfigs, pwr_gpio2), 0x34);
+    }
+
+    #[test]
+    fn struct_io_power_offset() {
+        assert_eq!(offset_of!(IoPower, sd0_pwrsw), 0x1F4);
     }
 }
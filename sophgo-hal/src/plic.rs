@@ -0,0 +1,444 @@
+//! Platform-Level Interrupt Controller of the C906 core.
+
+use volatile_register::{RO, RW};
+
+/// Platform-Level Interrupt Controller registers.
+#[repr(C)]
+pub struct RegisterBlock {
+    /// Priority registers of interrupt sources; source 0 does not exist.
+    pub priorities: [RW<u32>; 1024],
+    /// Interrupt pending bits, one bit per source.
+    pub pendings: [RO<u32>; 32],
+    _reserved0: [u8; 0xF80],
+    /// Interrupt enable bits of each context, one bit per source.
+    pub enables: [[RW<u32>; 32]; 2],
+    _reserved1: [u8; 0x1F_DEFC],
+    /// Permission control register; set bit 0 to allow supervisor mode access.
+    pub control: RW<u32>,
+    /// Threshold and claim registers of each context.
+    pub contexts: [ContextRegisters; 2],
+}
+
+/// Threshold and claim registers of a context.
+#[repr(C)]
+pub struct ContextRegisters {
+    /// Priority threshold register; only sources with higher priority interrupt the hart.
+    pub threshold: RW<u32>,
+    /// Reading claims the highest priority pending source, writing completes it.
+    pub claim_complete: RW<u32>,
+    _reserved0: [u8; 0xFF8],
+}
+
+/// Highest interrupt priority supported by the C906 core.
+pub const MAX_PRIORITY: u8 = 31;
+
+/// Privilege mode contexts of the C906 hart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Context {
+    /// Machine mode.
+    Machine = 0,
+    /// Supervisor mode.
+    Supervisor = 1,
+}
+
+/// Interrupt sources of the SoC.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(u16)]
+pub enum Interrupt {
+    /// Temperature sensor.
+    TemperatureSensor = 16,
+    /// RTC alarm.
+    RtcAlarm = 17,
+    /// RTC power button.
+    RtcPowerButton = 18,
+    /// System DMA controller.
+    Dma = 29,
+    /// USB controller.
+    Usb = 30,
+    /// Ethernet controller.
+    Ethernet = 31,
+    /// eMMC controller.
+    Emmc = 34,
+    /// SDIO-0 controller.
+    Sd0 = 36,
+    /// SDIO-1 controller.
+    Sd1 = 38,
+    /// SPI-NAND controller.
+    SpiNand = 39,
+    /// I2S controller 0.
+    I2s0 = 40,
+    /// I2S controller 1.
+    I2s1 = 41,
+    /// I2S controller 2.
+    I2s2 = 42,
+    /// I2S controller 3.
+    I2s3 = 43,
+    /// UART 0.
+    Uart0 = 44,
+    /// UART 1.
+    Uart1 = 45,
+    /// UART 2.
+    Uart2 = 46,
+    /// UART 3.
+    Uart3 = 47,
+    /// UART 4.
+    Uart4 = 48,
+    /// I2C controller 0.
+    I2c0 = 49,
+    /// I2C controller 1.
+    I2c1 = 50,
+    /// I2C controller 2.
+    I2c2 = 51,
+    /// I2C controller 3.
+    I2c3 = 52,
+    /// I2C controller 4.
+    I2c4 = 53,
+    /// SPI controller 0.
+    Spi0 = 54,
+    /// SPI controller 1.
+    Spi1 = 55,
+    /// SPI controller 2.
+    Spi2 = 56,
+    /// SPI controller 3.
+    Spi3 = 57,
+    /// Watchdog timer.
+    Watchdog = 58,
+    /// GPIO port 0.
+    Gpio0 = 60,
+    /// GPIO port 1.
+    Gpio1 = 61,
+    /// GPIO port 2.
+    Gpio2 = 62,
+    /// GPIO port 3.
+    Gpio3 = 63,
+    /// Low-power Domain GPIO port.
+    PwrGpio = 70,
+    /// Timer 0.
+    Timer0 = 79,
+    /// Timer 1.
+    Timer1 = 80,
+    /// Timer 2.
+    Timer2 = 81,
+    /// Timer 3.
+    Timer3 = 82,
+    /// Timer 4.
+    Timer4 = 83,
+    /// Timer 5.
+    Timer5 = 84,
+    /// Timer 6.
+    Timer6 = 85,
+    /// Timer 7.
+    Timer7 = 86,
+    /// PWM controller 0.
+    Pwm0 = 87,
+    /// PWM controller 1.
+    Pwm1 = 88,
+    /// PWM controller 2.
+    Pwm2 = 89,
+    /// PWM controller 3.
+    Pwm3 = 90,
+    /// SPI-NOR flash controller.
+    SpiNor = 95,
+    /// Analog-to-digital converter.
+    Adc = 100,
+    /// Low-power Domain analog-to-digital converter.
+    PwrAdc = 101,
+}
+
+impl Interrupt {
+    /// All interrupt sources, in ascending source number.
+    pub const ALL: [Interrupt; 49] = [
+        Interrupt::TemperatureSensor,
+        Interrupt::RtcAlarm,
+        Interrupt::RtcPowerButton,
+        Interrupt::Dma,
+        Interrupt::Usb,
+        Interrupt::Ethernet,
+        Interrupt::Emmc,
+        Interrupt::Sd0,
+        Interrupt::Sd1,
+        Interrupt::SpiNand,
+        Interrupt::I2s0,
+        Interrupt::I2s1,
+        Interrupt::I2s2,
+        Interrupt::I2s3,
+        Interrupt::Uart0,
+        Interrupt::Uart1,
+        Interrupt::Uart2,
+        Interrupt::Uart3,
+        Interrupt::Uart4,
+        Interrupt::I2c0,
+        Interrupt::I2c1,
+        Interrupt::I2c2,
+        Interrupt::I2c3,
+        Interrupt::I2c4,
+        Interrupt::Spi0,
+        Interrupt::Spi1,
+        Interrupt::Spi2,
+        Interrupt::Spi3,
+        Interrupt::Watchdog,
+        Interrupt::Gpio0,
+        Interrupt::Gpio1,
+        Interrupt::Gpio2,
+        Interrupt::Gpio3,
+        Interrupt::PwrGpio,
+        Interrupt::Timer0,
+        Interrupt::Timer1,
+        Interrupt::Timer2,
+        Interrupt::Timer3,
+        Interrupt::Timer4,
+        Interrupt::Timer5,
+        Interrupt::Timer6,
+        Interrupt::Timer7,
+        Interrupt::Pwm0,
+        Interrupt::Pwm1,
+        Interrupt::Pwm2,
+        Interrupt::Pwm3,
+        Interrupt::SpiNor,
+        Interrupt::Adc,
+        Interrupt::PwrAdc,
+    ];
+
+    /// Source number of this interrupt.
+    #[inline]
+    pub const fn number(self) -> u16 {
+        self as u16
+    }
+    /// Interrupt of source number `n`, or `None` if no peripheral uses it.
+    #[inline]
+    pub const fn from_number(n: u16) -> Option<Interrupt> {
+        let mut i = 0;
+        while i < Self::ALL.len() {
+            if Self::ALL[i] as u16 == n {
+                return Some(Self::ALL[i]);
+            }
+            i += 1;
+        }
+        None
+    }
+    /// Interrupt of UART `i`.
+    #[inline]
+    pub const fn uart(i: usize) -> Interrupt {
+        [
+            Interrupt::Uart0,
+            Interrupt::Uart1,
+            Interrupt::Uart2,
+            Interrupt::Uart3,
+            Interrupt::Uart4,
+        ][i]
+    }
+    /// Interrupt of I2C controller `i`.
+    #[inline]
+    pub const fn i2c(i: usize) -> Interrupt {
+        [
+            Interrupt::I2c0,
+            Interrupt::I2c1,
+            Interrupt::I2c2,
+            Interrupt::I2c3,
+            Interrupt::I2c4,
+        ][i]
+    }
+    /// Interrupt of SPI controller `i`.
+    #[inline]
+    pub const fn spi(i: usize) -> Interrupt {
+        [
+            Interrupt::Spi0,
+            Interrupt::Spi1,
+            Interrupt::Spi2,
+            Interrupt::Spi3,
+        ][i]
+    }
+    /// Interrupt of GPIO port `i`.
+    #[inline]
+    pub const fn gpio(i: usize) -> Interrupt {
+        [
+            Interrupt::Gpio0,
+            Interrupt::Gpio1,
+            Interrupt::Gpio2,
+            Interrupt::Gpio3,
+        ][i]
+    }
+    /// Interrupt of timer `n`.
+    #[inline]
+    pub const fn timer(n: usize) -> Interrupt {
+        [
+            Interrupt::Timer0,
+            Interrupt::Timer1,
+            Interrupt::Timer2,
+            Interrupt::Timer3,
+            Interrupt::Timer4,
+            Interrupt::Timer5,
+            Interrupt::Timer6,
+            Interrupt::Timer7,
+        ][n]
+    }
+    /// Interrupt of PWM controller `i`.
+    #[inline]
+    pub const fn pwm(i: usize) -> Interrupt {
+        [
+            Interrupt::Pwm0,
+            Interrupt::Pwm1,
+            Interrupt::Pwm2,
+            Interrupt::Pwm3,
+        ][i]
+    }
+    /// Interrupt of I2S controller `i`.
+    #[inline]
+    pub const fn i2s(i: usize) -> Interrupt {
+        [
+            Interrupt::I2s0,
+            Interrupt::I2s1,
+            Interrupt::I2s2,
+            Interrupt::I2s3,
+        ][i]
+    }
+    /// Interrupt of analog-to-digital converter `i`.
+    #[inline]
+    pub const fn adc(i: usize) -> Interrupt {
+        [Interrupt::Adc, Interrupt::PwrAdc][i]
+    }
+}
+
+/// Managed Platform-Level Interrupt Controller peripheral.
+pub struct Plic<T> {
+    plic: T,
+}
+
+/// Extension trait to create interrupt controller drivers from peripherals.
+pub trait PlicExt: AsRef<RegisterBlock> + Sized {
+    /// Creates an interrupt controller driver, all sources disabled in all contexts.
+    #[inline]
+    fn plic(self) -> Plic<Self> {
+        let plic = self.as_ref();
+        for enables in &plic.enables {
+            for word in enables {
+                unsafe { word.write(0) };
+            }
+        }
+        Plic { plic: self }
+    }
+}
+
+impl<T: AsRef<RegisterBlock>> Plic<T> {
+    /// Allows `interrupt` to reach `context`.
+    #[inline]
+    pub fn enable(&mut self, interrupt: Interrupt, context: Context) {
+        let (n, bit) = word_bit(interrupt);
+        unsafe { self.plic.as_ref().enables[context as usize][n].modify(|w| w | bit) };
+    }
+    /// Prevents `interrupt` from reaching `context`.
+    #[inline]
+    pub fn disable(&mut self, interrupt: Interrupt, context: Context) {
+        let (n, bit) = word_bit(interrupt);
+        unsafe { self.plic.as_ref().enables[context as usize][n].modify(|w| w & !bit) };
+    }
+    /// Checks if `interrupt` is enabled in `context`.
+    #[inline]
+    pub fn is_enabled(&self, interrupt: Interrupt, context: Context) -> bool {
+        let (n, bit) = word_bit(interrupt);
+        self.plic.as_ref().enables[context as usize][n].read() & bit != 0
+    }
+    /// Sets priority of `interrupt`, saturating at `MAX_PRIORITY`; priority 0 never interrupts.
+    #[inline]
+    pub fn set_priority(&mut self, interrupt: Interrupt, priority: u8) {
+        let priority = priority.min(MAX_PRIORITY) as u32;
+        unsafe { self.plic.as_ref().priorities[interrupt as usize].write(priority) };
+    }
+    /// Gets priority of `interrupt`.
+    #[inline]
+    pub fn priority(&self, interrupt: Interrupt) -> u8 {
+        self.plic.as_ref().priorities[interrupt as usize].read() as u8
+    }
+    /// Checks if `interrupt` is pending.
+    #[inline]
+    pub fn is_pending(&self, interrupt: Interrupt) -> bool {
+        let (n, bit) = word_bit(interrupt);
+        self.plic.as_ref().pendings[n].read() & bit != 0
+    }
+    /// Sets priority threshold of `context`, saturating at `MAX_PRIORITY`.
+    #[inline]
+    pub fn set_threshold(&mut self, context: Context, threshold: u8) {
+        let threshold = threshold.min(MAX_PRIORITY) as u32;
+        unsafe {
+            self.plic.as_ref().contexts[context as usize]
+                .threshold
+                .write(threshold)
+        };
+    }
+    /// Gets priority threshold of `context`.
+    #[inline]
+    pub fn threshold(&self, context: Context) -> u8 {
+        self.plic.as_ref().contexts[context as usize]
+            .threshold
+            .read() as u8
+    }
+    /// Claims the highest priority pending interrupt of `context`, if any.
+    ///
+    /// A claimed interrupt is not raised again until `complete` is called.
+    /// Sources without an `Interrupt` variant are completed here and reported as `None`.
+    #[inline]
+    pub fn claim(&self, context: Context) -> Option<Interrupt> {
+        let claim_complete = &self.plic.as_ref().contexts[context as usize].claim_complete;
+        let n = claim_complete.read();
+        let interrupt = Interrupt::from_number(n as u16);
+        if interrupt.is_none() && n != 0 {
+            unsafe { claim_complete.write(n) };
+        }
+        interrupt
+    }
+    /// Signals that handling of `interrupt` in `context` is complete.
+    #[inline]
+    pub fn complete(&self, context: Context, interrupt: Interrupt) {
+        unsafe {
+            self.plic.as_ref().contexts[context as usize]
+                .claim_complete
+                .write(interrupt as u32)
+        };
+    }
+    /// Allows supervisor mode to access interrupt controller registers.
+    #[inline]
+    pub fn allow_supervisor_access(&mut self) {
+        unsafe { self.plic.as_ref().control.write(1) };
+    }
+    /// Release interrupt controller driver and return its peripheral.
+    #[inline]
+    pub fn free(self) -> T {
+        self.plic
+    }
+}
+
+/// Register index and bit mask of an interrupt source in bit arrays.
+#[inline]
+const fn word_bit(interrupt: Interrupt) -> (usize, u32) {
+    let n = interrupt as usize;
+    (n / 32, 1 << (n % 32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{word_bit, ContextRegisters, Interrupt, RegisterBlock};
+    use memoffset::offset_of;
+
+    #[test]
+    fn struct_register_block_offset() {
+        assert_eq!(offset_of!(RegisterBlock, priorities), 0x0000);
+        assert_eq!(offset_of!(RegisterBlock, pendings), 0x1000);
+        assert_eq!(offset_of!(RegisterBlock, enables), 0x2000);
+        assert_eq!(offset_of!(RegisterBlock, control), 0x1F_FFFC);
+        assert_eq!(offset_of!(RegisterBlock, contexts), 0x20_0000);
+        assert_eq!(offset_of!(ContextRegisters, claim_complete), 0x4);
+        assert_eq!(core::mem::size_of::<ContextRegisters>(), 0x1000);
+    }
+
+    #[test]
+    fn interrupt_numbers() {
+        assert_eq!(Interrupt::uart(2), Interrupt::Uart2);
+        assert_eq!(Interrupt::timer(7).number(), 86);
+        assert_eq!(Interrupt::from_number(44), Some(Interrupt::Uart0));
+        assert_eq!(Interrupt::from_number(0), None);
+        for interrupt in Interrupt::ALL {
+            assert_eq!(Interrupt::from_number(interrupt.number()), Some(interrupt));
+        }
+        assert_eq!(word_bit(Interrupt::Gpio0), (1, 1 << 28));
+    }
+}
@@ -0,0 +1,781 @@
+//! Pulse Width Modulation.
+
+use crate::clock::Clocks;
+use crate::pad::{Pad, PwmFunc};
+use crate::plic::Interrupt;
+use crate::reset::{self, Controlled};
+use atomic_waker::AtomicWaker;
+use core::{
+    future::poll_fn,
+    mem::ManuallyDrop,
+    ptr::null_mut,
+    sync::atomic::{AtomicPtr, AtomicUsize, Ordering},
+    task::Poll,
+};
+use embedded_hal::pwm::{ErrorType, SetDutyCycle};
+use volatile_register::{RO, RW};
+
+/// Pulse Width Modulation registers.
+#[repr(C)]
+pub struct RegisterBlock {
+    /// Per-channel period and level timing registers.
+    pub channels: [ChannelRegisters; 4],
+    _reserved0: [u8; 0x20],
+    /// Polarity and mode register.
+    pub polarity: RW<PolarityMode>,
+    /// Start register, one bit per channel.
+    pub start: RW<u32>,
+    /// Pulse count done register, one bit per channel.
+    pub done: RO<u32>,
+    /// Update register, one bit per channel.
+    ///
+    /// Toggling a bit high then low applies new timings to a running channel.
+    pub update: RW<u32>,
+    /// Number of pulses to output in pulse count mode.
+    pub pulse_count: [RW<u32>; 4],
+    /// Number of pulses already output in pulse count mode.
+    pub pulse_output_count: [RO<u32>; 4],
+    _reserved1: [u8; 0x10],
+    /// Phase shift count registers in clock cycles.
+    pub shift_count: [RW<u32>; 4],
+    /// Phase shift start register.
+    pub shift_start: RW<u32>,
+    _reserved2: [u8; 0x8],
+    /// Input capture control register.
+    pub capture_control: RW<CaptureControl>,
+    /// Input capture done status, one bit per channel.
+    ///
+    /// A bit is cleared by hardware when capture of its channel is disabled.
+    pub capture_status: RO<u32>,
+    /// Captured input period in clock cycles.
+    pub capture_period: [RO<u32>; 4],
+    /// Captured input high level duration in clock cycles.
+    pub capture_high: [RO<u32>; 4],
+    _reserved3: [u8; 0xC],
+    /// Output enable register, one bit per channel.
+    pub output_enable: RW<u32>,
+}
+
+/// Period and level timing registers of a PWM channel.
+#[repr(C)]
+pub struct ChannelRegisters {
+    /// Number of clock cycles of the low (or high if inverted) level.
+    pub low_period: RW<u32>,
+    /// Number of clock cycles of a whole period.
+    pub period: RW<u32>,
+}
+
+/// Polarity and mode register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct PolarityMode(u32);
+
+impl PolarityMode {
+    const POLARITY: u32 = 0xF;
+    const PULSE_MODE: u32 = 0xF << 8;
+
+    /// Set output polarity of channel `n`.
+    #[inline]
+    pub fn set_polarity(self, n: usize, polarity: Polarity) -> Self {
+        let bit = (1 << n) & Self::POLARITY;
+        match polarity {
+            Polarity::ActiveHigh => Self(self.0 & !bit),
+            Polarity::ActiveLow => Self(self.0 | bit),
+        }
+    }
+    /// Get output polarity of channel `n`.
+    #[inline]
+    pub fn polarity(self, n: usize) -> Polarity {
+        match (self.0 >> n) & 1 {
+            0 => Polarity::ActiveHigh,
+            1 => Polarity::ActiveLow,
+            _ => unreachable!(),
+        }
+    }
+    /// Set channel `n` to output a given number of pulses then stop.
+    #[inline]
+    pub fn enable_pulse_mode(self, n: usize) -> Self {
+        Self(self.0 | ((1 << 8 << n) & Self::PULSE_MODE))
+    }
+    /// Set channel `n` to output pulses continuously.
+    #[inline]
+    pub fn disable_pulse_mode(self, n: usize) -> Self {
+        Self(self.0 & !((1 << 8 << n) & Self::PULSE_MODE))
+    }
+    /// Check if channel `n` is in pulse count mode.
+    #[inline]
+    pub fn is_pulse_mode_enabled(self, n: usize) -> bool {
+        self.0 & (1 << 8 << n) != 0
+    }
+}
+
+/// Input capture control register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct CaptureControl(u32);
+
+impl CaptureControl {
+    const ENABLE: u32 = 0xF;
+    const INTERRUPT: u32 = 0xF << 8;
+
+    /// Enable input capture on channel `n`.
+    #[inline]
+    pub fn enable_capture(self, n: usize) -> Self {
+        Self(self.0 | ((1 << n) & Self::ENABLE))
+    }
+    /// Disable input capture on channel `n`.
+    #[inline]
+    pub fn disable_capture(self, n: usize) -> Self {
+        Self(self.0 & !((1 << n) & Self::ENABLE))
+    }
+    /// Check if input capture is enabled on channel `n`.
+    #[inline]
+    pub fn is_capture_enabled(self, n: usize) -> bool {
+        self.0 & (1 << n) & Self::ENABLE != 0
+    }
+    /// Enable capture done interrupt of channel `n`.
+    #[inline]
+    pub fn enable_interrupt(self, n: usize) -> Self {
+        Self(self.0 | ((1 << 8 << n) & Self::INTERRUPT))
+    }
+    /// Disable capture done interrupt of channel `n`.
+    #[inline]
+    pub fn disable_interrupt(self, n: usize) -> Self {
+        Self(self.0 & !((1 << 8 << n) & Self::INTERRUPT))
+    }
+    /// Check if capture done interrupt of channel `n` is enabled.
+    #[inline]
+    pub fn is_interrupt_enabled(self, n: usize) -> bool {
+        self.0 & (1 << 8 << n) & Self::INTERRUPT != 0
+    }
+}
+
+/// PWM output polarity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Polarity {
+    /// Duty cycle is the high level.
+    ActiveHigh = 0,
+    /// Duty cycle is the low level.
+    ActiveLow = 1,
+}
+
+/// Managed PWM peripheral with four channels.
+pub struct Pwm<T, const I: usize> {
+    pwm: T,
+    clock: u32,
+}
+
+/// Extension trait to create PWM drivers from peripherals.
+pub trait PwmExt<const I: usize>: AsRef<RegisterBlock> + Controlled + Sized {
+    /// Creates a PWM driver, all channels stopped.
+    #[inline]
+    fn pwm(self, clocks: &Clocks) -> Pwm<Self, I> {
+        reset::enable(&self);
+        let pwm = self.as_ref();
+        unsafe {
+            pwm.start.write(0);
+            pwm.output_enable.write(0);
+            pwm.polarity.write(PolarityMode::default());
+        }
+        Pwm {
+            pwm: self,
+            clock: clocks.pwm().to_hz(),
+        }
+    }
+}
+
+impl<T: AsRef<RegisterBlock>, const I: usize> Pwm<T, I> {
+    /// Splits the peripheral into separately owned channels.
+    #[inline]
+    pub fn split(&mut self) -> Channels<'_, I> {
+        let pwm = self.pwm.as_ref();
+        let clock = self.clock;
+        Channels {
+            ch0: Idle { pwm, clock },
+            ch1: Idle { pwm, clock },
+            ch2: Idle { pwm, clock },
+            ch3: Idle { pwm, clock },
+            group: Group { pwm },
+        }
+    }
+    /// Interrupt source of this controller on the interrupt controller.
+    #[inline]
+    pub const fn interrupt(&self) -> Interrupt {
+        Interrupt::pwm(I)
+    }
+    /// Release PWM driver and return its peripheral, turning its clocks off.
+    #[inline]
+    pub fn free(self) -> T
+    where
+        T: Controlled,
+    {
+        let pwm = self.pwm.as_ref();
+        unsafe {
+            pwm.start.write(0);
+            pwm.output_enable.write(0);
+        }
+        reset::disable(&self.pwm);
+        self.pwm
+    }
+}
+
+/// Separately owned channels of PWM peripheral `I`.
+pub struct Channels<'a, const I: usize> {
+    /// Channel 0.
+    pub ch0: Idle<'a, I, 0>,
+    /// Channel 1.
+    pub ch1: Idle<'a, I, 1>,
+    /// Channel 2.
+    pub ch2: Idle<'a, I, 2>,
+    /// Channel 3.
+    pub ch3: Idle<'a, I, 3>,
+    /// Synchronous start and stop of several channels.
+    pub group: Group<'a, I>,
+}
+
+/// Channel `C` of PWM peripheral `I`, not bound to any pad.
+pub struct Idle<'a, const I: usize, const C: usize> {
+    pwm: &'a RegisterBlock,
+    clock: u32,
+}
+
+impl<'a, const I: usize, const C: usize> Idle<'a, I, C> {
+    /// Configures this channel to output on `pad`, stopped at given frequency with zero duty.
+    #[inline]
+    pub fn into_output<PAD: ChannelPad<I, C>>(
+        self,
+        pad: PAD,
+        frequency: u32,
+    ) -> Channel<'a, I, C, PAD> {
+        let mut channel = Channel {
+            pwm: self.pwm,
+            clock: self.clock,
+            pad,
+        };
+        channel.set_frequency(frequency);
+        unsafe { channel.pwm.output_enable.modify(|w| w | (1 << C)) };
+        channel
+    }
+    /// Configures this channel to measure the input signal on `pad`.
+    #[inline]
+    pub fn into_capture<PAD: ChannelPad<I, C>>(self, pad: PAD) -> Capture<'a, I, C, PAD> {
+        unsafe {
+            self.pwm.start.modify(|w| w & !(1 << C));
+            self.pwm.output_enable.modify(|w| w & !(1 << C));
+        }
+        Capture {
+            pwm: self.pwm,
+            clock: self.clock,
+            pad,
+        }
+    }
+}
+
+/// Synchronous start and stop of channels on PWM peripheral `I`.
+pub struct Group<'a, const I: usize> {
+    pwm: &'a RegisterBlock,
+}
+
+impl<'a, const I: usize> Group<'a, I> {
+    /// Starts all channels in `mask` synchronously.
+    ///
+    /// Masks are built by combining `Channel::mask` of each channel.
+    #[inline]
+    pub fn start(&mut self, mask: u8) {
+        let mask = mask as u32 & 0xF;
+        unsafe {
+            self.pwm.start.modify(|w| w & !mask);
+            self.pwm.start.modify(|w| w | mask);
+        }
+    }
+    /// Stops all channels in `mask` synchronously.
+    #[inline]
+    pub fn stop(&mut self, mask: u8) {
+        unsafe { self.pwm.start.modify(|w| w & !(mask as u32 & 0xF)) };
+    }
+}
+
+/// PWM channel `C` driving a pad.
+pub struct Channel<'a, const I: usize, const C: usize, PAD> {
+    pwm: &'a RegisterBlock,
+    clock: u32,
+    pad: PAD,
+}
+
+impl<'a, const I: usize, const C: usize, PAD> Channel<'a, I, C, PAD> {
+    /// Bit of this channel, to be used in `Group::start` and `Group::stop`.
+    #[inline]
+    pub const fn mask(&self) -> u8 {
+        1 << C
+    }
+    /// Sets output frequency, keeping current duty ratio.
+    ///
+    /// Zero frequency selects the longest period supported.
+    #[inline]
+    pub fn set_frequency(&mut self, frequency: u32) {
+        self.set_period_ticks(self.clock.checked_div(frequency).unwrap_or(u32::MAX));
+    }
+    /// Sets period in PWM clock cycles, keeping current duty ratio.
+    #[inline]
+    pub fn set_period_ticks(&mut self, period: u32) {
+        let regs = &self.pwm.channels[C];
+        let old_period = regs.period.read();
+        let duty = old_period - regs.low_period.read().min(old_period);
+        let duty = if old_period == 0 {
+            0
+        } else {
+            (duty as u64 * period as u64 / old_period as u64) as u32
+        };
+        self.set_timings(period, duty);
+    }
+    /// Gets period in PWM clock cycles.
+    #[inline]
+    pub fn period_ticks(&self) -> u32 {
+        self.pwm.channels[C].period.read()
+    }
+    /// Sets active level duration in PWM clock cycles.
+    #[inline]
+    pub fn set_duty_ticks(&mut self, duty: u32) {
+        let period = self.period_ticks();
+        self.set_timings(period, duty.min(period));
+    }
+    #[inline]
+    fn set_timings(&mut self, period: u32, duty: u32) {
+        let regs = &self.pwm.channels[C];
+        unsafe {
+            regs.period.write(period);
+            regs.low_period.write(period - duty);
+            // apply to a running channel
+            self.pwm.update.modify(|w| w | (1 << C));
+            self.pwm.update.modify(|w| w & !(1 << C));
+        }
+    }
+    /// Sets output polarity.
+    #[inline]
+    pub fn set_polarity(&mut self, polarity: Polarity) {
+        unsafe { self.pwm.polarity.modify(|w| w.set_polarity(C, polarity)) };
+    }
+    /// Starts continuous output of this channel.
+    #[inline]
+    pub fn start(&mut self) {
+        unsafe {
+            self.pwm.polarity.modify(|w| w.disable_pulse_mode(C));
+            self.pwm.start.modify(|w| w & !(1 << C));
+            self.pwm.start.modify(|w| w | (1 << C));
+        }
+    }
+    /// Stops output of this channel.
+    #[inline]
+    pub fn stop(&mut self) {
+        unsafe { self.pwm.start.modify(|w| w & !(1 << C)) };
+    }
+    /// Outputs exactly `count` pulses then stops; see `is_pulses_done`.
+    #[inline]
+    pub fn start_pulses(&mut self, count: u32) {
+        unsafe {
+            self.pwm.start.modify(|w| w & !(1 << C));
+            self.pwm.polarity.modify(|w| w.enable_pulse_mode(C));
+            self.pwm.pulse_count[C].write(count);
+            self.pwm.start.modify(|w| w | (1 << C));
+        }
+    }
+    /// Checks if all pulses started by `start_pulses` were output.
+    #[inline]
+    pub fn is_pulses_done(&self) -> bool {
+        self.pwm.done.read() & (1 << C) != 0
+    }
+    /// Release this channel and return its idle handle and pad.
+    #[inline]
+    pub fn free(self) -> (Idle<'a, I, C>, PAD) {
+        unsafe {
+            self.pwm.start.modify(|w| w & !(1 << C));
+            self.pwm.output_enable.modify(|w| w & !(1 << C));
+        }
+        let idle = Idle {
+            pwm: self.pwm,
+            clock: self.clock,
+        };
+        (idle, self.pad)
+    }
+}
+
+impl<'a, const I: usize, const C: usize, PAD> ErrorType for Channel<'a, I, C, PAD> {
+    type Error = core::convert::Infallible;
+}
+
+impl<'a, const I: usize, const C: usize, PAD> SetDutyCycle for Channel<'a, I, C, PAD> {
+    #[inline]
+    fn max_duty_cycle(&self) -> u16 {
+        self.period_ticks().clamp(1, u16::MAX as u32) as u16
+    }
+    #[inline]
+    fn set_duty_cycle(&mut self, duty: u16) -> Result<(), Self::Error> {
+        let period = self.period_ticks();
+        let max = self.max_duty_cycle() as u64;
+        let duty = (duty as u64 * period as u64 / max) as u32;
+        self.set_duty_ticks(duty);
+        Ok(())
+    }
+}
+
+/// PWM channel `C` measuring the input signal on a pad.
+pub struct Capture<'a, const I: usize, const C: usize, PAD> {
+    pwm: &'a RegisterBlock,
+    clock: u32,
+    pad: PAD,
+}
+
+/// Result of an input capture.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Measurement {
+    period: u32,
+    high: u32,
+    clock: u32,
+}
+
+impl Measurement {
+    /// Input period in PWM clock cycles.
+    #[inline]
+    pub const fn period_ticks(&self) -> u32 {
+        self.period
+    }
+    /// Input high level duration in PWM clock cycles.
+    #[inline]
+    pub const fn high_ticks(&self) -> u32 {
+        self.high
+    }
+    /// Input frequency in Hz, zero if no edge was seen.
+    #[inline]
+    pub const fn frequency(&self) -> u32 {
+        match self.clock.checked_div(self.period) {
+            Some(frequency) => frequency,
+            None => 0,
+        }
+    }
+    /// Input duty cycle in thousandths.
+    #[inline]
+    pub const fn duty_permille(&self) -> u32 {
+        if self.period == 0 {
+            0
+        } else {
+            (self.high as u64 * 1000 / self.period as u64) as u32
+        }
+    }
+}
+
+impl<'a, const I: usize, const C: usize, PAD> Capture<'a, I, C, PAD> {
+    /// Starts a new measurement, discarding the previous result.
+    #[inline]
+    pub fn start(&mut self) {
+        unsafe {
+            self.pwm.capture_control.modify(|w| w.disable_capture(C));
+            self.pwm.capture_control.modify(|w| w.enable_capture(C));
+        }
+    }
+    /// Stops measuring.
+    #[inline]
+    pub fn stop(&mut self) {
+        unsafe { self.pwm.capture_control.modify(|w| w.disable_capture(C)) };
+    }
+    /// Checks if a measurement started by `start` has completed.
+    #[inline]
+    pub fn is_done(&self) -> bool {
+        self.pwm.capture_status.read() & (1 << C) != 0
+    }
+    /// Takes the result of a completed measurement, if any.
+    ///
+    /// Capture stops after a result is taken; call `start` to measure again.
+    #[inline]
+    pub fn measurement(&mut self) -> Option<Measurement> {
+        if !self.is_done() {
+            return None;
+        }
+        let measurement = Measurement {
+            period: self.pwm.capture_period[C].read(),
+            high: self.pwm.capture_high[C].read(),
+            clock: self.clock,
+        };
+        self.stop();
+        Some(measurement)
+    }
+    /// Starts a measurement and blocks until it completes.
+    #[inline]
+    pub fn measure(&mut self) -> Measurement {
+        self.start();
+        loop {
+            if let Some(measurement) = self.measurement() {
+                return measurement;
+            }
+            core::hint::spin_loop();
+        }
+    }
+    /// Enable capture done interrupt.
+    #[inline]
+    pub fn listen(&mut self) {
+        unsafe { self.pwm.capture_control.modify(|w| w.enable_interrupt(C)) };
+    }
+    /// Disable capture done interrupt.
+    #[inline]
+    pub fn unlisten(&mut self) {
+        unsafe { self.pwm.capture_control.modify(|w| w.disable_interrupt(C)) };
+    }
+    /// Converts into an interrupt driven asynchronous capture.
+    ///
+    /// The interrupt handler of this PWM peripheral must call `state.on_interrupt()`.
+    #[inline]
+    pub fn into_async(self, state: &State) -> AsyncCapture<'a, '_, I, C, PAD> {
+        unsafe { self.pwm.capture_control.modify(|w| w.disable_interrupt(C)) };
+        state.channel.store(C, Ordering::Relaxed);
+        state
+            .registers
+            .store(self.pwm as *const _ as *mut _, Ordering::Release);
+        AsyncCapture { inner: self, state }
+    }
+    /// Release this channel and return its idle handle and pad.
+    #[inline]
+    pub fn free(self) -> (Idle<'a, I, C>, PAD) {
+        unsafe {
+            self.pwm
+                .capture_control
+                .modify(|w| w.disable_capture(C).disable_interrupt(C))
+        };
+        let idle = Idle {
+            pwm: self.pwm,
+            clock: self.clock,
+        };
+        (idle, self.pad)
+    }
+}
+
+/// Shared state between an asynchronous capture channel and its interrupt handler.
+pub struct State {
+    waker: AtomicWaker,
+    registers: AtomicPtr<RegisterBlock>,
+    channel: AtomicUsize,
+}
+
+impl State {
+    /// Creates an empty state, usually placed in a `static`.
+    #[inline]
+    pub const fn new() -> Self {
+        State {
+            waker: AtomicWaker::new(),
+            registers: AtomicPtr::new(null_mut()),
+            channel: AtomicUsize::new(0),
+        }
+    }
+    /// Interrupt handler hook for the PWM peripheral.
+    ///
+    /// Disables the interrupt of a completed capture and wakes the task waiting on it;
+    /// does nothing if no asynchronous capture is attached to this state.
+    #[inline]
+    pub fn on_interrupt(&self) {
+        let registers = self.registers.load(Ordering::Acquire);
+        if registers.is_null() {
+            return;
+        }
+        let pwm = unsafe { &*registers };
+        let n = self.channel.load(Ordering::Relaxed);
+        if pwm.capture_status.read() & (1 << n) != 0 {
+            unsafe { pwm.capture_control.modify(|w| w.disable_interrupt(n)) };
+            self.waker.wake();
+        }
+    }
+}
+
+impl Default for State {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Interrupt driven asynchronous capture channel.
+pub struct AsyncCapture<'a, 's, const I: usize, const C: usize, PAD> {
+    inner: Capture<'a, I, C, PAD>,
+    state: &'s State,
+}
+
+impl<'a, 's, const I: usize, const C: usize, PAD> AsyncCapture<'a, 's, I, C, PAD> {
+    /// Starts a measurement and waits for its completion.
+    #[inline]
+    pub async fn measure(&mut self) -> Measurement {
+        self.inner.start();
+        poll_fn(|cx| {
+            if let Some(measurement) = self.inner.measurement() {
+                return Poll::Ready(measurement);
+            }
+            self.state.waker.register(cx.waker());
+            self.inner.listen();
+            // capture may have completed before interrupt was enabled
+            if let Some(measurement) = self.inner.measurement() {
+                self.inner.unlisten();
+                return Poll::Ready(measurement);
+            }
+            Poll::Pending
+        })
+        .await
+    }
+    /// Converts back into a blocking capture channel.
+    #[inline]
+    pub fn into_blocking(self) -> Capture<'a, I, C, PAD> {
+        let this = ManuallyDrop::new(self);
+        this.detach();
+        unsafe { core::ptr::read(&this.inner) }
+    }
+    /// Release this channel and return its idle handle and pad.
+    #[inline]
+    pub fn free(self) -> (Idle<'a, I, C>, PAD) {
+        self.into_blocking().free()
+    }
+    #[inline]
+    fn detach(&self) {
+        unsafe {
+            self.inner
+                .pwm
+                .capture_control
+                .modify(|w| w.disable_interrupt(C))
+        };
+        self.state.registers.store(null_mut(), Ordering::Release);
+    }
+}
+
+impl<'a, 's, const I: usize, const C: usize, PAD> Drop for AsyncCapture<'a, 's, I, C, PAD> {
+    #[inline]
+    fn drop(&mut self) {
+        self.detach();
+    }
+}
+
+/// Pads valid as output of channel `C` on PWM peripheral `I`.
+pub trait ChannelPad<const I: usize, const C: usize> {}
+
+macro_rules! channel_pad {
+    ($($pad: expr, $func: expr => ($i: expr, $c: expr);)+) => {
+        $(
+impl<T> ChannelPad<$i, $c> for Pad<T, $pad, PwmFunc<$func>> {}
+        )+
+    };
+}
+
+// keep in sync with `pad::pwm_fmux`
+channel_pad! {
+    18, 4 => (1, 0);
+    19, 5 => (1, 1);
+    27, 6 => (1, 2);
+    26, 7 => (1, 3);
+    47, 8 => (2, 0);
+    48, 9 => (2, 1);
+    12, 10 => (2, 2);
+    49, 10 => (2, 2);
+    11, 11 => (2, 3);
+    10, 12 => (3, 0);
+    8, 13 => (3, 1);
+    7, 14 => (3, 2);
+    6, 15 => (3, 3);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Capture, CaptureControl, Channel, ChannelRegisters, Polarity, PolarityMode, RegisterBlock,
+        State,
+    };
+    use core::{
+        cell::UnsafeCell,
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+    use memoffset::offset_of;
+
+    #[test]
+    fn struct_register_block_offset() {
+        assert_eq!(offset_of!(RegisterBlock, channels), 0x00);
+        assert_eq!(offset_of!(ChannelRegisters, low_period), 0x00);
+        assert_eq!(offset_of!(ChannelRegisters, period), 0x04);
+        assert_eq!(offset_of!(RegisterBlock, polarity), 0x40);
+        assert_eq!(offset_of!(RegisterBlock, start), 0x44);
+        assert_eq!(offset_of!(RegisterBlock, done), 0x48);
+        assert_eq!(offset_of!(RegisterBlock, update), 0x4C);
+        assert_eq!(offset_of!(RegisterBlock, pulse_count), 0x50);
+        assert_eq!(offset_of!(RegisterBlock, pulse_output_count), 0x60);
+        assert_eq!(offset_of!(RegisterBlock, shift_count), 0x80);
+        assert_eq!(offset_of!(RegisterBlock, shift_start), 0x90);
+        assert_eq!(offset_of!(RegisterBlock, capture_control), 0x9C);
+        assert_eq!(offset_of!(RegisterBlock, capture_status), 0xA0);
+        assert_eq!(offset_of!(RegisterBlock, capture_period), 0xA4);
+        assert_eq!(offset_of!(RegisterBlock, capture_high), 0xB4);
+        assert_eq!(offset_of!(RegisterBlock, output_enable), 0xD0);
+    }
+
+    #[test]
+    fn polarity_mode_bits() {
+        let val = PolarityMode::default()
+            .set_polarity(2, Polarity::ActiveLow)
+            .enable_pulse_mode(1);
+        assert_eq!(val.0, (1 << 2) | (1 << 9));
+        assert_eq!(val.polarity(2), Polarity::ActiveLow);
+        assert_eq!(val.polarity(1), Polarity::ActiveHigh);
+        assert!(val.is_pulse_mode_enabled(1));
+        let val = val
+            .set_polarity(2, Polarity::ActiveHigh)
+            .disable_pulse_mode(1);
+        assert_eq!(val.0, 0);
+    }
+
+    #[test]
+    fn zero_frequency_selects_longest_period() {
+        let pwm: RegisterBlock = unsafe { core::mem::zeroed() };
+        let mut channel = Channel::<0, 1, ()> {
+            pwm: &pwm,
+            clock: 100_000_000,
+            pad: (),
+        };
+        channel.set_frequency(0);
+        assert_eq!(channel.period_ticks(), u32::MAX);
+        channel.set_frequency(1_000);
+        assert_eq!(channel.period_ticks(), 100_000);
+    }
+
+    /// Forces a register value as if written by hardware.
+    fn set<R>(register: &R, value: u32) {
+        // registers are backed by `UnsafeCell`, thus writable behind shared references
+        let cell = unsafe { &*(register as *const R).cast::<UnsafeCell<u32>>() };
+        unsafe { cell.get().write_volatile(value) }
+    }
+
+    #[test]
+    fn async_capture_completes_on_interrupt() {
+        let pwm: RegisterBlock = unsafe { core::mem::zeroed() };
+        let state = State::new();
+        let mut capture = Capture::<0, 2, ()> {
+            pwm: &pwm,
+            clock: 100_000_000,
+            pad: (),
+        }
+        .into_async(&state);
+        {
+            let mut fut = pin!(capture.measure());
+            let mut cx = Context::from_waker(Waker::noop());
+            assert!(fut.as_mut().poll(&mut cx).is_pending());
+            let control = pwm.capture_control.read();
+            assert!(control.is_capture_enabled(2));
+            assert!(control.is_interrupt_enabled(2));
+
+            set(&pwm.capture_period[2], 4_000_000);
+            set(&pwm.capture_high[2], 1_000_000);
+            set(&pwm.capture_status, 1 << 2);
+            state.on_interrupt();
+            assert!(!pwm.capture_control.read().is_interrupt_enabled(2));
+            let Poll::Ready(measurement) = fut.as_mut().poll(&mut cx) else {
+                panic!("capture should be complete");
+            };
+            assert_eq!(measurement.frequency(), 25);
+            assert_eq!(measurement.duty_permille(), 250);
+        }
+        capture.free();
+        assert_eq!(pwm.capture_control.read(), CaptureControl::default());
+        state.on_interrupt();
+    }
+}
@@ -0,0 +1,247 @@
+//! Reset controller and peripheral power-up sequencing.
+
+use crate::clock::{self, Gate};
+use volatile_register::RW;
+
+/// Reset controller registers.
+#[repr(C)]
+pub struct RegisterBlock {
+    /// Peripheral soft reset registers; a cleared bit holds the peripheral in reset.
+    pub soft_reset: [RW<u32>; 4],
+    _reserved0: [u8; 0x10],
+    /// Auto-clear CPU soft reset register.
+    pub cpu_auto_clear_reset: RW<u32>,
+    /// CPU soft reset register.
+    pub cpu_reset: RW<u32>,
+}
+
+/// Peripheral soft reset lines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reset {
+    /// USB controller.
+    Usb,
+    /// SDIO-0 controller.
+    Sd0,
+    /// SDIO-1 controller.
+    Sd1,
+    /// System DMA controller.
+    Dma,
+    /// I2S-0 controller.
+    I2s0,
+    /// I2S-1 controller.
+    I2s1,
+    /// I2S-2 controller.
+    I2s2,
+    /// I2S-3 controller.
+    I2s3,
+    /// UART-0 controller.
+    Uart0,
+    /// UART-1 controller.
+    Uart1,
+    /// UART-2 controller.
+    Uart2,
+    /// UART-3 controller.
+    Uart3,
+    /// I2C-0 controller.
+    I2c0,
+    /// I2C-1 controller.
+    I2c1,
+    /// I2C-2 controller.
+    I2c2,
+    /// I2C-3 controller.
+    I2c3,
+    /// I2C-4 controller.
+    I2c4,
+    /// PWM-0 controller.
+    Pwm0,
+    /// PWM-1 controller.
+    Pwm1,
+    /// PWM-2 controller.
+    Pwm2,
+    /// PWM-3 controller.
+    Pwm3,
+    /// SPI-0 controller.
+    Spi0,
+    /// SPI-1 controller.
+    Spi1,
+    /// SPI-2 controller.
+    Spi2,
+    /// SPI-3 controller.
+    Spi3,
+    /// Watchdog timer.
+    Watchdog,
+    /// SPI NOR flash controller.
+    SpiNor,
+    /// SAR analog-to-digital converter.
+    Adc,
+    /// SPI NAND controller.
+    SpiNand,
+    /// UART-4 controller.
+    Uart4,
+    /// Hardware timer block.
+    Timer,
+    /// Audio codec DAC.
+    AudioDac,
+    /// Audio codec DAC APB bus interface.
+    AudioDacApb,
+    /// Audio codec ADC.
+    AudioAdc,
+}
+
+impl Reset {
+    /// Index of `soft_reset` register and bit of this reset line.
+    #[inline]
+    pub const fn position(self) -> (usize, u32) {
+        match self {
+            Reset::Usb => (0, 11),
+            Reset::Sd0 => (0, 16),
+            Reset::Sd1 => (0, 17),
+            Reset::Dma => (0, 18),
+            Reset::I2s0 => (0, 19),
+            Reset::I2s1 => (0, 20),
+            Reset::I2s2 => (0, 21),
+            Reset::I2s3 => (0, 22),
+            Reset::Uart0 => (0, 23),
+            Reset::Uart1 => (0, 24),
+            Reset::Uart2 => (0, 25),
+            Reset::Uart3 => (0, 26),
+            Reset::I2c0 => (0, 27),
+            Reset::I2c1 => (0, 28),
+            Reset::I2c2 => (0, 29),
+            Reset::I2c3 => (0, 30),
+            Reset::I2c4 => (0, 31),
+            Reset::Pwm0 => (1, 0),
+            Reset::Pwm1 => (1, 1),
+            Reset::Pwm2 => (1, 2),
+            Reset::Pwm3 => (1, 3),
+            Reset::Spi0 => (1, 8),
+            Reset::Spi1 => (1, 9),
+            Reset::Spi2 => (1, 10),
+            Reset::Spi3 => (1, 11),
+            Reset::Watchdog => (1, 16),
+            Reset::SpiNor => (1, 18),
+            Reset::Adc => (1, 20),
+            Reset::SpiNand => (1, 29),
+            Reset::Uart4 => (2, 10),
+            Reset::Timer => (2, 13),
+            Reset::AudioDac => (2, 27),
+            Reset::AudioDacApb => (2, 28),
+            Reset::AudioAdc => (2, 29),
+        }
+    }
+}
+
+/// Holds a peripheral in reset or releases it.
+///
+/// Reset registers are shared by all peripheral drivers, so the update runs in a critical section.
+#[inline]
+fn set_reset(reset: &RegisterBlock, line: Reset, asserted: bool) {
+    let (n, bit) = line.position();
+    critical_section::with(|_| unsafe {
+        reset.soft_reset[n].modify(|w| {
+            if asserted {
+                w & !(1 << bit)
+            } else {
+                w | (1 << bit)
+            }
+        })
+    });
+}
+
+/// Waits for a reset to propagate through the peripheral clock domain.
+#[inline]
+fn settle() {
+    // reset must last a few cycles of the slowest peripheral clock (25 MHz),
+    // 100 loops is long enough with at most 1 GHz core clock
+    for _ in 0..100 {
+        core::hint::spin_loop();
+    }
+}
+
+/// Peripheral instances with clock gates and soft reset lines.
+///
+/// Driver constructors call `enable` on the peripheral before configuring it,
+/// and `free` calls `disable` after releasing it.
+pub trait Controlled {
+    /// Clock gates of this peripheral, including bus and function clocks.
+    const GATES: &'static [Gate];
+    /// Soft reset lines of this peripheral.
+    const RESETS: &'static [Reset];
+
+    /// Clock generator registers controlling this peripheral.
+    fn clock_gen(&self) -> &clock::RegisterBlock;
+    /// Reset controller registers controlling this peripheral.
+    fn reset_control(&self) -> &RegisterBlock;
+}
+
+/// Turns on clocks of a peripheral and pulses its soft reset.
+#[inline]
+pub fn enable<P: Controlled>(peripheral: &P) {
+    for gate in P::GATES {
+        clock::set_gate(peripheral.clock_gen(), *gate, true);
+    }
+    for line in P::RESETS {
+        set_reset(peripheral.reset_control(), *line, true);
+    }
+    settle();
+    for line in P::RESETS {
+        set_reset(peripheral.reset_control(), *line, false);
+    }
+}
+
+/// Holds a peripheral in soft reset and turns off its clocks.
+///
+/// Shared gates stay on, as other instances may still use them.
+#[inline]
+pub fn disable<P: Controlled>(peripheral: &P) {
+    for line in P::RESETS {
+        set_reset(peripheral.reset_control(), *line, true);
+    }
+    for gate in P::GATES.iter().filter(|gate| !gate.is_shared()) {
+        clock::set_gate(peripheral.clock_gen(), *gate, false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{disable, enable, Controlled, RegisterBlock, Reset};
+    use crate::clock::{self, Gate};
+    use memoffset::offset_of;
+
+    struct Sim {
+        clock_gen: clock::RegisterBlock,
+        reset: RegisterBlock,
+    }
+
+    impl Controlled for Sim {
+        const GATES: &'static [Gate] = &[Gate::Uart2, Gate::ApbUart2, Gate::Spi];
+        const RESETS: &'static [Reset] = &[Reset::Uart2];
+
+        fn clock_gen(&self) -> &clock::RegisterBlock {
+            &self.clock_gen
+        }
+        fn reset_control(&self) -> &RegisterBlock {
+            &self.reset
+        }
+    }
+
+    #[test]
+    fn struct_register_block_offset() {
+        assert_eq!(offset_of!(RegisterBlock, soft_reset), 0x00);
+        assert_eq!(offset_of!(RegisterBlock, cpu_auto_clear_reset), 0x20);
+        assert_eq!(offset_of!(RegisterBlock, cpu_reset), 0x24);
+    }
+
+    #[test]
+    fn enable_disable_peripheral() {
+        let sim: Sim = unsafe { core::mem::zeroed() };
+        enable(&sim);
+        assert_eq!(sim.clock_gen.clock_enable[1].read(), 0b11 << 18);
+        assert_eq!(sim.clock_gen.clock_enable[3].read(), 1 << 6);
+        assert_eq!(sim.reset.soft_reset[0].read(), 1 << 25);
+        disable(&sim);
+        assert_eq!(sim.clock_gen.clock_enable[1].read(), 0);
+        assert_eq!(sim.clock_gen.clock_enable[3].read(), 1 << 6);
+        assert_eq!(sim.reset.soft_reset[0].read(), 0);
+    }
+}
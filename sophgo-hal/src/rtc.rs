@@ -0,0 +1,454 @@
+//! Real-time clock.
+
+use volatile_register::{RO, RW, WO};
+
+/// Real-time clock registers.
+///
+/// Power-on reason at offset 0xF8 is read through `watchdog::ResetStatus`.
+#[repr(C)]
+pub struct RegisterBlock {
+    /// Analog calibration register.
+    pub analog_calibration: RW<u32>,
+    /// Second pulse generation register.
+    pub second_pulse_generation: RW<u32>,
+    /// Alarm time in seconds.
+    pub alarm_time: RW<u32>,
+    /// Alarm enable register.
+    pub alarm_enable: RW<u32>,
+    /// Value to be loaded into the seconds counter.
+    pub set_seconds_value: RW<u32>,
+    /// Writing 1 loads `set_seconds_value` into the seconds counter.
+    pub set_seconds_trigger: WO<u32>,
+    /// Seconds counter.
+    pub seconds_value: RO<u32>,
+    /// Scratch registers, preserved across system resets.
+    pub scratch: [RW<u32>; 6],
+    _reserved0: [u8; 0x88],
+    /// Wake-up source enable register.
+    pub wakeup_enable: RW<WakeupSources>,
+    /// Power button register.
+    pub power_button: RW<PowerButton>,
+    _reserved1: [u8; 0x38],
+    /// Power-off reason register.
+    pub power_off_reason: RO<u32>,
+}
+
+/// Wake-up source enable register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct WakeupSources(u32);
+
+impl WakeupSources {
+    const POWER_BUTTON: u32 = 1 << 0;
+    const ALARM: u32 = 1 << 1;
+    const WAKEUP0: u32 = 1 << 2;
+    const WAKEUP1: u32 = 1 << 3;
+
+    /// Enable wake-up on power button press.
+    #[inline]
+    pub const fn enable_power_button(self) -> Self {
+        Self(self.0 | Self::POWER_BUTTON)
+    }
+    /// Disable wake-up on power button press.
+    #[inline]
+    pub const fn disable_power_button(self) -> Self {
+        Self(self.0 & !Self::POWER_BUTTON)
+    }
+    /// Check if wake-up on power button press is enabled.
+    #[inline]
+    pub const fn is_power_button_enabled(self) -> bool {
+        self.0 & Self::POWER_BUTTON != 0
+    }
+    /// Enable wake-up on alarm.
+    #[inline]
+    pub const fn enable_alarm(self) -> Self {
+        Self(self.0 | Self::ALARM)
+    }
+    /// Disable wake-up on alarm.
+    #[inline]
+    pub const fn disable_alarm(self) -> Self {
+        Self(self.0 & !Self::ALARM)
+    }
+    /// Check if wake-up on alarm is enabled.
+    #[inline]
+    pub const fn is_alarm_enabled(self) -> bool {
+        self.0 & Self::ALARM != 0
+    }
+    /// Enable wake-up on `PWR_WAKEUP0` pad.
+    #[inline]
+    pub const fn enable_wakeup0(self) -> Self {
+        Self(self.0 | Self::WAKEUP0)
+    }
+    /// Disable wake-up on `PWR_WAKEUP0` pad.
+    #[inline]
+    pub const fn disable_wakeup0(self) -> Self {
+        Self(self.0 & !Self::WAKEUP0)
+    }
+    /// Check if wake-up on `PWR_WAKEUP0` pad is enabled.
+    #[inline]
+    pub const fn is_wakeup0_enabled(self) -> bool {
+        self.0 & Self::WAKEUP0 != 0
+    }
+    /// Enable wake-up on `PWR_WAKEUP1` pad.
+    #[inline]
+    pub const fn enable_wakeup1(self) -> Self {
+        Self(self.0 | Self::WAKEUP1)
+    }
+    /// Disable wake-up on `PWR_WAKEUP1` pad.
+    #[inline]
+    pub const fn disable_wakeup1(self) -> Self {
+        Self(self.0 & !Self::WAKEUP1)
+    }
+    /// Check if wake-up on `PWR_WAKEUP1` pad is enabled.
+    #[inline]
+    pub const fn is_wakeup1_enabled(self) -> bool {
+        self.0 & Self::WAKEUP1 != 0
+    }
+}
+
+/// Power button register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct PowerButton(u32);
+
+impl PowerButton {
+    const LONG_PRESS_SHUTDOWN: u32 = 1 << 0;
+    const PRESSED: u32 = 1 << 8;
+
+    /// Enable shutdown on long press of power button.
+    #[inline]
+    pub const fn enable_long_press_shutdown(self) -> Self {
+        Self(self.0 | Self::LONG_PRESS_SHUTDOWN)
+    }
+    /// Disable shutdown on long press of power button.
+    #[inline]
+    pub const fn disable_long_press_shutdown(self) -> Self {
+        Self(self.0 & !Self::LONG_PRESS_SHUTDOWN)
+    }
+    /// Check if shutdown on long press of power button is enabled.
+    #[inline]
+    pub const fn is_long_press_shutdown_enabled(self) -> bool {
+        self.0 & Self::LONG_PRESS_SHUTDOWN != 0
+    }
+    /// Check if power button is currently pressed.
+    #[inline]
+    pub const fn is_pressed(self) -> bool {
+        self.0 & Self::PRESSED != 0
+    }
+}
+
+/// Calendar date and time, in UTC.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DateTime {
+    /// Year, from 1970.
+    pub year: u16,
+    /// Month of year, from 1 to 12.
+    pub month: u8,
+    /// Day of month, from 1 to 31.
+    pub day: u8,
+    /// Hour of day, from 0 to 23.
+    pub hour: u8,
+    /// Minute of hour, from 0 to 59.
+    pub minute: u8,
+    /// Second of minute, from 0 to 59.
+    pub second: u8,
+}
+
+/// Day of week.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Weekday {
+    /// Monday.
+    Monday,
+    /// Tuesday.
+    Tuesday,
+    /// Wednesday.
+    Wednesday,
+    /// Thursday.
+    Thursday,
+    /// Friday.
+    Friday,
+    /// Saturday.
+    Saturday,
+    /// Sunday.
+    Sunday,
+}
+
+impl DateTime {
+    /// Converts seconds since Unix epoch into calendar date and time.
+    #[inline]
+    pub const fn from_timestamp(timestamp: u32) -> Self {
+        let days = (timestamp / 86400) as i64;
+        let secs = timestamp % 86400;
+        // days to civil date, proleptic Gregorian calendar
+        let z = days + 719468;
+        let era = z / 146097;
+        let doe = z - era * 146097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+        Self {
+            year: year as u16,
+            month: month as u8,
+            day: day as u8,
+            hour: (secs / 3600) as u8,
+            minute: (secs / 60 % 60) as u8,
+            second: (secs % 60) as u8,
+        }
+    }
+    /// Converts into seconds since Unix epoch.
+    ///
+    /// Fields are not validated; the result saturates to the range of `u32`.
+    #[inline]
+    pub const fn timestamp(&self) -> u32 {
+        let month = self.month as i64;
+        let year = self.year as i64 - if month <= 2 { 1 } else { 0 };
+        let era = year / 400;
+        let yoe = year - era * 400;
+        let mp = if month > 2 { month - 3 } else { month + 9 };
+        let doy = (153 * mp + 2) / 5 + self.day as i64 - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        let days = era * 146097 + doe - 719468;
+        let secs =
+            days * 86400 + self.hour as i64 * 3600 + self.minute as i64 * 60 + self.second as i64;
+        if secs < 0 {
+            0
+        } else if secs > u32::MAX as i64 {
+            u32::MAX
+        } else {
+            secs as u32
+        }
+    }
+    /// Day of week of this date.
+    #[inline]
+    pub const fn weekday(&self) -> Weekday {
+        // 1970-01-01 is a Thursday
+        match (self.timestamp() / 86400 + 3) % 7 {
+            0 => Weekday::Monday,
+            1 => Weekday::Tuesday,
+            2 => Weekday::Wednesday,
+            3 => Weekday::Thursday,
+            4 => Weekday::Friday,
+            5 => Weekday::Saturday,
+            _ => Weekday::Sunday,
+        }
+    }
+}
+
+/// Managed real-time clock peripheral.
+pub struct Rtc<T> {
+    rtc: T,
+}
+
+/// Extension trait to create real-time clock drivers from peripherals.
+pub trait RtcExt: AsRef<RegisterBlock> + Sized {
+    /// Creates a real-time clock driver; the counter keeps running.
+    #[inline]
+    fn rtc(self) -> Rtc<Self> {
+        Rtc { rtc: self }
+    }
+}
+
+impl<T: AsRef<RegisterBlock>> Rtc<T> {
+    /// Seconds counter value, usually seconds since Unix epoch.
+    #[inline]
+    pub fn seconds(&self) -> u32 {
+        let rtc = self.rtc.as_ref();
+        // counter is in 32 kHz domain, read until stable
+        loop {
+            let value = rtc.seconds_value.read();
+            if rtc.seconds_value.read() == value {
+                return value;
+            }
+        }
+    }
+    /// Loads seconds counter.
+    #[inline]
+    pub fn set_seconds(&mut self, seconds: u32) {
+        let rtc = self.rtc.as_ref();
+        unsafe {
+            rtc.set_seconds_value.write(seconds);
+            rtc.set_seconds_trigger.write(1);
+        }
+    }
+    /// Current calendar date and time.
+    #[inline]
+    pub fn date_time(&self) -> DateTime {
+        DateTime::from_timestamp(self.seconds())
+    }
+    /// Sets current calendar date and time.
+    #[inline]
+    pub fn set_date_time(&mut self, date_time: DateTime) {
+        self.set_seconds(date_time.timestamp());
+    }
+    /// Raises alarm interrupt when the seconds counter reaches `seconds`.
+    #[inline]
+    pub fn set_alarm(&mut self, seconds: u32) {
+        let rtc = self.rtc.as_ref();
+        unsafe {
+            rtc.alarm_enable.write(0);
+            rtc.alarm_time.write(seconds);
+            rtc.alarm_enable.write(1);
+        }
+    }
+    /// Raises alarm interrupt at calendar date and time.
+    #[inline]
+    pub fn set_alarm_at(&mut self, date_time: DateTime) {
+        self.set_alarm(date_time.timestamp());
+    }
+    /// Disables alarm, clearing its interrupt.
+    #[inline]
+    pub fn disable_alarm(&mut self) {
+        unsafe { self.rtc.as_ref().alarm_enable.write(0) };
+    }
+    /// Checks if alarm is enabled and the seconds counter has reached alarm time.
+    ///
+    /// The RTC has no separate alarm status bit; its alarm line stays raised
+    /// in this condition until the alarm is disabled.
+    #[inline]
+    pub fn is_alarm_pending(&self) -> bool {
+        let rtc = self.rtc.as_ref();
+        rtc.alarm_enable.read() & 1 != 0 && self.seconds() >= rtc.alarm_time.read()
+    }
+    /// Reads scratch register `n`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is not less than 6.
+    #[inline]
+    pub fn scratch(&self, n: usize) -> u32 {
+        self.rtc.as_ref().scratch[n].read()
+    }
+    /// Writes scratch register `n`, preserved across system resets.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is not less than 6.
+    #[inline]
+    pub fn set_scratch(&mut self, n: usize, value: u32) {
+        unsafe { self.rtc.as_ref().scratch[n].write(value) };
+    }
+    /// Sets sources able to wake the system from power off.
+    #[inline]
+    pub fn set_wakeup_sources(&mut self, sources: WakeupSources) {
+        unsafe { self.rtc.as_ref().wakeup_enable.write(sources) };
+    }
+    /// Sources able to wake the system from power off.
+    #[inline]
+    pub fn wakeup_sources(&self) -> WakeupSources {
+        self.rtc.as_ref().wakeup_enable.read()
+    }
+    /// Enables or disables shutdown on long press of power button.
+    #[inline]
+    pub fn set_power_button_shutdown(&mut self, enable: bool) {
+        unsafe {
+            self.rtc.as_ref().power_button.modify(|w| {
+                if enable {
+                    w.enable_long_press_shutdown()
+                } else {
+                    w.disable_long_press_shutdown()
+                }
+            })
+        };
+    }
+    /// Checks if power button is currently pressed.
+    #[inline]
+    pub fn is_power_button_pressed(&self) -> bool {
+        self.rtc.as_ref().power_button.read().is_pressed()
+    }
+    /// Release real-time clock driver and return its peripheral.
+    #[inline]
+    pub fn free(self) -> T {
+        self.rtc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DateTime, RegisterBlock, Rtc, WakeupSources, Weekday};
+    use core::cell::UnsafeCell;
+    use memoffset::offset_of;
+
+    #[test]
+    fn struct_register_block_offset() {
+        assert_eq!(offset_of!(RegisterBlock, analog_calibration), 0x00);
+        assert_eq!(offset_of!(RegisterBlock, alarm_time), 0x08);
+        assert_eq!(offset_of!(RegisterBlock, alarm_enable), 0x0C);
+        assert_eq!(offset_of!(RegisterBlock, set_seconds_value), 0x10);
+        assert_eq!(offset_of!(RegisterBlock, set_seconds_trigger), 0x14);
+        assert_eq!(offset_of!(RegisterBlock, seconds_value), 0x18);
+        assert_eq!(offset_of!(RegisterBlock, scratch), 0x1C);
+        assert_eq!(offset_of!(RegisterBlock, wakeup_enable), 0xBC);
+        assert_eq!(offset_of!(RegisterBlock, power_button), 0xC0);
+        assert_eq!(offset_of!(RegisterBlock, power_off_reason), 0xFC);
+    }
+
+    #[test]
+    fn struct_wakeup_sources_functions() {
+        let val = WakeupSources::default()
+            .enable_power_button()
+            .enable_alarm()
+            .enable_wakeup1();
+        assert_eq!(val.0, 0xB);
+        assert!(val.is_alarm_enabled());
+        assert!(!val.is_wakeup0_enabled());
+        let val = val.disable_alarm().disable_power_button();
+        assert_eq!(val.0, 0x8);
+    }
+
+    #[test]
+    fn date_time_conversion() {
+        let epoch = DateTime::from_timestamp(0);
+        assert_eq!(
+            epoch,
+            DateTime {
+                year: 1970,
+                month: 1,
+                day: 1,
+                hour: 0,
+                minute: 0,
+                second: 0
+            }
+        );
+        assert_eq!(epoch.weekday(), Weekday::Thursday);
+        let leap = DateTime {
+            year: 2024,
+            month: 2,
+            day: 29,
+            hour: 13,
+            minute: 37,
+            second: 42,
+        };
+        assert_eq!(leap.timestamp(), 1709213862);
+        assert_eq!(DateTime::from_timestamp(1709213862), leap);
+        assert_eq!(leap.weekday(), Weekday::Thursday);
+        let last = DateTime::from_timestamp(u32::MAX);
+        assert_eq!((last.year, last.month, last.day), (2106, 2, 7));
+        assert_eq!(last.timestamp(), u32::MAX);
+    }
+
+    struct Sim(RegisterBlock);
+
+    impl AsRef<RegisterBlock> for Sim {
+        fn as_ref(&self) -> &RegisterBlock {
+            &self.0
+        }
+    }
+
+    #[test]
+    fn alarm_pending_after_alarm_time() {
+        let sim = Sim(unsafe { core::mem::zeroed() });
+        let regs = &sim.0;
+        let mut rtc = Rtc { rtc: &sim };
+        rtc.set_alarm(100);
+        assert!(!rtc.is_alarm_pending());
+        // the seconds counter is read-only, advance it as the hardware would
+        let cell = unsafe { &*(&regs.seconds_value as *const _ as *const UnsafeCell<u32>) };
+        unsafe { cell.get().write_volatile(100) };
+        assert!(rtc.is_alarm_pending());
+        rtc.disable_alarm();
+        assert!(!rtc.is_alarm_pending());
+    }
+}
@@ -0,0 +1,1703 @@
+//! SD/MMC host controller, SD memory cards and eMMC devices.
+
+use crate::clint::Instant;
+use crate::clock::Clocks;
+use crate::pad::{IoPower, IoVoltage, Pad, SdFunc};
+use crate::reset::{self, Controlled};
+use core::cell::RefCell;
+use core::time::Duration;
+use volatile_register::{RO, RW};
+
+/// SD host controller registers.
+#[repr(C)]
+pub struct RegisterBlock {
+    /// SDMA system address, or argument of auto CMD23.
+    pub sdma_address: RW<u32>,
+    /// Transfer block size register.
+    pub block_size: RW<u16>,
+    /// Transfer block count register.
+    pub block_count: RW<u16>,
+    /// Command argument register.
+    pub argument: RW<u32>,
+    /// Transfer mode register.
+    pub transfer_mode: RW<TransferMode>,
+    /// Command register; writing it issues the command.
+    pub command: RW<Command>,
+    /// Command response registers, bits 8 to 135 of long responses.
+    pub response: [RO<u32>; 4],
+    /// Buffer data port for programmed I/O transfers.
+    pub buffer_data: RW<u32>,
+    /// Present state register.
+    pub present_state: RO<PresentState>,
+    /// Host control 1 register.
+    pub host_control1: RW<HostControl1>,
+    /// Power control register.
+    pub power_control: RW<PowerControl>,
+    /// Block gap control register.
+    pub block_gap_control: RW<u8>,
+    /// Wakeup control register.
+    pub wakeup_control: RW<u8>,
+    /// Clock control register.
+    pub clock_control: RW<ClockControl>,
+    /// Data timeout control register.
+    pub timeout_control: RW<u8>,
+    /// Software reset register.
+    pub software_reset: RW<u8>,
+    /// Normal interrupt status register; write 1 to clear.
+    pub normal_interrupt_status: RW<u16>,
+    /// Error interrupt status register; write 1 to clear.
+    pub error_interrupt_status: RW<u16>,
+    /// Normal interrupt status enable register.
+    pub normal_interrupt_status_enable: RW<u16>,
+    /// Error interrupt status enable register.
+    pub error_interrupt_status_enable: RW<u16>,
+    /// Normal interrupt signal enable register.
+    pub normal_interrupt_signal_enable: RW<u16>,
+    /// Error interrupt signal enable register.
+    pub error_interrupt_signal_enable: RW<u16>,
+    /// Auto CMD error status register.
+    pub auto_command_error_status: RO<u16>,
+    /// Host control 2 register.
+    pub host_control2: RW<HostControl2>,
+    /// Capabilities register.
+    pub capabilities: RO<Capabilities>,
+    /// Capabilities register, upper half.
+    pub capabilities2: RO<Capabilities2>,
+    _reserved0: [u8; 0xB6],
+    /// Host controller version register.
+    pub host_version: RO<u16>,
+}
+
+/// Transfer mode register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct TransferMode(u16);
+
+impl TransferMode {
+    const BLOCK_COUNT: u16 = 1 << 1;
+    const AUTO_CMD12: u16 = 0b01 << 2;
+    const READ: u16 = 1 << 4;
+    const MULTI_BLOCK: u16 = 1 << 5;
+
+    /// Stop transfer after `block_count` blocks.
+    #[inline]
+    pub const fn enable_block_count(self) -> Self {
+        Self(self.0 | Self::BLOCK_COUNT)
+    }
+    /// Check if block count is enabled.
+    #[inline]
+    pub const fn is_block_count_enabled(self) -> bool {
+        self.0 & Self::BLOCK_COUNT != 0
+    }
+    /// Issue CMD12 automatically after last block.
+    #[inline]
+    pub const fn enable_auto_cmd12(self) -> Self {
+        Self(self.0 | Self::AUTO_CMD12)
+    }
+    /// Check if auto CMD12 is enabled.
+    #[inline]
+    pub const fn is_auto_cmd12_enabled(self) -> bool {
+        self.0 & Self::AUTO_CMD12 != 0
+    }
+    /// Transfer data from card to host.
+    #[inline]
+    pub const fn set_read(self) -> Self {
+        Self(self.0 | Self::READ)
+    }
+    /// Transfer data from host to card.
+    #[inline]
+    pub const fn set_write(self) -> Self {
+        Self(self.0 & !Self::READ)
+    }
+    /// Check if data direction is card to host.
+    #[inline]
+    pub const fn is_read(self) -> bool {
+        self.0 & Self::READ != 0
+    }
+    /// Transfer more than one block.
+    #[inline]
+    pub const fn enable_multi_block(self) -> Self {
+        Self(self.0 | Self::MULTI_BLOCK)
+    }
+    /// Check if multi-block transfer is enabled.
+    #[inline]
+    pub const fn is_multi_block_enabled(self) -> bool {
+        self.0 & Self::MULTI_BLOCK != 0
+    }
+}
+
+/// Command register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct Command(u16);
+
+impl Command {
+    const RESPONSE_TYPE: u16 = 0x3;
+    const CRC_CHECK: u16 = 1 << 3;
+    const INDEX_CHECK: u16 = 1 << 4;
+    const DATA_PRESENT: u16 = 1 << 5;
+    const INDEX: u16 = 0x3F << 8;
+
+    /// Set expected response, including its CRC and index checks.
+    #[inline]
+    pub const fn set_response(self, response: Response) -> Self {
+        let (length, crc, index) = match response {
+            Response::None => (0, false, false),
+            Response::R2 => (1, true, false),
+            Response::R3 => (2, false, false),
+            Response::R1 | Response::R6 | Response::R7 => (2, true, true),
+            Response::R1b => (3, true, true),
+        };
+        let mut bits = self.0 & !(Self::RESPONSE_TYPE | Self::CRC_CHECK | Self::INDEX_CHECK);
+        bits |= length;
+        if crc {
+            bits |= Self::CRC_CHECK;
+        }
+        if index {
+            bits |= Self::INDEX_CHECK;
+        }
+        Self(bits)
+    }
+    /// Get response length field: none, 136 bits, 48 bits, or 48 bits with busy.
+    #[inline]
+    pub const fn response_type(self) -> u8 {
+        (self.0 & Self::RESPONSE_TYPE) as u8
+    }
+    /// Command transfers data on the data lines.
+    #[inline]
+    pub const fn enable_data_present(self) -> Self {
+        Self(self.0 | Self::DATA_PRESENT)
+    }
+    /// Check if command transfers data.
+    #[inline]
+    pub const fn is_data_present(self) -> bool {
+        self.0 & Self::DATA_PRESENT != 0
+    }
+    /// Set command index.
+    #[inline]
+    pub const fn set_index(self, index: u8) -> Self {
+        Self((self.0 & !Self::INDEX) | (((index as u16) << 8) & Self::INDEX))
+    }
+    /// Get command index.
+    #[inline]
+    pub const fn index(self) -> u8 {
+        ((self.0 & Self::INDEX) >> 8) as u8
+    }
+}
+
+/// Command response formats.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Response {
+    /// No response.
+    None,
+    /// Normal response.
+    R1,
+    /// Normal response with busy signal on data line 0.
+    R1b,
+    /// CID or CSD register, 136 bits.
+    R2,
+    /// OCR register, without CRC.
+    R3,
+    /// Published relative card address.
+    R6,
+    /// Card interface condition.
+    R7,
+}
+
+/// Present state register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct PresentState(u32);
+
+impl PresentState {
+    const COMMAND_INHIBIT: u32 = 1 << 0;
+    const DATA_INHIBIT: u32 = 1 << 1;
+    const CARD_INSERTED: u32 = 1 << 16;
+    const WRITE_PROTECT: u32 = 1 << 19;
+    const DAT0_LEVEL: u32 = 1 << 20;
+
+    /// Check if command line is in use.
+    #[inline]
+    pub const fn is_command_inhibited(self) -> bool {
+        self.0 & Self::COMMAND_INHIBIT != 0
+    }
+    /// Check if data lines are in use.
+    #[inline]
+    pub const fn is_data_inhibited(self) -> bool {
+        self.0 & Self::DATA_INHIBIT != 0
+    }
+    /// Check if a card is inserted.
+    #[inline]
+    pub const fn is_card_inserted(self) -> bool {
+        self.0 & Self::CARD_INSERTED != 0
+    }
+    /// Check if write protect switch allows writing.
+    #[inline]
+    pub const fn is_write_enabled(self) -> bool {
+        self.0 & Self::WRITE_PROTECT != 0
+    }
+    /// Check if data line 0 is high, i.e. card is not busy.
+    #[inline]
+    pub const fn is_dat0_high(self) -> bool {
+        self.0 & Self::DAT0_LEVEL != 0
+    }
+}
+
+/// Host control 1 register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct HostControl1(u8);
+
+impl HostControl1 {
+    const DATA_WIDTH_4: u8 = 1 << 1;
+    const HIGH_SPEED: u8 = 1 << 2;
+    const DATA_WIDTH_8: u8 = 1 << 5;
+
+    /// Set data bus width.
+    #[inline]
+    pub const fn set_bus_width(self, width: BusWidth) -> Self {
+        let bits = self.0 & !(Self::DATA_WIDTH_4 | Self::DATA_WIDTH_8);
+        Self(match width {
+            BusWidth::One => bits,
+            BusWidth::Four => bits | Self::DATA_WIDTH_4,
+            BusWidth::Eight => bits | Self::DATA_WIDTH_8,
+        })
+    }
+    /// Get data bus width.
+    #[inline]
+    pub const fn bus_width(self) -> BusWidth {
+        if self.0 & Self::DATA_WIDTH_8 != 0 {
+            BusWidth::Eight
+        } else if self.0 & Self::DATA_WIDTH_4 != 0 {
+            BusWidth::Four
+        } else {
+            BusWidth::One
+        }
+    }
+    /// Drive signals on rising clock edge for high speed mode.
+    #[inline]
+    pub const fn enable_high_speed(self) -> Self {
+        Self(self.0 | Self::HIGH_SPEED)
+    }
+    /// Drive signals on falling clock edge for default speed mode.
+    #[inline]
+    pub const fn disable_high_speed(self) -> Self {
+        Self(self.0 & !Self::HIGH_SPEED)
+    }
+    /// Check if high speed mode is enabled.
+    #[inline]
+    pub const fn is_high_speed_enabled(self) -> bool {
+        self.0 & Self::HIGH_SPEED != 0
+    }
+}
+
+/// Data bus width.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BusWidth {
+    /// Data line 0 only.
+    One,
+    /// Data lines 0 to 3.
+    Four,
+    /// Data lines 0 to 7, for eMMC devices.
+    Eight,
+}
+
+/// Power control register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct PowerControl(u8);
+
+impl PowerControl {
+    const BUS_POWER: u8 = 1 << 0;
+    const VOLTAGE: u8 = 0x7 << 1;
+    const VOLTAGE_3V3: u8 = 0x7 << 1;
+
+    /// Power on bus at 3.3V.
+    #[inline]
+    pub const fn power_on(self) -> Self {
+        Self((self.0 & !Self::VOLTAGE) | Self::VOLTAGE_3V3 | Self::BUS_POWER)
+    }
+    /// Power off bus.
+    #[inline]
+    pub const fn power_off(self) -> Self {
+        Self(self.0 & !Self::BUS_POWER)
+    }
+    /// Check if bus is powered.
+    #[inline]
+    pub const fn is_powered(self) -> bool {
+        self.0 & Self::BUS_POWER != 0
+    }
+}
+
+/// Clock control register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct ClockControl(u16);
+
+impl ClockControl {
+    const INTERNAL_CLOCK_ENABLE: u16 = 1 << 0;
+    const INTERNAL_CLOCK_STABLE: u16 = 1 << 1;
+    const SD_CLOCK_ENABLE: u16 = 1 << 2;
+    const DIVIDER_LOW: u16 = 0xFF << 8;
+    const DIVIDER_HIGH: u16 = 0x3 << 6;
+
+    /// Enable internal clock.
+    #[inline]
+    pub const fn enable_internal_clock(self) -> Self {
+        Self(self.0 | Self::INTERNAL_CLOCK_ENABLE)
+    }
+    /// Check if internal clock is stable.
+    #[inline]
+    pub const fn is_internal_clock_stable(self) -> bool {
+        self.0 & Self::INTERNAL_CLOCK_STABLE != 0
+    }
+    /// Enable clock output to card.
+    #[inline]
+    pub const fn enable_sd_clock(self) -> Self {
+        Self(self.0 | Self::SD_CLOCK_ENABLE)
+    }
+    /// Disable clock output to card.
+    #[inline]
+    pub const fn disable_sd_clock(self) -> Self {
+        Self(self.0 & !Self::SD_CLOCK_ENABLE)
+    }
+    /// Check if clock output to card is enabled.
+    #[inline]
+    pub const fn is_sd_clock_enabled(self) -> bool {
+        self.0 & Self::SD_CLOCK_ENABLE != 0
+    }
+    /// Set 10-bit clock divider `n`; card clock is base clock divided by `2n`, or undivided if zero.
+    #[inline]
+    pub const fn set_divider(self, n: u16) -> Self {
+        let low = (n & 0xFF) << 8;
+        let high = ((n >> 8) & 0x3) << 6;
+        Self((self.0 & !(Self::DIVIDER_LOW | Self::DIVIDER_HIGH)) | low | high)
+    }
+    /// Get 10-bit clock divider.
+    #[inline]
+    pub const fn divider(self) -> u16 {
+        ((self.0 & Self::DIVIDER_LOW) >> 8) | (((self.0 & Self::DIVIDER_HIGH) >> 6) << 8)
+    }
+}
+
+/// Host control 2 register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct HostControl2(u16);
+
+impl HostControl2 {
+    const UHS_MODE: u16 = 0x7;
+    const SIGNALING_1V8: u16 = 1 << 3;
+    const EXECUTE_TUNING: u16 = 1 << 6;
+    const SAMPLING_CLOCK_TUNED: u16 = 1 << 7;
+
+    /// Set bus speed mode for UHS-I cards and eMMC DDR52 or HS200.
+    #[inline]
+    pub const fn set_uhs_mode(self, mode: UhsMode) -> Self {
+        Self((self.0 & !Self::UHS_MODE) | mode as u16)
+    }
+    /// Get bus speed mode.
+    #[inline]
+    pub const fn uhs_mode(self) -> UhsMode {
+        match self.0 & Self::UHS_MODE {
+            0 => UhsMode::Sdr12,
+            1 => UhsMode::Sdr25,
+            2 => UhsMode::Sdr50,
+            3 => UhsMode::Sdr104,
+            _ => UhsMode::Ddr50,
+        }
+    }
+    /// Use 1.8V signaling on bus.
+    #[inline]
+    pub const fn enable_1v8_signaling(self) -> Self {
+        Self(self.0 | Self::SIGNALING_1V8)
+    }
+    /// Use 3.3V signaling on bus.
+    #[inline]
+    pub const fn disable_1v8_signaling(self) -> Self {
+        Self(self.0 & !Self::SIGNALING_1V8)
+    }
+    /// Check if 1.8V signaling is enabled.
+    #[inline]
+    pub const fn is_1v8_signaling_enabled(self) -> bool {
+        self.0 & Self::SIGNALING_1V8 != 0
+    }
+    /// Start sampling clock tuning procedure.
+    #[inline]
+    pub const fn start_tuning(self) -> Self {
+        Self(self.0 | Self::EXECUTE_TUNING)
+    }
+    /// Check if tuning procedure is in progress.
+    #[inline]
+    pub const fn is_tuning(self) -> bool {
+        self.0 & Self::EXECUTE_TUNING != 0
+    }
+    /// Check if tuned sampling clock is in use.
+    #[inline]
+    pub const fn is_sampling_clock_tuned(self) -> bool {
+        self.0 & Self::SAMPLING_CLOCK_TUNED != 0
+    }
+}
+
+/// Bus speed modes of host control 2 register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UhsMode {
+    /// Single data rate up to 25 MHz.
+    Sdr12 = 0,
+    /// Single data rate up to 50 MHz.
+    Sdr25 = 1,
+    /// Single data rate up to 100 MHz.
+    Sdr50 = 2,
+    /// Single data rate up to 208 MHz, also used for eMMC HS200.
+    Sdr104 = 3,
+    /// Double data rate up to 50 MHz, also used for eMMC DDR52.
+    Ddr50 = 4,
+}
+
+/// Capabilities register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct Capabilities(u32);
+
+impl Capabilities {
+    const BUS_8BIT: u32 = 1 << 18;
+    const HIGH_SPEED: u32 = 1 << 21;
+    const VOLTAGE_1V8: u32 = 1 << 26;
+
+    /// Check if host supports 8-bit bus for embedded devices.
+    #[inline]
+    pub const fn is_8bit_supported(self) -> bool {
+        self.0 & Self::BUS_8BIT != 0
+    }
+    /// Check if host supports high speed mode.
+    #[inline]
+    pub const fn is_high_speed_supported(self) -> bool {
+        self.0 & Self::HIGH_SPEED != 0
+    }
+    /// Check if host supports 1.8V bus power.
+    #[inline]
+    pub const fn is_1v8_supported(self) -> bool {
+        self.0 & Self::VOLTAGE_1V8 != 0
+    }
+}
+
+/// Capabilities register, upper half.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct Capabilities2(u32);
+
+impl Capabilities2 {
+    const SDR104: u32 = 1 << 1;
+    const DDR50: u32 = 1 << 2;
+
+    /// Check if host supports SDR104 and HS200 modes.
+    #[inline]
+    pub const fn is_sdr104_supported(self) -> bool {
+        self.0 & Self::SDR104 != 0
+    }
+    /// Check if host supports DDR50 and DDR52 modes.
+    #[inline]
+    pub const fn is_ddr50_supported(self) -> bool {
+        self.0 & Self::DDR50 != 0
+    }
+}
+
+/// Software reset bits.
+mod software_reset {
+    pub const ALL: u8 = 1 << 0;
+    pub const COMMAND: u8 = 1 << 1;
+    pub const DATA: u8 = 1 << 2;
+}
+
+/// Normal interrupt status bits.
+mod normal {
+    pub const COMMAND_COMPLETE: u16 = 1 << 0;
+    pub const TRANSFER_COMPLETE: u16 = 1 << 1;
+    pub const BUFFER_WRITE_READY: u16 = 1 << 4;
+    pub const BUFFER_READ_READY: u16 = 1 << 5;
+    pub const ERROR: u16 = 1 << 15;
+}
+
+/// Size of a card data block in bytes.
+pub const BLOCK_SIZE: usize = 512;
+
+/// SD host controller error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// Card did not respond to a command.
+    CommandTimeout,
+    /// Command response failed CRC check.
+    CommandCrc,
+    /// Command response has a wrong end bit or index.
+    CommandResponse,
+    /// Card did not send or accept data in time.
+    DataTimeout,
+    /// Data block failed CRC check.
+    DataCrc,
+    /// Data block has a wrong end bit.
+    DataEndBit,
+    /// Auto CMD12 after a multi-block transfer failed.
+    AutoCommand,
+    /// Card did not finish power-up or does not support host voltage.
+    UnsupportedCard,
+    /// Card status reports an error, carrying raw card status.
+    Card(u32),
+    /// Sampling clock tuning did not converge.
+    Tuning,
+    /// Transfer exceeds device capacity.
+    OutOfRange,
+}
+
+impl Error {
+    /// Converts error interrupt status into an error.
+    #[inline]
+    const fn from_status(status: u16) -> Error {
+        if status & (1 << 0) != 0 {
+            Error::CommandTimeout
+        } else if status & (1 << 1) != 0 {
+            Error::CommandCrc
+        } else if status & (0b11 << 2) != 0 {
+            Error::CommandResponse
+        } else if status & (1 << 4) != 0 {
+            Error::DataTimeout
+        } else if status & (1 << 5) != 0 {
+            Error::DataCrc
+        } else if status & (1 << 6) != 0 {
+            Error::DataEndBit
+        } else {
+            Error::AutoCommand
+        }
+    }
+}
+
+/// Managed SD host controller peripheral.
+pub struct Sdhci<T, PADS> {
+    sdhci: T,
+    pads: PADS,
+    base_clock: u32,
+}
+
+/// Extension trait to create SD host controllers from peripherals.
+pub trait SdhciExt<const I: usize>: AsRef<RegisterBlock> + Controlled + Sized {
+    /// Configures the peripheral as an SD host running on `pads`.
+    ///
+    /// Bus is powered at 3.3V, one data line wide, with identification clock of 400 kHz.
+    #[inline]
+    fn sdhci<PADS>(self, pads: PADS, clocks: &Clocks) -> Sdhci<Self, PADS>
+    where
+        PADS: Pads<I>,
+    {
+        reset::enable(&self);
+        let base_clock = match I {
+            0 => clocks.sd0(),
+            _ => clocks.sd1(),
+        };
+        let mut host = Sdhci {
+            sdhci: self,
+            pads,
+            base_clock: base_clock.to_hz(),
+        };
+        host.reset(software_reset::ALL);
+        let sdhci = host.sdhci.as_ref();
+        unsafe {
+            sdhci.normal_interrupt_status_enable.write(0xFFFF);
+            sdhci.error_interrupt_status_enable.write(0xFFFF);
+            sdhci.normal_interrupt_signal_enable.write(0);
+            sdhci.error_interrupt_signal_enable.write(0);
+            sdhci.timeout_control.write(0xE);
+            sdhci
+                .power_control
+                .write(PowerControl::default().power_on());
+        }
+        host.set_bus_width(BusWidth::One);
+        host.set_clock(400_000);
+        host
+    }
+}
+
+impl<T: AsRef<RegisterBlock>, PADS> Sdhci<T, PADS> {
+    /// Sets card clock to at most `frequency` Hz, returns actual frequency.
+    #[inline]
+    pub fn set_clock(&mut self, frequency: u32) -> u32 {
+        let sdhci = self.sdhci.as_ref();
+        let n = clock_divider(self.base_clock, frequency);
+        unsafe {
+            sdhci.clock_control.modify(|w| w.disable_sd_clock());
+            sdhci.clock_control.write(
+                ClockControl::default()
+                    .set_divider(n)
+                    .enable_internal_clock(),
+            );
+        }
+        while !sdhci.clock_control.read().is_internal_clock_stable() {
+            core::hint::spin_loop();
+        }
+        unsafe { sdhci.clock_control.modify(|w| w.enable_sd_clock()) };
+        match n {
+            0 => self.base_clock,
+            n => self.base_clock / (2 * n as u32),
+        }
+    }
+    /// Sets data bus width of the host; switch card bus width first.
+    #[inline]
+    pub fn set_bus_width(&mut self, width: BusWidth) {
+        unsafe {
+            self.sdhci
+                .as_ref()
+                .host_control1
+                .modify(|w| w.set_bus_width(width))
+        };
+    }
+    /// Enables or disables high speed timing of the host.
+    #[inline]
+    pub fn set_high_speed(&mut self, enable: bool) {
+        unsafe {
+            self.sdhci.as_ref().host_control1.modify(|w| {
+                if enable {
+                    w.enable_high_speed()
+                } else {
+                    w.disable_high_speed()
+                }
+            })
+        };
+    }
+    /// Sets bus timing; signaling voltage is kept.
+    ///
+    /// Switch card timing first; change clock after this call.
+    #[inline]
+    pub fn set_timing(&mut self, timing: Timing) {
+        self.set_high_speed(timing != Timing::Legacy);
+        let sdhci = self.sdhci.as_ref();
+        let mode = match timing {
+            Timing::Legacy | Timing::HighSpeed => UhsMode::Sdr12,
+            Timing::Ddr52 => UhsMode::Ddr50,
+            Timing::Hs200 => UhsMode::Sdr104,
+        };
+        unsafe {
+            sdhci.clock_control.modify(|w| w.disable_sd_clock());
+            sdhci.host_control2.modify(|w| w.set_uhs_mode(mode));
+            sdhci.clock_control.modify(|w| w.enable_sd_clock());
+        }
+    }
+    /// Sets signaling voltage of the host.
+    ///
+    /// `voltage` must match supply of the pad IO group; on SDIO-0 pads use
+    /// [`switch_io_voltage`](Self::switch_io_voltage) to change both.
+    #[inline]
+    pub fn set_signal_voltage(&mut self, voltage: IoVoltage) {
+        let sdhci = self.sdhci.as_ref();
+        unsafe {
+            sdhci.clock_control.modify(|w| w.disable_sd_clock());
+            sdhci.host_control2.modify(|w| match voltage {
+                IoVoltage::V3_3 => w.disable_1v8_signaling(),
+                IoVoltage::V1_8 => w.enable_1v8_signaling(),
+            });
+            sdhci.clock_control.modify(|w| w.enable_sd_clock());
+        }
+    }
+    /// Gets signaling voltage of the host.
+    #[inline]
+    pub fn signal_voltage(&self) -> IoVoltage {
+        match self
+            .sdhci
+            .as_ref()
+            .host_control2
+            .read()
+            .is_1v8_signaling_enabled()
+        {
+            true => IoVoltage::V1_8,
+            false => IoVoltage::V3_3,
+        }
+    }
+    /// Checks if host supports `timing`.
+    #[inline]
+    pub fn supports_timing(&self, timing: Timing) -> bool {
+        let sdhci = self.sdhci.as_ref();
+        match timing {
+            Timing::Legacy => true,
+            Timing::HighSpeed => sdhci.capabilities.read().is_high_speed_supported(),
+            Timing::Ddr52 => sdhci.capabilities2.read().is_ddr50_supported(),
+            Timing::Hs200 => {
+                sdhci.capabilities2.read().is_sdr104_supported()
+                    && sdhci.capabilities.read().is_1v8_supported()
+            }
+        }
+    }
+    /// Checks if host supports 8-bit data bus.
+    #[inline]
+    pub fn supports_8bit(&self) -> bool {
+        self.sdhci.as_ref().capabilities.read().is_8bit_supported()
+    }
+    /// Tunes sampling clock by repeating a tuning command reading `block_size` bytes of tuning pattern.
+    #[inline]
+    pub fn tune(&mut self, index: u8, block_size: usize) -> Result<(), Error> {
+        let sdhci = self.sdhci.as_ref();
+        unsafe { sdhci.host_control2.modify(|w| w.start_tuning()) };
+        for _ in 0..TUNING_RETRIES {
+            self.start_data(index, 0, 1, block_size, true)?;
+            self.wait_status(normal::BUFFER_READ_READY)?;
+            let sdhci = self.sdhci.as_ref();
+            for _ in 0..block_size / 4 {
+                sdhci.buffer_data.read();
+            }
+            if !sdhci.host_control2.read().is_tuning() {
+                break;
+            }
+        }
+        let control = self.sdhci.as_ref().host_control2.read();
+        if control.is_tuning() || !control.is_sampling_clock_tuned() {
+            return Err(Error::Tuning);
+        }
+        Ok(())
+    }
+    /// Checks if a card is inserted.
+    #[inline]
+    pub fn is_card_inserted(&self) -> bool {
+        self.sdhci.as_ref().present_state.read().is_card_inserted()
+    }
+    /// Sends a command without data, returns its response.
+    ///
+    /// Short responses are in the lower 32 bits; long responses hold bits 0 to 127
+    /// of the card register with CRC bits cleared.
+    #[inline]
+    pub fn command(&mut self, index: u8, argument: u32, response: Response) -> Result<u128, Error> {
+        let busy = response == Response::R1b;
+        self.issue(
+            Command::default().set_index(index).set_response(response),
+            argument,
+            TransferMode::default(),
+            busy,
+        )?;
+        if busy {
+            self.wait_status(normal::TRANSFER_COMPLETE)?;
+        }
+        Ok(self.response(response))
+    }
+    /// Sends a command reading `buf.len() / block_size` blocks, returns its R1 response.
+    #[inline]
+    pub fn read_data(
+        &mut self,
+        index: u8,
+        argument: u32,
+        buf: &mut [u8],
+        block_size: usize,
+    ) -> Result<u32, Error> {
+        let blocks = buf.len() / block_size;
+        self.start_data(index, argument, blocks, block_size, true)?;
+        for block in buf.chunks_exact_mut(block_size) {
+            self.wait_status(normal::BUFFER_READ_READY)?;
+            let sdhci = self.sdhci.as_ref();
+            for word in block.chunks_exact_mut(4) {
+                word.copy_from_slice(&sdhci.buffer_data.read().to_le_bytes());
+            }
+        }
+        self.wait_status(normal::TRANSFER_COMPLETE)?;
+        Ok(self.response(Response::R1) as u32)
+    }
+    /// Sends a command writing `buf.len() / block_size` blocks, returns its R1 response.
+    #[inline]
+    pub fn write_data(
+        &mut self,
+        index: u8,
+        argument: u32,
+        buf: &[u8],
+        block_size: usize,
+    ) -> Result<u32, Error> {
+        let blocks = buf.len() / block_size;
+        self.start_data(index, argument, blocks, block_size, false)?;
+        for block in buf.chunks_exact(block_size) {
+            self.wait_status(normal::BUFFER_WRITE_READY)?;
+            let sdhci = self.sdhci.as_ref();
+            for word in block.chunks_exact(4) {
+                let word = u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
+                unsafe { sdhci.buffer_data.write(word) };
+            }
+        }
+        // transfer completes after card releases busy signal on data line 0
+        self.wait_status(normal::TRANSFER_COMPLETE)?;
+        Ok(self.response(Response::R1) as u32)
+    }
+    /// Reads consecutive 512-byte blocks from card `address`, in blocks or bytes as the card uses.
+    #[inline]
+    pub fn read_blocks(
+        &mut self,
+        address: u32,
+        blocks: &mut [[u8; BLOCK_SIZE]],
+    ) -> Result<(), Error> {
+        // CMD17 READ_SINGLE_BLOCK or CMD18 READ_MULTIPLE_BLOCK
+        let index = if blocks.len() > 1 { 18 } else { 17 };
+        let status = self.read_data(index, address, blocks.as_flattened_mut(), BLOCK_SIZE)?;
+        check_status(status)
+    }
+    /// Writes consecutive 512-byte blocks to card `address`, in blocks or bytes as the card uses.
+    #[inline]
+    pub fn write_blocks(&mut self, address: u32, blocks: &[[u8; BLOCK_SIZE]]) -> Result<(), Error> {
+        // CMD24 WRITE_BLOCK or CMD25 WRITE_MULTIPLE_BLOCK
+        let index = if blocks.len() > 1 { 25 } else { 24 };
+        let status = self.write_data(index, address, blocks.as_flattened(), BLOCK_SIZE)?;
+        check_status(status)
+    }
+    /// Release SD host instance and return its peripheral and pads, turning its clocks off.
+    #[inline]
+    pub fn free(self) -> (T, PADS)
+    where
+        T: Controlled,
+    {
+        unsafe {
+            let sdhci = self.sdhci.as_ref();
+            sdhci.clock_control.write(ClockControl::default());
+            sdhci.power_control.write(PowerControl::default());
+        }
+        reset::disable(&self.sdhci);
+        (self.sdhci, self.pads)
+    }
+    #[inline]
+    fn start_data(
+        &mut self,
+        index: u8,
+        argument: u32,
+        blocks: usize,
+        block_size: usize,
+        read: bool,
+    ) -> Result<(), Error> {
+        let sdhci = self.sdhci.as_ref();
+        let mut mode = TransferMode::default().enable_block_count();
+        mode = if read {
+            mode.set_read()
+        } else {
+            mode.set_write()
+        };
+        if blocks > 1 {
+            mode = mode.enable_multi_block().enable_auto_cmd12();
+        }
+        unsafe {
+            sdhci.block_size.write(block_size as u16);
+            sdhci.block_count.write(blocks as u16);
+        }
+        self.issue(
+            Command::default()
+                .set_index(index)
+                .set_response(Response::R1)
+                .enable_data_present(),
+            argument,
+            mode,
+            true,
+        )
+    }
+    #[inline]
+    fn issue(
+        &mut self,
+        command: Command,
+        argument: u32,
+        mode: TransferMode,
+        uses_data: bool,
+    ) -> Result<(), Error> {
+        let sdhci = self.sdhci.as_ref();
+        loop {
+            let state = sdhci.present_state.read();
+            if !(state.is_command_inhibited() || uses_data && state.is_data_inhibited()) {
+                break;
+            }
+            core::hint::spin_loop();
+        }
+        unsafe {
+            sdhci.normal_interrupt_status.write(0xFFFF);
+            sdhci.error_interrupt_status.write(0xFFFF);
+            sdhci.argument.write(argument);
+            sdhci.transfer_mode.write(mode);
+            sdhci.command.write(command);
+        }
+        self.wait_status(normal::COMMAND_COMPLETE)
+    }
+    /// Waits for and clears a normal interrupt status bit, or returns the first error.
+    #[inline]
+    fn wait_status(&mut self, bit: u16) -> Result<(), Error> {
+        let sdhci = self.sdhci.as_ref();
+        loop {
+            let status = sdhci.normal_interrupt_status.read();
+            if status & normal::ERROR != 0 {
+                let error = sdhci.error_interrupt_status.read();
+                unsafe { sdhci.error_interrupt_status.write(error) };
+                self.reset(software_reset::COMMAND | software_reset::DATA);
+                return Err(Error::from_status(error));
+            }
+            if status & bit != 0 {
+                unsafe { sdhci.normal_interrupt_status.write(bit) };
+                return Ok(());
+            }
+            core::hint::spin_loop();
+        }
+    }
+    #[inline]
+    fn response(&self, response: Response) -> u128 {
+        let sdhci = self.sdhci.as_ref();
+        match response {
+            Response::None => 0,
+            Response::R2 => {
+                let mut value = 0u128;
+                for word in sdhci.response.iter().rev() {
+                    value = (value << 32) | word.read() as u128;
+                }
+                value << 8
+            }
+            _ => sdhci.response[0].read() as u128,
+        }
+    }
+    #[inline]
+    fn reset(&mut self, bits: u8) {
+        let sdhci = self.sdhci.as_ref();
+        unsafe { sdhci.software_reset.write(bits) };
+        while sdhci.software_reset.read() & bits != 0 {
+            core::hint::spin_loop();
+        }
+    }
+}
+
+/// Number of tuning commands before tuning is considered failed.
+const TUNING_RETRIES: u32 = 40;
+
+/// Bus timings, slowest first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Timing {
+    /// Default speed, up to 26 MHz for eMMC or 25 MHz for SD.
+    Legacy,
+    /// High speed single data rate, up to 52 MHz for eMMC or 50 MHz for SD.
+    HighSpeed,
+    /// eMMC high speed double data rate up to 52 MHz.
+    Ddr52,
+    /// eMMC HS200 single data rate up to 200 MHz at 1.8V, requires tuning.
+    ///
+    /// Only selected when host signals at 1.8V, see [`Sdhci::switch_io_voltage`].
+    Hs200,
+}
+
+/// Smallest 10-bit divider `n` so that `base / 2n` does not exceed `frequency`.
+#[inline]
+const fn clock_divider(base: u32, frequency: u32) -> u16 {
+    if frequency >= base {
+        return 0;
+    }
+    let n = base.div_ceil(2 * frequency);
+    if n > 0x3FF {
+        0x3FF
+    } else {
+        n as u16
+    }
+}
+
+/// Number of 512-byte blocks from a CSD register.
+#[inline]
+pub const fn csd_block_count(csd: u128) -> u32 {
+    match bits(csd, 126, 2) {
+        // CSD version 2.0, high and extended capacity cards
+        1 => (bits(csd, 48, 22) + 1) * 1024,
+        // CSD version 1.0, standard capacity cards
+        _ => {
+            let c_size = bits(csd, 62, 12);
+            let c_size_mult = bits(csd, 47, 3);
+            let read_bl_len = bits(csd, 80, 4);
+            let bytes = ((c_size as u64 + 1) << (c_size_mult + 2)) << read_bl_len;
+            (bytes / BLOCK_SIZE as u64) as u32
+        }
+    }
+}
+
+/// Extracts `len` bits starting at bit `lsb` of a long response.
+#[inline]
+pub(crate) const fn bits(value: u128, lsb: u32, len: u32) -> u32 {
+    ((value >> lsb) & ((1 << len) - 1)) as u32
+}
+
+/// Card status bits indicating an error in R1 responses.
+const CARD_STATUS_ERRORS: u32 = 0xFDF9_8008;
+
+/// Host supported voltage window of 2.7V to 3.6V in OCR.
+const OCR_VOLTAGE_WINDOW: u32 = 0x00FF_8000;
+/// OCR bit set when card power-up completes.
+const OCR_POWERED_UP: u32 = 1 << 31;
+/// OCR bit for high or extended capacity cards.
+const OCR_HIGH_CAPACITY: u32 = 1 << 30;
+
+/// Number of tries of ACMD41 before a card is considered unusable.
+const POWER_UP_RETRIES: u32 = 4000;
+
+/// SD memory card on an SD host controller.
+pub struct SdCard<T, PADS> {
+    host: RefCell<Sdhci<T, PADS>>,
+    rca: u32,
+    high_capacity: bool,
+    blocks: u32,
+}
+
+impl<T: AsRef<RegisterBlock>, PADS> SdCard<T, PADS> {
+    /// Identifies and selects the card, switching to 4-bit bus and highest supported default or high speed clock.
+    #[inline]
+    pub fn new(mut host: Sdhci<T, PADS>) -> Result<Self, Error> {
+        host.set_bus_width(BusWidth::One);
+        host.set_high_speed(false);
+        host.set_clock(400_000);
+        // CMD0 GO_IDLE_STATE
+        host.command(0, 0, Response::None)?;
+        // CMD8 SEND_IF_COND, 2.7-3.6V and check pattern; version 1 cards do not respond
+        let version2 = match host.command(8, 0x1AA, Response::R7) {
+            Ok(r7) if r7 as u32 & 0xFFF == 0x1AA => true,
+            Ok(_) => return Err(Error::UnsupportedCard),
+            Err(Error::CommandTimeout) => false,
+            Err(e) => return Err(e),
+        };
+        let hcs = if version2 { OCR_HIGH_CAPACITY } else { 0 };
+        let mut ocr = 0;
+        for _ in 0..POWER_UP_RETRIES {
+            // CMD55 APP_CMD, ACMD41 SD_SEND_OP_COND
+            host.command(55, 0, Response::R1)?;
+            ocr = host.command(41, OCR_VOLTAGE_WINDOW | hcs, Response::R3)? as u32;
+            if ocr & OCR_POWERED_UP != 0 {
+                break;
+            }
+        }
+        if ocr & OCR_POWERED_UP == 0 {
+            return Err(Error::UnsupportedCard);
+        }
+        // CMD2 ALL_SEND_CID
+        host.command(2, 0, Response::R2)?;
+        // CMD3 SEND_RELATIVE_ADDR
+        let rca = host.command(3, 0, Response::R6)? as u32 & 0xFFFF_0000;
+        // CMD9 SEND_CSD
+        let csd = host.command(9, rca, Response::R2)?;
+        // CMD7 SELECT_CARD
+        host.command(7, rca, Response::R1b)?;
+        let high_capacity = ocr & OCR_HIGH_CAPACITY != 0;
+        if !high_capacity {
+            // CMD16 SET_BLOCKLEN
+            host.command(16, BLOCK_SIZE as u32, Response::R1)?;
+        }
+        // ACMD6 SET_BUS_WIDTH to 4 bits
+        host.command(55, rca, Response::R1)?;
+        host.command(6, 0b10, Response::R1)?;
+        host.set_bus_width(BusWidth::Four);
+        // CMD6 SWITCH_FUNC, switch access mode to high speed
+        let mut status = [0u8; 64];
+        let high_speed = version2
+            && host.read_data(6, 0x80FF_FFF1, &mut status, 64).is_ok()
+            && status[16] & 0xF == 1;
+        if high_speed {
+            host.set_high_speed(true);
+            host.set_clock(50_000_000);
+        } else {
+            host.set_clock(25_000_000);
+        }
+        Ok(SdCard {
+            host: RefCell::new(host),
+            rca,
+            high_capacity,
+            blocks: csd_block_count(csd),
+        })
+    }
+    /// Relative card address, in upper 16 bits as used in command arguments.
+    #[inline]
+    pub fn rca(&self) -> u32 {
+        self.rca
+    }
+    /// Checks if card is a high or extended capacity card.
+    #[inline]
+    pub fn is_high_capacity(&self) -> bool {
+        self.high_capacity
+    }
+    /// Number of 512-byte blocks on the card.
+    #[inline]
+    pub fn num_blocks(&self) -> u32 {
+        self.blocks
+    }
+    /// Reads consecutive blocks starting at block `start`.
+    #[inline]
+    pub fn read_blocks(&self, blocks: &mut [[u8; BLOCK_SIZE]], start: u32) -> Result<(), Error> {
+        check_range(blocks.len(), start, self.blocks)?;
+        if blocks.is_empty() {
+            return Ok(());
+        }
+        let address = self.address(start);
+        self.host.borrow_mut().read_blocks(address, blocks)
+    }
+    /// Writes consecutive blocks starting at block `start`.
+    #[inline]
+    pub fn write_blocks(&self, blocks: &[[u8; BLOCK_SIZE]], start: u32) -> Result<(), Error> {
+        check_range(blocks.len(), start, self.blocks)?;
+        if blocks.is_empty() {
+            return Ok(());
+        }
+        let address = self.address(start);
+        self.host.borrow_mut().write_blocks(address, blocks)
+    }
+    /// Release card and return its host controller.
+    #[inline]
+    pub fn free(self) -> Sdhci<T, PADS> {
+        self.host.into_inner()
+    }
+    #[inline]
+    fn address(&self, block: u32) -> u32 {
+        if self.high_capacity {
+            block
+        } else {
+            block * BLOCK_SIZE as u32
+        }
+    }
+}
+
+/// Checks that `len` blocks from block `start` lie within `total` blocks.
+#[inline]
+const fn check_range(len: usize, start: u32, total: u32) -> Result<(), Error> {
+    let end = start as u64 + len as u64;
+    if end <= total as u64 && len <= u16::MAX as usize {
+        Ok(())
+    } else {
+        Err(Error::OutOfRange)
+    }
+}
+
+/// Converts error bits in an R1 card status into an error.
+#[inline]
+pub(crate) const fn check_status(status: u32) -> Result<(), Error> {
+    if status & CARD_STATUS_ERRORS != 0 {
+        Err(Error::Card(status))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "embedded-sdmmc")]
+impl<T: AsRef<RegisterBlock>, PADS> embedded_sdmmc::BlockDevice for SdCard<T, PADS> {
+    type Error = Error;
+
+    #[inline]
+    fn read(
+        &self,
+        blocks: &mut [embedded_sdmmc::Block],
+        start_block_idx: embedded_sdmmc::BlockIdx,
+        _reason: &str,
+    ) -> Result<(), Error> {
+        self.read_blocks(sdmmc_blocks_mut(blocks), start_block_idx.0)
+    }
+    #[inline]
+    fn write(
+        &self,
+        blocks: &[embedded_sdmmc::Block],
+        start_block_idx: embedded_sdmmc::BlockIdx,
+    ) -> Result<(), Error> {
+        self.write_blocks(sdmmc_blocks(blocks), start_block_idx.0)
+    }
+    #[inline]
+    fn num_blocks(&self) -> Result<embedded_sdmmc::BlockCount, Error> {
+        Ok(embedded_sdmmc::BlockCount(self.blocks))
+    }
+}
+
+// `embedded_sdmmc::Block` only wraps a block sized byte array
+#[cfg(feature = "embedded-sdmmc")]
+const _: () = assert!(core::mem::size_of::<embedded_sdmmc::Block>() == BLOCK_SIZE);
+
+/// Views `embedded-sdmmc` blocks as block buffers.
+#[cfg(feature = "embedded-sdmmc")]
+#[inline]
+fn sdmmc_blocks(blocks: &[embedded_sdmmc::Block]) -> &[[u8; BLOCK_SIZE]] {
+    unsafe { core::slice::from_raw_parts(blocks.as_ptr().cast(), blocks.len()) }
+}
+
+/// Views `embedded-sdmmc` blocks as mutable block buffers.
+#[cfg(feature = "embedded-sdmmc")]
+#[inline]
+fn sdmmc_blocks_mut(blocks: &mut [embedded_sdmmc::Block]) -> &mut [[u8; BLOCK_SIZE]] {
+    unsafe { core::slice::from_raw_parts_mut(blocks.as_mut_ptr().cast(), blocks.len()) }
+}
+
+/// OCR argument of CMD1: 2.7V to 3.6V and sector addressing.
+const MMC_OCR_ARGUMENT: u32 = 0x40FF_8080;
+/// OCR bit for sector addressed devices larger than 2 GB.
+const MMC_OCR_SECTOR_MODE: u32 = 1 << 30;
+/// Card status bit set when a CMD6 switch fails.
+const MMC_SWITCH_ERROR: u32 = 1 << 7;
+/// Relative address assigned to the eMMC device.
+const MMC_RCA: u32 = 1 << 16;
+
+/// EXT_CSD byte offsets.
+mod ext_csd {
+    pub const PARTITION_CONFIG: usize = 179;
+    pub const BUS_WIDTH: usize = 183;
+    pub const HS_TIMING: usize = 185;
+    pub const DEVICE_TYPE: usize = 196;
+    pub const SEC_COUNT: usize = 212;
+    pub const BOOT_SIZE_MULT: usize = 226;
+}
+
+/// eMMC hardware partitions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Partition {
+    /// User data area.
+    User = 0,
+    /// Boot partition 1.
+    Boot1 = 1,
+    /// Boot partition 2.
+    Boot2 = 2,
+}
+
+/// eMMC configuration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EmmcConfig {
+    /// Widest data bus to use, if host and board support it.
+    pub bus_width: BusWidth,
+    /// Fastest bus timing to use, if host and device support it.
+    pub max_timing: Timing,
+}
+
+impl Default for EmmcConfig {
+    /// eMMC configuration defaults to 8-bit bus and high speed timing.
+    #[inline]
+    fn default() -> Self {
+        EmmcConfig {
+            bus_width: BusWidth::Eight,
+            max_timing: Timing::HighSpeed,
+        }
+    }
+}
+
+/// Fastest timing allowed by EXT_CSD `DEVICE_TYPE`, host support of
+/// high speed, DDR52 and HS200 in this order, and configured maximum.
+#[inline]
+const fn select_timing(
+    device_type: u8,
+    bus_width: BusWidth,
+    max: Timing,
+    host: [bool; 3],
+) -> Timing {
+    let [high_speed, ddr52, hs200] = host;
+    let wide = !matches!(bus_width, BusWidth::One);
+    let hs200_ok = device_type & (1 << 4) != 0 && hs200 && wide;
+    let ddr52_ok = device_type & (0b11 << 2) != 0 && ddr52 && wide;
+    let high_speed_ok = device_type & (1 << 1) != 0 && high_speed;
+    if hs200_ok && max as u8 >= Timing::Hs200 as u8 {
+        Timing::Hs200
+    } else if ddr52_ok && max as u8 >= Timing::Ddr52 as u8 {
+        Timing::Ddr52
+    } else if high_speed_ok && max as u8 >= Timing::HighSpeed as u8 {
+        Timing::HighSpeed
+    } else {
+        Timing::Legacy
+    }
+}
+
+/// Argument of CMD6 SWITCH writing `value` into EXT_CSD byte `index`.
+#[inline]
+const fn switch_argument(index: usize, value: u8) -> u32 {
+    (0b11 << 24) | ((index as u32) << 16) | ((value as u32) << 8)
+}
+
+/// eMMC device on an SD host controller.
+pub struct Emmc<T, PADS> {
+    host: RefCell<Sdhci<T, PADS>>,
+    sector_mode: bool,
+    timing: Timing,
+    partition_config: u8,
+    user_blocks: u32,
+    boot_blocks: u32,
+}
+
+impl<T: AsRef<RegisterBlock>, PADS> Emmc<T, PADS> {
+    /// Identifies and selects the device, switching to fastest bus allowed by `config`.
+    #[inline]
+    pub fn new(mut host: Sdhci<T, PADS>, config: EmmcConfig) -> Result<Self, Error> {
+        host.set_bus_width(BusWidth::One);
+        host.set_timing(Timing::Legacy);
+        host.set_clock(400_000);
+        // CMD0 GO_IDLE_STATE
+        host.command(0, 0, Response::None)?;
+        let mut ocr = 0;
+        for _ in 0..POWER_UP_RETRIES {
+            // CMD1 SEND_OP_COND
+            ocr = host.command(1, MMC_OCR_ARGUMENT, Response::R3)? as u32;
+            if ocr & OCR_POWERED_UP != 0 {
+                break;
+            }
+        }
+        if ocr & OCR_POWERED_UP == 0 {
+            return Err(Error::UnsupportedCard);
+        }
+        // CMD2 ALL_SEND_CID
+        host.command(2, 0, Response::R2)?;
+        // CMD3 SET_RELATIVE_ADDR
+        host.command(3, MMC_RCA, Response::R1)?;
+        // CMD9 SEND_CSD
+        let csd = host.command(9, MMC_RCA, Response::R2)?;
+        // CMD7 SELECT_CARD
+        host.command(7, MMC_RCA, Response::R1b)?;
+        host.set_clock(26_000_000);
+        let ext = read_ext_csd(&mut host)?;
+        let sector_mode = ocr & MMC_OCR_SECTOR_MODE != 0;
+        let user_blocks = if sector_mode {
+            let n = ext_csd::SEC_COUNT;
+            u32::from_le_bytes([ext[n], ext[n + 1], ext[n + 2], ext[n + 3]])
+        } else {
+            csd_block_count(csd)
+        };
+        let mut emmc = Emmc {
+            host: RefCell::new(host),
+            sector_mode,
+            timing: Timing::Legacy,
+            partition_config: ext[ext_csd::PARTITION_CONFIG],
+            user_blocks,
+            // BOOT_SIZE_MULT is in units of 128 KiB
+            boot_blocks: ext[ext_csd::BOOT_SIZE_MULT] as u32 * 256,
+        };
+        emmc.configure_bus(config, ext[ext_csd::DEVICE_TYPE])?;
+        Ok(emmc)
+    }
+    /// Bus timing in use.
+    #[inline]
+    pub fn timing(&self) -> Timing {
+        self.timing
+    }
+    /// Currently accessed hardware partition.
+    #[inline]
+    pub fn partition(&self) -> Partition {
+        match self.partition_config & 0x7 {
+            1 => Partition::Boot1,
+            2 => Partition::Boot2,
+            _ => Partition::User,
+        }
+    }
+    /// Switches block access to another hardware partition.
+    #[inline]
+    pub fn select_partition(&mut self, partition: Partition) -> Result<(), Error> {
+        let value = (self.partition_config & !0x7) | partition as u8;
+        self.switch(ext_csd::PARTITION_CONFIG, value)?;
+        self.partition_config = value;
+        Ok(())
+    }
+    /// Number of 512-byte blocks in the current partition.
+    #[inline]
+    pub fn num_blocks(&self) -> u32 {
+        match self.partition() {
+            Partition::User => self.user_blocks,
+            Partition::Boot1 | Partition::Boot2 => self.boot_blocks,
+        }
+    }
+    /// Reads the 512-byte EXT_CSD register.
+    #[inline]
+    pub fn ext_csd(&self) -> Result<[u8; 512], Error> {
+        read_ext_csd(&mut self.host.borrow_mut())
+    }
+    /// Reads consecutive blocks of the current partition starting at block `start`.
+    #[inline]
+    pub fn read_blocks(&self, blocks: &mut [[u8; BLOCK_SIZE]], start: u32) -> Result<(), Error> {
+        check_range(blocks.len(), start, self.num_blocks())?;
+        if blocks.is_empty() {
+            return Ok(());
+        }
+        let address = self.address(start);
+        self.host.borrow_mut().read_blocks(address, blocks)
+    }
+    /// Writes consecutive blocks of the current partition starting at block `start`.
+    #[inline]
+    pub fn write_blocks(&self, blocks: &[[u8; BLOCK_SIZE]], start: u32) -> Result<(), Error> {
+        check_range(blocks.len(), start, self.num_blocks())?;
+        if blocks.is_empty() {
+            return Ok(());
+        }
+        let address = self.address(start);
+        self.host.borrow_mut().write_blocks(address, blocks)
+    }
+    /// Release device and return its host controller.
+    #[inline]
+    pub fn free(self) -> Sdhci<T, PADS> {
+        self.host.into_inner()
+    }
+    #[inline]
+    fn configure_bus(&mut self, config: EmmcConfig, device_type: u8) -> Result<(), Error> {
+        let host = self.host.get_mut();
+        let bus_width = match config.bus_width {
+            BusWidth::Eight if !host.supports_8bit() => BusWidth::Four,
+            width => width,
+        };
+        // HS200 signals at 1.8V, so IO group must have been switched beforehand
+        let supported = [
+            host.supports_timing(Timing::HighSpeed),
+            host.supports_timing(Timing::Ddr52),
+            host.supports_timing(Timing::Hs200) && host.signal_voltage() == IoVoltage::V1_8,
+        ];
+        let timing = select_timing(device_type, bus_width, config.max_timing, supported);
+        // EXT_CSD BUS_WIDTH values for single and double data rate
+        let (width_sdr, width_ddr) = match bus_width {
+            BusWidth::One => (0, 0),
+            BusWidth::Four => (1, 5),
+            BusWidth::Eight => (2, 6),
+        };
+        match timing {
+            Timing::Hs200 => {
+                // HS200 requires wide bus before switching timing, then tuning
+                self.switch(ext_csd::BUS_WIDTH, width_sdr)?;
+                self.host.get_mut().set_bus_width(bus_width);
+                self.switch(ext_csd::HS_TIMING, 2)?;
+                let host = self.host.get_mut();
+                host.set_timing(Timing::Hs200);
+                host.set_clock(200_000_000);
+                let tuning_block = if bus_width == BusWidth::Eight {
+                    128
+                } else {
+                    64
+                };
+                // CMD21 SEND_TUNING_BLOCK
+                host.tune(21, tuning_block)?;
+            }
+            Timing::Ddr52 | Timing::HighSpeed => {
+                // DDR52 is entered from high speed timing by a DDR bus width
+                self.switch(ext_csd::HS_TIMING, 1)?;
+                let host = self.host.get_mut();
+                host.set_timing(Timing::HighSpeed);
+                host.set_clock(52_000_000);
+                let width = match timing {
+                    Timing::Ddr52 => width_ddr,
+                    _ => width_sdr,
+                };
+                self.switch(ext_csd::BUS_WIDTH, width)?;
+                let host = self.host.get_mut();
+                host.set_bus_width(bus_width);
+                host.set_timing(timing);
+            }
+            Timing::Legacy => {
+                self.switch(ext_csd::BUS_WIDTH, width_sdr)?;
+                self.host.get_mut().set_bus_width(bus_width);
+            }
+        }
+        self.timing = timing;
+        Ok(())
+    }
+    /// Writes `value` into EXT_CSD byte `index` and checks the result.
+    #[inline]
+    fn switch(&mut self, index: usize, value: u8) -> Result<(), Error> {
+        let host = self.host.get_mut();
+        // CMD6 SWITCH
+        host.command(6, switch_argument(index, value), Response::R1b)?;
+        // CMD13 SEND_STATUS
+        let status = host.command(13, MMC_RCA, Response::R1)? as u32;
+        if status & MMC_SWITCH_ERROR != 0 {
+            return Err(Error::Card(status));
+        }
+        check_status(status)
+    }
+    #[inline]
+    fn address(&self, block: u32) -> u32 {
+        if self.sector_mode {
+            block
+        } else {
+            block * BLOCK_SIZE as u32
+        }
+    }
+}
+
+/// Reads EXT_CSD register of the selected eMMC device.
+#[inline]
+fn read_ext_csd<T: AsRef<RegisterBlock>, PADS>(
+    host: &mut Sdhci<T, PADS>,
+) -> Result<[u8; 512], Error> {
+    let mut ext_csd = [0u8; 512];
+    // CMD8 SEND_EXT_CSD
+    let status = host.read_data(8, 0, &mut ext_csd, 512)?;
+    check_status(status)?;
+    Ok(ext_csd)
+}
+
+#[cfg(feature = "embedded-sdmmc")]
+impl<T: AsRef<RegisterBlock>, PADS> embedded_sdmmc::BlockDevice for Emmc<T, PADS> {
+    type Error = Error;
+
+    #[inline]
+    fn read(
+        &self,
+        blocks: &mut [embedded_sdmmc::Block],
+        start_block_idx: embedded_sdmmc::BlockIdx,
+        _reason: &str,
+    ) -> Result<(), Error> {
+        self.read_blocks(sdmmc_blocks_mut(blocks), start_block_idx.0)
+    }
+    #[inline]
+    fn write(
+        &self,
+        blocks: &[embedded_sdmmc::Block],
+        start_block_idx: embedded_sdmmc::BlockIdx,
+    ) -> Result<(), Error> {
+        self.write_blocks(sdmmc_blocks(blocks), start_block_idx.0)
+    }
+    #[inline]
+    fn num_blocks(&self) -> Result<embedded_sdmmc::BlockCount, Error> {
+        Ok(embedded_sdmmc::BlockCount(self.num_blocks()))
+    }
+}
+
+impl<T: AsRef<RegisterBlock>, PADS: Pads<0>> Sdhci<T, PADS> {
+    /// Switches SDIO-0 IO group power and host signaling to `voltage`.
+    ///
+    /// Required before selecting HS200 timing on SDIO-0 pads.
+    #[inline]
+    pub fn switch_io_voltage(&mut self, io_power: &IoPower, voltage: IoVoltage) {
+        let sdhci = self.sdhci.as_ref();
+        unsafe {
+            sdhci.clock_control.modify(|w| w.disable_sd_clock());
+            io_power
+                .sd0_pwrsw
+                .modify(|w| w.set_voltage(voltage).enable_auto().enable());
+        }
+        // IO group supply settles within 10 ms
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_millis(10) {
+            core::hint::spin_loop();
+        }
+        self.set_signal_voltage(voltage);
+    }
+}
+
+/// Valid SD host pads.
+pub trait Pads<const I: usize> {}
+
+impl<T1, T2, T3, T4, T5, T6> Pads<0>
+    for (
+        Pad<T1, 6, SdFunc<0>>,
+        Pad<T2, 7, SdFunc<0>>,
+        Pad<T3, 8, SdFunc<0>>,
+        Pad<T4, 10, SdFunc<0>>,
+        Pad<T5, 11, SdFunc<0>>,
+        Pad<T6, 12, SdFunc<0>>,
+    )
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        clock_divider, csd_block_count, select_timing, switch_argument, BusWidth, ClockControl,
+        Command, HostControl1, HostControl2, RegisterBlock, Response, Timing, TransferMode,
+        UhsMode,
+    };
+    use memoffset::offset_of;
+
+    #[test]
+    fn struct_register_block_offset() {
+        assert_eq!(offset_of!(RegisterBlock, sdma_address), 0x00);
+        assert_eq!(offset_of!(RegisterBlock, block_size), 0x04);
+        assert_eq!(offset_of!(RegisterBlock, block_count), 0x06);
+        assert_eq!(offset_of!(RegisterBlock, argument), 0x08);
+        assert_eq!(offset_of!(RegisterBlock, transfer_mode), 0x0C);
+        assert_eq!(offset_of!(RegisterBlock, command), 0x0E);
+        assert_eq!(offset_of!(RegisterBlock, response), 0x10);
+        assert_eq!(offset_of!(RegisterBlock, buffer_data), 0x20);
+        assert_eq!(offset_of!(RegisterBlock, present_state), 0x24);
+        assert_eq!(offset_of!(RegisterBlock, host_control1), 0x28);
+        assert_eq!(offset_of!(RegisterBlock, power_control), 0x29);
+        assert_eq!(offset_of!(RegisterBlock, clock_control), 0x2C);
+        assert_eq!(offset_of!(RegisterBlock, timeout_control), 0x2E);
+        assert_eq!(offset_of!(RegisterBlock, software_reset), 0x2F);
+        assert_eq!(offset_of!(RegisterBlock, normal_interrupt_status), 0x30);
+        assert_eq!(offset_of!(RegisterBlock, error_interrupt_status), 0x32);
+        assert_eq!(
+            offset_of!(RegisterBlock, normal_interrupt_status_enable),
+            0x34
+        );
+        assert_eq!(
+            offset_of!(RegisterBlock, error_interrupt_signal_enable),
+            0x3A
+        );
+        assert_eq!(offset_of!(RegisterBlock, auto_command_error_status), 0x3C);
+        assert_eq!(offset_of!(RegisterBlock, host_control2), 0x3E);
+        assert_eq!(offset_of!(RegisterBlock, capabilities), 0x40);
+        assert_eq!(offset_of!(RegisterBlock, host_version), 0xFE);
+    }
+
+    #[test]
+    fn struct_command_functions() {
+        let val = Command::default()
+            .set_index(17)
+            .set_response(Response::R1)
+            .enable_data_present();
+        assert_eq!(val.0, 0x113A);
+        assert_eq!(val.index(), 17);
+        let val = Command::default().set_index(2).set_response(Response::R2);
+        assert_eq!(val.0, 0x0209);
+        assert_eq!(Command::default().set_response(Response::R3).0, 0x02);
+        assert_eq!(Command::default().set_response(Response::R1b).0, 0x1B);
+
+        let val = TransferMode::default()
+            .enable_block_count()
+            .enable_auto_cmd12()
+            .set_read()
+            .enable_multi_block();
+        assert_eq!(val.0, 0x36);
+        assert!(!val.set_write().is_read());
+    }
+
+    #[test]
+    fn struct_clock_control_functions() {
+        let val = ClockControl::default()
+            .set_divider(0x2F5)
+            .enable_internal_clock()
+            .enable_sd_clock();
+        assert_eq!(val.0, 0xF585);
+        assert_eq!(val.divider(), 0x2F5);
+        assert_eq!(clock_divider(375_000_000, 400_000), 469);
+        assert_eq!(clock_divider(375_000_000, 50_000_000), 4);
+        assert_eq!(clock_divider(375_000_000, 400_000_000), 0);
+
+        let val = HostControl1::default().set_bus_width(BusWidth::Four);
+        assert_eq!(val.0, 0x02);
+        assert_eq!(val.set_bus_width(BusWidth::Eight).0, 0x20);
+        assert_eq!(val.bus_width(), BusWidth::Four);
+
+        let val = HostControl2::default()
+            .set_uhs_mode(UhsMode::Ddr50)
+            .enable_1v8_signaling()
+            .start_tuning();
+        assert_eq!(val.0, 0x4C);
+        assert_eq!(val.uhs_mode(), UhsMode::Ddr50);
+        assert!(!val.disable_1v8_signaling().is_1v8_signaling_enabled());
+    }
+
+    #[test]
+    fn emmc_bus_selection() {
+        // HS26, HS52, DDR52 and HS200 capable device
+        let device_type = 0x1F;
+        let all = [true; 3];
+        assert_eq!(
+            select_timing(device_type, BusWidth::Eight, Timing::Hs200, all),
+            Timing::Hs200
+        );
+        assert_eq!(
+            select_timing(
+                device_type,
+                BusWidth::Eight,
+                Timing::Hs200,
+                [true, true, false]
+            ),
+            Timing::Ddr52
+        );
+        assert_eq!(
+            select_timing(device_type, BusWidth::One, Timing::Hs200, all),
+            Timing::HighSpeed
+        );
+        assert_eq!(
+            select_timing(0x01, BusWidth::Eight, Timing::Hs200, all),
+            Timing::Legacy
+        );
+        assert_eq!(
+            select_timing(device_type, BusWidth::Four, Timing::Legacy, all),
+            Timing::Legacy
+        );
+        assert_eq!(switch_argument(179, 0x49), 0x03B3_4900);
+    }
+
+    #[test]
+    fn csd_capacity() {
+        // 32 GB card, CSD version 2.0 with C_SIZE 0xEDC7
+        let csd = (1u128 << 126) | (0xEDC7u128 << 48);
+        assert_eq!(csd_block_count(csd), 62_332_928);
+        // 1 GB card, CSD version 1.0 with C_SIZE 0xF1D, C_SIZE_MULT 7, READ_BL_LEN 9
+        let csd = (0xF1Du128 << 62) | (7u128 << 47) | (9u128 << 80);
+        assert_eq!(csd_block_count(csd), 1_981_440);
+    }
+}
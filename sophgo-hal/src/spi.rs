@@ -0,0 +1,1136 @@
+//! Serial Peripheral Interface bus.
+
+use crate::clock::Clocks;
+use crate::dma;
+use crate::pad::{Pad, SpiFunc};
+use crate::reset::{self, Controlled};
+use atomic_waker::AtomicWaker;
+use core::{
+    future::poll_fn,
+    mem::ManuallyDrop,
+    ptr::null_mut,
+    sync::atomic::{AtomicPtr, Ordering},
+    task::Poll,
+};
+use embedded_hal::spi::{ErrorKind, Mode, Phase, Polarity};
+use volatile_register::{RO, RW};
+
+/// Serial Peripheral Interface registers.
+#[repr(C)]
+pub struct RegisterBlock {
+    /// Control register 0.
+    pub control0: RW<Control0>,
+    /// Control register 1, number of data frames to receive minus one.
+    pub control1: RW<u32>,
+    /// Enable register.
+    pub enable: RW<u32>,
+    /// Microwire control register.
+    pub microwire_control: RW<u32>,
+    /// Slave enable register.
+    pub slave_enable: RW<u32>,
+    /// Baud rate select register, divider of the SPI controller clock.
+    pub baud_rate: RW<u32>,
+    /// Transmit FIFO threshold level register.
+    pub tx_threshold: RW<u32>,
+    /// Receive FIFO threshold level register.
+    pub rx_threshold: RW<u32>,
+    /// Transmit FIFO level register.
+    pub tx_fifo_level: RO<u32>,
+    /// Receive FIFO level register.
+    pub rx_fifo_level: RO<u32>,
+    /// Status register.
+    pub status: RO<Status>,
+    /// Interrupt mask register.
+    pub interrupt_mask: RW<Interrupts>,
+    /// Interrupt status register.
+    pub interrupt_status: RO<Interrupts>,
+    /// Raw interrupt status register.
+    pub raw_interrupt_status: RO<Interrupts>,
+    /// Transmit FIFO overflow interrupt clear register, cleared on read.
+    pub clear_tx_overflow: RO<u32>,
+    /// Receive FIFO overflow interrupt clear register, cleared on read.
+    pub clear_rx_overflow: RO<u32>,
+    /// Receive FIFO underflow interrupt clear register, cleared on read.
+    pub clear_rx_underflow: RO<u32>,
+    /// Multi-master interrupt clear register, cleared on read.
+    pub clear_multi_master: RO<u32>,
+    /// Interrupt clear register, cleared on read.
+    pub clear_interrupt: RO<u32>,
+    /// DMA control register.
+    pub dma_control: RW<u32>,
+    /// DMA transmit data level register.
+    pub dma_tx_data_level: RW<u32>,
+    /// DMA receive data level register.
+    pub dma_rx_data_level: RW<u32>,
+    /// Identification register.
+    pub identification: RO<u32>,
+    /// Component version register.
+    pub version: RO<u32>,
+    /// Data register.
+    pub data: RW<u32>,
+    _reserved0: [u8; 0x8C],
+    /// Receive sample delay register.
+    pub rx_sample_delay: RW<u32>,
+}
+
+/// Control register 0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct Control0(u32);
+
+impl Control0 {
+    const FRAME_SIZE: u32 = 0xF;
+    const FRAME_FORMAT: u32 = 0b11 << 4;
+    const PHASE: u32 = 1 << 6;
+    const POLARITY: u32 = 1 << 7;
+    const TRANSFER_MODE: u32 = 0b11 << 8;
+    const SLAVE_OUTPUT_DISABLE: u32 = 1 << 10;
+    const LOOPBACK: u32 = 1 << 11;
+    const MASTER: u32 = 1 << 31;
+
+    /// Set data frame size in bits, from 4 to 16.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits` is out of range.
+    #[inline]
+    pub fn set_frame_size(self, bits: u8) -> Self {
+        assert!(
+            (4..=16).contains(&bits),
+            "SPI frame size must be 4 to 16 bits"
+        );
+        Self((self.0 & !Self::FRAME_SIZE) | ((bits as u32 - 1) & Self::FRAME_SIZE))
+    }
+    /// Get data frame size in bits.
+    #[inline]
+    pub fn frame_size(self) -> u8 {
+        ((self.0 & Self::FRAME_SIZE) + 1) as u8
+    }
+    /// Use Motorola SPI frame format.
+    #[inline]
+    pub fn set_motorola_frame_format(self) -> Self {
+        Self(self.0 & !Self::FRAME_FORMAT)
+    }
+    /// Set SPI mode, i.e. clock polarity and phase.
+    #[inline]
+    pub fn set_mode(self, mode: Mode) -> Self {
+        let mut bits = self.0 & !(Self::PHASE | Self::POLARITY);
+        if mode.phase == Phase::CaptureOnSecondTransition {
+            bits |= Self::PHASE;
+        }
+        if mode.polarity == Polarity::IdleHigh {
+            bits |= Self::POLARITY;
+        }
+        Self(bits)
+    }
+    /// Get SPI mode.
+    #[inline]
+    pub fn mode(self) -> Mode {
+        Mode {
+            polarity: if self.0 & Self::POLARITY != 0 {
+                Polarity::IdleHigh
+            } else {
+                Polarity::IdleLow
+            },
+            phase: if self.0 & Self::PHASE != 0 {
+                Phase::CaptureOnSecondTransition
+            } else {
+                Phase::CaptureOnFirstTransition
+            },
+        }
+    }
+    /// Set transfer mode.
+    #[inline]
+    pub fn set_transfer_mode(self, mode: TransferMode) -> Self {
+        Self((self.0 & !Self::TRANSFER_MODE) | ((mode as u32) << 8))
+    }
+    /// Get transfer mode.
+    #[inline]
+    pub fn transfer_mode(self) -> TransferMode {
+        match (self.0 & Self::TRANSFER_MODE) >> 8 {
+            0 => TransferMode::TransmitReceive,
+            1 => TransferMode::TransmitOnly,
+            2 => TransferMode::ReceiveOnly,
+            3 => TransferMode::EepromRead,
+            _ => unreachable!(),
+        }
+    }
+    /// Enable slave output, only valid in slave mode.
+    #[inline]
+    pub fn enable_slave_output(self) -> Self {
+        Self(self.0 & !Self::SLAVE_OUTPUT_DISABLE)
+    }
+    /// Disable slave output, only valid in slave mode.
+    #[inline]
+    pub fn disable_slave_output(self) -> Self {
+        Self(self.0 | Self::SLAVE_OUTPUT_DISABLE)
+    }
+    /// Enable internal loopback for testing.
+    #[inline]
+    pub fn enable_loopback(self) -> Self {
+        Self(self.0 | Self::LOOPBACK)
+    }
+    /// Disable internal loopback.
+    #[inline]
+    pub fn disable_loopback(self) -> Self {
+        Self(self.0 & !Self::LOOPBACK)
+    }
+    /// Operate as an SPI master.
+    #[inline]
+    pub fn enable_master_mode(self) -> Self {
+        Self(self.0 | Self::MASTER)
+    }
+    /// Operate as an SPI slave.
+    #[inline]
+    pub fn disable_master_mode(self) -> Self {
+        Self(self.0 & !Self::MASTER)
+    }
+    /// Check if operating as an SPI master.
+    #[inline]
+    pub fn is_master_mode_enabled(self) -> bool {
+        self.0 & Self::MASTER != 0
+    }
+}
+
+/// SPI transfer mode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransferMode {
+    /// Transmit and receive.
+    TransmitReceive = 0,
+    /// Transmit only.
+    TransmitOnly = 1,
+    /// Receive only.
+    ReceiveOnly = 2,
+    /// EEPROM read.
+    EepromRead = 3,
+}
+
+/// Status register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct Status(u32);
+
+impl Status {
+    const BUSY: u32 = 1 << 0;
+    const TX_FIFO_NOT_FULL: u32 = 1 << 1;
+    const TX_FIFO_EMPTY: u32 = 1 << 2;
+    const RX_FIFO_NOT_EMPTY: u32 = 1 << 3;
+    const RX_FIFO_FULL: u32 = 1 << 4;
+
+    /// Check if a serial transfer is in progress.
+    #[inline]
+    pub fn is_busy(self) -> bool {
+        self.0 & Self::BUSY != 0
+    }
+    /// Check if transmit FIFO is not full.
+    #[inline]
+    pub fn is_tx_fifo_not_full(self) -> bool {
+        self.0 & Self::TX_FIFO_NOT_FULL != 0
+    }
+    /// Check if transmit FIFO is empty.
+    #[inline]
+    pub fn is_tx_fifo_empty(self) -> bool {
+        self.0 & Self::TX_FIFO_EMPTY != 0
+    }
+    /// Check if receive FIFO is not empty.
+    #[inline]
+    pub fn is_rx_fifo_not_empty(self) -> bool {
+        self.0 & Self::RX_FIFO_NOT_EMPTY != 0
+    }
+    /// Check if receive FIFO is full.
+    #[inline]
+    pub fn is_rx_fifo_full(self) -> bool {
+        self.0 & Self::RX_FIFO_FULL != 0
+    }
+}
+
+/// Interrupt mask, status or raw status register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct Interrupts(u32);
+
+impl Interrupts {
+    /// Check if the given interrupt is set.
+    #[inline]
+    pub const fn has_interrupt(self, val: Interrupt) -> bool {
+        self.0 & (val as u32) != 0
+    }
+    /// Set the given interrupt bit.
+    #[inline]
+    pub const fn set_interrupt(self, val: Interrupt) -> Self {
+        Self(self.0 | (val as u32))
+    }
+    /// Clear the given interrupt bit.
+    #[inline]
+    pub const fn clear_interrupt(self, val: Interrupt) -> Self {
+        Self(self.0 & !(val as u32))
+    }
+}
+
+/// SPI interrupt sources.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum Interrupt {
+    /// Transmit FIFO at or below threshold.
+    TxEmpty = 1 << 0,
+    /// Transmit FIFO overflowed.
+    TxOverflow = 1 << 1,
+    /// Receive FIFO read while empty.
+    RxUnderflow = 1 << 2,
+    /// Receive FIFO overflowed.
+    RxOverflow = 1 << 3,
+    /// Receive FIFO above threshold.
+    RxFull = 1 << 4,
+    /// Multi-master contention.
+    MultiMaster = 1 << 5,
+    /// Transmit FIFO read while empty in slave mode.
+    TxUnderflow = 1 << 7,
+}
+
+/// Maximum number of frames in flight, bounded by FIFO depth.
+const MAX_PENDING_FRAMES: usize = 8;
+
+/// Managed SPI master peripheral.
+///
+/// Only `SpiBus` is implemented, as the hardware chip select line rises whenever
+/// the transmit FIFO runs empty. For `SpiDevice`, wrap the bus in
+/// `embedded_hal_bus::spi::ExclusiveDevice` with a GPIO chip select.
+pub struct Spi<T, PADS> {
+    spi: T,
+    pads: PADS,
+}
+
+impl<T: AsRef<RegisterBlock> + Controlled, PADS> Spi<T, PADS> {
+    /// Release SPI instance and return its peripheral and pads, turning its clocks off.
+    #[inline]
+    pub fn free(self) -> (T, PADS) {
+        let spi = self.spi.as_ref();
+        unsafe {
+            spi.enable.write(0);
+            spi.dma_control.write(0);
+        }
+        reset::disable(&self.spi);
+        (self.spi, self.pads)
+    }
+}
+
+/// Extension trait to create SPI masters from peripherals.
+pub trait SpiExt<const I: usize>: AsRef<RegisterBlock> + Controlled + Sized {
+    /// Configures the peripheral as an SPI master running on `pads`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `config.frame_size` is not from 4 to 16 bits, or `config.frequency` is zero.
+    #[inline]
+    fn spi<PADS>(self, config: Config, pads: PADS, clocks: &Clocks) -> Spi<Self, PADS>
+    where
+        PADS: Pads<I>,
+    {
+        assert!(
+            (4..=16).contains(&config.frame_size),
+            "SPI frame size must be 4 to 16 bits"
+        );
+        assert!(
+            config.frequency != 0,
+            "SPI clock frequency must be non-zero"
+        );
+        reset::enable(&self);
+        let spi = self.as_ref();
+        unsafe {
+            spi.enable.write(0);
+            spi.control0.write(
+                Control0::default()
+                    .set_frame_size(config.frame_size)
+                    .set_motorola_frame_format()
+                    .set_mode(config.mode)
+                    .set_transfer_mode(TransferMode::TransmitReceive)
+                    .enable_master_mode(),
+            );
+            spi.baud_rate
+                .write(baud_rate_divider(clocks.spi().to_hz(), config.frequency));
+            spi.interrupt_mask.write(Interrupts::default());
+            // master transfers only start with a slave selected; the hardware
+            // CS line is simply left unrouted when a GPIO chip select is used
+            spi.slave_enable.write(1);
+            spi.enable.write(1);
+        }
+        Spi { spi: self, pads }
+    }
+    /// Configures the peripheral as an SPI slave running on `pads`.
+    ///
+    /// The slave shifts frames from its transmit FIFO out and into its receive
+    /// FIFO whenever the remote master clocks the bus. It is only selected
+    /// through the hardware chip select pad, so `pads` must include it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `config.frame_size` is not from 4 to 16 bits.
+    #[inline]
+    fn spi_slave<PADS>(self, config: SlaveConfig, pads: PADS) -> SpiSlave<Self, PADS>
+    where
+        PADS: Pads<I>,
+    {
+        const { assert!(PADS::CS, "SPI slave requires the hardware chip select pad") };
+        assert!(
+            (4..=16).contains(&config.frame_size),
+            "SPI frame size must be 4 to 16 bits"
+        );
+        reset::enable(&self);
+        let spi = self.as_ref();
+        unsafe {
+            spi.enable.write(0);
+            spi.control0.write(
+                Control0::default()
+                    .set_frame_size(config.frame_size)
+                    .set_motorola_frame_format()
+                    .set_mode(config.mode)
+                    .set_transfer_mode(TransferMode::TransmitReceive)
+                    .enable_slave_output()
+                    .disable_master_mode(),
+            );
+            spi.interrupt_mask.write(Interrupts::default());
+            spi.rx_threshold.write(0);
+            spi.tx_threshold.write(0);
+            spi.enable.write(1);
+            let _ = spi.clear_interrupt.read();
+        }
+        SpiSlave { spi: self, pads }
+    }
+}
+
+/// Checks that configured data frames fit in word type `W`.
+#[inline]
+fn check_word<W: Word>(spi: &RegisterBlock) -> Result<(), Error> {
+    if spi.control0.read().frame_size() > W::BITS {
+        return Err(Error::WordSize);
+    }
+    Ok(())
+}
+
+/// Computes the smallest even divider not exceeding the requested frequency.
+#[inline]
+fn baud_rate_divider(clock: u32, frequency: u32) -> u32 {
+    let divider = clock.div_ceil(frequency).clamp(2, 0xFFFE);
+    (divider + 1) & !1
+}
+
+impl<T: AsRef<RegisterBlock>, PADS> Spi<T, PADS> {
+    /// Exchanges frames, writing `write` (padded with zeros) and reading into `read`.
+    fn transfer_frames<W: Word>(&mut self, read: &mut [W], write: &[W]) {
+        let spi = self.spi.as_ref();
+        let len = read.len().max(write.len());
+        let (mut sent, mut received) = (0, 0);
+        while received < len {
+            while sent < len
+                && sent - received < MAX_PENDING_FRAMES
+                && spi.status.read().is_tx_fifo_not_full()
+            {
+                let frame = write.get(sent).copied().map_or(0, W::into_frame);
+                unsafe { spi.data.write(frame) };
+                sent += 1;
+            }
+            while received < sent && spi.status.read().is_rx_fifo_not_empty() {
+                let frame = W::from_frame(spi.data.read());
+                if let Some(word) = read.get_mut(received) {
+                    *word = frame;
+                }
+                received += 1;
+            }
+        }
+    }
+    /// Exchanges frames in place.
+    fn transfer_frames_in_place<W: Word>(&mut self, words: &mut [W]) {
+        let spi = self.spi.as_ref();
+        let len = words.len();
+        let (mut sent, mut received) = (0, 0);
+        while received < len {
+            while sent < len
+                && sent - received < MAX_PENDING_FRAMES
+                && spi.status.read().is_tx_fifo_not_full()
+            {
+                unsafe { spi.data.write(words[sent].into_frame()) };
+                sent += 1;
+            }
+            while received < sent && spi.status.read().is_rx_fifo_not_empty() {
+                words[received] = W::from_frame(spi.data.read());
+                received += 1;
+            }
+        }
+    }
+    /// Waits until all frames are shifted out.
+    #[inline]
+    fn wait_idle(&self) {
+        let spi = self.spi.as_ref();
+        loop {
+            let status = spi.status.read();
+            if status.is_tx_fifo_empty() && !status.is_busy() {
+                break;
+            }
+            core::hint::spin_loop();
+        }
+    }
+}
+
+/// SPI data word types, i.e. `u8` for frames up to 8 bits and `u16` up to 16 bits.
+pub trait Word: Copy + Default + 'static {
+    /// Widest data frame in bits this word holds.
+    const BITS: u8;
+    /// Converts the word into a data register value.
+    fn into_frame(self) -> u32;
+    /// Converts a data register value into a word.
+    fn from_frame(frame: u32) -> Self;
+}
+
+impl Word for u8 {
+    const BITS: u8 = 8;
+    #[inline]
+    fn into_frame(self) -> u32 {
+        self as u32
+    }
+    #[inline]
+    fn from_frame(frame: u32) -> Self {
+        frame as u8
+    }
+}
+
+impl Word for u16 {
+    const BITS: u8 = 16;
+    #[inline]
+    fn into_frame(self) -> u32 {
+        self as u32
+    }
+    #[inline]
+    fn from_frame(frame: u32) -> Self {
+        frame as u16
+    }
+}
+
+impl embedded_hal::spi::Error for Error {
+    #[inline]
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Error::Overrun => ErrorKind::Overrun,
+            Error::Underrun => ErrorKind::Other,
+            Error::WordSize => ErrorKind::FrameFormat,
+        }
+    }
+}
+
+impl<T: AsRef<RegisterBlock>, PADS> embedded_hal::spi::ErrorType for Spi<T, PADS> {
+    type Error = Error;
+}
+
+impl<T: AsRef<RegisterBlock>, PADS, W: Word> embedded_hal::spi::SpiBus<W> for Spi<T, PADS> {
+    #[inline]
+    fn read(&mut self, words: &mut [W]) -> Result<(), Self::Error> {
+        check_word::<W>(self.spi.as_ref())?;
+        self.transfer_frames(words, &[]);
+        Ok(())
+    }
+    #[inline]
+    fn write(&mut self, words: &[W]) -> Result<(), Self::Error> {
+        check_word::<W>(self.spi.as_ref())?;
+        self.transfer_frames(&mut [], words);
+        Ok(())
+    }
+    #[inline]
+    fn transfer(&mut self, read: &mut [W], write: &[W]) -> Result<(), Self::Error> {
+        check_word::<W>(self.spi.as_ref())?;
+        self.transfer_frames(read, write);
+        Ok(())
+    }
+    #[inline]
+    fn transfer_in_place(&mut self, words: &mut [W]) -> Result<(), Self::Error> {
+        check_word::<W>(self.spi.as_ref())?;
+        self.transfer_frames_in_place(words);
+        Ok(())
+    }
+    #[inline]
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.wait_idle();
+        Ok(())
+    }
+}
+
+impl<T: AsRef<RegisterBlock>, PADS> Spi<T, PADS> {
+    /// Converts into an interrupt driven asynchronous SPI master.
+    ///
+    /// The interrupt handler of this SPI peripheral must call `state.on_interrupt()`.
+    #[inline]
+    pub fn into_async(self, state: &State) -> AsyncSpi<'_, T, PADS> {
+        let spi = self.spi.as_ref();
+        unsafe { spi.interrupt_mask.write(Interrupts::default()) };
+        let _ = spi.clear_interrupt.read();
+        state
+            .registers
+            .store(spi as *const _ as *mut _, Ordering::Release);
+        AsyncSpi { inner: self, state }
+    }
+    /// Enables DMA handshakes on both FIFOs and returns the data port.
+    ///
+    /// Pair a transmit transfer on [`dma::Request::spi_tx`] with a receive transfer on
+    /// [`dma::Request::spi_rx`] of equal length, as every frame sent clocks one in.
+    #[inline]
+    pub fn dma_port(&mut self) -> dma::Port<u8> {
+        let spi = self.spi.as_ref();
+        unsafe {
+            // request single frames: transmit while FIFO has room, receive on every frame
+            spi.dma_tx_data_level.write(MAX_PENDING_FRAMES as u32 - 1);
+            spi.dma_rx_data_level.write(0);
+            spi.dma_control.write(0b11);
+            dma::Port::new(&spi.data as *const _ as usize)
+        }
+    }
+    /// Stops DMA handshakes on both FIFOs.
+    #[inline]
+    pub fn disable_dma(&mut self) {
+        unsafe { self.spi.as_ref().dma_control.write(0) };
+    }
+}
+
+/// Shared state between an asynchronous SPI master and its interrupt handler.
+pub struct State {
+    waker: AtomicWaker,
+    registers: AtomicPtr<RegisterBlock>,
+}
+
+impl State {
+    /// Creates an empty state, usually placed in a `static`.
+    #[inline]
+    pub const fn new() -> Self {
+        State {
+            waker: AtomicWaker::new(),
+            registers: AtomicPtr::new(null_mut()),
+        }
+    }
+    /// Interrupt handler hook for the SPI peripheral.
+    ///
+    /// Masks pending interrupts and wakes the task waiting on them;
+    /// does nothing if no asynchronous SPI master is attached to this state.
+    #[inline]
+    pub fn on_interrupt(&self) {
+        let registers = self.registers.load(Ordering::Acquire);
+        if registers.is_null() {
+            return;
+        }
+        let spi = unsafe { &*registers };
+        let pending = spi.interrupt_status.read();
+        unsafe { spi.interrupt_mask.modify(|w| Interrupts(w.0 & !pending.0)) };
+        self.waker.wake();
+    }
+}
+
+impl Default for State {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Interrupt driven asynchronous SPI master.
+pub struct AsyncSpi<'a, T: AsRef<RegisterBlock>, PADS> {
+    inner: Spi<T, PADS>,
+    state: &'a State,
+}
+
+impl<'a, T: AsRef<RegisterBlock>, PADS> AsyncSpi<'a, T, PADS> {
+    /// Converts back into a blocking SPI master.
+    #[inline]
+    pub fn into_blocking(self) -> Spi<T, PADS> {
+        let this = ManuallyDrop::new(self);
+        this.detach();
+        unsafe { core::ptr::read(&this.inner) }
+    }
+    /// Release SPI instance and return its peripheral and pads, turning its clocks off.
+    #[inline]
+    pub fn free(self) -> (T, PADS)
+    where
+        T: Controlled,
+    {
+        self.into_blocking().free()
+    }
+    #[inline]
+    fn detach(&self) {
+        let spi = self.inner.spi.as_ref();
+        unsafe { spi.interrupt_mask.write(Interrupts::default()) };
+        self.state.registers.store(null_mut(), Ordering::Release);
+    }
+    /// Checks and clears receive FIFO overflow.
+    #[inline]
+    fn check_overrun(&self) -> Result<(), Error> {
+        let spi = self.inner.spi.as_ref();
+        if spi
+            .raw_interrupt_status
+            .read()
+            .has_interrupt(Interrupt::RxOverflow)
+        {
+            let _ = spi.clear_rx_overflow.read();
+            return Err(Error::Overrun);
+        }
+        Ok(())
+    }
+    /// Waits until `ready` holds, sleeping on `interrupt` meanwhile.
+    async fn wait(
+        &self,
+        interrupt: Interrupt,
+        ready: impl Fn(&RegisterBlock) -> bool,
+    ) -> Result<(), Error> {
+        let spi = self.inner.spi.as_ref();
+        poll_fn(|cx| {
+            self.check_overrun()?;
+            if ready(spi) {
+                return Poll::Ready(Ok(()));
+            }
+            self.state.waker.register(cx.waker());
+            unsafe {
+                spi.interrupt_mask.modify(|w| {
+                    w.set_interrupt(interrupt)
+                        .set_interrupt(Interrupt::RxOverflow)
+                })
+            };
+            // condition may have changed before interrupt was unmasked
+            self.check_overrun()?;
+            if ready(spi) {
+                return Poll::Ready(Ok(()));
+            }
+            Poll::Pending
+        })
+        .await
+    }
+    /// Exchanges frames, writing `write` (padded with zeros) and reading into `read`.
+    async fn transfer_frames<W: Word>(&mut self, read: &mut [W], write: &[W]) -> Result<(), Error> {
+        let spi = self.inner.spi.as_ref();
+        check_word::<W>(spi)?;
+        let len = read.len().max(write.len());
+        let (mut sent, mut received) = (0, 0);
+        let result = async {
+            while received < len {
+                while sent < len
+                    && sent - received < MAX_PENDING_FRAMES
+                    && spi.status.read().is_tx_fifo_not_full()
+                {
+                    let frame = write.get(sent).copied().map_or(0, W::into_frame);
+                    unsafe { spi.data.write(frame) };
+                    sent += 1;
+                }
+                let pending = sent - received;
+                if pending == 0 {
+                    self.wait(Interrupt::TxEmpty, |spi| {
+                        spi.status.read().is_tx_fifo_not_full()
+                    })
+                    .await?;
+                    continue;
+                }
+                // receive FIFO threshold interrupt raises when all frames in flight arrived
+                unsafe { spi.rx_threshold.write(pending as u32 - 1) };
+                self.wait(Interrupt::RxFull, |spi| {
+                    spi.rx_fifo_level.read() as usize >= pending
+                })
+                .await?;
+                while received < sent && spi.status.read().is_rx_fifo_not_empty() {
+                    let frame = W::from_frame(spi.data.read());
+                    if let Some(word) = read.get_mut(received) {
+                        *word = frame;
+                    }
+                    received += 1;
+                }
+            }
+            Ok(())
+        }
+        .await;
+        unsafe { spi.interrupt_mask.write(Interrupts::default()) };
+        result
+    }
+    /// Exchanges frames in place, in chunks bounded by FIFO depth.
+    async fn transfer_frames_in_place<W: Word>(&mut self, words: &mut [W]) -> Result<(), Error> {
+        for chunk in words.chunks_mut(MAX_PENDING_FRAMES) {
+            let mut write = [W::default(); MAX_PENDING_FRAMES];
+            let write = &mut write[..chunk.len()];
+            write.copy_from_slice(chunk);
+            self.transfer_frames(chunk, write).await?;
+        }
+        Ok(())
+    }
+    /// Waits until all frames are shifted out.
+    async fn wait_idle(&self) -> Result<(), Error> {
+        let spi = self.inner.spi.as_ref();
+        unsafe { spi.tx_threshold.write(0) };
+        self.wait(Interrupt::TxEmpty, |spi| {
+            spi.status.read().is_tx_fifo_empty()
+        })
+        .await?;
+        unsafe { spi.interrupt_mask.write(Interrupts::default()) };
+        // the last frame is shifting out, busy clears within one frame time
+        self.inner.wait_idle();
+        Ok(())
+    }
+}
+
+impl<'a, T: AsRef<RegisterBlock>, PADS> Drop for AsyncSpi<'a, T, PADS> {
+    #[inline]
+    fn drop(&mut self) {
+        self.detach();
+    }
+}
+
+impl<'a, T: AsRef<RegisterBlock>, PADS> embedded_hal::spi::ErrorType for AsyncSpi<'a, T, PADS> {
+    type Error = Error;
+}
+
+impl<'a, T: AsRef<RegisterBlock>, PADS, W: Word> embedded_hal_async::spi::SpiBus<W>
+    for AsyncSpi<'a, T, PADS>
+{
+    #[inline]
+    async fn read(&mut self, words: &mut [W]) -> Result<(), Self::Error> {
+        self.transfer_frames(words, &[]).await
+    }
+    #[inline]
+    async fn write(&mut self, words: &[W]) -> Result<(), Self::Error> {
+        self.transfer_frames(&mut [], words).await
+    }
+    #[inline]
+    async fn transfer(&mut self, read: &mut [W], write: &[W]) -> Result<(), Self::Error> {
+        self.transfer_frames(read, write).await
+    }
+    #[inline]
+    async fn transfer_in_place(&mut self, words: &mut [W]) -> Result<(), Self::Error> {
+        self.transfer_frames_in_place(words).await
+    }
+    #[inline]
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.wait_idle().await
+    }
+}
+
+/// Managed SPI slave peripheral.
+pub struct SpiSlave<T, PADS> {
+    spi: T,
+    pads: PADS,
+}
+
+impl<T: AsRef<RegisterBlock>, PADS> SpiSlave<T, PADS> {
+    /// Queues frames to be shifted out on the next master transfers.
+    ///
+    /// Returns number of frames queued, limited by free space in transmit FIFO.
+    #[inline]
+    pub fn write<W: Word>(&mut self, words: &[W]) -> Result<usize, Error> {
+        let spi = self.spi.as_ref();
+        check_word::<W>(spi)?;
+        let mut len = 0;
+        while len < words.len() && spi.status.read().is_tx_fifo_not_full() {
+            unsafe { spi.data.write(words[len].into_frame()) };
+            len += 1;
+        }
+        Ok(len)
+    }
+    /// Reads frames received from the master.
+    ///
+    /// Returns number of frames read, limited by frames available in receive FIFO.
+    #[inline]
+    pub fn read<W: Word>(&mut self, words: &mut [W]) -> Result<usize, Error> {
+        let spi = self.spi.as_ref();
+        check_word::<W>(spi)?;
+        let mut len = 0;
+        while len < words.len() && spi.status.read().is_rx_fifo_not_empty() {
+            words[len] = W::from_frame(spi.data.read());
+            len += 1;
+        }
+        Ok(len)
+    }
+    /// Number of frames waiting in receive FIFO.
+    #[inline]
+    pub fn rx_level(&self) -> usize {
+        self.spi.as_ref().rx_fifo_level.read() as usize
+    }
+    /// Number of frames waiting in transmit FIFO.
+    #[inline]
+    pub fn tx_level(&self) -> usize {
+        self.spi.as_ref().tx_fifo_level.read() as usize
+    }
+    /// Checks and clears FIFO errors since last check.
+    ///
+    /// Receive FIFO overflow is reported as `Error::Overrun`, and the master
+    /// clocking frames out of an empty transmit FIFO as `Error::Underrun`.
+    #[inline]
+    pub fn check_errors(&mut self) -> Result<(), Error> {
+        let spi = self.spi.as_ref();
+        let raw = spi.raw_interrupt_status.read();
+        if raw.has_interrupt(Interrupt::RxOverflow) {
+            let _ = spi.clear_rx_overflow.read();
+            return Err(Error::Overrun);
+        }
+        if raw.has_interrupt(Interrupt::TxUnderflow) {
+            let _ = spi.clear_interrupt.read();
+            return Err(Error::Underrun);
+        }
+        Ok(())
+    }
+    /// Enables the given interrupt, e.g. receive FIFO threshold for the slave.
+    #[inline]
+    pub fn listen(&mut self, interrupt: Interrupt) {
+        let spi = self.spi.as_ref();
+        unsafe { spi.interrupt_mask.modify(|w| w.set_interrupt(interrupt)) };
+    }
+    /// Disables the given interrupt.
+    #[inline]
+    pub fn unlisten(&mut self, interrupt: Interrupt) {
+        let spi = self.spi.as_ref();
+        unsafe { spi.interrupt_mask.modify(|w| w.clear_interrupt(interrupt)) };
+    }
+    /// Sets receive FIFO threshold, the receive full interrupt raises above it.
+    #[inline]
+    pub fn set_rx_threshold(&mut self, level: u8) {
+        unsafe { self.spi.as_ref().rx_threshold.write(level as u32) };
+    }
+    /// Sets transmit FIFO threshold, the transmit empty interrupt raises at or below it.
+    #[inline]
+    pub fn set_tx_threshold(&mut self, level: u8) {
+        unsafe { self.spi.as_ref().tx_threshold.write(level as u32) };
+    }
+    /// Release SPI slave instance and return its peripheral and pads, turning its clocks off.
+    #[inline]
+    pub fn free(self) -> (T, PADS)
+    where
+        T: Controlled,
+    {
+        let spi = self.spi.as_ref();
+        unsafe {
+            spi.enable.write(0);
+            spi.interrupt_mask.write(Interrupts::default());
+        }
+        reset::disable(&self.spi);
+        (self.spi, self.pads)
+    }
+}
+
+/// SPI slave configuration.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SlaveConfig {
+    /// Clock polarity and phase.
+    pub mode: Mode,
+    /// Data frame size in bits, from 4 to 16.
+    pub frame_size: u8,
+}
+
+impl Default for SlaveConfig {
+    /// SPI slave configuration defaults to mode 0, 8-bit frames.
+    #[inline]
+    fn default() -> Self {
+        SlaveConfig {
+            mode: embedded_hal::spi::MODE_0,
+            frame_size: 8,
+        }
+    }
+}
+
+/// SPI configuration.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Config {
+    /// Clock polarity and phase.
+    pub mode: Mode,
+    /// SCK clock frequency in Hz.
+    pub frequency: u32,
+    /// Data frame size in bits, from 4 to 16.
+    ///
+    /// Frames up to 8 bits are exchanged as `u8` words, larger ones as `u16`.
+    pub frame_size: u8,
+}
+
+impl Default for Config {
+    /// SPI configuration defaults to mode 0, 1 MHz, 8-bit frames.
+    #[inline]
+    fn default() -> Self {
+        Config {
+            mode: embedded_hal::spi::MODE_0,
+            frequency: 1_000_000,
+            frame_size: 8,
+        }
+    }
+}
+
+/// SPI error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// Receive FIFO overflowed.
+    Overrun,
+    /// Transmit FIFO ran empty while data was requested.
+    Underrun,
+    /// Data frame size exceeds bits of the word type, e.g. 12-bit frames as `u8`.
+    WordSize,
+}
+
+/// Valid SPI pads.
+pub trait Pads<const I: usize> {
+    /// Checks if this pad configuration includes the hardware chip select line.
+    ///
+    /// Masters work with or without it; slaves require it.
+    const CS: bool;
+}
+
+impl<T1, T2, T3> Pads<0>
+    for (
+        Pad<T1, 6, SpiFunc<0>>,
+        Pad<T2, 7, SpiFunc<0>>,
+        Pad<T3, 8, SpiFunc<0>>,
+    )
+{
+    const CS: bool = false;
+}
+
+impl<T1, T2, T3, T4> Pads<0>
+    for (
+        Pad<T1, 6, SpiFunc<0>>,
+        Pad<T2, 7, SpiFunc<0>>,
+        Pad<T3, 8, SpiFunc<0>>,
+        Pad<T4, 12, SpiFunc<0>>,
+    )
+{
+    const CS: bool = true;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        baud_rate_divider, Control0, Error, Interrupt, RegisterBlock, Spi, SpiSlave, State,
+    };
+    use core::{
+        cell::UnsafeCell,
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+    use embedded_hal::spi::{SpiBus, MODE_3};
+    use memoffset::offset_of;
+
+    #[test]
+    fn struct_register_block_offset() {
+        assert_eq!(offset_of!(RegisterBlock, control0), 0x00);
+        assert_eq!(offset_of!(RegisterBlock, control1), 0x04);
+        assert_eq!(offset_of!(RegisterBlock, enable), 0x08);
+        assert_eq!(offset_of!(RegisterBlock, slave_enable), 0x10);
+        assert_eq!(offset_of!(RegisterBlock, baud_rate), 0x14);
+        assert_eq!(offset_of!(RegisterBlock, tx_threshold), 0x18);
+        assert_eq!(offset_of!(RegisterBlock, rx_threshold), 0x1C);
+        assert_eq!(offset_of!(RegisterBlock, tx_fifo_level), 0x20);
+        assert_eq!(offset_of!(RegisterBlock, rx_fifo_level), 0x24);
+        assert_eq!(offset_of!(RegisterBlock, status), 0x28);
+        assert_eq!(offset_of!(RegisterBlock, interrupt_mask), 0x2C);
+        assert_eq!(offset_of!(RegisterBlock, interrupt_status), 0x30);
+        assert_eq!(offset_of!(RegisterBlock, raw_interrupt_status), 0x34);
+        assert_eq!(offset_of!(RegisterBlock, clear_interrupt), 0x48);
+        assert_eq!(offset_of!(RegisterBlock, dma_control), 0x4C);
+        assert_eq!(offset_of!(RegisterBlock, identification), 0x58);
+        assert_eq!(offset_of!(RegisterBlock, version), 0x5C);
+        assert_eq!(offset_of!(RegisterBlock, data), 0x60);
+        assert_eq!(offset_of!(RegisterBlock, rx_sample_delay), 0xF0);
+    }
+
+    #[test]
+    fn control0_mode_and_frame_size() {
+        let val = Control0::default().set_mode(MODE_3).set_frame_size(12);
+        assert_eq!(val.mode(), MODE_3);
+        assert_eq!(val.frame_size(), 12);
+        assert_eq!(val.0, 0xC0 | 11);
+    }
+
+    #[test]
+    fn baud_rate_divider_is_even_and_not_faster() {
+        assert_eq!(baud_rate_divider(100_000_000, 1_000_000), 100);
+        assert_eq!(baud_rate_divider(100_000_000, 30_000_000), 4);
+        assert_eq!(baud_rate_divider(100_000_000, 200_000_000), 2);
+    }
+
+    /// Simulated SPI register block; data register loops written frames back.
+    struct Sim(RegisterBlock);
+
+    impl AsRef<RegisterBlock> for Sim {
+        fn as_ref(&self) -> &RegisterBlock {
+            &self.0
+        }
+    }
+
+    #[test]
+    fn transfer_pads_write_with_zeros() {
+        let sim = Sim(unsafe { core::mem::zeroed() });
+        // transmit FIFO not full, receive FIFO not empty
+        set(&sim.0.status, (1 << 1) | (1 << 3));
+        let mut spi = Spi {
+            spi: &sim,
+            pads: (),
+        };
+        let mut words = [0x5Au8];
+        spi.transfer_in_place(&mut words).unwrap();
+        assert_eq!(words, [0x5A]);
+        // the simulated data register holds one frame, so only the last frame is observed
+        let mut read = [0xFFu8; 2];
+        spi.transfer(&mut read, &[0xA5]).unwrap();
+        assert_eq!(sim.0.data.read(), 0x00);
+        assert_eq!(read, [0x00, 0x00]);
+    }
+
+    #[test]
+    fn wide_frames_reject_byte_words() {
+        let sim = Sim(unsafe { core::mem::zeroed() });
+        set(&sim.0.status, (1 << 1) | (1 << 3));
+        set(&sim.0.control0, Control0::default().set_frame_size(12).0);
+        let mut spi = Spi {
+            spi: &sim,
+            pads: (),
+        };
+        assert_eq!(spi.write(&[0x5Au8]), Err(Error::WordSize));
+        assert_eq!(spi.write(&[0x5A5u16]), Ok(()));
+    }
+
+    /// Forces a register value as if written by hardware.
+    fn set<R>(register: &R, value: u32) {
+        let cell = unsafe { &*(register as *const R).cast::<UnsafeCell<u32>>() };
+        unsafe { cell.get().write_volatile(value) }
+    }
+
+    #[test]
+    fn async_transfer_waits_for_rx_threshold() {
+        let sim = Sim(unsafe { core::mem::zeroed() });
+        set(&sim.0.status, 1 << 1);
+        let state = State::new();
+        let mut spi = Spi {
+            spi: &sim,
+            pads: (),
+        }
+        .into_async(&state);
+        let mut read = [0u16; 3];
+        {
+            let mut fut = pin!(embedded_hal_async::spi::SpiBus::transfer(
+                &mut spi,
+                &mut read,
+                &[0x123, 0x456, 0x789]
+            ));
+            let mut cx = Context::from_waker(Waker::noop());
+            assert!(fut.as_mut().poll(&mut cx).is_pending());
+            assert_eq!(sim.0.rx_threshold.read(), 2);
+            assert!(sim.0.interrupt_mask.read().has_interrupt(Interrupt::RxFull));
+
+            set(&sim.0.status, (1 << 1) | (1 << 3));
+            set(&sim.0.rx_fifo_level, 3);
+            set(&sim.0.interrupt_status, Interrupt::RxFull as u32);
+            state.on_interrupt();
+            assert!(!sim.0.interrupt_mask.read().has_interrupt(Interrupt::RxFull));
+            assert_eq!(fut.as_mut().poll(&mut cx), Poll::Ready(Ok(())));
+        }
+        assert_eq!(read, [0x789; 3]);
+    }
+
+    #[test]
+    fn slave_reports_fifo_errors() {
+        let sim = Sim(unsafe { core::mem::zeroed() });
+        let mut slave = SpiSlave {
+            spi: &sim,
+            pads: (),
+        };
+        assert_eq!(slave.check_errors(), Ok(()));
+        set(&sim.0.raw_interrupt_status, Interrupt::RxOverflow as u32);
+        assert_eq!(slave.check_errors(), Err(Error::Overrun));
+        set(&sim.0.raw_interrupt_status, Interrupt::TxUnderflow as u32);
+        assert_eq!(slave.check_errors(), Err(Error::Underrun));
+    }
+}
@@ -0,0 +1,1009 @@
+//! SPI-NAND flash controller and NAND flash devices.
+
+use crate::clock::Clocks;
+use crate::pad::{Pad, SpiNandFunc};
+use crate::reset::{self, Controlled};
+use volatile_register::{RO, RW, WO};
+
+/// SPI-NAND flash controller registers.
+#[repr(C)]
+pub struct RegisterBlock {
+    /// Transaction control register 0.
+    pub trx_control0: RW<TrxControl0>,
+    /// Transaction control register 1, data phase length in bytes.
+    pub trx_control1: RW<u32>,
+    /// Transaction control register 2.
+    pub trx_control2: RW<TrxControl2>,
+    /// Transaction control register 3.
+    pub trx_control3: RW<TrxControl3>,
+    /// Interrupt enable register.
+    pub interrupt_enable: RW<u32>,
+    /// Interrupt clear register; write 1 to clear.
+    pub interrupt_clear: WO<u32>,
+    /// Interrupt signal mask register.
+    pub interrupt_mask: RW<u32>,
+    /// Interrupt status register.
+    pub interrupt_status: RO<u32>,
+    _reserved0: [u8; 0x4],
+    /// Serial clock control register, divider of the controller clock.
+    pub clock_control: RW<u32>,
+    _reserved1: [u8; 0x8],
+    /// Command and address bytes; first byte is sent from the lowest bits of `command[0]`.
+    pub command: [RW<u32>; 2],
+    _reserved2: [u8; 0x28],
+    /// Transmit FIFO data port.
+    pub tx_data: WO<u32>,
+    /// Receive FIFO data port.
+    pub rx_data: RO<u32>,
+    /// FIFO status register.
+    pub fifo_status: RO<FifoStatus>,
+}
+
+/// Transaction control register 0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct TrxControl0(u32);
+
+impl TrxControl0 {
+    const START: u32 = 1 << 0;
+    const WRITE: u32 = 1 << 1;
+
+    /// Start the transaction; cleared by hardware once started.
+    #[inline]
+    pub const fn start(self) -> Self {
+        Self(self.0 | Self::START)
+    }
+    /// Check if transaction start is pending.
+    #[inline]
+    pub const fn is_started(self) -> bool {
+        self.0 & Self::START != 0
+    }
+    /// Send data phase from host to device.
+    #[inline]
+    pub const fn set_write(self) -> Self {
+        Self(self.0 | Self::WRITE)
+    }
+    /// Receive data phase from device to host.
+    #[inline]
+    pub const fn set_read(self) -> Self {
+        Self(self.0 & !Self::WRITE)
+    }
+    /// Check if data phase is sent from host to device.
+    #[inline]
+    pub const fn is_write(self) -> bool {
+        self.0 & Self::WRITE != 0
+    }
+}
+
+/// Transaction control register 2.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct TrxControl2(u32);
+
+impl TrxControl2 {
+    const COMMAND_LENGTH: u32 = 0xF;
+    const DUMMY_CYCLES: u32 = 0x3F << 8;
+
+    /// Set number of command and address bytes, at most 8.
+    #[inline]
+    pub const fn set_command_length(self, len: u8) -> Self {
+        Self((self.0 & !Self::COMMAND_LENGTH) | (len as u32 & 0xF))
+    }
+    /// Get number of command and address bytes.
+    #[inline]
+    pub const fn command_length(self) -> u8 {
+        (self.0 & Self::COMMAND_LENGTH) as u8
+    }
+    /// Set number of dummy clock cycles between address and data phases.
+    #[inline]
+    pub const fn set_dummy_cycles(self, cycles: u8) -> Self {
+        Self((self.0 & !Self::DUMMY_CYCLES) | ((cycles as u32 & 0x3F) << 8))
+    }
+    /// Get number of dummy clock cycles.
+    #[inline]
+    pub const fn dummy_cycles(self) -> u8 {
+        ((self.0 & Self::DUMMY_CYCLES) >> 8) as u8
+    }
+}
+
+/// Transaction control register 3.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct TrxControl3(u32);
+
+impl TrxControl3 {
+    const DATA_WIDTH: u32 = 0b11;
+
+    /// Set number of data lines used in data phase.
+    #[inline]
+    pub const fn set_data_width(self, width: DataWidth) -> Self {
+        Self((self.0 & !Self::DATA_WIDTH) | width as u32)
+    }
+    /// Get number of data lines used in data phase.
+    #[inline]
+    pub const fn data_width(self) -> DataWidth {
+        match self.0 & Self::DATA_WIDTH {
+            0 => DataWidth::Single,
+            1 => DataWidth::Dual,
+            _ => DataWidth::Quad,
+        }
+    }
+}
+
+/// FIFO status register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct FifoStatus(u32);
+
+impl FifoStatus {
+    const RX_LEVEL: u32 = 0x1FF;
+    const TX_FREE: u32 = 0x1FF << 16;
+
+    /// Number of bytes in receive FIFO.
+    #[inline]
+    pub const fn rx_level(self) -> usize {
+        (self.0 & Self::RX_LEVEL) as usize
+    }
+    /// Number of free bytes in transmit FIFO.
+    #[inline]
+    pub const fn tx_free(self) -> usize {
+        ((self.0 & Self::TX_FREE) >> 16) as usize
+    }
+}
+
+/// Data lines used in data phase.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DataWidth {
+    /// Standard SPI, data on MOSI and MISO.
+    Single = 0,
+    /// Dual output, data on IO0 and IO1.
+    Dual = 1,
+    /// Quad output, data on IO0 to IO3.
+    Quad = 2,
+}
+
+/// Interrupt status bit of a finished transaction.
+const TRANSFER_DONE: u32 = 1 << 0;
+
+/// SPI-NAND command opcodes.
+mod opcode {
+    pub const RESET: u8 = 0xFF;
+    pub const READ_ID: u8 = 0x9F;
+    pub const GET_FEATURE: u8 = 0x0F;
+    pub const SET_FEATURE: u8 = 0x1F;
+    pub const WRITE_ENABLE: u8 = 0x06;
+    pub const PAGE_READ: u8 = 0x13;
+    pub const READ_CACHE: u8 = 0x03;
+    pub const READ_CACHE_X2: u8 = 0x3B;
+    pub const READ_CACHE_X4: u8 = 0x6B;
+    pub const PROGRAM_LOAD: u8 = 0x02;
+    pub const PROGRAM_LOAD_X4: u8 = 0x32;
+    pub const PROGRAM_EXECUTE: u8 = 0x10;
+    pub const BLOCK_ERASE: u8 = 0xD8;
+}
+
+/// Feature register addresses.
+pub mod feature {
+    /// Block protection register.
+    pub const PROTECTION: u8 = 0xA0;
+    /// Configuration register.
+    pub const CONFIG: u8 = 0xB0;
+    /// Status register.
+    pub const STATUS: u8 = 0xC0;
+}
+
+/// On-die ECC enable bit of configuration feature.
+const CONFIG_ECC_ENABLE: u8 = 1 << 4;
+
+/// One-time programmable area access bit of configuration feature.
+const CONFIG_OTP_ENABLE: u8 = 1 << 6;
+
+/// Page of the one-time programmable area holding parameter page copies.
+const PARAMETER_PAGE: u32 = 1;
+
+/// Bytes per parameter page copy.
+pub const PARAMETER_PAGE_LEN: usize = 256;
+
+/// Number of redundant parameter page copies read while probing.
+const PARAMETER_PAGE_COPIES: usize = 3;
+
+/// Status feature register of a NAND device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct Status(u8);
+
+impl Status {
+    const BUSY: u8 = 1 << 0;
+    const WRITE_ENABLED: u8 = 1 << 1;
+    const ERASE_FAILED: u8 = 1 << 2;
+    const PROGRAM_FAILED: u8 = 1 << 3;
+    const ECC: u8 = 0b11 << 4;
+
+    /// Check if an operation is in progress.
+    #[inline]
+    pub const fn is_busy(self) -> bool {
+        self.0 & Self::BUSY != 0
+    }
+    /// Check if write enable latch is set.
+    #[inline]
+    pub const fn is_write_enabled(self) -> bool {
+        self.0 & Self::WRITE_ENABLED != 0
+    }
+    /// Check if last block erase failed.
+    #[inline]
+    pub const fn is_erase_failed(self) -> bool {
+        self.0 & Self::ERASE_FAILED != 0
+    }
+    /// Check if last page program failed.
+    #[inline]
+    pub const fn is_program_failed(self) -> bool {
+        self.0 & Self::PROGRAM_FAILED != 0
+    }
+    /// ECC result of last page read.
+    #[inline]
+    pub const fn ecc(self) -> EccStatus {
+        match (self.0 & Self::ECC) >> 4 {
+            0 => EccStatus::NoError,
+            2 => EccStatus::Uncorrectable,
+            _ => EccStatus::Corrected,
+        }
+    }
+}
+
+/// ECC result of a page read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EccStatus {
+    /// Page has no bit errors.
+    NoError,
+    /// Bit errors were found and corrected.
+    Corrected,
+    /// Page has more bit errors than on-die ECC can correct; data is corrupted.
+    Uncorrectable,
+}
+
+/// NAND flash array geometry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Geometry {
+    /// Main area bytes per page.
+    pub page_size: u32,
+    /// Spare area bytes per page.
+    pub spare_size: u32,
+    /// Pages per erase block.
+    pub pages_per_block: u32,
+    /// Number of erase blocks.
+    pub blocks: u32,
+}
+
+impl Geometry {
+    /// Parses geometry from a copy of the ONFI parameter page.
+    ///
+    /// Returns `None` if signature or CRC of the copy mismatches, or a field is zero.
+    #[inline]
+    pub fn from_parameter_page(page: &[u8; PARAMETER_PAGE_LEN]) -> Option<Geometry> {
+        let crc = u16::from_le_bytes([page[254], page[255]]);
+        if !page.starts_with(b"ONFI") || parameter_page_crc(&page[..254]) != crc {
+            return None;
+        }
+        let word = |i: usize| u32::from_le_bytes([page[i], page[i + 1], page[i + 2], page[i + 3]]);
+        let geometry = Geometry {
+            page_size: word(80),
+            spare_size: u16::from_le_bytes([page[84], page[85]]) as u32,
+            pages_per_block: word(92),
+            blocks: word(96) * page[100] as u32,
+        };
+        if geometry.page_size == 0 || geometry.pages_per_block == 0 || geometry.blocks == 0 {
+            return None;
+        }
+        Some(geometry)
+    }
+    /// Looks up geometry of a known device by its `READ ID` response.
+    #[inline]
+    pub const fn from_id(id: [u8; 3]) -> Option<Geometry> {
+        let (spare_size, blocks) = match id {
+            // Winbond W25N01GV
+            [0xEF, 0xAA, 0x21] => (64, 1024),
+            // Winbond W25N02KV
+            [0xEF, 0xAA, 0x22] => (128, 2048),
+            // GigaDevice GD5F1GQ4UA
+            [0xC8, 0xF1, _] => (64, 1024),
+            // GigaDevice GD5F1GQ5UE
+            [0xC8, 0x51, _] => (128, 1024),
+            // Macronix MX35LF1GE4AB
+            [0xC2, 0x12, _] => (64, 1024),
+            // Micron MT29F1G01ABAFD
+            [0x2C, 0x14, _] => (128, 1024),
+            // Kioxia TC58CVG0S3HRAIG
+            [0x98, 0xC2, _] => (128, 1024),
+            _ => return None,
+        };
+        Some(Geometry {
+            page_size: 2048,
+            spare_size,
+            pages_per_block: 64,
+            blocks,
+        })
+    }
+    /// Total number of pages.
+    #[inline]
+    pub const fn pages(&self) -> u32 {
+        self.pages_per_block * self.blocks
+    }
+    /// Main area bytes per erase block.
+    #[inline]
+    pub const fn block_size(&self) -> u32 {
+        self.page_size * self.pages_per_block
+    }
+}
+
+/// CRC-16 of an ONFI parameter page, polynomial 0x8005 with initial value 0x4F4E.
+#[inline]
+const fn parameter_page_crc(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0x4F4E;
+    let mut i = 0;
+    while i < data.len() {
+        crc ^= (data[i] as u16) << 8;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x8005
+            } else {
+                crc << 1
+            };
+            bit += 1;
+        }
+        i += 1;
+    }
+    crc
+}
+
+/// SPI-NAND error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// Device has no valid parameter page and its ID is not in the known device table.
+    UnknownDevice([u8; 3]),
+    /// Page has more bit errors than on-die ECC can correct.
+    Uncorrectable,
+    /// Device reported a failed page program.
+    ProgramFailed,
+    /// Device reported a failed block erase.
+    EraseFailed,
+    /// Access exceeds device page, block or buffer bounds.
+    OutOfRange,
+}
+
+/// Data phase of an SPI-NAND transaction.
+#[derive(Debug)]
+pub enum Data<'a> {
+    /// No data phase.
+    None,
+    /// Receive data from device.
+    Read(&'a mut [u8]),
+    /// Send data to device.
+    Write(&'a [u8]),
+}
+
+/// SPI-NAND controller configuration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Config {
+    /// Serial clock frequency in Hz.
+    pub frequency: u32,
+    /// Data lines used for page cache reads and program loads.
+    ///
+    /// Quad width needs pads including write protect and hold lines, and may
+    /// need the device quad enable bit set through `set_feature`.
+    pub data_width: DataWidth,
+}
+
+impl Default for Config {
+    /// SPI-NAND configuration defaults to 25 MHz single data line.
+    #[inline]
+    fn default() -> Self {
+        Config {
+            frequency: 25_000_000,
+            data_width: DataWidth::Single,
+        }
+    }
+}
+
+/// Managed SPI-NAND flash controller peripheral.
+pub struct SpiNand<T, PADS> {
+    nand: T,
+    pads: PADS,
+    data_width: DataWidth,
+}
+
+/// Extension trait to create SPI-NAND flash controllers from peripherals.
+pub trait SpiNandExt: AsRef<RegisterBlock> + Controlled + Sized {
+    /// Configures the peripheral as an SPI-NAND flash controller running on `pads`.
+    ///
+    /// Controller is clocked from the SPI controller clock.
+    #[inline]
+    fn spi_nand<PADS>(self, config: Config, pads: PADS, clocks: &Clocks) -> SpiNand<Self, PADS>
+    where
+        PADS: Pads,
+    {
+        reset::enable(&self);
+        let nand = self.as_ref();
+        unsafe {
+            nand.interrupt_enable.write(TRANSFER_DONE);
+            nand.interrupt_mask.write(0);
+            nand.interrupt_clear.write(u32::MAX);
+            nand.clock_control
+                .write(clock_divider(clocks.spi().to_hz(), config.frequency));
+        }
+        let data_width = match config.data_width {
+            DataWidth::Quad if !PADS::QUAD => DataWidth::Dual,
+            width => width,
+        };
+        SpiNand {
+            nand: self,
+            pads,
+            data_width,
+        }
+    }
+}
+
+impl<T: AsRef<RegisterBlock>, PADS> SpiNand<T, PADS> {
+    /// Performs a raw transaction of `command` bytes, `dummy_cycles` clocks and `data`.
+    ///
+    /// Command and address phase always uses a single data line; `width`
+    /// applies to data phase only.
+    #[inline]
+    pub fn transfer(&mut self, command: &[u8], dummy_cycles: u8, width: DataWidth, data: Data) {
+        let nand = self.nand.as_ref();
+        let words = pack_command(command);
+        let (len, control) = match &data {
+            Data::None => (0, TrxControl0::default().set_read()),
+            Data::Read(buf) => (buf.len(), TrxControl0::default().set_read()),
+            Data::Write(buf) => (buf.len(), TrxControl0::default().set_write()),
+        };
+        unsafe {
+            nand.command[0].write(words[0]);
+            nand.command[1].write(words[1]);
+            nand.trx_control1.write(len as u32);
+            nand.trx_control2.write(
+                TrxControl2::default()
+                    .set_command_length(command.len() as u8)
+                    .set_dummy_cycles(dummy_cycles),
+            );
+            nand.trx_control3
+                .write(TrxControl3::default().set_data_width(width));
+            nand.interrupt_clear.write(TRANSFER_DONE);
+            nand.trx_control0.write(control.start());
+        }
+        match data {
+            Data::None => {}
+            Data::Read(buf) => {
+                for chunk in buf.chunks_mut(4) {
+                    while nand.fifo_status.read().rx_level() < chunk.len() {
+                        core::hint::spin_loop();
+                    }
+                    let word = nand.rx_data.read().to_le_bytes();
+                    chunk.copy_from_slice(&word[..chunk.len()]);
+                }
+            }
+            Data::Write(buf) => {
+                for chunk in buf.chunks(4) {
+                    while nand.fifo_status.read().tx_free() < 4 {
+                        core::hint::spin_loop();
+                    }
+                    let mut word = [0xFF; 4];
+                    word[..chunk.len()].copy_from_slice(chunk);
+                    unsafe { nand.tx_data.write(u32::from_le_bytes(word)) };
+                }
+            }
+        }
+        while nand.interrupt_status.read() & TRANSFER_DONE == 0 {
+            core::hint::spin_loop();
+        }
+        unsafe { nand.interrupt_clear.write(TRANSFER_DONE) };
+    }
+    /// Resets the device, aborting any operation in progress.
+    #[inline]
+    pub fn reset_device(&mut self) {
+        self.transfer(&[opcode::RESET], 0, DataWidth::Single, Data::None);
+        self.wait_ready();
+    }
+    /// Reads manufacturer and device ID bytes.
+    #[inline]
+    pub fn read_id(&mut self) -> [u8; 3] {
+        let mut id = [0; 3];
+        self.transfer(
+            &[opcode::READ_ID, 0],
+            0,
+            DataWidth::Single,
+            Data::Read(&mut id),
+        );
+        id
+    }
+    /// Reads a feature register.
+    #[inline]
+    pub fn get_feature(&mut self, address: u8) -> u8 {
+        let mut value = [0];
+        self.transfer(
+            &[opcode::GET_FEATURE, address],
+            0,
+            DataWidth::Single,
+            Data::Read(&mut value),
+        );
+        value[0]
+    }
+    /// Writes a feature register.
+    #[inline]
+    pub fn set_feature(&mut self, address: u8, value: u8) {
+        self.transfer(
+            &[opcode::SET_FEATURE, address, value],
+            0,
+            DataWidth::Single,
+            Data::None,
+        );
+    }
+    /// Reads device status.
+    #[inline]
+    pub fn status(&mut self) -> Status {
+        Status(self.get_feature(feature::STATUS))
+    }
+    /// Waits for operation in progress to finish, returns final status.
+    #[inline]
+    pub fn wait_ready(&mut self) -> Status {
+        loop {
+            let status = self.status();
+            if !status.is_busy() {
+                return status;
+            }
+            core::hint::spin_loop();
+        }
+    }
+    /// Sets write enable latch before program or erase.
+    #[inline]
+    pub fn write_enable(&mut self) {
+        self.transfer(&[opcode::WRITE_ENABLE], 0, DataWidth::Single, Data::None);
+    }
+    /// Data lines used for page cache reads and program loads.
+    #[inline]
+    pub fn data_width(&self) -> DataWidth {
+        self.data_width
+    }
+    /// Release controller instance and return its peripheral and pads, turning its clocks off.
+    #[inline]
+    pub fn free(self) -> (T, PADS)
+    where
+        T: Controlled,
+    {
+        unsafe { self.nand.as_ref().interrupt_enable.write(0) };
+        reset::disable(&self.nand);
+        (self.nand, self.pads)
+    }
+}
+
+/// Serial clock divider for `frequency` from controller clock `source`, rounding frequency down.
+#[inline]
+const fn clock_divider(source: u32, frequency: u32) -> u32 {
+    // serial clock is source / (2 * (divider + 1))
+    let n = source.div_ceil(2 * frequency);
+    if n == 0 {
+        0
+    } else if n > 0x100 {
+        0xFF
+    } else {
+        n - 1
+    }
+}
+
+/// Packs command and address bytes into command registers, first byte lowest.
+#[inline]
+const fn pack_command(command: &[u8]) -> [u32; 2] {
+    let mut words = [0; 2];
+    let mut i = 0;
+    while i < command.len() && i < 8 {
+        words[i / 4] |= (command[i] as u32) << (8 * (i % 4));
+        i += 1;
+    }
+    words
+}
+
+/// NAND flash device on an SPI-NAND controller.
+pub struct NandFlash<T, PADS> {
+    nand: SpiNand<T, PADS>,
+    id: [u8; 3],
+    geometry: Geometry,
+}
+
+impl<T: AsRef<RegisterBlock>, PADS> NandFlash<T, PADS> {
+    /// Resets and identifies a device, unlocks all its blocks and turns on on-die ECC.
+    ///
+    /// Geometry is read from the device parameter page, or looked up in the
+    /// known device table if the device has no valid parameter page.
+    #[inline]
+    pub fn new(mut nand: SpiNand<T, PADS>) -> Result<Self, Error> {
+        nand.reset_device();
+        let id = nand.read_id();
+        let geometry = Self::probe_geometry(&mut nand)
+            .or(Geometry::from_id(id))
+            .ok_or(Error::UnknownDevice(id))?;
+        Ok(Self::init(nand, id, geometry))
+    }
+    /// Resets a device with given geometry, unlocks all its blocks and turns on on-die ECC.
+    #[inline]
+    pub fn with_geometry(mut nand: SpiNand<T, PADS>, geometry: Geometry) -> Self {
+        nand.reset_device();
+        let id = nand.read_id();
+        Self::init(nand, id, geometry)
+    }
+    /// Device ID bytes.
+    #[inline]
+    pub fn id(&self) -> [u8; 3] {
+        self.id
+    }
+    /// Flash array geometry.
+    #[inline]
+    pub fn geometry(&self) -> Geometry {
+        self.geometry
+    }
+    /// Reads `buf.len()` bytes of `page` starting at byte `column`, returns ECC result.
+    ///
+    /// Columns from `page_size` on address the spare area.
+    #[inline]
+    pub fn read_page(
+        &mut self,
+        page: u32,
+        column: u32,
+        buf: &mut [u8],
+    ) -> Result<EccStatus, Error> {
+        self.check_range(page, column, buf.len())?;
+        let ecc = self.load_page(page);
+        self.read_cache(column, buf);
+        Ok(ecc)
+    }
+    /// Programs `data` into `page` starting at byte `column`.
+    ///
+    /// Bytes of the page outside `data` are left unchanged.
+    #[inline]
+    pub fn program_page(&mut self, page: u32, column: u32, data: &[u8]) -> Result<(), Error> {
+        self.check_range(page, column, data.len())?;
+        self.nand.write_enable();
+        let (op, width) = match self.nand.data_width {
+            DataWidth::Quad => (opcode::PROGRAM_LOAD_X4, DataWidth::Quad),
+            _ => (opcode::PROGRAM_LOAD, DataWidth::Single),
+        };
+        let [_, _, hi, lo] = column.to_be_bytes();
+        self.nand
+            .transfer(&[op, hi, lo], 0, width, Data::Write(data));
+        let [_, a2, a1, a0] = page.to_be_bytes();
+        self.nand.transfer(
+            &[opcode::PROGRAM_EXECUTE, a2, a1, a0],
+            0,
+            DataWidth::Single,
+            Data::None,
+        );
+        if self.nand.wait_ready().is_program_failed() {
+            return Err(Error::ProgramFailed);
+        }
+        Ok(())
+    }
+    /// Erases all pages of `block`.
+    #[inline]
+    pub fn erase_block(&mut self, block: u32) -> Result<(), Error> {
+        if block >= self.geometry.blocks {
+            return Err(Error::OutOfRange);
+        }
+        self.nand.write_enable();
+        let [_, a2, a1, a0] = (block * self.geometry.pages_per_block).to_be_bytes();
+        self.nand.transfer(
+            &[opcode::BLOCK_ERASE, a2, a1, a0],
+            0,
+            DataWidth::Single,
+            Data::None,
+        );
+        if self.nand.wait_ready().is_erase_failed() {
+            return Err(Error::EraseFailed);
+        }
+        Ok(())
+    }
+    /// Checks bad block marker in spare area of the first page of `block`.
+    #[inline]
+    pub fn is_bad_block(&mut self, block: u32) -> Result<bool, Error> {
+        let mut marker = [0; 2];
+        let page = block * self.geometry.pages_per_block;
+        self.read_page(page, self.geometry.page_size, &mut marker)?;
+        Ok(marker != [0xFF; 2])
+    }
+    /// Writes bad block marker into spare area of the first page of `block`.
+    #[inline]
+    pub fn mark_bad_block(&mut self, block: u32) -> Result<(), Error> {
+        if block >= self.geometry.blocks {
+            return Err(Error::OutOfRange);
+        }
+        let page = block * self.geometry.pages_per_block;
+        self.program_page(page, self.geometry.page_size, &[0; 2])
+    }
+    /// Creates a sequential reader of main areas starting at `block`, skipping bad blocks.
+    #[inline]
+    pub fn reader(&mut self, block: u32) -> Reader<'_, T, PADS> {
+        Reader {
+            flash: self,
+            block,
+            page: 0,
+            column: 0,
+            block_checked: false,
+            page_loaded: false,
+        }
+    }
+    /// Release device and return its controller.
+    #[inline]
+    pub fn free(self) -> SpiNand<T, PADS> {
+        self.nand
+    }
+    /// Reads parameter page copies from the one-time programmable area, returns first valid geometry.
+    #[inline]
+    fn probe_geometry(nand: &mut SpiNand<T, PADS>) -> Option<Geometry> {
+        let config = nand.get_feature(feature::CONFIG);
+        nand.set_feature(feature::CONFIG, config | CONFIG_OTP_ENABLE);
+        let [_, a2, a1, a0] = PARAMETER_PAGE.to_be_bytes();
+        nand.transfer(
+            &[opcode::PAGE_READ, a2, a1, a0],
+            0,
+            DataWidth::Single,
+            Data::None,
+        );
+        nand.wait_ready();
+        let mut copies = [[0; PARAMETER_PAGE_LEN]; PARAMETER_PAGE_COPIES];
+        for (n, copy) in copies.iter_mut().enumerate() {
+            let [_, _, hi, lo] = ((n * PARAMETER_PAGE_LEN) as u32).to_be_bytes();
+            nand.transfer(
+                &[opcode::READ_CACHE, hi, lo],
+                8,
+                DataWidth::Single,
+                Data::Read(copy),
+            );
+        }
+        nand.set_feature(feature::CONFIG, config);
+        copies.iter().find_map(Geometry::from_parameter_page)
+    }
+    #[inline]
+    fn init(mut nand: SpiNand<T, PADS>, id: [u8; 3], geometry: Geometry) -> Self {
+        nand.set_feature(feature::PROTECTION, 0);
+        let config = nand.get_feature(feature::CONFIG);
+        nand.set_feature(feature::CONFIG, config | CONFIG_ECC_ENABLE);
+        NandFlash { nand, id, geometry }
+    }
+    /// Loads `page` into device cache, returns ECC result.
+    #[inline]
+    fn load_page(&mut self, page: u32) -> EccStatus {
+        let [_, a2, a1, a0] = page.to_be_bytes();
+        self.nand.transfer(
+            &[opcode::PAGE_READ, a2, a1, a0],
+            0,
+            DataWidth::Single,
+            Data::None,
+        );
+        self.nand.wait_ready().ecc()
+    }
+    /// Reads bytes from device cache starting at `column`.
+    #[inline]
+    fn read_cache(&mut self, column: u32, buf: &mut [u8]) {
+        let width = self.nand.data_width;
+        let op = match width {
+            DataWidth::Single => opcode::READ_CACHE,
+            DataWidth::Dual => opcode::READ_CACHE_X2,
+            DataWidth::Quad => opcode::READ_CACHE_X4,
+        };
+        let [_, _, hi, lo] = column.to_be_bytes();
+        self.nand.transfer(&[op, hi, lo], 8, width, Data::Read(buf));
+    }
+    #[inline]
+    fn check_range(&self, page: u32, column: u32, len: usize) -> Result<(), Error> {
+        let page_len = self.geometry.page_size + self.geometry.spare_size;
+        if page >= self.geometry.pages() || column as usize + len > page_len as usize {
+            return Err(Error::OutOfRange);
+        }
+        Ok(())
+    }
+}
+
+/// Sequential reader of NAND main areas, skipping bad blocks.
+///
+/// Reads stop at the end of the device; uncorrectable pages are reported as errors.
+pub struct Reader<'a, T, PADS> {
+    flash: &'a mut NandFlash<T, PADS>,
+    block: u32,
+    page: u32,
+    column: u32,
+    block_checked: bool,
+    page_loaded: bool,
+}
+
+impl<'a, T: AsRef<RegisterBlock>, PADS> Reader<'a, T, PADS> {
+    /// Block being read, after skipping bad blocks.
+    #[inline]
+    pub fn block(&self) -> u32 {
+        self.block
+    }
+    /// Reads up to `buf.len()` bytes, returns number of bytes read or 0 at end of device.
+    #[inline]
+    pub fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let geometry = self.flash.geometry;
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.column == geometry.page_size {
+            self.column = 0;
+            self.page += 1;
+            self.page_loaded = false;
+        }
+        if self.page == geometry.pages_per_block {
+            self.page = 0;
+            self.block += 1;
+            self.block_checked = false;
+        }
+        if !self.block_checked {
+            while self.block < geometry.blocks && self.flash.is_bad_block(self.block)? {
+                self.block += 1;
+            }
+            self.block_checked = true;
+        }
+        if self.block >= geometry.blocks {
+            return Ok(0);
+        }
+        if !self.page_loaded {
+            let page = self.block * geometry.pages_per_block + self.page;
+            if self.flash.load_page(page) == EccStatus::Uncorrectable {
+                return Err(Error::Uncorrectable);
+            }
+            self.page_loaded = true;
+        }
+        let len = buf.len().min((geometry.page_size - self.column) as usize);
+        self.flash.read_cache(self.column, &mut buf[..len]);
+        self.column += len as u32;
+        Ok(len)
+    }
+}
+
+impl embedded_io::Error for Error {
+    #[inline(always)]
+    fn kind(&self) -> embedded_io::ErrorKind {
+        embedded_io::ErrorKind::Other
+    }
+}
+
+impl<'a, T: AsRef<RegisterBlock>, PADS> embedded_io::ErrorType for Reader<'a, T, PADS> {
+    type Error = Error;
+}
+
+impl<'a, T: AsRef<RegisterBlock>, PADS> embedded_io::Read for Reader<'a, T, PADS> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        Reader::read(self, buf)
+    }
+}
+
+/// Valid SPI-NAND pads.
+pub trait Pads {
+    /// Checks if this pad configuration includes write protect and hold lines for quad data.
+    const QUAD: bool;
+}
+
+impl<T1, T2, T3, T4> Pads
+    for (
+        Pad<T1, 21, SpiNandFunc>,
+        Pad<T2, 22, SpiNandFunc>,
+        Pad<T3, 24, SpiNandFunc>,
+        Pad<T4, 25, SpiNandFunc>,
+    )
+{
+    const QUAD: bool = false;
+}
+
+impl<T1, T2, T3, T4, T5, T6> Pads
+    for (
+        Pad<T1, 20, SpiNandFunc>,
+        Pad<T2, 21, SpiNandFunc>,
+        Pad<T3, 22, SpiNandFunc>,
+        Pad<T4, 23, SpiNandFunc>,
+        Pad<T5, 24, SpiNandFunc>,
+        Pad<T6, 25, SpiNandFunc>,
+    )
+{
+    const QUAD: bool = true;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        clock_divider, pack_command, parameter_page_crc, DataWidth, EccStatus, FifoStatus,
+        Geometry, RegisterBlock, Status, TrxControl0, TrxControl2, TrxControl3, PARAMETER_PAGE_LEN,
+    };
+    use memoffset::offset_of;
+
+    #[test]
+    fn struct_register_block_offset() {
+        assert_eq!(offset_of!(RegisterBlock, trx_control0), 0x00);
+        assert_eq!(offset_of!(RegisterBlock, trx_control1), 0x04);
+        assert_eq!(offset_of!(RegisterBlock, trx_control2), 0x08);
+        assert_eq!(offset_of!(RegisterBlock, trx_control3), 0x0C);
+        assert_eq!(offset_of!(RegisterBlock, interrupt_enable), 0x10);
+        assert_eq!(offset_of!(RegisterBlock, interrupt_clear), 0x14);
+        assert_eq!(offset_of!(RegisterBlock, interrupt_mask), 0x18);
+        assert_eq!(offset_of!(RegisterBlock, interrupt_status), 0x1C);
+        assert_eq!(offset_of!(RegisterBlock, clock_control), 0x24);
+        assert_eq!(offset_of!(RegisterBlock, command), 0x30);
+        assert_eq!(offset_of!(RegisterBlock, tx_data), 0x60);
+        assert_eq!(offset_of!(RegisterBlock, rx_data), 0x64);
+        assert_eq!(offset_of!(RegisterBlock, fifo_status), 0x68);
+    }
+
+    #[test]
+    fn struct_transaction_functions() {
+        let val = TrxControl0::default().set_write().start();
+        assert_eq!(val.0, 0x3);
+        assert!(val.is_write() && val.is_started());
+        assert!(!val.set_read().is_write());
+
+        let val = TrxControl2::default()
+            .set_command_length(3)
+            .set_dummy_cycles(8);
+        assert_eq!(val.0, 0x0803);
+        assert_eq!(val.command_length(), 3);
+        assert_eq!(val.dummy_cycles(), 8);
+
+        let val = TrxControl3::default().set_data_width(DataWidth::Quad);
+        assert_eq!(val.0, 0x2);
+        assert_eq!(val.data_width(), DataWidth::Quad);
+
+        let val = FifoStatus(0x0100_0004);
+        assert_eq!(val.rx_level(), 4);
+        assert_eq!(val.tx_free(), 256);
+
+        assert_eq!(
+            pack_command(&[0x13, 0x01, 0x02, 0x03, 0x04]),
+            [0x0302_0113, 0x04]
+        );
+        assert_eq!(clock_divider(500_000_000, 25_000_000), 9);
+        assert_eq!(clock_divider(500_000_000, 24_000_000), 10);
+        assert_eq!(clock_divider(500_000_000, 1_000_000_000), 0);
+    }
+
+    #[test]
+    fn device_status_and_geometry() {
+        let val = Status(0x11);
+        assert!(val.is_busy());
+        assert_eq!(val.ecc(), EccStatus::Corrected);
+        assert_eq!(Status(0x20).ecc(), EccStatus::Uncorrectable);
+        assert_eq!(Status(0x30).ecc(), EccStatus::Corrected);
+        assert_eq!(Status(0x02).ecc(), EccStatus::NoError);
+        assert!(Status(0x08).is_program_failed());
+        assert!(Status(0x04).is_erase_failed());
+
+        let val = Geometry::from_id([0xEF, 0xAA, 0x21]).unwrap();
+        assert_eq!(val.pages(), 65536);
+        assert_eq!(val.block_size(), 128 * 1024);
+        assert_eq!(val.spare_size, 64);
+        assert_eq!(
+            Geometry::from_id([0xC8, 0x51, 0x00]).map(|g| g.spare_size),
+            Some(128)
+        );
+        assert_eq!(Geometry::from_id([0xFF, 0xFF, 0xFF]), None);
+    }
+
+    #[test]
+    fn geometry_from_parameter_page() {
+        let mut page = [0; PARAMETER_PAGE_LEN];
+        page[..4].copy_from_slice(b"ONFI");
+        page[80..84].copy_from_slice(&4096u32.to_le_bytes());
+        page[84..86].copy_from_slice(&256u16.to_le_bytes());
+        page[92..96].copy_from_slice(&64u32.to_le_bytes());
+        page[96..100].copy_from_slice(&2048u32.to_le_bytes());
+        page[100] = 1;
+        let crc = parameter_page_crc(&page[..254]);
+        page[254..].copy_from_slice(&crc.to_le_bytes());
+        let val = Geometry::from_parameter_page(&page).unwrap();
+        assert_eq!(val.page_size, 4096);
+        assert_eq!(val.spare_size, 256);
+        assert_eq!(val.pages_per_block, 64);
+        assert_eq!(val.blocks, 2048);
+
+        page[100] = 2;
+        assert_eq!(Geometry::from_parameter_page(&page), None);
+    }
+}
@@ -0,0 +1,1004 @@
+//! SPI-NOR flash controller and NOR flash devices.
+
+use crate::clock::Clocks;
+use crate::pad::{Pad, SpiNorFunc};
+use crate::reset::{self, Controlled};
+use embedded_storage::nor_flash::{self, NorFlashErrorKind};
+use volatile_register::RW;
+
+/// SPI-NOR flash controller registers.
+#[repr(C)]
+pub struct RegisterBlock {
+    /// Control register.
+    pub control: RW<Control>,
+    /// Chip enable control register.
+    pub ce_control: RW<u32>,
+    /// Sampling delay control register.
+    pub delay_control: RW<u32>,
+    /// Direct memory mapped read enable register.
+    pub memory_map: RW<u32>,
+    /// Transaction control and status register.
+    pub transaction: RW<Transaction>,
+    /// Transaction data length register.
+    pub transaction_length: RW<u32>,
+    /// FIFO data port register.
+    pub fifo_data: RW<u8>,
+    _reserved0: [u8; 0x7],
+    /// FIFO pointer register, number of bytes in FIFO; write 0 to flush.
+    pub fifo_level: RW<u32>,
+    _reserved1: [u8; 0x4],
+    /// Interrupt status register; write 0 to clear.
+    pub interrupt_status: RW<u32>,
+    /// Interrupt enable register.
+    pub interrupt_enable: RW<u32>,
+}
+
+/// Control register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct Control(u32);
+
+impl Control {
+    const CLOCK_DIVIDER: u32 = 0x7FF;
+
+    /// Set serial clock divider; serial clock is controller clock / (2 * (divider + 1)).
+    #[inline]
+    pub const fn set_clock_divider(self, divider: u16) -> Self {
+        Self((self.0 & !Self::CLOCK_DIVIDER) | (divider as u32 & Self::CLOCK_DIVIDER))
+    }
+    /// Get serial clock divider.
+    #[inline]
+    pub const fn clock_divider(self) -> u16 {
+        (self.0 & Self::CLOCK_DIVIDER) as u16
+    }
+}
+
+/// Transaction control and status register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct Transaction(u32);
+
+impl Transaction {
+    const RECEIVE: u32 = 1 << 0;
+    const TRANSMIT: u32 = 1 << 1;
+    const FAST_MODE: u32 = 1 << 3;
+    const DATA_WIDTH: u32 = 0b11 << 4;
+    const ADDRESS_BYTES: u32 = 0b111 << 8;
+    const WITH_COMMAND: u32 = 1 << 11;
+    const BUSY: u32 = 1 << 15;
+    const DUMMY_CYCLES: u32 = 0xF << 16;
+
+    /// Receive data phase from device.
+    #[inline]
+    pub const fn set_receive(self) -> Self {
+        Self((self.0 & !Self::TRANSMIT) | Self::RECEIVE)
+    }
+    /// Transmit data phase to device.
+    #[inline]
+    pub const fn set_transmit(self) -> Self {
+        Self((self.0 & !Self::RECEIVE) | Self::TRANSMIT)
+    }
+    /// Check if data phase is received from device.
+    #[inline]
+    pub const fn is_receive(self) -> bool {
+        self.0 & Self::RECEIVE != 0
+    }
+    /// Check if data phase is transmitted to device.
+    #[inline]
+    pub const fn is_transmit(self) -> bool {
+        self.0 & Self::TRANSMIT != 0
+    }
+    /// Insert dummy cycles between address and data phases.
+    #[inline]
+    pub const fn enable_fast_mode(self) -> Self {
+        Self(self.0 | Self::FAST_MODE)
+    }
+    /// Set number of data lines used in data phase.
+    #[inline]
+    pub const fn set_data_width(self, width: DataWidth) -> Self {
+        Self((self.0 & !Self::DATA_WIDTH) | ((width as u32) << 4))
+    }
+    /// Get number of data lines used in data phase.
+    #[inline]
+    pub const fn data_width(self) -> DataWidth {
+        match (self.0 & Self::DATA_WIDTH) >> 4 {
+            0 => DataWidth::Single,
+            1 => DataWidth::Dual,
+            _ => DataWidth::Quad,
+        }
+    }
+    /// Set number of address bytes following the command byte in FIFO.
+    #[inline]
+    pub const fn set_address_bytes(self, n: u8) -> Self {
+        Self((self.0 & !Self::ADDRESS_BYTES) | ((n as u32 & 0b111) << 8))
+    }
+    /// Get number of address bytes.
+    #[inline]
+    pub const fn address_bytes(self) -> u8 {
+        ((self.0 & Self::ADDRESS_BYTES) >> 8) as u8
+    }
+    /// Send first FIFO byte as command byte.
+    #[inline]
+    pub const fn enable_command(self) -> Self {
+        Self(self.0 | Self::WITH_COMMAND)
+    }
+    /// Start the transaction.
+    #[inline]
+    pub const fn start(self) -> Self {
+        Self(self.0 | Self::BUSY)
+    }
+    /// Check if transaction is in progress.
+    #[inline]
+    pub const fn is_busy(self) -> bool {
+        self.0 & Self::BUSY != 0
+    }
+    /// Set number of dummy clock cycles in fast mode.
+    #[inline]
+    pub const fn set_dummy_cycles(self, cycles: u8) -> Self {
+        Self((self.0 & !Self::DUMMY_CYCLES) | ((cycles as u32 & 0xF) << 16))
+    }
+    /// Get number of dummy clock cycles.
+    #[inline]
+    pub const fn dummy_cycles(self) -> u8 {
+        ((self.0 & Self::DUMMY_CYCLES) >> 16) as u8
+    }
+}
+
+/// Data lines used in data phase.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DataWidth {
+    /// Standard SPI, data on MOSI and MISO.
+    Single = 0,
+    /// Dual output, data on IO0 and IO1.
+    Dual = 1,
+    /// Quad output, data on IO0 to IO3.
+    Quad = 2,
+}
+
+/// Interrupt status bit of a finished transaction.
+const TRANSFER_DONE: u32 = 1 << 0;
+/// Depth of controller FIFO in bytes.
+const FIFO_DEPTH: usize = 8;
+
+/// SPI-NOR command opcodes.
+mod opcode {
+    pub const READ_JEDEC_ID: u8 = 0x9F;
+    pub const READ_SFDP: u8 = 0x5A;
+    pub const READ_STATUS: u8 = 0x05;
+    pub const READ_STATUS2: u8 = 0x35;
+    pub const READ_STATUS2_ALT: u8 = 0x3F;
+    pub const WRITE_STATUS: u8 = 0x01;
+    pub const WRITE_STATUS2: u8 = 0x31;
+    pub const WRITE_STATUS2_ALT: u8 = 0x3E;
+    pub const WRITE_ENABLE: u8 = 0x06;
+    pub const RESET_ENABLE: u8 = 0x66;
+    pub const RESET: u8 = 0x99;
+    pub const ENTER_4_BYTE_ADDRESS: u8 = 0xB7;
+    pub const FAST_READ: u8 = 0x0B;
+    pub const PAGE_PROGRAM: u8 = 0x02;
+}
+
+/// Status register 1 bit of a program or erase in progress.
+const STATUS_BUSY: u8 = 1 << 0;
+
+/// Data phase of an SPI-NOR transaction.
+#[derive(Debug)]
+pub enum Data<'a> {
+    /// No data phase.
+    None,
+    /// Receive data from device.
+    Read(&'a mut [u8]),
+    /// Send data to device.
+    Write(&'a [u8]),
+}
+
+/// SPI-NOR error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// Address or length is not aligned to an erase size.
+    NotAligned,
+    /// Access exceeds device capacity.
+    OutOfBounds,
+    /// Device has no valid SFDP basic parameter table.
+    NoSfdp,
+    /// Smallest erase size of device is not `SECTOR_SIZE`.
+    UnsupportedEraseSize,
+}
+
+/// Erase instruction of a device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EraseType {
+    /// Bytes erased, or 0 if unsupported.
+    pub size: u32,
+    /// Erase opcode.
+    pub opcode: u8,
+}
+
+/// Fast read instruction of a device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FastRead {
+    /// Read opcode.
+    pub opcode: u8,
+    /// Dummy and mode clock cycles before data.
+    pub dummy_cycles: u8,
+}
+
+/// Way to set quad enable bit of a device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuadEnable {
+    /// Device has no quad enable bit.
+    None,
+    /// Bit 6 of status register 1.
+    Status1Bit6,
+    /// Bit 1 of status register 2, written together with status register 1.
+    Status2Bit1,
+    /// Bit 1 of status register 2, written with its own instruction.
+    Status2Bit1Separate,
+    /// Bit 7 of status register 2, accessed with `0x3F` and `0x3E`.
+    Status2Bit7,
+}
+
+/// Device parameters from the SFDP basic flash parameter table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Parameters {
+    /// Capacity in bytes.
+    pub size: u32,
+    /// Program page size in bytes.
+    pub page_size: u32,
+    /// Supported erase instructions, from smallest to largest; unsupported ones have size 0.
+    pub erase_types: [EraseType; 4],
+    /// Dual output (1-1-2) fast read, if supported.
+    pub dual_read: Option<FastRead>,
+    /// Quad output (1-1-4) fast read, if supported.
+    pub quad_read: Option<FastRead>,
+    /// Way to enable quad data lines.
+    pub quad_enable: QuadEnable,
+    /// Device only supports 4-byte addresses, or is larger than 16 MiB.
+    pub four_byte_address: bool,
+}
+
+impl Parameters {
+    /// Parses basic flash parameter table `dwords`.
+    #[inline]
+    pub const fn from_bfpt(dwords: &[u32]) -> Option<Parameters> {
+        if dwords.len() < 9 {
+            return None;
+        }
+        let density = dwords[1];
+        let size = if density & (1 << 31) == 0 {
+            (density / 8) + 1
+        } else {
+            let n = density & 0x7FFF_FFFF;
+            if n < 3 || n >= 35 {
+                return None;
+            }
+            1 << (n - 3)
+        };
+        let mut erase_types = [EraseType { size: 0, opcode: 0 }; 4];
+        let mut i = 0;
+        while i < 4 {
+            let word = dwords[7 + i / 2] >> (16 * (i % 2));
+            let exponent = word & 0xFF;
+            if exponent != 0 && exponent < 32 {
+                erase_types[i] = EraseType {
+                    size: 1 << exponent,
+                    opcode: (word >> 8) as u8,
+                };
+            }
+            i += 1;
+        }
+        // unsupported types sort last
+        let mut i = 1;
+        while i < 4 {
+            let mut j = i;
+            while j > 0 && sort_key(erase_types[j]) < sort_key(erase_types[j - 1]) {
+                let t = erase_types[j];
+                erase_types[j] = erase_types[j - 1];
+                erase_types[j - 1] = t;
+                j -= 1;
+            }
+            i += 1;
+        }
+        let dual_read = if dwords[0] & (1 << 16) != 0 {
+            Some(fast_read(dwords[3]))
+        } else {
+            None
+        };
+        let quad_read = if dwords[0] & (1 << 22) != 0 {
+            Some(fast_read(dwords[2] >> 16))
+        } else {
+            None
+        };
+        let page_size = if dwords.len() >= 11 {
+            1 << ((dwords[10] >> 4) & 0xF)
+        } else {
+            256
+        };
+        let quad_enable = if dwords.len() >= 15 {
+            match (dwords[14] >> 20) & 0b111 {
+                2 => QuadEnable::Status1Bit6,
+                1 | 4 | 5 => QuadEnable::Status2Bit1,
+                3 => QuadEnable::Status2Bit7,
+                6 => QuadEnable::Status2Bit1Separate,
+                _ => QuadEnable::None,
+            }
+        } else {
+            QuadEnable::None
+        };
+        let four_byte_address = (dwords[0] >> 17) & 0b11 == 0b10 || size > (1 << 24);
+        Some(Parameters {
+            size,
+            page_size,
+            erase_types,
+            dual_read,
+            quad_read,
+            quad_enable,
+            four_byte_address,
+        })
+    }
+}
+
+/// Erase type size for sorting, unsupported types last.
+#[inline]
+const fn sort_key(erase: EraseType) -> u64 {
+    if erase.size == 0 {
+        u64::MAX
+    } else {
+        erase.size as u64
+    }
+}
+
+/// Parses a fast read descriptor of dummy cycles, mode cycles and opcode in the low 16 bits.
+#[inline]
+const fn fast_read(word: u32) -> FastRead {
+    FastRead {
+        opcode: (word >> 8) as u8,
+        dummy_cycles: ((word & 0x1F) + ((word >> 5) & 0b111)) as u8,
+    }
+}
+
+/// SFDP header signature, "SFDP" in little endian.
+const SFDP_SIGNATURE: u32 = 0x5044_4653;
+
+/// Locates basic flash parameter table from first 16 bytes of SFDP, returns its address and length in dwords.
+#[inline]
+const fn find_bfpt(header: &[u8; 16]) -> Option<(u32, usize)> {
+    let signature = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+    // first parameter header always describes basic flash parameter table, ID 0xFF00
+    if signature != SFDP_SIGNATURE || header[8] != 0x00 || header[15] != 0xFF {
+        return None;
+    }
+    let address = u32::from_le_bytes([header[12], header[13], header[14], 0]);
+    Some((address, header[11] as usize))
+}
+
+/// SPI-NOR controller configuration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Config {
+    /// Serial clock frequency in Hz.
+    pub frequency: u32,
+    /// Widest data bus to use for reads, if pads and device support it.
+    pub data_width: DataWidth,
+}
+
+impl Default for Config {
+    /// SPI-NOR configuration defaults to 25 MHz quad data lines.
+    #[inline]
+    fn default() -> Self {
+        Config {
+            frequency: 25_000_000,
+            data_width: DataWidth::Quad,
+        }
+    }
+}
+
+/// Managed SPI-NOR flash controller peripheral.
+pub struct SpiNor<T, PADS> {
+    nor: T,
+    pads: PADS,
+    data_width: DataWidth,
+}
+
+/// Extension trait to create SPI-NOR flash controllers from peripherals.
+pub trait SpiNorExt: AsRef<RegisterBlock> + Controlled + Sized {
+    /// Configures the peripheral as an SPI-NOR flash controller running on `pads`.
+    ///
+    /// Controller is clocked from the SPI controller clock.
+    #[inline]
+    fn spi_nor<PADS>(self, config: Config, pads: PADS, clocks: &Clocks) -> SpiNor<Self, PADS>
+    where
+        PADS: Pads,
+    {
+        reset::enable(&self);
+        let nor = self.as_ref();
+        let divider = clock_divider(clocks.spi().to_hz(), config.frequency);
+        unsafe {
+            nor.memory_map.write(0);
+            nor.control.modify(|w| w.set_clock_divider(divider));
+            nor.interrupt_enable.write(TRANSFER_DONE);
+            nor.interrupt_status.write(0);
+            nor.fifo_level.write(0);
+        }
+        let data_width = match config.data_width {
+            DataWidth::Quad if !PADS::QUAD => DataWidth::Dual,
+            width => width,
+        };
+        SpiNor {
+            nor: self,
+            pads,
+            data_width,
+        }
+    }
+}
+
+impl<T: AsRef<RegisterBlock>, PADS> SpiNor<T, PADS> {
+    /// Performs a raw transaction of `command`, `address_bytes` bytes of `address`, `dummy_cycles` clocks and `data`.
+    ///
+    /// Command and address phases always use a single data line; `width`
+    /// applies to data phase only.
+    #[inline]
+    pub fn transfer(
+        &mut self,
+        command: u8,
+        address: u32,
+        address_bytes: u8,
+        dummy_cycles: u8,
+        width: DataWidth,
+        data: Data,
+    ) {
+        let nor = self.nor.as_ref();
+        let mut control = Transaction::default()
+            .enable_command()
+            .set_address_bytes(address_bytes)
+            .set_data_width(width);
+        if dummy_cycles != 0 {
+            control = control.enable_fast_mode().set_dummy_cycles(dummy_cycles);
+        }
+        let (len, control) = match &data {
+            Data::None => (0, control),
+            Data::Read(buf) => (buf.len(), control.set_receive()),
+            Data::Write(buf) => (buf.len(), control.set_transmit()),
+        };
+        let address = address.to_be_bytes();
+        unsafe {
+            nor.fifo_level.write(0);
+            nor.interrupt_status.write(0);
+            nor.transaction_length.write(len as u32);
+            nor.fifo_data.write(command);
+            for byte in &address[4 - address_bytes as usize..] {
+                nor.fifo_data.write(*byte);
+            }
+            nor.transaction.write(control.start());
+        }
+        match data {
+            Data::None => {}
+            Data::Read(buf) => {
+                for chunk in buf.chunks_mut(FIFO_DEPTH) {
+                    while (nor.fifo_level.read() as usize) < chunk.len() {
+                        core::hint::spin_loop();
+                    }
+                    for byte in chunk {
+                        *byte = nor.fifo_data.read();
+                    }
+                }
+            }
+            Data::Write(buf) => {
+                for chunk in buf.chunks(FIFO_DEPTH) {
+                    while nor.fifo_level.read() != 0 {
+                        core::hint::spin_loop();
+                    }
+                    for byte in chunk {
+                        unsafe { nor.fifo_data.write(*byte) };
+                    }
+                }
+            }
+        }
+        while nor.interrupt_status.read() & TRANSFER_DONE == 0 {
+            core::hint::spin_loop();
+        }
+        unsafe { nor.interrupt_status.write(0) };
+    }
+    /// Resets the device to its power-on state.
+    #[inline]
+    pub fn reset_device(&mut self) {
+        self.command(opcode::RESET_ENABLE);
+        self.command(opcode::RESET);
+        self.wait_ready();
+    }
+    /// Reads JEDEC manufacturer ID, memory type and capacity bytes.
+    #[inline]
+    pub fn read_jedec_id(&mut self) -> [u8; 3] {
+        let mut id = [0; 3];
+        self.transfer(
+            opcode::READ_JEDEC_ID,
+            0,
+            0,
+            0,
+            DataWidth::Single,
+            Data::Read(&mut id),
+        );
+        id
+    }
+    /// Reads Serial Flash Discoverable Parameters starting at `address`.
+    #[inline]
+    pub fn read_sfdp(&mut self, address: u32, buf: &mut [u8]) {
+        self.transfer(
+            opcode::READ_SFDP,
+            address,
+            3,
+            8,
+            DataWidth::Single,
+            Data::Read(buf),
+        );
+    }
+    /// Reads status register 1.
+    #[inline]
+    pub fn read_status(&mut self) -> u8 {
+        self.read_register(opcode::READ_STATUS)
+    }
+    /// Waits for program or erase in progress to finish.
+    #[inline]
+    pub fn wait_ready(&mut self) {
+        while self.read_status() & STATUS_BUSY != 0 {
+            core::hint::spin_loop();
+        }
+    }
+    /// Sets write enable latch before program, erase or register write.
+    #[inline]
+    pub fn write_enable(&mut self) {
+        self.command(opcode::WRITE_ENABLE);
+    }
+    /// Data lines used for reads.
+    #[inline]
+    pub fn data_width(&self) -> DataWidth {
+        self.data_width
+    }
+    /// Release controller instance and return its peripheral and pads, turning its clocks off.
+    #[inline]
+    pub fn free(self) -> (T, PADS)
+    where
+        T: Controlled,
+    {
+        unsafe { self.nor.as_ref().interrupt_enable.write(0) };
+        reset::disable(&self.nor);
+        (self.nor, self.pads)
+    }
+    #[inline]
+    fn command(&mut self, opcode: u8) {
+        self.transfer(opcode, 0, 0, 0, DataWidth::Single, Data::None);
+    }
+    #[inline]
+    fn read_register(&mut self, opcode: u8) -> u8 {
+        let mut value = [0];
+        self.transfer(opcode, 0, 0, 0, DataWidth::Single, Data::Read(&mut value));
+        value[0]
+    }
+    #[inline]
+    fn write_registers(&mut self, opcode: u8, values: &[u8]) {
+        self.write_enable();
+        self.transfer(opcode, 0, 0, 0, DataWidth::Single, Data::Write(values));
+        self.wait_ready();
+    }
+}
+
+/// Serial clock divider for `frequency` from controller clock `source`, rounding frequency down.
+#[inline]
+const fn clock_divider(source: u32, frequency: u32) -> u16 {
+    // serial clock is source / (2 * (divider + 1))
+    let n = source.div_ceil(2 * frequency);
+    if n == 0 {
+        0
+    } else if n > 0x800 {
+        0x7FF
+    } else {
+        (n - 1) as u16
+    }
+}
+
+/// Smallest erase size supported by `NorFlash`, as reported by `embedded-storage`.
+pub const SECTOR_SIZE: u32 = 4096;
+
+/// NOR flash device on an SPI-NOR controller.
+pub struct NorFlash<T, PADS> {
+    nor: SpiNor<T, PADS>,
+    id: [u8; 3],
+    parameters: Parameters,
+    read: (FastRead, DataWidth),
+    address_bytes: u8,
+}
+
+impl<T: AsRef<RegisterBlock>, PADS> NorFlash<T, PADS> {
+    /// Resets and identifies a device, discovering its parameters through SFDP.
+    ///
+    /// Quad data lines are enabled on the device if configured and supported.
+    #[inline]
+    pub fn new(mut nor: SpiNor<T, PADS>) -> Result<Self, Error> {
+        nor.reset_device();
+        let mut header = [0; 16];
+        nor.read_sfdp(0, &mut header);
+        let (address, len) = find_bfpt(&header).ok_or(Error::NoSfdp)?;
+        let mut dwords = [0u32; 16];
+        let len = len.min(dwords.len());
+        for (i, dword) in dwords[..len].iter_mut().enumerate() {
+            let mut bytes = [0; 4];
+            nor.read_sfdp(address + 4 * i as u32, &mut bytes);
+            *dword = u32::from_le_bytes(bytes);
+        }
+        let parameters = Parameters::from_bfpt(&dwords[..len]).ok_or(Error::NoSfdp)?;
+        Self::with_parameters(nor, parameters)
+    }
+    /// Resets a device with given parameters.
+    ///
+    /// Quad data lines are enabled on the device if configured and supported.
+    /// Fails if the smallest erase size is not `SECTOR_SIZE`.
+    #[inline]
+    pub fn with_parameters(
+        mut nor: SpiNor<T, PADS>,
+        parameters: Parameters,
+    ) -> Result<Self, Error> {
+        if parameters.erase_types[0].size != SECTOR_SIZE {
+            return Err(Error::UnsupportedEraseSize);
+        }
+        nor.reset_device();
+        let id = nor.read_jedec_id();
+        let single = (
+            FastRead {
+                opcode: opcode::FAST_READ,
+                dummy_cycles: 8,
+            },
+            DataWidth::Single,
+        );
+        let read = match (nor.data_width, parameters.quad_read, parameters.dual_read) {
+            (DataWidth::Quad, Some(quad), _) => {
+                enable_quad(&mut nor, parameters.quad_enable);
+                (quad, DataWidth::Quad)
+            }
+            (DataWidth::Quad | DataWidth::Dual, _, Some(dual)) => (dual, DataWidth::Dual),
+            _ => single,
+        };
+        let address_bytes = if parameters.four_byte_address {
+            nor.command(opcode::ENTER_4_BYTE_ADDRESS);
+            4
+        } else {
+            3
+        };
+        Ok(NorFlash {
+            nor,
+            id,
+            parameters,
+            read,
+            address_bytes,
+        })
+    }
+    /// JEDEC ID bytes.
+    #[inline]
+    pub fn id(&self) -> [u8; 3] {
+        self.id
+    }
+    /// Device parameters.
+    #[inline]
+    pub fn parameters(&self) -> Parameters {
+        self.parameters
+    }
+    /// Capacity in bytes.
+    #[inline]
+    pub fn capacity(&self) -> u32 {
+        self.parameters.size
+    }
+    /// Reads `buf.len()` bytes starting at `address`.
+    #[inline]
+    pub fn read(&mut self, address: u32, buf: &mut [u8]) -> Result<(), Error> {
+        self.check_range(address, buf.len())?;
+        let (read, width) = self.read;
+        self.nor.transfer(
+            read.opcode,
+            address,
+            self.address_bytes,
+            read.dummy_cycles,
+            width,
+            Data::Read(buf),
+        );
+        Ok(())
+    }
+    /// Programs `data` starting at `address`, splitting it at page boundaries.
+    ///
+    /// Programming only clears bits; the range should be erased first.
+    #[inline]
+    pub fn write(&mut self, mut address: u32, mut data: &[u8]) -> Result<(), Error> {
+        self.check_range(address, data.len())?;
+        let page_size = self.parameters.page_size;
+        while !data.is_empty() {
+            let len = data.len().min((page_size - address % page_size) as usize);
+            self.nor.write_enable();
+            self.nor.transfer(
+                opcode::PAGE_PROGRAM,
+                address,
+                self.address_bytes,
+                0,
+                DataWidth::Single,
+                Data::Write(&data[..len]),
+            );
+            self.nor.wait_ready();
+            address += len as u32;
+            data = &data[len..];
+        }
+        Ok(())
+    }
+    /// Erases range from `from` to `to`, using largest erase instructions that fit.
+    ///
+    /// Both ends must be aligned to the smallest erase size.
+    #[inline]
+    pub fn erase(&mut self, from: u32, to: u32) -> Result<(), Error> {
+        if from > to || to > self.parameters.size {
+            return Err(Error::OutOfBounds);
+        }
+        let smallest = self.parameters.erase_types[0].size;
+        if smallest == 0 || !from.is_multiple_of(smallest) || !to.is_multiple_of(smallest) {
+            return Err(Error::NotAligned);
+        }
+        let mut address = from;
+        while address < to {
+            let erase = select_erase(&self.parameters.erase_types, address, to - address);
+            self.nor.write_enable();
+            self.nor.transfer(
+                erase.opcode,
+                address,
+                self.address_bytes,
+                0,
+                DataWidth::Single,
+                Data::None,
+            );
+            self.nor.wait_ready();
+            address += erase.size;
+        }
+        Ok(())
+    }
+    /// Release device and return its controller.
+    #[inline]
+    pub fn free(self) -> SpiNor<T, PADS> {
+        self.nor
+    }
+    #[inline]
+    fn check_range(&self, address: u32, len: usize) -> Result<(), Error> {
+        if address as usize + len > self.parameters.size as usize {
+            return Err(Error::OutOfBounds);
+        }
+        Ok(())
+    }
+}
+
+/// Sets quad enable bit of a device.
+#[inline]
+fn enable_quad<T: AsRef<RegisterBlock>, PADS>(nor: &mut SpiNor<T, PADS>, quad_enable: QuadEnable) {
+    match quad_enable {
+        QuadEnable::None => {}
+        QuadEnable::Status1Bit6 => {
+            let status = nor.read_status();
+            nor.write_registers(opcode::WRITE_STATUS, &[status | (1 << 6)]);
+        }
+        QuadEnable::Status2Bit1 => {
+            let status = nor.read_status();
+            let status2 = nor.read_register(opcode::READ_STATUS2);
+            nor.write_registers(opcode::WRITE_STATUS, &[status, status2 | (1 << 1)]);
+        }
+        QuadEnable::Status2Bit1Separate => {
+            let status2 = nor.read_register(opcode::READ_STATUS2);
+            nor.write_registers(opcode::WRITE_STATUS2, &[status2 | (1 << 1)]);
+        }
+        QuadEnable::Status2Bit7 => {
+            let status2 = nor.read_register(opcode::READ_STATUS2_ALT);
+            nor.write_registers(opcode::WRITE_STATUS2_ALT, &[status2 | (1 << 7)]);
+        }
+    }
+}
+
+/// Largest erase type aligned at `address` and no larger than `remaining` bytes.
+///
+/// Smallest erase type must be supported and divide both.
+#[inline]
+const fn select_erase(erase_types: &[EraseType; 4], address: u32, remaining: u32) -> EraseType {
+    let mut selected = erase_types[0];
+    let mut i = 1;
+    while i < 4 {
+        let erase = erase_types[i];
+        if erase.size != 0 && address.is_multiple_of(erase.size) && erase.size <= remaining {
+            selected = erase;
+        }
+        i += 1;
+    }
+    selected
+}
+
+impl nor_flash::NorFlashError for Error {
+    #[inline]
+    fn kind(&self) -> NorFlashErrorKind {
+        match self {
+            Error::NotAligned => NorFlashErrorKind::NotAligned,
+            Error::OutOfBounds => NorFlashErrorKind::OutOfBounds,
+            Error::NoSfdp | Error::UnsupportedEraseSize => NorFlashErrorKind::Other,
+        }
+    }
+}
+
+impl<T: AsRef<RegisterBlock>, PADS> nor_flash::ErrorType for NorFlash<T, PADS> {
+    type Error = Error;
+}
+
+impl<T: AsRef<RegisterBlock>, PADS> nor_flash::ReadNorFlash for NorFlash<T, PADS> {
+    const READ_SIZE: usize = 1;
+
+    #[inline]
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Error> {
+        NorFlash::read(self, offset, bytes)
+    }
+    #[inline]
+    fn capacity(&self) -> usize {
+        self.parameters.size as usize
+    }
+}
+
+impl<T: AsRef<RegisterBlock>, PADS> nor_flash::NorFlash for NorFlash<T, PADS> {
+    const WRITE_SIZE: usize = 1;
+    const ERASE_SIZE: usize = SECTOR_SIZE as usize;
+
+    #[inline]
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Error> {
+        NorFlash::erase(self, from, to)
+    }
+    #[inline]
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Error> {
+        NorFlash::write(self, offset, bytes)
+    }
+}
+
+/// Valid SPI-NOR pads.
+pub trait Pads {
+    /// Checks if this pad configuration includes write protect and hold lines for quad data.
+    const QUAD: bool;
+}
+
+impl<T1, T2, T3, T4> Pads
+    for (
+        Pad<T1, 21, SpiNorFunc>,
+        Pad<T2, 22, SpiNorFunc>,
+        Pad<T3, 24, SpiNorFunc>,
+        Pad<T4, 25, SpiNorFunc>,
+    )
+{
+    const QUAD: bool = false;
+}
+
+impl<T1, T2, T3, T4, T5, T6> Pads
+    for (
+        Pad<T1, 20, SpiNorFunc>,
+        Pad<T2, 21, SpiNorFunc>,
+        Pad<T3, 22, SpiNorFunc>,
+        Pad<T4, 23, SpiNorFunc>,
+        Pad<T5, 24, SpiNorFunc>,
+        Pad<T6, 25, SpiNorFunc>,
+    )
+{
+    const QUAD: bool = true;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        clock_divider, find_bfpt, select_erase, Control, DataWidth, EraseType, FastRead,
+        Parameters, QuadEnable, RegisterBlock, Transaction,
+    };
+    use memoffset::offset_of;
+
+    #[test]
+    fn struct_register_block_offset() {
+        assert_eq!(offset_of!(RegisterBlock, control), 0x00);
+        assert_eq!(offset_of!(RegisterBlock, ce_control), 0x04);
+        assert_eq!(offset_of!(RegisterBlock, delay_control), 0x08);
+        assert_eq!(offset_of!(RegisterBlock, memory_map), 0x0C);
+        assert_eq!(offset_of!(RegisterBlock, transaction), 0x10);
+        assert_eq!(offset_of!(RegisterBlock, transaction_length), 0x14);
+        assert_eq!(offset_of!(RegisterBlock, fifo_data), 0x18);
+        assert_eq!(offset_of!(RegisterBlock, fifo_level), 0x20);
+        assert_eq!(offset_of!(RegisterBlock, interrupt_status), 0x28);
+        assert_eq!(offset_of!(RegisterBlock, interrupt_enable), 0x2C);
+    }
+
+    #[test]
+    fn struct_transaction_functions() {
+        let val = Transaction::default()
+            .enable_command()
+            .set_address_bytes(3)
+            .set_data_width(DataWidth::Quad)
+            .enable_fast_mode()
+            .set_dummy_cycles(8)
+            .set_receive()
+            .start();
+        assert_eq!(val.0, 0x0008_8B29);
+        assert_eq!(val.address_bytes(), 3);
+        assert_eq!(val.data_width(), DataWidth::Quad);
+        assert_eq!(val.dummy_cycles(), 8);
+        assert!(val.is_busy() && val.is_receive());
+        assert!(val.set_transmit().is_transmit() && !val.set_transmit().is_receive());
+
+        let val = Control(0xFFFF_F000).set_clock_divider(9);
+        assert_eq!(val.0, 0xFFFF_F009);
+        assert_eq!(val.clock_divider(), 9);
+        assert_eq!(clock_divider(500_000_000, 25_000_000), 9);
+        assert_eq!(clock_divider(500_000_000, 100_000), 0x7FF);
+    }
+
+    #[test]
+    fn sfdp_basic_parameters() {
+        let header = [
+            0x53, 0x46, 0x44, 0x50, 0x06, 0x01, 0x00, 0xFF, 0x00, 0x06, 0x01, 0x10, 0x80, 0x00,
+            0x00, 0xFF,
+        ];
+        assert_eq!(find_bfpt(&header), Some((0x80, 16)));
+        assert_eq!(find_bfpt(&[0xFF; 16]), None);
+
+        // 128 Mbit device with 4K, 32K and 64K erase, quad enable in status register 2
+        let mut bfpt = [0u32; 16];
+        bfpt[..9].copy_from_slice(&[
+            0xFFF9_20E5,
+            0x07FF_FFFF,
+            0x6B08_EB44,
+            0xBB42_3B08,
+            0xFFFF_FFFE,
+            0xFF00_FFFF,
+            0xEB40_FFFF,
+            0x520F_200C,
+            0xFF00_D810,
+        ]);
+        bfpt[10] = 0x0000_0080;
+        bfpt[14] = 0x0040_0000;
+        let val = Parameters::from_bfpt(&bfpt).unwrap();
+        assert_eq!(val.size, 16 * 1024 * 1024);
+        assert_eq!(val.page_size, 256);
+        assert_eq!(
+            val.erase_types,
+            [
+                EraseType {
+                    size: 4096,
+                    opcode: 0x20
+                },
+                EraseType {
+                    size: 32768,
+                    opcode: 0x52
+                },
+                EraseType {
+                    size: 65536,
+                    opcode: 0xD8
+                },
+                EraseType { size: 0, opcode: 0 },
+            ]
+        );
+        assert_eq!(
+            val.quad_read,
+            Some(FastRead {
+                opcode: 0x6B,
+                dummy_cycles: 8
+            })
+        );
+        assert_eq!(
+            val.dual_read,
+            Some(FastRead {
+                opcode: 0x3B,
+                dummy_cycles: 8
+            })
+        );
+        assert_eq!(val.quad_enable, QuadEnable::Status2Bit1);
+        assert!(!val.four_byte_address);
+
+        // JESD216 first revision table has 9 dwords
+        let val = Parameters::from_bfpt(&bfpt[..9]).unwrap();
+        assert_eq!(val.quad_enable, QuadEnable::None);
+        assert_eq!(Parameters::from_bfpt(&bfpt[..8]), None);
+
+        let erase_types = Parameters::from_bfpt(&bfpt).unwrap().erase_types;
+        assert_eq!(select_erase(&erase_types, 0, 0x20000).size, 65536);
+        assert_eq!(select_erase(&erase_types, 0x8000, 0x18000).size, 32768);
+        assert_eq!(select_erase(&erase_types, 0x1000, 0x1F000).size, 4096);
+        assert_eq!(select_erase(&erase_types, 0x10000, 0x2000).size, 4096);
+    }
+}
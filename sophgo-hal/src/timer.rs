@@ -0,0 +1,334 @@
+//! Hardware timers.
+
+use crate::clock::Clocks;
+use crate::plic::Interrupt;
+use crate::reset::{self, Controlled};
+use embedded_hal::delay::DelayNs;
+use volatile_register::{RO, RW};
+
+/// Timer registers.
+#[repr(C)]
+pub struct RegisterBlock {
+    /// Registers of timer 0 to 7.
+    pub timers: [TimerRegisters; 8],
+    /// Interrupt status of all timers, after masking.
+    pub interrupt_status: RO<u32>,
+    /// Reading this register clears interrupts of all timers.
+    pub end_of_interrupt: RO<u32>,
+    /// Interrupt status of all timers, before masking.
+    pub raw_interrupt_status: RO<u32>,
+    /// Component version register.
+    pub component_version: RO<u32>,
+    /// Secondary load count of timer 0 to 7, used in PWM mode.
+    pub load_count2: [RW<u32>; 8],
+}
+
+/// Registers of a single timer.
+#[repr(C)]
+pub struct TimerRegisters {
+    /// Value loaded into the counter on start and on each reload.
+    pub load_count: RW<u32>,
+    /// Current value of the down counter.
+    pub current_value: RO<u32>,
+    /// Control register.
+    pub control: RW<Control>,
+    /// Reading this register clears interrupt of this timer.
+    pub end_of_interrupt: RO<u32>,
+    /// Interrupt status of this timer, after masking.
+    pub interrupt_status: RO<u32>,
+}
+
+/// Timer control register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct Control(u32);
+
+impl Control {
+    const ENABLE: u32 = 1 << 0;
+    const MODE: u32 = 1 << 1;
+    const INTERRUPT_MASK: u32 = 1 << 2;
+
+    /// Enable this timer.
+    #[inline]
+    pub const fn enable(self) -> Self {
+        Self(self.0 | Self::ENABLE)
+    }
+    /// Disable this timer.
+    #[inline]
+    pub const fn disable(self) -> Self {
+        Self(self.0 & !Self::ENABLE)
+    }
+    /// Check if this timer is enabled.
+    #[inline]
+    pub const fn is_enabled(self) -> bool {
+        self.0 & Self::ENABLE != 0
+    }
+    /// Set counter reload mode.
+    #[inline]
+    pub const fn set_mode(self, mode: Mode) -> Self {
+        Self((self.0 & !Self::MODE) | ((mode as u32) << 1))
+    }
+    /// Get counter reload mode.
+    #[inline]
+    pub const fn mode(self) -> Mode {
+        match (self.0 & Self::MODE) >> 1 {
+            0 => Mode::FreeRunning,
+            1 => Mode::UserDefined,
+            _ => unreachable!(),
+        }
+    }
+    /// Mask interrupt of this timer.
+    #[inline]
+    pub const fn mask_interrupt(self) -> Self {
+        Self(self.0 | Self::INTERRUPT_MASK)
+    }
+    /// Unmask interrupt of this timer.
+    #[inline]
+    pub const fn unmask_interrupt(self) -> Self {
+        Self(self.0 & !Self::INTERRUPT_MASK)
+    }
+    /// Check if interrupt of this timer is masked.
+    #[inline]
+    pub const fn is_interrupt_masked(self) -> bool {
+        self.0 & Self::INTERRUPT_MASK != 0
+    }
+}
+
+/// Counter reload mode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// Counter reloads from `0xFFFF_FFFF` when it reaches zero.
+    FreeRunning = 0,
+    /// Counter reloads from `load_count` when it reaches zero.
+    UserDefined = 1,
+}
+
+/// Managed timer peripheral.
+pub struct Timers<T> {
+    timer: T,
+    clock: u32,
+}
+
+/// Extension trait to create timer drivers from peripherals.
+pub trait TimerExt: AsRef<RegisterBlock> + Controlled + Sized {
+    /// Creates a timer driver, all timers stopped.
+    #[inline]
+    fn timer(self, clocks: &Clocks) -> Timers<Self> {
+        reset::enable(&self);
+        for timer in &self.as_ref().timers {
+            unsafe { timer.control.write(Control::default().mask_interrupt()) };
+        }
+        Timers {
+            timer: self,
+            clock: clocks.timer().to_hz(),
+        }
+    }
+}
+
+impl<T: AsRef<RegisterBlock>> Timers<T> {
+    /// Split into separate timers.
+    #[inline]
+    pub fn split(&mut self) -> Channels<'_> {
+        let timer = self.timer.as_ref();
+        let clock = self.clock;
+        Channels {
+            timer0: Timer { timer, clock },
+            timer1: Timer { timer, clock },
+            timer2: Timer { timer, clock },
+            timer3: Timer { timer, clock },
+            timer4: Timer { timer, clock },
+            timer5: Timer { timer, clock },
+            timer6: Timer { timer, clock },
+            timer7: Timer { timer, clock },
+        }
+    }
+    /// Release timer driver and return its peripheral, turning its clocks off.
+    #[inline]
+    pub fn free(self) -> T
+    where
+        T: Controlled,
+    {
+        for timer in &self.timer.as_ref().timers {
+            unsafe { timer.control.write(Control::default().mask_interrupt()) };
+        }
+        reset::disable(&self.timer);
+        self.timer
+    }
+}
+
+/// Separate timers of a timer peripheral.
+pub struct Channels<'a> {
+    /// Timer 0.
+    pub timer0: Timer<'a, 0>,
+    /// Timer 1.
+    pub timer1: Timer<'a, 1>,
+    /// Timer 2.
+    pub timer2: Timer<'a, 2>,
+    /// Timer 3.
+    pub timer3: Timer<'a, 3>,
+    /// Timer 4.
+    pub timer4: Timer<'a, 4>,
+    /// Timer 5.
+    pub timer5: Timer<'a, 5>,
+    /// Timer 6.
+    pub timer6: Timer<'a, 6>,
+    /// Timer 7.
+    pub timer7: Timer<'a, 7>,
+}
+
+/// Timer `N` of a timer peripheral.
+pub struct Timer<'a, const N: usize> {
+    timer: &'a RegisterBlock,
+    clock: u32,
+}
+
+impl<'a, const N: usize> Timer<'a, N> {
+    #[inline]
+    fn regs(&self) -> &TimerRegisters {
+        &self.timer.timers[N]
+    }
+    /// Counter clock frequency in Hz.
+    #[inline]
+    pub const fn frequency(&self) -> u32 {
+        self.clock
+    }
+    /// Interrupt source of this timer on the interrupt controller.
+    #[inline]
+    pub const fn interrupt(&self) -> Interrupt {
+        Interrupt::timer(N)
+    }
+    /// Starts counting down from `0xFFFF_FFFF`, wrapping around at zero.
+    #[inline]
+    pub fn start_free_running(&mut self) {
+        self.start(Mode::FreeRunning, u32::MAX);
+    }
+    /// Starts expiring every `ticks` counter clock cycles.
+    #[inline]
+    pub fn start_periodic(&mut self, ticks: u32) {
+        self.start(Mode::UserDefined, ticks);
+    }
+    /// Starts expiring once after `ticks` counter clock cycles; see `wait_oneshot`.
+    #[inline]
+    pub fn start_oneshot(&mut self, ticks: u32) {
+        self.start(Mode::UserDefined, ticks);
+    }
+    #[inline]
+    fn start(&mut self, mode: Mode, ticks: u32) {
+        let regs = self.regs();
+        unsafe {
+            regs.control.modify(|w| w.disable());
+            regs.load_count.write(ticks);
+            regs.end_of_interrupt.read();
+            regs.control.modify(|w| w.set_mode(mode).enable());
+        }
+    }
+    /// Stops this timer.
+    #[inline]
+    pub fn stop(&mut self) {
+        unsafe { self.regs().control.modify(|w| w.disable()) };
+    }
+    /// Current value of the down counter.
+    #[inline]
+    pub fn counter(&self) -> u32 {
+        self.regs().current_value.read()
+    }
+    /// Checks if the counter reached zero since last `clear_interrupt`.
+    #[inline]
+    pub fn is_expired(&self) -> bool {
+        self.timer.raw_interrupt_status.read() & (1 << N) != 0
+    }
+    /// Clears expiry flag and interrupt of this timer.
+    #[inline]
+    pub fn clear_interrupt(&mut self) {
+        self.regs().end_of_interrupt.read();
+    }
+    /// Blocks until the timer expires, then clears expiry flag.
+    #[inline]
+    pub fn wait(&mut self) {
+        while !self.is_expired() {
+            core::hint::spin_loop();
+        }
+        self.clear_interrupt();
+    }
+    /// Blocks until a timer started by `start_oneshot` expires, then stops it.
+    ///
+    /// Hardware has no one-shot mode and would reload the counter otherwise.
+    #[inline]
+    pub fn wait_oneshot(&mut self) {
+        self.wait();
+        self.stop();
+    }
+    /// Enable interrupt on expiry.
+    #[inline]
+    pub fn listen(&mut self) {
+        unsafe { self.regs().control.modify(|w| w.unmask_interrupt()) };
+    }
+    /// Disable interrupt on expiry.
+    #[inline]
+    pub fn unlisten(&mut self) {
+        unsafe { self.regs().control.modify(|w| w.mask_interrupt()) };
+    }
+}
+
+/// Converts nanoseconds into counter clock cycles, rounding up.
+#[inline]
+const fn ns_to_ticks(ns: u64, clock: u32) -> u64 {
+    (ns * clock as u64).div_ceil(1_000_000_000)
+}
+
+impl<'a, const N: usize> DelayNs for Timer<'a, N> {
+    #[inline]
+    fn delay_ns(&mut self, ns: u32) {
+        let mut ticks = ns_to_ticks(ns as u64, self.clock);
+        while ticks > 0 {
+            let chunk = ticks.min(u32::MAX as u64) as u32;
+            self.start_oneshot(chunk);
+            self.wait_oneshot();
+            ticks -= chunk as u64;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ns_to_ticks, Control, Mode, RegisterBlock, TimerRegisters};
+    use memoffset::offset_of;
+
+    #[test]
+    fn struct_register_block_offset() {
+        assert_eq!(offset_of!(RegisterBlock, timers), 0x00);
+        assert_eq!(offset_of!(TimerRegisters, load_count), 0x00);
+        assert_eq!(offset_of!(TimerRegisters, current_value), 0x04);
+        assert_eq!(offset_of!(TimerRegisters, control), 0x08);
+        assert_eq!(offset_of!(TimerRegisters, end_of_interrupt), 0x0C);
+        assert_eq!(offset_of!(TimerRegisters, interrupt_status), 0x10);
+        assert_eq!(offset_of!(RegisterBlock, interrupt_status), 0xA0);
+        assert_eq!(offset_of!(RegisterBlock, end_of_interrupt), 0xA4);
+        assert_eq!(offset_of!(RegisterBlock, raw_interrupt_status), 0xA8);
+        assert_eq!(offset_of!(RegisterBlock, component_version), 0xAC);
+        assert_eq!(offset_of!(RegisterBlock, load_count2), 0xB0);
+    }
+
+    #[test]
+    fn struct_control_functions() {
+        let val = Control::default()
+            .set_mode(Mode::UserDefined)
+            .mask_interrupt()
+            .enable();
+        assert_eq!(val.0, 0x7);
+        assert!(val.is_enabled());
+        assert!(val.is_interrupt_masked());
+        assert_eq!(val.mode(), Mode::UserDefined);
+        let val = val.set_mode(Mode::FreeRunning).unmask_interrupt().disable();
+        assert_eq!(val.0, 0x0);
+        assert_eq!(val.mode(), Mode::FreeRunning);
+    }
+
+    #[test]
+    fn delay_ticks_round_up() {
+        assert_eq!(ns_to_ticks(1_000, 25_000_000), 25);
+        assert_eq!(ns_to_ticks(1, 25_000_000), 1);
+        assert_eq!(ns_to_ticks(0, 25_000_000), 0);
+        assert_eq!(ns_to_ticks(u32::MAX as u64, 25_000_000), 107_374_183);
+    }
+}
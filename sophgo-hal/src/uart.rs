@@ -1,6 +1,9 @@
 //! Universal Asynchronous Receiver/Transmitter.
 
+use crate::clock::Clocks;
+use crate::dma;
 use crate::pad::{Pad, UartFunc};
+use crate::reset::{self, Controlled};
 use volatile_register::{RO, RW, WO};
 
 /// Universal Asynchoronous Receiver/Transmitter registers.
@@ -202,28 +205,34 @@ pub struct Serial<T, PADS> {
     pads: PADS,
 }
 
-impl<T, PADS> Serial<T, PADS> {
-    /// Release serial instance and return its peripheral and pads.
+impl<T: AsRef<RegisterBlock> + Controlled, PADS> Serial<T, PADS> {
+    /// Release serial instance and return its peripheral and pads, turning its clocks off.
     #[inline]
     pub fn free(self) -> (T, PADS) {
+        // FIFO enable, DMA mode 0
+        unsafe { self.uart.as_ref().fcr_iir.write(0x01) };
+        reset::disable(&self.uart);
         (self.uart, self.pads)
     }
 }
 
-pub trait UartExt<const I: usize>: AsRef<RegisterBlock> + Sized {
+pub trait UartExt<const I: usize>: AsRef<RegisterBlock> + Controlled + Sized {
+    /// Configures the peripheral as a serial port running on `pads`.
     #[inline]
-    fn serial<PADS>(self, config: Config, pads: PADS) -> Serial<Self, PADS>
+    fn serial<PADS>(self, config: Config, pads: PADS, clocks: &Clocks) -> Serial<Self, PADS>
     where
         PADS: Pads<I>,
     {
+        reset::enable(&self);
         let uart = self.as_ref();
-        // TODO clock source and baudrate
-        let interval = 14;
+        let interval = divisor(clocks.uart().to_hz(), config.baudrate);
         unsafe {
             uart.lcr.modify(|w| w.enable_divisor_latch_access());
-            uart.lpdll.write(interval & 0xff);
-            uart.lpdlh.write((interval >> 8) & 0xff);
+            uart.rbr_thr_dll.write(interval & 0xff);
+            uart.ier_dlh.write((interval >> 8) & 0xff);
             uart.lcr.modify(|w| w.disable_divisor_latch_access());
+            // FIFO enable, clear receive and transmit FIFOs
+            uart.fcr_iir.write(0x07);
         }
 
         unsafe {
@@ -238,6 +247,17 @@ pub trait UartExt<const I: usize>: AsRef<RegisterBlock> + Sized {
     }
 }
 
+/// Divisor latch value for `baudrate` with 16x oversampling, rounded to nearest.
+#[inline]
+const fn divisor(clock: u32, baudrate: u32) -> u32 {
+    let divisor = (clock + 8 * baudrate) / (16 * baudrate);
+    if divisor == 0 {
+        1
+    } else {
+        divisor
+    }
+}
+
 impl embedded_io::Error for Error {
     #[inline(always)]
     fn kind(&self) -> embedded_io::ErrorKind {
@@ -249,6 +269,27 @@ impl<T: AsRef<RegisterBlock>, PADS> embedded_io::ErrorType for Serial<T, PADS> {
     type Error = Error;
 }
 
+impl<T: AsRef<RegisterBlock>, PADS> Serial<T, PADS> {
+    /// Enables FIFOs in DMA mode 1 and returns the data port.
+    ///
+    /// Transfers use [`dma::Request::uart_tx`] or [`dma::Request::uart_rx`] of this instance.
+    #[inline]
+    pub fn dma_port(&mut self) -> dma::Port<u8> {
+        let uart = self.uart.as_ref();
+        unsafe {
+            // FIFO enable, DMA mode 1, receive trigger at 1 byte, transmit trigger at empty
+            uart.fcr_iir.write(0x09);
+            dma::Port::new(&uart.rbr_thr_dll as *const _ as usize)
+        }
+    }
+    /// Stops DMA handshakes, keeping FIFOs enabled in DMA mode 0.
+    #[inline]
+    pub fn disable_dma(&mut self) {
+        // FIFO enable, DMA mode 0
+        unsafe { self.uart.as_ref().fcr_iir.write(0x01) };
+    }
+}
+
 impl<T: AsRef<RegisterBlock>, PADS> embedded_io::Write for Serial<T, PADS> {
     #[inline]
     fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
@@ -283,6 +324,8 @@ impl<T: AsRef<RegisterBlock>, PADS> embedded_io::Read for Serial<T, PADS> {
 /// Serial configuration.
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Config {
+    /// Baudrate in bits per second.
+    pub baudrate: u32,
     /// Parity settings.
     pub parity: Parity,
     /// Serial stop bits.
@@ -292,10 +335,11 @@ pub struct Config {
 }
 
 impl Default for Config {
-    /// Serial configuration defaults to 8-bit word, no parity check, 1 stop bit, LSB first.
+    /// Serial configuration defaults to 115200 bps, 8-bit word, no parity check, 1 stop bit, LSB first.
     #[inline]
     fn default() -> Self {
         Config {
+            baudrate: 115_200,
             parity: Parity::None,
             stop_bits: StopBits::One,
             word_length: WordLength::Eight,
@@ -0,0 +1,1265 @@
+//! USB 2.0 on-the-go controller in device mode.
+//!
+//! [`Usb`] implements [`usb_device::bus::UsbBus`] in slave mode, moving packets through
+//! the controller FIFOs by the CPU. Received packets stay in the shared receive FIFO
+//! until the class reads them, so no packet buffers are kept in memory.
+
+use crate::reset::{self, Controlled};
+use usb_device::{
+    bus::PollResult,
+    endpoint::{EndpointAddress, EndpointType},
+    UsbDirection, UsbError,
+};
+use volatile_register::{RO, RW};
+
+/// Number of device endpoints in each direction, including endpoint 0.
+pub const ENDPOINTS: usize = 8;
+
+/// Size of the shared receive FIFO in words.
+const RX_FIFO_WORDS: u16 = 256;
+
+/// USB on-the-go controller registers.
+#[repr(C)]
+pub struct RegisterBlock {
+    /// On-the-go control and status register.
+    pub otg_control: RW<OtgControl>,
+    /// On-the-go interrupt register.
+    pub otg_interrupt: RW<u32>,
+    /// AHB configuration register.
+    pub ahb_config: RW<AhbConfig>,
+    /// USB configuration register.
+    pub usb_config: RW<UsbConfig>,
+    /// Reset control register.
+    pub reset_control: RW<ResetControl>,
+    /// Core interrupt status register; write 1 to clear.
+    pub interrupt_status: RW<Interrupts>,
+    /// Core interrupt mask register.
+    pub interrupt_mask: RW<Interrupts>,
+    /// Receive status read register, peeks top of receive FIFO.
+    pub rx_status_read: RO<RxStatus>,
+    /// Receive status pop register, pops top of receive FIFO.
+    pub rx_status_pop: RO<RxStatus>,
+    /// Receive FIFO size register in words.
+    pub rx_fifo_size: RW<u32>,
+    /// Endpoint 0 transmit FIFO size register.
+    pub tx_fifo0_size: RW<FifoSize>,
+    /// Non-periodic transmit FIFO status register.
+    pub nonperiodic_tx_status: RO<u32>,
+    _reserved0: [u8; 0x10],
+    /// Synopsys identification register.
+    pub synopsys_id: RO<u32>,
+    /// Hardware configuration registers 1 to 4.
+    pub hardware_config: [RO<u32>; 4],
+    _reserved1: [u8; 0xB0],
+    /// Transmit FIFO size registers of endpoint 1 to 15.
+    pub tx_fifo_size: [RW<FifoSize>; 15],
+    _reserved2: [u8; 0x6C0],
+    /// Device configuration register.
+    pub device_config: RW<DeviceConfig>,
+    /// Device control register.
+    pub device_control: RW<DeviceControl>,
+    /// Device status register.
+    pub device_status: RO<u32>,
+    _reserved3: [u8; 0x4],
+    /// IN endpoint common interrupt mask register.
+    pub in_interrupt_mask: RW<u32>,
+    /// OUT endpoint common interrupt mask register.
+    pub out_interrupt_mask: RW<u32>,
+    /// All endpoints interrupt register; IN endpoints in bits 15:0, OUT endpoints in bits 31:16.
+    pub endpoint_interrupt: RO<u32>,
+    /// All endpoints interrupt mask register.
+    pub endpoint_interrupt_mask: RW<u32>,
+    _reserved4: [u8; 0xE0],
+    /// IN endpoint registers.
+    pub in_endpoints: [InEndpoint; 16],
+    /// OUT endpoint registers.
+    pub out_endpoints: [OutEndpoint; 16],
+    _reserved5: [u8; 0x100],
+    /// Power and clock gating control register.
+    pub power_clock: RW<u32>,
+    _reserved6: [u8; 0x1FC],
+    /// Data FIFO ports; writes go to transmit FIFO `n`, reads of any port pop receive FIFO.
+    pub fifos: [Fifo; 16],
+}
+
+/// IN endpoint registers.
+#[repr(C)]
+pub struct InEndpoint {
+    /// Control register.
+    pub control: RW<EndpointControl>,
+    _reserved0: [u8; 0x4],
+    /// Interrupt register; write 1 to clear.
+    pub interrupt: RW<u32>,
+    _reserved1: [u8; 0x4],
+    /// Transfer size register.
+    pub transfer_size: RW<TransferSize>,
+    /// DMA address register.
+    pub dma: RW<u32>,
+    /// Transmit FIFO status register, free space in words.
+    pub tx_fifo_status: RO<u32>,
+    _reserved2: [u8; 0x4],
+}
+
+/// OUT endpoint registers.
+#[repr(C)]
+pub struct OutEndpoint {
+    /// Control register.
+    pub control: RW<EndpointControl>,
+    _reserved0: [u8; 0x4],
+    /// Interrupt register; write 1 to clear.
+    pub interrupt: RW<u32>,
+    _reserved1: [u8; 0x4],
+    /// Transfer size register.
+    pub transfer_size: RW<TransferSize>,
+    /// DMA address register.
+    pub dma: RW<u32>,
+    _reserved2: [u8; 0x8],
+}
+
+/// Data FIFO port.
+#[repr(C)]
+pub struct Fifo {
+    /// Data register.
+    pub data: RW<u32>,
+    _reserved0: [u8; 0xFFC],
+}
+
+/// On-the-go control and status register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct OtgControl(u32);
+
+impl OtgControl {
+    const B_VALID_OVERRIDE: u32 = 0b11 << 6;
+
+    /// Report B-session valid regardless of VBUS sensing.
+    #[inline]
+    pub const fn override_b_valid(self) -> Self {
+        Self(self.0 | Self::B_VALID_OVERRIDE)
+    }
+    /// Use VBUS sensing for B-session valid.
+    #[inline]
+    pub const fn sense_b_valid(self) -> Self {
+        Self(self.0 & !Self::B_VALID_OVERRIDE)
+    }
+    /// Check if B-session valid is overridden.
+    #[inline]
+    pub const fn is_b_valid_overridden(self) -> bool {
+        self.0 & Self::B_VALID_OVERRIDE == Self::B_VALID_OVERRIDE
+    }
+}
+
+/// AHB configuration register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct AhbConfig(u32);
+
+impl AhbConfig {
+    const GLOBAL_INTERRUPT: u32 = 1 << 0;
+    const DMA: u32 = 1 << 5;
+    const TX_EMPTY_LEVEL: u32 = 1 << 7;
+
+    /// Enable interrupt output of the controller.
+    #[inline]
+    pub const fn enable_global_interrupt(self) -> Self {
+        Self(self.0 | Self::GLOBAL_INTERRUPT)
+    }
+    /// Disable interrupt output of the controller.
+    #[inline]
+    pub const fn disable_global_interrupt(self) -> Self {
+        Self(self.0 & !Self::GLOBAL_INTERRUPT)
+    }
+    /// Check if interrupt output is enabled.
+    #[inline]
+    pub const fn is_global_interrupt_enabled(self) -> bool {
+        self.0 & Self::GLOBAL_INTERRUPT != 0
+    }
+    /// Move packets by internal DMA instead of CPU FIFO accesses.
+    #[inline]
+    pub const fn enable_dma(self) -> Self {
+        Self(self.0 | Self::DMA)
+    }
+    /// Move packets by CPU FIFO accesses.
+    #[inline]
+    pub const fn disable_dma(self) -> Self {
+        Self(self.0 & !Self::DMA)
+    }
+    /// Check if internal DMA is enabled.
+    #[inline]
+    pub const fn is_dma_enabled(self) -> bool {
+        self.0 & Self::DMA != 0
+    }
+    /// Transmit FIFO empty interrupt fires when FIFO is completely empty.
+    #[inline]
+    pub const fn set_tx_empty_completely(self) -> Self {
+        Self(self.0 | Self::TX_EMPTY_LEVEL)
+    }
+}
+
+/// USB configuration register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct UsbConfig(u32);
+
+impl UsbConfig {
+    const PHY_16BIT: u32 = 1 << 3;
+    const TURNAROUND: u32 = 0xF << 10;
+    const FORCE_HOST: u32 = 1 << 29;
+    const FORCE_DEVICE: u32 = 1 << 30;
+
+    /// Use 8-bit UTMI+ interface to the PHY.
+    #[inline]
+    pub const fn set_phy_8bit(self) -> Self {
+        Self(self.0 & !Self::PHY_16BIT)
+    }
+    /// Check if UTMI+ interface is 16 bits wide.
+    #[inline]
+    pub const fn is_phy_16bit(self) -> bool {
+        self.0 & Self::PHY_16BIT != 0
+    }
+    /// Set USB turnaround time in PHY clocks.
+    #[inline]
+    pub const fn set_turnaround(self, clocks: u8) -> Self {
+        Self((self.0 & !Self::TURNAROUND) | (((clocks as u32) << 10) & Self::TURNAROUND))
+    }
+    /// Get USB turnaround time in PHY clocks.
+    #[inline]
+    pub const fn turnaround(self) -> u8 {
+        ((self.0 & Self::TURNAROUND) >> 10) as u8
+    }
+    /// Force device mode regardless of ID pin.
+    #[inline]
+    pub const fn force_device_mode(self) -> Self {
+        Self((self.0 & !Self::FORCE_HOST) | Self::FORCE_DEVICE)
+    }
+    /// Check if device mode is forced.
+    #[inline]
+    pub const fn is_device_mode_forced(self) -> bool {
+        self.0 & Self::FORCE_DEVICE != 0
+    }
+}
+
+/// Reset control register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct ResetControl(u32);
+
+impl ResetControl {
+    const CORE_RESET: u32 = 1 << 0;
+    const RX_FLUSH: u32 = 1 << 4;
+    const TX_FLUSH: u32 = 1 << 5;
+    const TX_FIFO: u32 = 0x1F << 6;
+    const AHB_IDLE: u32 = 1 << 31;
+
+    /// Request core soft reset.
+    #[inline]
+    pub const fn reset_core(self) -> Self {
+        Self(self.0 | Self::CORE_RESET)
+    }
+    /// Check if core soft reset is in progress.
+    #[inline]
+    pub const fn is_core_resetting(self) -> bool {
+        self.0 & Self::CORE_RESET != 0
+    }
+    /// Request receive FIFO flush.
+    #[inline]
+    pub const fn flush_rx_fifo(self) -> Self {
+        Self(self.0 | Self::RX_FLUSH)
+    }
+    /// Request flush of all transmit FIFOs.
+    #[inline]
+    pub const fn flush_tx_fifos(self) -> Self {
+        Self((self.0 & !Self::TX_FIFO) | Self::TX_FLUSH | (0x10 << 6))
+    }
+    /// Check if any FIFO flush is in progress.
+    #[inline]
+    pub const fn is_flushing(self) -> bool {
+        self.0 & (Self::RX_FLUSH | Self::TX_FLUSH) != 0
+    }
+    /// Check if AHB master is idle.
+    #[inline]
+    pub const fn is_ahb_idle(self) -> bool {
+        self.0 & Self::AHB_IDLE != 0
+    }
+}
+
+/// Core interrupt status and mask register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct Interrupts(u32);
+
+impl Interrupts {
+    /// Enable or clear an interrupt, depending on register.
+    #[inline]
+    pub const fn set_interrupt(self, interrupt: Interrupt) -> Self {
+        Self(self.0 | (1 << interrupt as u32))
+    }
+    /// Remove an interrupt from this value.
+    #[inline]
+    pub const fn clear_interrupt(self, interrupt: Interrupt) -> Self {
+        Self(self.0 & !(1 << interrupt as u32))
+    }
+    /// Check if an interrupt is enabled or pending.
+    #[inline]
+    pub const fn has_interrupt(self, interrupt: Interrupt) -> bool {
+        self.0 & (1 << interrupt as u32) != 0
+    }
+}
+
+/// Core interrupt sources in device mode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interrupt {
+    /// Start of frame.
+    StartOfFrame = 3,
+    /// Receive FIFO not empty.
+    RxFifoLevel = 4,
+    /// Bus idle for 3 ms, suspend to follow.
+    EarlySuspend = 10,
+    /// Bus suspended.
+    Suspend = 11,
+    /// Bus reset.
+    Reset = 12,
+    /// Speed enumeration after bus reset done.
+    EnumerationDone = 13,
+    /// An IN endpoint has a pending interrupt.
+    InEndpoint = 18,
+    /// An OUT endpoint has a pending interrupt.
+    OutEndpoint = 19,
+    /// Resume signaling detected.
+    Wakeup = 31,
+}
+
+/// Receive status register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct RxStatus(u32);
+
+impl RxStatus {
+    const ENDPOINT: u32 = 0xF;
+    const BYTE_COUNT: u32 = 0x7FF << 4;
+    const PACKET_STATUS: u32 = 0xF << 17;
+
+    /// Endpoint number of this entry.
+    #[inline]
+    pub const fn endpoint(self) -> usize {
+        (self.0 & Self::ENDPOINT) as usize
+    }
+    /// Number of data bytes of this entry.
+    #[inline]
+    pub const fn byte_count(self) -> usize {
+        ((self.0 & Self::BYTE_COUNT) >> 4) as usize
+    }
+    /// Kind of this entry.
+    #[inline]
+    pub const fn packet_status(self) -> PacketStatus {
+        match (self.0 & Self::PACKET_STATUS) >> 17 {
+            2 => PacketStatus::OutData,
+            3 => PacketStatus::OutComplete,
+            4 => PacketStatus::SetupComplete,
+            6 => PacketStatus::SetupData,
+            _ => PacketStatus::GlobalOutNak,
+        }
+    }
+}
+
+/// Receive FIFO entry kinds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PacketStatus {
+    /// Global OUT NAK effective.
+    GlobalOutNak,
+    /// OUT data packet received.
+    OutData,
+    /// OUT transfer completed.
+    OutComplete,
+    /// SETUP stage completed.
+    SetupComplete,
+    /// SETUP data packet received.
+    SetupData,
+}
+
+/// Transmit FIFO size register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct FifoSize(u32);
+
+impl FifoSize {
+    /// FIFO of `depth` words starting at word `start` of FIFO memory.
+    #[inline]
+    pub const fn new(start: u16, depth: u16) -> Self {
+        Self(((depth as u32) << 16) | start as u32)
+    }
+    /// Start address in words.
+    #[inline]
+    pub const fn start(self) -> u16 {
+        self.0 as u16
+    }
+    /// Depth in words.
+    #[inline]
+    pub const fn depth(self) -> u16 {
+        (self.0 >> 16) as u16
+    }
+}
+
+/// Device configuration register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct DeviceConfig(u32);
+
+impl DeviceConfig {
+    const SPEED: u32 = 0x3;
+    const ADDRESS: u32 = 0x7F << 4;
+
+    /// Set device speed.
+    #[inline]
+    pub const fn set_speed(self, speed: Speed) -> Self {
+        Self((self.0 & !Self::SPEED) | speed as u32)
+    }
+    /// Get device speed.
+    #[inline]
+    pub const fn speed(self) -> Speed {
+        match self.0 & Self::SPEED {
+            0 => Speed::High,
+            _ => Speed::Full,
+        }
+    }
+    /// Set device address.
+    #[inline]
+    pub const fn set_address(self, address: u8) -> Self {
+        Self((self.0 & !Self::ADDRESS) | (((address as u32) << 4) & Self::ADDRESS))
+    }
+    /// Get device address.
+    #[inline]
+    pub const fn address(self) -> u8 {
+        ((self.0 & Self::ADDRESS) >> 4) as u8
+    }
+}
+
+/// Device control register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct DeviceControl(u32);
+
+impl DeviceControl {
+    const REMOTE_WAKEUP: u32 = 1 << 0;
+    const SOFT_DISCONNECT: u32 = 1 << 1;
+
+    /// Signal remote wakeup to the host.
+    #[inline]
+    pub const fn set_remote_wakeup(self) -> Self {
+        Self(self.0 | Self::REMOTE_WAKEUP)
+    }
+    /// Stop remote wakeup signaling.
+    #[inline]
+    pub const fn clear_remote_wakeup(self) -> Self {
+        Self(self.0 & !Self::REMOTE_WAKEUP)
+    }
+    /// Detach from the bus.
+    #[inline]
+    pub const fn disconnect(self) -> Self {
+        Self(self.0 | Self::SOFT_DISCONNECT)
+    }
+    /// Attach to the bus.
+    #[inline]
+    pub const fn connect(self) -> Self {
+        Self(self.0 & !Self::SOFT_DISCONNECT)
+    }
+    /// Check if device is detached from the bus.
+    #[inline]
+    pub const fn is_disconnected(self) -> bool {
+        self.0 & Self::SOFT_DISCONNECT != 0
+    }
+}
+
+/// Endpoint control register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct EndpointControl(u32);
+
+impl EndpointControl {
+    const MAX_PACKET_SIZE: u32 = 0x7FF;
+    const ACTIVE: u32 = 1 << 15;
+    const TYPE: u32 = 0x3 << 18;
+    const STALL: u32 = 1 << 21;
+    const TX_FIFO: u32 = 0xF << 22;
+    const CLEAR_NAK: u32 = 1 << 26;
+    const SET_NAK: u32 = 1 << 27;
+    const SET_DATA0: u32 = 1 << 28;
+    const DISABLE: u32 = 1 << 30;
+    const ENABLE: u32 = 1 << 31;
+
+    /// Set maximum packet size in bytes; endpoint 0 takes an encoded size instead.
+    #[inline]
+    pub const fn set_max_packet_size(self, size: u16) -> Self {
+        Self((self.0 & !Self::MAX_PACKET_SIZE) | (size as u32 & Self::MAX_PACKET_SIZE))
+    }
+    /// Get maximum packet size field.
+    #[inline]
+    pub const fn max_packet_size(self) -> u16 {
+        (self.0 & Self::MAX_PACKET_SIZE) as u16
+    }
+    /// Activate endpoint in current configuration.
+    #[inline]
+    pub const fn activate(self) -> Self {
+        Self(self.0 | Self::ACTIVE)
+    }
+    /// Check if endpoint is active.
+    #[inline]
+    pub const fn is_active(self) -> bool {
+        self.0 & Self::ACTIVE != 0
+    }
+    /// Set transfer type as `bmAttributes` encoding.
+    #[inline]
+    pub const fn set_type(self, attributes: u8) -> Self {
+        Self((self.0 & !Self::TYPE) | (((attributes as u32) & 0x3) << 18))
+    }
+    /// Get transfer type as `bmAttributes` encoding.
+    #[inline]
+    pub const fn endpoint_type(self) -> u8 {
+        ((self.0 & Self::TYPE) >> 18) as u8
+    }
+    /// Set handshake to STALL.
+    #[inline]
+    pub const fn set_stall(self) -> Self {
+        Self(self.0 | Self::STALL)
+    }
+    /// Clear STALL handshake.
+    #[inline]
+    pub const fn clear_stall(self) -> Self {
+        Self(self.0 & !Self::STALL)
+    }
+    /// Check if handshake is STALL.
+    #[inline]
+    pub const fn is_stalled(self) -> bool {
+        self.0 & Self::STALL != 0
+    }
+    /// Set transmit FIFO of an IN endpoint.
+    #[inline]
+    pub const fn set_tx_fifo(self, fifo: u8) -> Self {
+        Self((self.0 & !Self::TX_FIFO) | (((fifo as u32) & 0xF) << 22))
+    }
+    /// Get transmit FIFO of an IN endpoint.
+    #[inline]
+    pub const fn tx_fifo(self) -> u8 {
+        ((self.0 & Self::TX_FIFO) >> 22) as u8
+    }
+    /// Stop answering NAK.
+    #[inline]
+    pub const fn clear_nak(self) -> Self {
+        Self((self.0 & !Self::SET_NAK) | Self::CLEAR_NAK)
+    }
+    /// Answer NAK to all tokens.
+    #[inline]
+    pub const fn set_nak(self) -> Self {
+        Self((self.0 & !Self::CLEAR_NAK) | Self::SET_NAK)
+    }
+    /// Reset data toggle to DATA0.
+    #[inline]
+    pub const fn set_data0(self) -> Self {
+        Self(self.0 | Self::SET_DATA0)
+    }
+    /// Request endpoint disable.
+    #[inline]
+    pub const fn disable(self) -> Self {
+        Self(self.0 | Self::DISABLE)
+    }
+    /// Arm endpoint for a transfer.
+    #[inline]
+    pub const fn enable(self) -> Self {
+        Self(self.0 | Self::ENABLE)
+    }
+    /// Check if endpoint is armed.
+    #[inline]
+    pub const fn is_enabled(self) -> bool {
+        self.0 & Self::ENABLE != 0
+    }
+    /// Remove one-shot command bits before writing back a read value.
+    #[inline]
+    const fn without_commands(self) -> Self {
+        Self(
+            self.0
+                & !(Self::CLEAR_NAK
+                    | Self::SET_NAK
+                    | Self::SET_DATA0
+                    | Self::DISABLE
+                    | Self::ENABLE),
+        )
+    }
+}
+
+/// Endpoint transfer size register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct TransferSize(u32);
+
+impl TransferSize {
+    const SIZE: u32 = 0x7FFFF;
+    const PACKET_COUNT: u32 = 0x3FF << 19;
+    const SETUP_COUNT: u32 = 0x3 << 29;
+
+    /// Set transfer size in bytes.
+    #[inline]
+    pub const fn set_size(self, bytes: u32) -> Self {
+        Self((self.0 & !Self::SIZE) | (bytes & Self::SIZE))
+    }
+    /// Get remaining transfer size in bytes.
+    #[inline]
+    pub const fn size(self) -> u32 {
+        self.0 & Self::SIZE
+    }
+    /// Set number of packets.
+    #[inline]
+    pub const fn set_packet_count(self, packets: u16) -> Self {
+        Self((self.0 & !Self::PACKET_COUNT) | (((packets as u32) << 19) & Self::PACKET_COUNT))
+    }
+    /// Get remaining number of packets.
+    #[inline]
+    pub const fn packet_count(self) -> u16 {
+        ((self.0 & Self::PACKET_COUNT) >> 19) as u16
+    }
+    /// Set number of back-to-back SETUP packets endpoint 0 accepts.
+    #[inline]
+    pub const fn set_setup_count(self, packets: u8) -> Self {
+        Self((self.0 & !Self::SETUP_COUNT) | (((packets as u32) << 29) & Self::SETUP_COUNT))
+    }
+    /// Get remaining number of SETUP packets.
+    #[inline]
+    pub const fn setup_count(self) -> u8 {
+        ((self.0 & Self::SETUP_COUNT) >> 29) as u8
+    }
+}
+
+/// Bus speed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Speed {
+    /// High speed, 480 Mbit/s.
+    High = 0,
+    /// Full speed, 12 Mbit/s, on the high speed PHY.
+    Full = 1,
+}
+
+/// USB device configuration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Config {
+    /// Highest bus speed to negotiate.
+    pub speed: Speed,
+    /// Report VBUS present without sensing it, for boards not routing VBUS to the controller.
+    pub vbus_override: bool,
+}
+
+impl Default for Config {
+    /// USB device configuration defaults to high speed with VBUS override.
+    #[inline]
+    fn default() -> Self {
+        Config {
+            speed: Speed::High,
+            vbus_override: true,
+        }
+    }
+}
+
+/// Endpoint 0 maximum packet size field encoding.
+#[inline]
+const fn ep0_max_packet_code(size: u16) -> u16 {
+    match size {
+        8 => 3,
+        16 => 2,
+        32 => 1,
+        _ => 0,
+    }
+}
+
+/// Transmit FIFO depth in words for an IN endpoint of `max_packet_size` bytes.
+#[inline]
+const fn tx_fifo_depth(max_packet_size: u16) -> u16 {
+    let words = max_packet_size.div_ceil(4);
+    if words < 16 {
+        16
+    } else {
+        words
+    }
+}
+
+/// Endpoint allocation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Endpoint {
+    max_packet_size: u16,
+    attributes: u8,
+}
+
+/// USB device bus over the on-the-go controller.
+pub struct Usb<T> {
+    usb: T,
+    config: Config,
+    in_endpoints: [Option<Endpoint>; ENDPOINTS],
+    out_endpoints: [Option<Endpoint>; ENDPOINTS],
+}
+
+/// Extension trait to create USB device buses from peripherals.
+pub trait UsbExt: AsRef<RegisterBlock> + Controlled + Sized {
+    /// Creates a USB device bus, detached until the bus allocator enables it.
+    #[inline]
+    fn usb(self, config: Config) -> Usb<Self> {
+        Usb {
+            usb: self,
+            config,
+            in_endpoints: [None; ENDPOINTS],
+            out_endpoints: [None; ENDPOINTS],
+        }
+    }
+}
+
+impl<T: AsRef<RegisterBlock>> Usb<T> {
+    /// Interrupt source of this controller on the interrupt controller.
+    #[inline]
+    pub const fn interrupt(&self) -> crate::plic::Interrupt {
+        crate::plic::Interrupt::Usb
+    }
+    /// Release USB controller and return its peripheral, detaching from the bus.
+    #[inline]
+    pub fn free(self) -> T
+    where
+        T: Controlled,
+    {
+        let usb = self.usb.as_ref();
+        unsafe { usb.device_control.modify(|w| w.disconnect()) };
+        reset::disable(&self.usb);
+        self.usb
+    }
+    /// Total FIFO memory in words, from hardware configuration.
+    #[inline]
+    fn fifo_words(&self) -> u16 {
+        (self.usb.as_ref().hardware_config[2].read() >> 16) as u16
+    }
+    /// FIFO memory in words used by receive FIFO and allocated IN endpoints.
+    #[inline]
+    fn used_fifo_words(&self) -> u16 {
+        RX_FIFO_WORDS
+            + self
+                .in_endpoints
+                .iter()
+                .flatten()
+                .map(|ep| tx_fifo_depth(ep.max_packet_size))
+                .sum::<u16>()
+    }
+    #[inline]
+    fn flush_fifos(&self) {
+        let usb = self.usb.as_ref();
+        unsafe {
+            usb.reset_control
+                .write(ResetControl::default().flush_rx_fifo().flush_tx_fifos())
+        };
+        while usb.reset_control.read().is_flushing() {
+            core::hint::spin_loop();
+        }
+    }
+    /// Sets up endpoint 0 OUT to receive SETUP and control OUT packets.
+    #[inline]
+    fn arm_ep0_out(&self) {
+        let usb = self.usb.as_ref();
+        let max_packet_size = self.out_endpoints[0].map_or(64, |ep| ep.max_packet_size);
+        unsafe {
+            usb.out_endpoints[0].transfer_size.write(
+                TransferSize::default()
+                    .set_setup_count(3)
+                    .set_packet_count(1)
+                    .set_size(max_packet_size as u32),
+            );
+            usb.out_endpoints[0]
+                .control
+                .modify(|w| w.without_commands().clear_nak().enable());
+        }
+    }
+    /// Arms OUT endpoint `index` to receive one packet.
+    #[inline]
+    fn arm_out(&self, index: usize) {
+        if index == 0 {
+            return self.arm_ep0_out();
+        }
+        let Some(ep) = self.out_endpoints[index] else {
+            return;
+        };
+        let out = &self.usb.as_ref().out_endpoints[index];
+        unsafe {
+            out.transfer_size.write(
+                TransferSize::default()
+                    .set_packet_count(1)
+                    .set_size(ep.max_packet_size as u32),
+            );
+            out.control
+                .modify(|w| w.without_commands().clear_nak().enable());
+        }
+    }
+}
+
+/// Waits `millis` milliseconds on the system counter.
+#[inline]
+fn delay_ms(millis: u64) {
+    let start = crate::clint::read_time();
+    let ticks = crate::clint::FREQUENCY as u64 / 1000 * millis;
+    while crate::clint::read_time().wrapping_sub(start) < ticks {
+        core::hint::spin_loop();
+    }
+}
+
+impl<T: AsRef<RegisterBlock> + Controlled + Sync> usb_device::bus::UsbBus for Usb<T> {
+    fn alloc_ep(
+        &mut self,
+        ep_dir: UsbDirection,
+        ep_addr: Option<EndpointAddress>,
+        ep_type: EndpointType,
+        max_packet_size: u16,
+        _interval: u8,
+    ) -> usb_device::Result<EndpointAddress> {
+        let endpoints = match ep_dir {
+            UsbDirection::In => &self.in_endpoints,
+            UsbDirection::Out => &self.out_endpoints,
+        };
+        let index = match ep_addr {
+            Some(addr) if addr.index() >= ENDPOINTS => return Err(UsbError::InvalidEndpoint),
+            Some(addr) if endpoints[addr.index()].is_some() => {
+                return Err(UsbError::InvalidEndpoint)
+            }
+            Some(addr) => addr.index(),
+            None => (1..ENDPOINTS)
+                .find(|&i| endpoints[i].is_none())
+                .ok_or(UsbError::EndpointOverflow)?,
+        };
+        let valid = match ep_type {
+            EndpointType::Control => index == 0 && matches!(max_packet_size, 8 | 16 | 32 | 64),
+            _ => index != 0 && max_packet_size <= 1024,
+        };
+        if !valid {
+            return Err(UsbError::Unsupported);
+        }
+        if ep_dir == UsbDirection::In
+            && self.used_fifo_words() + tx_fifo_depth(max_packet_size) > self.fifo_words()
+        {
+            return Err(UsbError::EndpointMemoryOverflow);
+        }
+        let endpoint = Some(Endpoint {
+            max_packet_size,
+            attributes: ep_type.to_bm_attributes(),
+        });
+        match ep_dir {
+            UsbDirection::In => self.in_endpoints[index] = endpoint,
+            UsbDirection::Out => self.out_endpoints[index] = endpoint,
+        }
+        Ok(EndpointAddress::from_parts(index, ep_dir))
+    }
+
+    fn enable(&mut self) {
+        reset::enable(&self.usb);
+        let usb = self.usb.as_ref();
+        unsafe {
+            while !usb.reset_control.read().is_ahb_idle() {
+                core::hint::spin_loop();
+            }
+            usb.reset_control
+                .write(ResetControl::default().reset_core());
+            while usb.reset_control.read().is_core_resetting() {
+                core::hint::spin_loop();
+            }
+            usb.usb_config
+                .modify(|w| w.set_phy_8bit().set_turnaround(9).force_device_mode());
+        }
+        // forced mode takes effect after 25 ms
+        delay_ms(25);
+        unsafe {
+            usb.device_control.modify(|w| w.disconnect());
+            if self.config.vbus_override {
+                usb.otg_control.modify(|w| w.override_b_valid());
+            }
+            usb.device_config.write(
+                DeviceConfig::default()
+                    .set_speed(self.config.speed)
+                    .set_address(0),
+            );
+            usb.power_clock.write(0);
+
+            // receive FIFO first, then one transmit FIFO per allocated IN endpoint
+            usb.rx_fifo_size.write(RX_FIFO_WORDS as u32);
+            let mut start = RX_FIFO_WORDS;
+            for (index, ep) in self.in_endpoints.iter().enumerate() {
+                let depth = ep.map_or(0, |ep| tx_fifo_depth(ep.max_packet_size));
+                let size = FifoSize::new(start, depth);
+                if index == 0 {
+                    usb.tx_fifo0_size.write(size);
+                } else {
+                    usb.tx_fifo_size[index - 1].write(size);
+                }
+                start += depth;
+            }
+        }
+        self.flush_fifos();
+        unsafe {
+            usb.in_interrupt_mask.write(1 << 0);
+            usb.out_interrupt_mask.write((1 << 0) | (1 << 3));
+            usb.endpoint_interrupt_mask.write(u32::MAX);
+            usb.interrupt_status.write(Interrupts(u32::MAX));
+            usb.interrupt_mask.write(
+                Interrupts::default()
+                    .set_interrupt(Interrupt::RxFifoLevel)
+                    .set_interrupt(Interrupt::Suspend)
+                    .set_interrupt(Interrupt::Reset)
+                    .set_interrupt(Interrupt::EnumerationDone)
+                    .set_interrupt(Interrupt::InEndpoint)
+                    .set_interrupt(Interrupt::OutEndpoint)
+                    .set_interrupt(Interrupt::Wakeup),
+            );
+            usb.ahb_config.write(
+                AhbConfig::default()
+                    .disable_dma()
+                    .set_tx_empty_completely()
+                    .enable_global_interrupt(),
+            );
+            usb.device_control.modify(|w| w.connect());
+        }
+    }
+
+    fn reset(&self) {
+        let usb = self.usb.as_ref();
+        unsafe {
+            usb.device_config.modify(|w| w.set_address(0));
+            for index in 0..ENDPOINTS {
+                let in_ep = &usb.in_endpoints[index];
+                let out_ep = &usb.out_endpoints[index];
+                in_ep.interrupt.write(u32::MAX);
+                out_ep.interrupt.write(u32::MAX);
+                match self.in_endpoints[index] {
+                    Some(ep) if index == 0 => in_ep.control.write(
+                        EndpointControl::default()
+                            .set_max_packet_size(ep0_max_packet_code(ep.max_packet_size))
+                            .set_nak(),
+                    ),
+                    Some(ep) => in_ep.control.write(
+                        EndpointControl::default()
+                            .set_max_packet_size(ep.max_packet_size)
+                            .set_type(ep.attributes)
+                            .set_tx_fifo(index as u8)
+                            .activate()
+                            .set_data0()
+                            .set_nak(),
+                    ),
+                    None => in_ep.control.write(EndpointControl::default()),
+                }
+                match self.out_endpoints[index] {
+                    Some(ep) if index == 0 => out_ep.control.write(
+                        EndpointControl::default()
+                            .set_max_packet_size(ep0_max_packet_code(ep.max_packet_size)),
+                    ),
+                    Some(ep) => out_ep.control.write(
+                        EndpointControl::default()
+                            .set_max_packet_size(ep.max_packet_size)
+                            .set_type(ep.attributes)
+                            .activate()
+                            .set_data0(),
+                    ),
+                    None => out_ep.control.write(EndpointControl::default()),
+                }
+            }
+        }
+        self.flush_fifos();
+        for index in 0..ENDPOINTS {
+            self.arm_out(index);
+        }
+    }
+
+    fn set_device_address(&self, addr: u8) {
+        unsafe {
+            self.usb
+                .as_ref()
+                .device_config
+                .modify(|w| w.set_address(addr))
+        };
+    }
+
+    fn write(&self, ep_addr: EndpointAddress, buf: &[u8]) -> usb_device::Result<usize> {
+        let index = ep_addr.index();
+        let ep = self
+            .in_endpoints
+            .get(index)
+            .copied()
+            .flatten()
+            .filter(|_| ep_addr.is_in())
+            .ok_or(UsbError::InvalidEndpoint)?;
+        if buf.len() > ep.max_packet_size as usize {
+            return Err(UsbError::BufferOverflow);
+        }
+        let usb = self.usb.as_ref();
+        let in_ep = &usb.in_endpoints[index];
+        let words = buf.len().div_ceil(4);
+        if in_ep.control.read().is_enabled()
+            || (in_ep.tx_fifo_status.read() as usize & 0xFFFF) < words
+        {
+            return Err(UsbError::WouldBlock);
+        }
+        unsafe {
+            in_ep.transfer_size.write(
+                TransferSize::default()
+                    .set_packet_count(1)
+                    .set_size(buf.len() as u32),
+            );
+            in_ep
+                .control
+                .modify(|w| w.without_commands().clear_nak().enable());
+            let fifo = &usb.fifos[index].data;
+            for chunk in buf.chunks(4) {
+                let mut word = [0u8; 4];
+                word[..chunk.len()].copy_from_slice(chunk);
+                fifo.write(u32::from_le_bytes(word));
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn read(&self, ep_addr: EndpointAddress, buf: &mut [u8]) -> usb_device::Result<usize> {
+        let index = ep_addr.index();
+        if !ep_addr.is_out() || self.out_endpoints.get(index).copied().flatten().is_none() {
+            return Err(UsbError::InvalidEndpoint);
+        }
+        let usb = self.usb.as_ref();
+        if !usb
+            .interrupt_status
+            .read()
+            .has_interrupt(Interrupt::RxFifoLevel)
+        {
+            return Err(UsbError::WouldBlock);
+        }
+        let status = usb.rx_status_read.read();
+        let is_data = matches!(
+            status.packet_status(),
+            PacketStatus::OutData | PacketStatus::SetupData
+        );
+        if !is_data || status.endpoint() != index {
+            return Err(UsbError::WouldBlock);
+        }
+        let len = status.byte_count();
+        if len > buf.len() {
+            return Err(UsbError::BufferOverflow);
+        }
+        let _ = usb.rx_status_pop.read();
+        let fifo = &usb.fifos[0].data;
+        for chunk in buf[..len].chunks_mut(4) {
+            let word = fifo.read().to_le_bytes();
+            chunk.copy_from_slice(&word[..chunk.len()]);
+        }
+        if status.packet_status() == PacketStatus::OutData {
+            self.arm_out(index);
+        }
+        Ok(len)
+    }
+
+    fn set_stalled(&self, ep_addr: EndpointAddress, stalled: bool) {
+        let index = ep_addr.index();
+        if index >= ENDPOINTS {
+            return;
+        }
+        let usb = self.usb.as_ref();
+        let control = match ep_addr.direction() {
+            UsbDirection::In => &usb.in_endpoints[index].control,
+            UsbDirection::Out => &usb.out_endpoints[index].control,
+        };
+        unsafe {
+            control.modify(|w| {
+                let w = w.without_commands();
+                if stalled {
+                    w.set_stall()
+                } else if index == 0 {
+                    w.clear_stall()
+                } else {
+                    w.clear_stall().set_data0()
+                }
+            })
+        };
+        if !stalled && ep_addr.is_out() {
+            self.arm_out(index);
+        }
+    }
+
+    fn is_stalled(&self, ep_addr: EndpointAddress) -> bool {
+        let index = ep_addr.index();
+        if index >= ENDPOINTS {
+            return false;
+        }
+        let usb = self.usb.as_ref();
+        match ep_addr.direction() {
+            UsbDirection::In => usb.in_endpoints[index].control.read().is_stalled(),
+            UsbDirection::Out => usb.out_endpoints[index].control.read().is_stalled(),
+        }
+    }
+
+    fn suspend(&self) {}
+
+    fn resume(&self) {}
+
+    fn poll(&self) -> PollResult {
+        let usb = self.usb.as_ref();
+        let status = usb.interrupt_status.read();
+        if status.has_interrupt(Interrupt::Reset)
+            || status.has_interrupt(Interrupt::EnumerationDone)
+        {
+            unsafe {
+                usb.interrupt_status.write(
+                    Interrupts::default()
+                        .set_interrupt(Interrupt::Reset)
+                        .set_interrupt(Interrupt::EnumerationDone),
+                )
+            };
+            return PollResult::Reset;
+        }
+        if status.has_interrupt(Interrupt::Wakeup) {
+            unsafe {
+                usb.interrupt_status
+                    .write(Interrupts::default().set_interrupt(Interrupt::Wakeup))
+            };
+            return PollResult::Resume;
+        }
+        if status.has_interrupt(Interrupt::Suspend) {
+            unsafe {
+                usb.interrupt_status
+                    .write(Interrupts::default().set_interrupt(Interrupt::Suspend))
+            };
+            return PollResult::Suspend;
+        }
+
+        let (mut ep_out, mut ep_in_complete, mut ep_setup) = (0u16, 0u16, 0u16);
+        // pop completion entries until a data packet waits for its reader
+        while usb
+            .interrupt_status
+            .read()
+            .has_interrupt(Interrupt::RxFifoLevel)
+        {
+            let entry = usb.rx_status_read.read();
+            match entry.packet_status() {
+                PacketStatus::OutData => {
+                    ep_out |= 1 << entry.endpoint();
+                    break;
+                }
+                PacketStatus::SetupData => {
+                    ep_setup |= 1 << entry.endpoint();
+                    break;
+                }
+                PacketStatus::SetupComplete => {
+                    let _ = usb.rx_status_pop.read();
+                    self.arm_ep0_out();
+                }
+                PacketStatus::OutComplete | PacketStatus::GlobalOutNak => {
+                    let _ = usb.rx_status_pop.read();
+                }
+            }
+        }
+        let pending = usb.endpoint_interrupt.read();
+        for index in 0..ENDPOINTS {
+            if pending & (1 << index) != 0 {
+                let in_ep = &usb.in_endpoints[index];
+                let flags = in_ep.interrupt.read();
+                unsafe { in_ep.interrupt.write(flags) };
+                if flags & 1 != 0 {
+                    ep_in_complete |= 1 << index;
+                }
+            }
+            if pending & (1 << (16 + index)) != 0 {
+                let out_ep = &usb.out_endpoints[index];
+                let flags = out_ep.interrupt.read();
+                unsafe { out_ep.interrupt.write(flags) };
+            }
+        }
+        if ep_out | ep_in_complete | ep_setup != 0 {
+            PollResult::Data {
+                ep_out,
+                ep_in_complete,
+                ep_setup,
+            }
+        } else {
+            PollResult::None
+        }
+    }
+
+    const QUIRK_SET_ADDRESS_BEFORE_STATUS: bool = true;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ep0_max_packet_code, tx_fifo_depth, DeviceConfig, EndpointControl, FifoSize, InEndpoint,
+        OutEndpoint, PacketStatus, RegisterBlock, RxStatus, Speed, TransferSize,
+    };
+    use core::mem::size_of;
+    use memoffset::offset_of;
+
+    #[test]
+    fn struct_register_block_offset() {
+        assert_eq!(offset_of!(RegisterBlock, reset_control), 0x010);
+        assert_eq!(offset_of!(RegisterBlock, rx_status_pop), 0x020);
+        assert_eq!(offset_of!(RegisterBlock, tx_fifo0_size), 0x028);
+        assert_eq!(offset_of!(RegisterBlock, synopsys_id), 0x040);
+        assert_eq!(offset_of!(RegisterBlock, hardware_config), 0x044);
+        assert_eq!(offset_of!(RegisterBlock, tx_fifo_size), 0x104);
+        assert_eq!(offset_of!(RegisterBlock, device_config), 0x800);
+        assert_eq!(offset_of!(RegisterBlock, endpoint_interrupt_mask), 0x81C);
+        assert_eq!(offset_of!(RegisterBlock, in_endpoints), 0x900);
+        assert_eq!(offset_of!(RegisterBlock, out_endpoints), 0xB00);
+        assert_eq!(offset_of!(RegisterBlock, power_clock), 0xE00);
+        assert_eq!(offset_of!(RegisterBlock, fifos), 0x1000);
+        assert_eq!(size_of::<InEndpoint>(), 0x20);
+        assert_eq!(size_of::<OutEndpoint>(), 0x20);
+        assert_eq!(offset_of!(InEndpoint, tx_fifo_status), 0x18);
+    }
+
+    #[test]
+    fn struct_endpoint_functions() {
+        let val = EndpointControl::default()
+            .set_max_packet_size(512)
+            .set_type(2)
+            .set_tx_fifo(3)
+            .activate()
+            .set_data0();
+        assert_eq!(val.0, 0x10C8_8200);
+        assert_eq!(val.endpoint_type(), 2);
+        assert_eq!(val.tx_fifo(), 3);
+        let val = val.clear_nak().enable();
+        assert!(val.is_enabled());
+        assert_eq!(val.without_commands().0, 0x00C8_8200);
+        assert!(val.set_stall().is_stalled());
+
+        let val = TransferSize::default()
+            .set_setup_count(3)
+            .set_packet_count(1)
+            .set_size(64);
+        assert_eq!(val.0, 0x6008_0040);
+        assert_eq!(val.packet_count(), 1);
+        assert_eq!(val.setup_count(), 3);
+
+        let val = DeviceConfig::default()
+            .set_speed(Speed::Full)
+            .set_address(0x2A);
+        assert_eq!(val.0, 0x2A1);
+        assert_eq!(val.address(), 0x2A);
+        assert_eq!(val.speed(), Speed::Full);
+
+        let val = FifoSize::new(256, 128);
+        assert_eq!(val.0, 0x0080_0100);
+        assert_eq!((val.start(), val.depth()), (256, 128));
+    }
+
+    #[test]
+    fn struct_rx_status_functions() {
+        let val = RxStatus((6 << 17) | (8 << 4));
+        assert_eq!(val.endpoint(), 0);
+        assert_eq!(val.byte_count(), 8);
+        assert_eq!(val.packet_status(), PacketStatus::SetupData);
+        let val = RxStatus((2 << 17) | (64 << 4) | 2);
+        assert_eq!(val.endpoint(), 2);
+        assert_eq!(val.byte_count(), 64);
+        assert_eq!(val.packet_status(), PacketStatus::OutData);
+    }
+
+    #[test]
+    fn fifo_sizes() {
+        assert_eq!(ep0_max_packet_code(64), 0);
+        assert_eq!(ep0_max_packet_code(8), 3);
+        assert_eq!(tx_fifo_depth(8), 16);
+        assert_eq!(tx_fifo_depth(512), 128);
+        assert_eq!(tx_fifo_depth(1023), 256);
+    }
+}
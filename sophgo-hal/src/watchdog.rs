@@ -0,0 +1,362 @@
+//! Watchdog timer.
+
+use crate::clock::Clocks;
+use crate::reset::{self, Controlled};
+use core::time::Duration;
+use volatile_register::{RO, RW, WO};
+
+/// Watchdog timer registers.
+#[repr(C)]
+pub struct RegisterBlock {
+    /// Control register.
+    pub control: RW<Control>,
+    /// Timeout range register.
+    pub timeout_range: RW<TimeoutRange>,
+    /// Current counter value register.
+    pub current_value: RO<u32>,
+    /// Counter restart register; write `RESTART_KEY` to feed the watchdog.
+    pub counter_restart: WO<u32>,
+    /// Interrupt status register.
+    pub interrupt_status: RO<u32>,
+    /// Reading this register clears the watchdog interrupt.
+    pub end_of_interrupt: RO<u32>,
+}
+
+/// Value to write into `counter_restart` to restart the counter.
+pub const RESTART_KEY: u32 = 0x76;
+
+/// Watchdog control register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct Control(u32);
+
+impl Control {
+    const ENABLE: u32 = 1 << 0;
+    const RESPONSE_MODE: u32 = 1 << 1;
+    const RESET_PULSE_LENGTH: u32 = 0x7 << 2;
+
+    /// Enable watchdog counter.
+    #[inline]
+    pub const fn enable(self) -> Self {
+        Self(self.0 | Self::ENABLE)
+    }
+    /// Disable watchdog counter.
+    #[inline]
+    pub const fn disable(self) -> Self {
+        Self(self.0 & !Self::ENABLE)
+    }
+    /// Check if watchdog counter is enabled.
+    #[inline]
+    pub const fn is_enabled(self) -> bool {
+        self.0 & Self::ENABLE != 0
+    }
+    /// Set response on counter timeout.
+    #[inline]
+    pub const fn set_response_mode(self, mode: ResponseMode) -> Self {
+        Self((self.0 & !Self::RESPONSE_MODE) | ((mode as u32) << 1))
+    }
+    /// Get response on counter timeout.
+    #[inline]
+    pub const fn response_mode(self) -> ResponseMode {
+        match (self.0 & Self::RESPONSE_MODE) >> 1 {
+            0 => ResponseMode::Reset,
+            1 => ResponseMode::InterruptThenReset,
+            _ => unreachable!(),
+        }
+    }
+    /// Set system reset pulse length to `2^(n + 1)` clock cycles.
+    #[inline]
+    pub const fn set_reset_pulse_length(self, n: u8) -> Self {
+        Self((self.0 & !Self::RESET_PULSE_LENGTH) | (((n as u32) << 2) & Self::RESET_PULSE_LENGTH))
+    }
+    /// Get system reset pulse length exponent.
+    #[inline]
+    pub const fn reset_pulse_length(self) -> u8 {
+        ((self.0 & Self::RESET_PULSE_LENGTH) >> 2) as u8
+    }
+}
+
+/// Response on watchdog counter timeout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResponseMode {
+    /// Reset the system on first timeout.
+    Reset = 0,
+    /// Raise an interrupt on first timeout, reset the system if not cleared by second timeout.
+    InterruptThenReset = 1,
+}
+
+/// Watchdog timeout range register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct TimeoutRange(u32);
+
+impl TimeoutRange {
+    const TIMEOUT: u32 = 0xF;
+    const INITIAL_TIMEOUT: u32 = 0xF << 4;
+
+    /// Set timeout to `2^(16 + n)` clock cycles.
+    #[inline]
+    pub const fn set_timeout(self, n: u8) -> Self {
+        Self((self.0 & !Self::TIMEOUT) | (n as u32 & Self::TIMEOUT))
+    }
+    /// Get timeout exponent.
+    #[inline]
+    pub const fn timeout(self) -> u8 {
+        (self.0 & Self::TIMEOUT) as u8
+    }
+    /// Set timeout before first restart to `2^(16 + n)` clock cycles.
+    #[inline]
+    pub const fn set_initial_timeout(self, n: u8) -> Self {
+        Self((self.0 & !Self::INITIAL_TIMEOUT) | (((n as u32) << 4) & Self::INITIAL_TIMEOUT))
+    }
+    /// Get timeout before first restart exponent.
+    #[inline]
+    pub const fn initial_timeout(self) -> u8 {
+        ((self.0 & Self::INITIAL_TIMEOUT) >> 4) as u8
+    }
+}
+
+/// Power-on reason register block of the RTC domain, preserved across system resets.
+#[repr(C)]
+pub struct ResetStatus {
+    _reserved0: [u8; 0xF8],
+    /// Power-on reason register.
+    pub power_on_reason: RO<PowerOnReason>,
+}
+
+/// Power-on reason register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(transparent)]
+pub struct PowerOnReason(u32);
+
+impl PowerOnReason {
+    const WARM_RESET: u32 = 1 << 1;
+    const WATCHDOG_RESET: u32 = 1 << 2;
+
+    /// Check if last reset is a warm reset not caused by power loss.
+    #[inline]
+    pub const fn is_warm_reset(self) -> bool {
+        self.0 & Self::WARM_RESET != 0
+    }
+    /// Check if last reset was caused by watchdog timeout.
+    #[inline]
+    pub const fn is_watchdog_reset(self) -> bool {
+        self.0 & Self::WATCHDOG_RESET != 0
+    }
+}
+
+/// Checks if last system reset was caused by a watchdog timeout.
+#[inline]
+pub fn is_last_reset_from_watchdog(status: &impl AsRef<ResetStatus>) -> bool {
+    status.as_ref().power_on_reason.read().is_watchdog_reset()
+}
+
+/// Watchdog configuration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Config {
+    /// Minimum time between two feeds before timeout.
+    pub timeout: Duration,
+    /// Response on timeout.
+    pub mode: ResponseMode,
+}
+
+impl Default for Config {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(1),
+            mode: ResponseMode::Reset,
+        }
+    }
+}
+
+/// Smallest timeout exponent covering `timeout`, saturating at maximum supported timeout.
+#[inline]
+pub const fn timeout_exponent(timeout: Duration, clock: u32) -> u8 {
+    let cycles = timeout.as_nanos() * clock as u128 / 1_000_000_000;
+    let mut n = 0;
+    while n < 15 && (1u128 << (16 + n)) < cycles {
+        n += 1;
+    }
+    n
+}
+
+/// Managed watchdog peripheral.
+pub struct Watchdog<T> {
+    watchdog: T,
+    clock: u32,
+}
+
+/// Extension trait to create watchdog drivers from peripherals.
+pub trait WatchdogExt: AsRef<RegisterBlock> + Controlled + Sized {
+    /// Configures and starts the watchdog.
+    ///
+    /// Pulses the watchdog soft reset first, which also stops a watchdog armed at boot.
+    #[inline]
+    fn watchdog(self, config: Config, clocks: &Clocks) -> Watchdog<Self> {
+        reset::enable(&self);
+        let mut watchdog = Watchdog {
+            watchdog: self,
+            clock: clocks.watchdog().to_hz(),
+        };
+        watchdog.set_timeout(config.timeout);
+        let wdt = watchdog.watchdog.as_ref();
+        unsafe {
+            wdt.control
+                .modify(|w| w.set_response_mode(config.mode).enable());
+            wdt.counter_restart.write(RESTART_KEY);
+        }
+        watchdog
+    }
+}
+
+impl<T: AsRef<RegisterBlock>> Watchdog<T> {
+    /// Restarts the counter, postponing timeout; also clears a pending interrupt.
+    #[inline]
+    pub fn feed(&mut self) {
+        unsafe { self.watchdog.as_ref().counter_restart.write(RESTART_KEY) };
+    }
+    /// Sets timeout, rounded up to the next supported value.
+    ///
+    /// Takes effect after next `feed`.
+    #[inline]
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        let n = timeout_exponent(timeout, self.clock);
+        unsafe {
+            self.watchdog.as_ref().timeout_range.write(
+                TimeoutRange::default()
+                    .set_timeout(n)
+                    .set_initial_timeout(n),
+            )
+        };
+    }
+    /// Actual timeout after rounding to supported values.
+    #[inline]
+    pub fn timeout(&self) -> Duration {
+        let n = self.watchdog.as_ref().timeout_range.read().timeout();
+        let cycles = 1u64 << (16 + n);
+        Duration::from_nanos(cycles * 1_000_000_000 / self.clock as u64)
+    }
+    /// Sets response on timeout.
+    #[inline]
+    pub fn set_response_mode(&mut self, mode: ResponseMode) {
+        unsafe {
+            self.watchdog
+                .as_ref()
+                .control
+                .modify(|w| w.set_response_mode(mode))
+        };
+    }
+    /// Checks if first timeout interrupt is pending in `InterruptThenReset` mode.
+    #[inline]
+    pub fn is_interrupt_pending(&self) -> bool {
+        self.watchdog.as_ref().interrupt_status.read() & 1 != 0
+    }
+    /// Clears timeout interrupt without restarting the counter.
+    #[inline]
+    pub fn clear_interrupt(&mut self) {
+        self.watchdog.as_ref().end_of_interrupt.read();
+    }
+}
+
+impl<T: AsRef<RegisterBlock> + Controlled> Watchdog<T> {
+    /// Stops the watchdog and return its peripheral.
+    ///
+    /// `WDT_EN` cannot be cleared once set, so the watchdog is held in soft reset instead.
+    #[inline]
+    pub fn disable(self) -> T {
+        reset::disable(&self.watchdog);
+        self.watchdog
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        timeout_exponent, Control, RegisterBlock, ResetStatus, ResponseMode, TimeoutRange, Watchdog,
+    };
+    use crate::clock::{self, Gate};
+    use crate::reset::{self, Controlled, Reset};
+    use core::time::Duration;
+    use memoffset::offset_of;
+
+    struct Sim {
+        wdt: RegisterBlock,
+        clock_gen: clock::RegisterBlock,
+        reset: reset::RegisterBlock,
+    }
+
+    impl AsRef<RegisterBlock> for Sim {
+        fn as_ref(&self) -> &RegisterBlock {
+            &self.wdt
+        }
+    }
+
+    impl Controlled for Sim {
+        const GATES: &'static [Gate] = &[Gate::ApbWatchdog];
+        const RESETS: &'static [Reset] = &[Reset::Watchdog];
+
+        fn clock_gen(&self) -> &clock::RegisterBlock {
+            &self.clock_gen
+        }
+        fn reset_control(&self) -> &reset::RegisterBlock {
+            &self.reset
+        }
+    }
+
+    #[test]
+    fn struct_register_block_offset() {
+        assert_eq!(offset_of!(RegisterBlock, control), 0x00);
+        assert_eq!(offset_of!(RegisterBlock, timeout_range), 0x04);
+        assert_eq!(offset_of!(RegisterBlock, current_value), 0x08);
+        assert_eq!(offset_of!(RegisterBlock, counter_restart), 0x0C);
+        assert_eq!(offset_of!(RegisterBlock, interrupt_status), 0x10);
+        assert_eq!(offset_of!(RegisterBlock, end_of_interrupt), 0x14);
+        assert_eq!(offset_of!(ResetStatus, power_on_reason), 0xF8);
+    }
+
+    #[test]
+    fn struct_control_functions() {
+        let val = Control::default()
+            .set_response_mode(ResponseMode::InterruptThenReset)
+            .set_reset_pulse_length(0x7)
+            .enable();
+        assert_eq!(val.0, 0x1F);
+        assert_eq!(val.response_mode(), ResponseMode::InterruptThenReset);
+        assert_eq!(val.reset_pulse_length(), 0x7);
+        let val = val.set_response_mode(ResponseMode::Reset).disable();
+        assert_eq!(val.0, 0x1C);
+        assert!(!val.is_enabled());
+
+        let val = TimeoutRange::default()
+            .set_timeout(0xA)
+            .set_initial_timeout(0x3);
+        assert_eq!(val.0, 0x3A);
+        assert_eq!(val.timeout(), 0xA);
+        assert_eq!(val.initial_timeout(), 0x3);
+    }
+
+    #[test]
+    fn timeout_exponent_rounds_up() {
+        // 2^16 cycles at 25 MHz is 2.62 ms
+        assert_eq!(timeout_exponent(Duration::from_millis(1), 25_000_000), 0);
+        assert_eq!(timeout_exponent(Duration::from_millis(3), 25_000_000), 1);
+        // 2^25 cycles at 25 MHz is 1.34 s
+        assert_eq!(timeout_exponent(Duration::from_secs(1), 25_000_000), 9);
+        assert_eq!(timeout_exponent(Duration::from_secs(3600), 25_000_000), 15);
+    }
+
+    #[test]
+    fn disable_holds_watchdog_in_reset() {
+        let sim: Sim = unsafe { core::mem::zeroed() };
+        reset::enable(&sim);
+        assert_eq!(sim.reset.soft_reset[1].read(), 1 << 16);
+        let watchdog = Watchdog {
+            watchdog: sim,
+            clock: 25_000_000,
+        };
+        let sim = watchdog.disable();
+        assert_eq!(sim.reset.soft_reset[1].read(), 0);
+        assert_eq!(sim.clock_gen.clock_enable[1].read(), 0);
+    }
+}
@@ -10,9 +10,15 @@ pub mod prelude {
 
 #[macro_use]
 mod macros;
+
+#[cfg(feature = "embassy-time-driver")]
+pub mod time_driver;
 use sophgo_hal::{
     gpio::{Gpio, Input},
-    pad::{FMux, Floating, GpioFunc, Pad, PadConfigs, PinMux, PwrPadConfigs, UartFunc},
+    pad::{
+        AdcFunc, FMux, Floating, GpioFunc, I2cFunc, IoPower, JtagFunc, Pad, PadConfigs, PadDomain,
+        PinMux, PwrPadConfigs, SdFunc, SpiNorFunc, UartFunc,
+    },
 };
 
 /// Peripherals available on ROM start.
@@ -28,22 +34,45 @@ pub struct Peripherals {
     pub gpio2: GPIO2,
     /// General Purpose Input/Output 3.
     pub gpio3: GPIO3,
+    /// Watchdog timer.
+    ///
+    /// Already running on entry if `boot-watchdog` feature is enabled.
+    pub watchdog: WATCHDOG,
+    /// Real-time clock, also holding reset status of the RTC domain.
+    pub rtc: RTC,
 
-    // TODO pub pwm0: sophgo_hal::PWM<Static<0x03060000>>,
-    // TODO pub pwm1: sophgo_hal::PWM<Static<0x03061000>>,
-    // TODO pub pwm2: sophgo_hal::PWM<Static<0x03062000>>,
-    // TODO pub pwm3: sophgo_hal::PWM<Static<0x03063000>>,
-    // TODO pub timer: sophgo_hal::Timer<Static<0x030A0000>>,
-    // TODO pub i2c0: sophgo_hal::I2C<Static<0x04000000>>,
-    // TODO pub i2c1: sophgo_hal::I2C<Static<0x04010000>>,
-    // TODO pub i2c2: sophgo_hal::I2C<Static<0x04020000>>,
-    // TODO pub i2c3: sophgo_hal::I2C<Static<0x04030000>>,
-    // TODO pub i2c4: sophgo_hal::I2C<Static<0x04040000>>,
-    // TODO pub spi_nand: sophgo_hal::SPINand<Static<0x04060000>>,
-    // TODO pub i2s0: sophgo_hal::I2S<Static<0x04100000>>,
-    // TODO pub i2s1: sophgo_hal::I2S<Static<0x04110000>>,
-    // TODO pub i2s2: sophgo_hal::I2S<Static<0x04120000>>,
-    // TODO pub i2s3: sophgo_hal::I2S<Static<0x04130000>>,
+    /// Pulse Width Modulation 0.
+    pub pwm0: PWM0,
+    /// Pulse Width Modulation 1.
+    pub pwm1: PWM1,
+    /// Pulse Width Modulation 2.
+    pub pwm2: PWM2,
+    /// Pulse Width Modulation 3.
+    pub pwm3: PWM3,
+    /// Hardware timers.
+    pub timer: TIMER,
+    /// Inter-Integrated Circuit bus 0.
+    pub i2c0: I2C0,
+    /// Inter-Integrated Circuit bus 1.
+    pub i2c1: I2C1,
+    /// Inter-Integrated Circuit bus 2.
+    pub i2c2: I2C2,
+    /// Inter-Integrated Circuit bus 3.
+    pub i2c3: I2C3,
+    /// Inter-Integrated Circuit bus 4.
+    pub i2c4: I2C4,
+    /// SPI-NAND flash controller.
+    pub spi_nand: SPI_NAND,
+    /// SPI-NOR flash controller.
+    pub spi_nor: SPI_NOR,
+    /// Inter-IC Sound bus 0.
+    pub i2s0: I2S0,
+    /// Inter-IC Sound bus 1.
+    pub i2s1: I2S1,
+    /// Inter-IC Sound bus 2.
+    pub i2s2: I2S2,
+    /// Inter-IC Sound bus 3.
+    pub i2s3: I2S3,
     /// Universal Asynchronous Receiver/Transmitter 0.
     pub uart0: UART0,
     /// Universal Asynchronous Receiver/Transmitter 1.
@@ -53,15 +82,22 @@ pub struct Peripherals {
     /// Universal Asynchronous Receiver/Transmitter 3.
     pub uart3: UART3,
 
-    // TODO spi0: sophgo_hal::SPI<Static<0x04180000>>,
-    // TODO spi1: sophgo_hal::SPI<Static<0x04190000>>,
-    // TODO spi2: sophgo_hal::SPI<Static<0x041A0000>>,
-    // TODO spi3: sophgo_hal::SPI<Static<0x041B0000>>,
+    /// Serial Peripheral Interface 0.
+    pub spi0: SPI0,
+    /// Serial Peripheral Interface 1.
+    pub spi1: SPI1,
+    /// Serial Peripheral Interface 2.
+    pub spi2: SPI2,
+    /// Serial Peripheral Interface 3.
+    pub spi3: SPI3,
     /// Universal Asynchronous Receiver/Transmitter 4.
     pub uart4: UART4,
-    // TODO sd0: sophgo_hal::SD<Static<0x04310000>>,
-    // TODO sd1: sophgo_hal::SD<Static<0x04320000>>,
-    // TODO usb: sophgo_hal::USB<Static<0x04340000>>,
+    /// SD/MMC host controller 0.
+    pub sd0: SD0,
+    /// SD/MMC host controller 1.
+    pub sd1: SD1,
+    /// USB 2.0 on-the-go controller.
+    pub usb: USB,
     // TODO documents
     /// SoC pads.
     pub pads: Pads<PINMUX>,
@@ -69,6 +105,27 @@ pub struct Peripherals {
     pub pwr_gpio: GpioPort<PWR_GPIO>,
     /// Low-power Domain SoC pads.
     pub pwr_pads: PwrPads<PWR_PINMUX>,
+    /// IO group power switches.
+    pub io_power: IO_POWER,
+    /// Analog-to-digital converter.
+    pub adc: ADC,
+    /// Low-power Domain analog-to-digital converter.
+    pub pwr_adc: PWR_ADC,
+    /// Internal audio codec.
+    pub audio_codec: AUDIO_CODEC,
+    /// System direct memory access controller.
+    pub dma: DMA,
+    /// DMA channel request remap registers.
+    pub dma_remap: DMA_REMAP,
+    /// Core Local Interruptor of the C906 core.
+    ///
+    /// Owned by `time_driver` instead if `embassy-time-driver` feature is enabled.
+    #[cfg(not(feature = "embassy-time-driver"))]
+    pub clint: CLINT,
+    /// Clock generator.
+    pub clk_gen: CLK_GEN,
+    /// Platform-Level Interrupt Controller of the C906 core.
+    pub plic: PLIC,
 }
 
 soc! {
@@ -82,6 +139,30 @@ soc! {
     pub struct GPIO2 => 0x03022000, sophgo_hal::gpio::RegisterBlock;
     /// General Purpose Input/Output peripheral 3.
     pub struct GPIO3 => 0x03023000, sophgo_hal::gpio::RegisterBlock;
+    /// Watchdog timer peripheral.
+    pub struct WATCHDOG => 0x03010000, sophgo_hal::watchdog::RegisterBlock;
+    /// Pulse Width Modulation peripheral 0.
+    pub struct PWM0 => 0x03060000, sophgo_hal::pwm::RegisterBlock;
+    /// Pulse Width Modulation peripheral 1.
+    pub struct PWM1 => 0x03061000, sophgo_hal::pwm::RegisterBlock;
+    /// Pulse Width Modulation peripheral 2.
+    pub struct PWM2 => 0x03062000, sophgo_hal::pwm::RegisterBlock;
+    /// Pulse Width Modulation peripheral 3.
+    pub struct PWM3 => 0x03063000, sophgo_hal::pwm::RegisterBlock;
+    /// Hardware timer peripheral.
+    pub struct TIMER => 0x030A0000, sophgo_hal::timer::RegisterBlock;
+    /// Inter-Integrated Circuit bus peripheral 0.
+    pub struct I2C0 => 0x04000000, sophgo_hal::i2c::RegisterBlock;
+    /// Inter-Integrated Circuit bus peripheral 1.
+    pub struct I2C1 => 0x04010000, sophgo_hal::i2c::RegisterBlock;
+    /// Inter-Integrated Circuit bus peripheral 2.
+    pub struct I2C2 => 0x04020000, sophgo_hal::i2c::RegisterBlock;
+    /// Inter-Integrated Circuit bus peripheral 3.
+    pub struct I2C3 => 0x04030000, sophgo_hal::i2c::RegisterBlock;
+    /// Inter-Integrated Circuit bus peripheral 4.
+    pub struct I2C4 => 0x04040000, sophgo_hal::i2c::RegisterBlock;
+    /// SPI-NAND flash controller peripheral.
+    pub struct SPI_NAND => 0x04060000, sophgo_hal::spi_nand::RegisterBlock;
     /// Universal Asynchronous Receiver/Transmitter peripheral 0.
     pub struct UART0 => 0x04140000, sophgo_hal::uart::RegisterBlock;
     /// Universal Asynchronous Receiver/Transmitter peripheral 1.
@@ -90,12 +171,94 @@ soc! {
     pub struct UART2 => 0x04160000, sophgo_hal::uart::RegisterBlock;
     /// Universal Asynchronous Receiver/Transmitter peripheral 3.
     pub struct UART3 => 0x04170000, sophgo_hal::uart::RegisterBlock;
+    /// Inter-IC Sound peripheral 0.
+    pub struct I2S0 => 0x04100000, sophgo_hal::i2s::RegisterBlock;
+    /// Inter-IC Sound peripheral 1.
+    pub struct I2S1 => 0x04110000, sophgo_hal::i2s::RegisterBlock;
+    /// Inter-IC Sound peripheral 2.
+    pub struct I2S2 => 0x04120000, sophgo_hal::i2s::RegisterBlock;
+    /// Inter-IC Sound peripheral 3.
+    pub struct I2S3 => 0x04130000, sophgo_hal::i2s::RegisterBlock;
+    /// Serial Peripheral Interface peripheral 0.
+    pub struct SPI0 => 0x04180000, sophgo_hal::spi::RegisterBlock;
+    /// Serial Peripheral Interface peripheral 1.
+    pub struct SPI1 => 0x04190000, sophgo_hal::spi::RegisterBlock;
+    /// Serial Peripheral Interface peripheral 2.
+    pub struct SPI2 => 0x041A0000, sophgo_hal::spi::RegisterBlock;
+    /// Serial Peripheral Interface peripheral 3.
+    pub struct SPI3 => 0x041B0000, sophgo_hal::spi::RegisterBlock;
     /// Universal Asynchronous Receiver/Transmitter peripheral 4.
     pub struct UART4 => 0x041C0000, sophgo_hal::uart::RegisterBlock;
+    /// SD/MMC host controller peripheral 0.
+    pub struct SD0 => 0x04310000, sophgo_hal::sdhci::RegisterBlock;
+    /// SD/MMC host controller peripheral 1.
+    pub struct SD1 => 0x04320000, sophgo_hal::sdhci::RegisterBlock;
+    /// USB 2.0 on-the-go controller peripheral.
+    pub struct USB => 0x04340000, sophgo_hal::usb::RegisterBlock;
+    /// SPI-NOR flash controller peripheral.
+    pub struct SPI_NOR => 0x10000000, sophgo_hal::spi_nor::RegisterBlock;
     /// Low-power Domain General Purpose Input/Output peripheral.
     pub struct PWR_GPIO => 0x05021000, sophgo_hal::gpio::RegisterBlock;
+    /// Real-time clock peripheral.
+    pub struct RTC => 0x05026000, sophgo_hal::rtc::RegisterBlock, sophgo_hal::watchdog::ResetStatus;
     /// Low-power Domain pad configuration peripheral.
-    pub struct PWR_PINMUX => 0x05027000, PadConfigs, PwrPadConfigs;
+    pub struct PWR_PINMUX => 0x05027000, PwrPadConfigs;
+    /// IO group power switch peripheral.
+    pub struct IO_POWER => 0x03000000, IoPower;
+    /// System direct memory access controller peripheral.
+    pub struct DMA => 0x04330000, sophgo_hal::dma::RegisterBlock;
+    /// DMA channel request remap peripheral in the top misc block.
+    pub struct DMA_REMAP => 0x03000154, sophgo_hal::dma::ChannelRemap;
+    /// Internal audio codec peripheral.
+    pub struct AUDIO_CODEC => 0x0300A000, sophgo_hal::audio_codec::RegisterBlock;
+    /// Analog-to-digital converter peripheral.
+    pub struct ADC => 0x030F0000, sophgo_hal::adc::RegisterBlock;
+    /// Low-power Domain analog-to-digital converter peripheral.
+    pub struct PWR_ADC => 0x0502C000, sophgo_hal::adc::RegisterBlock;
+    /// Core Local Interruptor peripheral of the C906 core.
+    pub struct CLINT => 0x74000000, sophgo_hal::clint::RegisterBlock;
+    /// Clock generator peripheral.
+    pub struct CLK_GEN => 0x03002000, sophgo_hal::clock::RegisterBlock;
+    /// Reset controller peripheral, driven by peripheral drivers on construction and release.
+    pub struct RESET => 0x03003000, sophgo_hal::reset::RegisterBlock;
+    /// Platform-Level Interrupt Controller peripheral of the C906 core.
+    pub struct PLIC => 0x70000000, sophgo_hal::plic::RegisterBlock;
+}
+
+controlled! {
+    UART0 => [Uart0, ApbUart0], [Uart0];
+    UART1 => [Uart1, ApbUart1], [Uart1];
+    UART2 => [Uart2, ApbUart2], [Uart2];
+    UART3 => [Uart3, ApbUart3], [Uart3];
+    UART4 => [Uart4, ApbUart4], [Uart4];
+    I2C0 => [I2c, ApbI2c], [I2c0];
+    I2C1 => [I2c, ApbI2c], [I2c1];
+    I2C2 => [I2c, ApbI2c], [I2c2];
+    I2C3 => [I2c, ApbI2c], [I2c3];
+    I2C4 => [I2c, ApbI2c], [I2c4];
+    I2S0 => [I2s0, ApbI2s0], [I2s0];
+    I2S1 => [I2s1, ApbI2s1], [I2s1];
+    I2S2 => [I2s2, ApbI2s2], [I2s2];
+    I2S3 => [I2s3, ApbI2s3], [I2s3];
+    SPI0 => [Spi, ApbSpi0], [Spi0];
+    SPI1 => [Spi, ApbSpi1], [Spi1];
+    SPI2 => [Spi, ApbSpi2], [Spi2];
+    SPI3 => [Spi, ApbSpi3], [Spi3];
+    PWM0 => [Pwm], [Pwm0];
+    PWM1 => [Pwm], [Pwm1];
+    PWM2 => [Pwm], [Pwm2];
+    PWM3 => [Pwm], [Pwm3];
+    TIMER => [], [Timer];
+    ADC => [Saradc], [Adc];
+    AUDIO_CODEC => [], [AudioDac, AudioDacApb, AudioAdc];
+    DMA => [AxiDma], [Dma];
+    SD0 => [AxiSd0, Sd0, Sd0Slow], [Sd0];
+    SD1 => [AxiSd1, Sd1, Sd1Slow], [Sd1];
+    USB => [AxiUsb, ApbUsb, Usb125m, Usb33k, Usb12m], [Usb];
+    SPI_NAND => [SpiNand], [SpiNand];
+    SPI_NOR => [AhbSpiNor], [SpiNor];
+    PWR_ADC => [], [];
+    WATCHDOG => [ApbWatchdog], [Watchdog];
 }
 
 impl AsRef<FMux> for PINMUX {
@@ -112,6 +275,22 @@ impl AsRef<PadConfigs> for PINMUX {
     }
 }
 
+impl PadDomain for PINMUX {
+    type Configs = PadConfigs;
+    #[inline(always)]
+    fn pad_configs(&self) -> &PadConfigs {
+        self.as_ref()
+    }
+}
+
+impl PadDomain for PWR_PINMUX {
+    type Configs = PwrPadConfigs;
+    #[inline(always)]
+    fn pad_configs(&self) -> &PwrPadConfigs {
+        self.as_ref()
+    }
+}
+
 /// General Purpose Input/Output signal port.
 pub struct GpioPort<T> {
     pub a0: Gpio<T, 0, Input>,
@@ -122,27 +301,94 @@ pub struct GpioPort<T> {
 
 /// SoC pads.
 pub struct Pads<T> {
-    pub sd0_clk: Pad<T, 6, ()>, // TODO sd0_clk default function
+    pub sd0_clk: Pad<T, 6, SdFunc<0>>,
+    pub sd0_cmd: Pad<T, 7, SdFunc<0>>,
+    pub sd0_d0: Pad<T, 8, SdFunc<0>>,
+    pub sd0_d1: Pad<T, 10, SdFunc<0>>,
+    pub sd0_d2: Pad<T, 11, SdFunc<0>>,
+    pub sd0_d3: Pad<T, 12, SdFunc<0>>,
     pub uart0_tx: Pad<T, 18, UartFunc<0>>,
     pub uart0_rx: Pad<T, 19, UartFunc<0>>,
-    pub i2c0_scl: Pad<T, 28, ()>,
-    pub i2c0_sda: Pad<T, 29, ()>,
+    pub spinor_hold_x: Pad<T, 20, SpiNorFunc>,
+    pub spinor_sck: Pad<T, 21, SpiNorFunc>,
+    pub spinor_mosi: Pad<T, 22, SpiNorFunc>,
+    pub spinor_wp_x: Pad<T, 23, SpiNorFunc>,
+    pub spinor_miso: Pad<T, 24, SpiNorFunc>,
+    pub spinor_cs_x: Pad<T, 25, SpiNorFunc>,
+    pub jtag_cpu_tms: Pad<T, 26, JtagFunc>,
+    pub jtag_cpu_tck: Pad<T, 27, JtagFunc>,
+    pub i2c0_scl: Pad<T, 28, I2cFunc<0>>,
+    pub i2c0_sda: Pad<T, 29, I2cFunc<0>>,
+    pub aux0: Pad<T, 30, GpioFunc<Floating>>,
+    pub adc3: Pad<T, 43, AdcFunc<3>>,
+    pub adc2: Pad<T, 44, AdcFunc<2>>,
+    pub adc1: Pad<T, 45, AdcFunc<1>>,
     // TODO ...
 }
 
 /// Low-power Domain SoC pads.
 pub struct PwrPads<T> {
+    pub gpio0: Pad<T, 47, GpioFunc<Floating>>,
     pub gpio1: Pad<T, 48, GpioFunc<Floating>>,
     pub gpio2: Pad<T, 49, GpioFunc<Floating>>,
     // TODO ...
 }
 
+impl sophgo_hal::adc::AdcExt<0> for ADC {}
+impl sophgo_hal::adc::AdcExt<1> for PWR_ADC {}
+
+impl sophgo_hal::audio_codec::AudioCodecExt for AUDIO_CODEC {}
+
+impl sophgo_hal::clint::ClintExt for CLINT {}
+
+impl sophgo_hal::clock::ClockGenExt for CLK_GEN {}
+
+impl sophgo_hal::dma::DmaExt for DMA {}
+
+impl sophgo_hal::i2c::I2cExt<0> for I2C0 {}
+impl sophgo_hal::i2c::I2cExt<1> for I2C1 {}
+impl sophgo_hal::i2c::I2cExt<2> for I2C2 {}
+impl sophgo_hal::i2c::I2cExt<3> for I2C3 {}
+impl sophgo_hal::i2c::I2cExt<4> for I2C4 {}
+
+impl sophgo_hal::i2s::I2sExt<0> for I2S0 {}
+impl sophgo_hal::i2s::I2sExt<1> for I2S1 {}
+impl sophgo_hal::i2s::I2sExt<2> for I2S2 {}
+impl sophgo_hal::i2s::I2sExt<3> for I2S3 {}
+
+impl sophgo_hal::plic::PlicExt for PLIC {}
+
+impl sophgo_hal::pwm::PwmExt<0> for PWM0 {}
+impl sophgo_hal::pwm::PwmExt<1> for PWM1 {}
+impl sophgo_hal::pwm::PwmExt<2> for PWM2 {}
+impl sophgo_hal::pwm::PwmExt<3> for PWM3 {}
+
+// SDIO-1 only routes to pads not modeled by `sophgo_hal::pad` yet,
+// thus it has no SD host driver.
+impl sophgo_hal::sdhci::SdhciExt<0> for SD0 {}
+
+// SPI-1 to SPI-3 only route to pads not modeled by `sophgo_hal::pad` yet,
+// thus they have no SPI drivers.
+impl sophgo_hal::spi::SpiExt<0> for SPI0 {}
+
+impl sophgo_hal::spi_nand::SpiNandExt for SPI_NAND {}
+
+impl sophgo_hal::spi_nor::SpiNorExt for SPI_NOR {}
+
+impl sophgo_hal::rtc::RtcExt for RTC {}
+
+impl sophgo_hal::timer::TimerExt for TIMER {}
+
+impl sophgo_hal::watchdog::WatchdogExt for WATCHDOG {}
+
 impl sophgo_hal::uart::UartExt<0> for UART0 {}
 impl sophgo_hal::uart::UartExt<1> for UART1 {}
 impl sophgo_hal::uart::UartExt<2> for UART2 {}
 impl sophgo_hal::uart::UartExt<3> for UART3 {}
 impl sophgo_hal::uart::UartExt<4> for UART4 {}
 
+impl sophgo_hal::usb::UsbExt for USB {}
+
 #[cfg(target_arch = "riscv64")]
 use core::arch::naked_asm;
 
@@ -210,10 +456,63 @@ unsafe extern "C" fn entry() -> ! {
         "   call    {main}",
         stack = sym STACK,
         hart_stack_size = const LEN_STACK,
-        main = sym main,
+        main = sym start,
     )
 }
 
+/// Critical section of the single C906 hart, masking machine interrupts.
+#[cfg(target_arch = "riscv64")]
+struct SingleHartCriticalSection;
+
+#[cfg(target_arch = "riscv64")]
+critical_section::set_impl!(SingleHartCriticalSection);
+
+#[cfg(target_arch = "riscv64")]
+unsafe impl critical_section::Impl for SingleHartCriticalSection {
+    #[inline]
+    unsafe fn acquire() -> critical_section::RawRestoreState {
+        let mstatus: usize;
+        // clear mstatus.MIE and return whether it was set
+        core::arch::asm!("csrrci {}, mstatus, 0b1000", out(reg) mstatus);
+        mstatus & 0b1000 != 0
+    }
+    #[inline]
+    unsafe fn release(was_enabled: critical_section::RawRestoreState) {
+        if was_enabled {
+            core::arch::asm!("csrsi mstatus, 0b1000");
+        }
+    }
+}
+
+/// Timeout exponent of the boot watchdog, `2^(16 + 12)` cycles or about 10.7 seconds at 25 MHz.
+#[cfg(all(target_arch = "riscv64", feature = "boot-watchdog"))]
+const BOOT_WATCHDOG_TIMEOUT: u8 = 12;
+
+#[cfg(target_arch = "riscv64")]
+extern "C" fn start() -> ! {
+    // arm watchdog before user code, so a hang in bring-up resets the chip
+    #[cfg(feature = "boot-watchdog")]
+    {
+        use sophgo_hal::watchdog::{Control, ResponseMode, TimeoutRange, RESTART_KEY};
+        let wdt = WATCHDOG { _private: () };
+        let wdt = wdt.as_ref();
+        unsafe {
+            wdt.timeout_range.write(
+                TimeoutRange::default()
+                    .set_timeout(BOOT_WATCHDOG_TIMEOUT)
+                    .set_initial_timeout(BOOT_WATCHDOG_TIMEOUT),
+            );
+            wdt.control.write(
+                Control::default()
+                    .set_response_mode(ResponseMode::Reset)
+                    .enable(),
+            );
+            wdt.counter_restart.write(RESTART_KEY);
+        }
+    }
+    unsafe { main() }
+}
+
 #[cfg(target_arch = "riscv64")]
 extern "Rust" {
     // This symbol is generated by `#[entry]` macro
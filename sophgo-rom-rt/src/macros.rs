@@ -17,3 +17,23 @@ impl AsRef<$AsRefTy> for $Ty {
         )+
     };
 }
+
+macro_rules! controlled {
+    ($($Ty: ident => [$($gate: ident),*], [$($reset: ident),*];)+) => {
+        $(
+impl sophgo_hal::reset::Controlled for $Ty {
+    const GATES: &'static [sophgo_hal::clock::Gate] = &[$(sophgo_hal::clock::Gate::$gate),*];
+    const RESETS: &'static [sophgo_hal::reset::Reset] = &[$(sophgo_hal::reset::Reset::$reset),*];
+
+    #[inline(always)]
+    fn clock_gen(&self) -> &sophgo_hal::clock::RegisterBlock {
+        (&CLK_GEN { _private: () }).as_ref()
+    }
+    #[inline(always)]
+    fn reset_control(&self) -> &sophgo_hal::reset::RegisterBlock {
+        (&RESET { _private: () }).as_ref()
+    }
+}
+        )+
+    };
+}
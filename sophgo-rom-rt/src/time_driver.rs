@@ -0,0 +1,126 @@
+//! `embassy-time` driver on the machine timer.
+//!
+//! The machine timer interrupt handler must call `on_interrupt`,
+//! and machine timer interrupt must be enabled in `mie`.
+//!
+//! The driver owns the Core Local Interruptor, thus it is not part of `Peripherals`
+//! when this module is enabled.
+
+use crate::CLINT;
+use core::{cell::RefCell, task::Waker};
+use critical_section::{CriticalSection, Mutex};
+use embassy_time_driver::{Driver, TICK_HZ};
+use sophgo_hal::clint::{read_time, Clint, ClintExt, Instant, FREQUENCY};
+
+/// Maximum number of pending timers.
+const QUEUE_LEN: usize = 16;
+
+struct Queue {
+    entries: [Option<(u64, Waker)>; QUEUE_LEN],
+}
+
+impl Queue {
+    /// Adds or updates a timer, returns `false` if queue is full.
+    fn schedule(&mut self, at: u64, waker: &Waker) -> bool {
+        for (expires, queued) in self.entries.iter_mut().flatten() {
+            if queued.will_wake(waker) {
+                *expires = (*expires).min(at);
+                return true;
+            }
+        }
+        match self.entries.iter_mut().find(|entry| entry.is_none()) {
+            Some(entry) => {
+                *entry = Some((at, waker.clone()));
+                true
+            }
+            None => false,
+        }
+    }
+    /// Wakes expired timers and returns the next expiration.
+    fn next_expiration(&mut self, now: u64) -> u64 {
+        let mut next = u64::MAX;
+        for entry in self.entries.iter_mut() {
+            match entry {
+                Some((expires, _)) if *expires <= now => {
+                    if let Some((_, waker)) = entry.take() {
+                        waker.wake();
+                    }
+                }
+                Some((expires, _)) => next = next.min(*expires),
+                None => {}
+            }
+        }
+        next
+    }
+}
+
+struct MachineTimerDriver {
+    queue: Mutex<RefCell<Queue>>,
+    clint: Mutex<RefCell<Option<Clint<CLINT>>>>,
+}
+
+#[inline]
+fn to_ticks(time: u64) -> u64 {
+    (time as u128 * TICK_HZ as u128 / FREQUENCY as u128) as u64
+}
+
+#[inline]
+fn to_time(ticks: u64) -> u64 {
+    (ticks as u128 * FREQUENCY as u128)
+        .div_ceil(TICK_HZ as u128)
+        .min(u64::MAX as u128) as u64
+}
+
+impl MachineTimerDriver {
+    /// Wakes expired timers and arms the alarm for the next one.
+    fn update(&self, cs: CriticalSection, queue: &mut Queue) {
+        let mut clint = self.clint.borrow_ref_mut(cs);
+        let clint = clint.get_or_insert_with(|| CLINT { _private: () }.clint());
+        loop {
+            let next = queue.next_expiration(self.now());
+            if next == u64::MAX {
+                clint.clear_alarm();
+                return;
+            }
+            clint.set_alarm(Instant::from_ticks(to_time(next)));
+            // alarm may already have passed while being set
+            if self.now() < next {
+                return;
+            }
+        }
+    }
+}
+
+impl Driver for MachineTimerDriver {
+    #[inline]
+    fn now(&self) -> u64 {
+        to_ticks(read_time())
+    }
+    #[inline]
+    fn schedule_wake(&self, at: u64, waker: &Waker) {
+        critical_section::with(|cs| {
+            let mut queue = self.queue.borrow_ref_mut(cs);
+            if queue.schedule(at, waker) {
+                self.update(cs, &mut queue);
+            } else {
+                // queue full, let the task poll again later
+                waker.wake_by_ref();
+            }
+        })
+    }
+}
+
+embassy_time_driver::time_driver_impl!(static DRIVER: MachineTimerDriver = MachineTimerDriver {
+    queue: Mutex::new(RefCell::new(Queue {
+        entries: [const { None }; QUEUE_LEN],
+    })),
+    clint: Mutex::new(RefCell::new(None)),
+});
+
+/// Machine timer interrupt handler hook.
+///
+/// Wakes expired timers and rearms machine timer for the next one.
+#[inline]
+pub fn on_interrupt() {
+    critical_section::with(|cs| DRIVER.update(cs, &mut DRIVER.queue.borrow_ref_mut(cs)))
+}